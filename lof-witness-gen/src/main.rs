@@ -1,10 +1,53 @@
 mod wasm_gen;
 
 use lof::IRCircuit;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use thiserror::Error;
 
-fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let args: Vec<String> = std::env::args().collect();
+#[derive(Debug, Error)]
+pub enum WitnessGenError {
+    #[error("Invalid IR: {0}")]
+    InvalidIr(std::io::Error),
+    #[error("Could not access {}: {source}", path.display())]
+    IoError {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[error("Cannot name the generated files after {}: the file name is not valid UTF-8", .0.display())]
+    InvalidFileName(PathBuf),
+    #[error("Code generation failed: {0}")]
+    CodeGenError(String),
+}
+
+impl From<std::fmt::Error> for WitnessGenError {
+    fn from(err: std::fmt::Error) -> Self {
+        WitnessGenError::CodeGenError(err.to_string())
+    }
+}
+
+// a file that is there but does not decode as IR is reported apart from one that cannot be read
+fn read_circuit(path: &Path) -> Result<IRCircuit, WitnessGenError> {
+    IRCircuit::read_from_file(path).map_err(|source| match source.kind() {
+        std::io::ErrorKind::InvalidData | std::io::ErrorKind::UnexpectedEof => {
+            WitnessGenError::InvalidIr(source)
+        }
+        _ => WitnessGenError::IoError {
+            path: path.to_path_buf(),
+            source,
+        },
+    })
+}
+
+fn io_error(path: &Path) -> impl FnOnce(std::io::Error) -> WitnessGenError + '_ {
+    move |source| WitnessGenError::IoError {
+        path: path.to_path_buf(),
+        source,
+    }
+}
+
+fn main() {
+    // read as `OsString` so a path that is not UTF-8 reaches `run` and gets a proper error
+    let args: Vec<std::ffi::OsString> = std::env::args_os().collect();
 
     if args.len() < 2 {
         eprintln!("Usage: lof-witness-gen <circuit.ir> [output_dir]");
@@ -12,18 +55,25 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         std::process::exit(1);
     }
 
+    if let Err(err) = run(&args) {
+        eprintln!("Error: {}", err);
+        std::process::exit(1);
+    }
+}
+
+fn run(args: &[std::ffi::OsString]) -> Result<(), WitnessGenError> {
     let ir_path = PathBuf::from(&args[1]);
     let output_dir = if args.len() > 2 {
         PathBuf::from(&args[2])
     } else {
         ir_path
             .parent()
-            .unwrap_or_else(|| std::path::Path::new("."))
+            .unwrap_or_else(|| Path::new("."))
             .to_path_buf()
     };
 
     println!("Reading IR from: {}", ir_path.display());
-    let circuit = IRCircuit::read_from_file(&ir_path)?;
+    let circuit = read_circuit(&ir_path)?;
 
     println!("Circuit: {}", circuit.name);
     println!("  Public inputs: {}", circuit.pub_inputs.len());
@@ -38,27 +88,31 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     let circuit_name = ir_path
         .file_stem()
         .and_then(|s| s.to_str())
-        .ok_or("Invalid file name")?;
+        .ok_or_else(|| WitnessGenError::InvalidFileName(ir_path.clone()))?;
 
     let output_path = output_dir.join(format!("{}_witness.rs", circuit_name));
-    std::fs::write(&output_path, calculator_code)?;
+    std::fs::write(&output_path, calculator_code).map_err(io_error(&output_path))?;
 
     let wasm_output_path = output_dir.join(format!("{}_witness_wasm", circuit_name));
-    std::fs::create_dir_all(&wasm_output_path)?;
+    let wasm_src_path = wasm_output_path.join("src");
+    std::fs::create_dir_all(&wasm_src_path).map_err(io_error(&wasm_src_path))?;
 
-    let lib_path = wasm_output_path.join("src").join("lib.rs");
-    std::fs::create_dir_all(wasm_output_path.join("src"))?;
-    std::fs::write(&lib_path, wasm_code)?;
+    let lib_path = wasm_src_path.join("lib.rs");
+    std::fs::write(&lib_path, wasm_code).map_err(io_error(&lib_path))?;
 
     let cargo_toml = wasm_gen::generate_wasm_cargo_toml(circuit_name)?;
-    std::fs::write(wasm_output_path.join("Cargo.toml"), cargo_toml)?;
+    let cargo_toml_path = wasm_output_path.join("Cargo.toml");
+    std::fs::write(&cargo_toml_path, cargo_toml).map_err(io_error(&cargo_toml_path))?;
 
     println!("\nGenerated witness calculator: {}", output_path.display());
     println!("Generated WASM project: {}", wasm_output_path.display());
     println!("\nNext steps:");
     println!("  Regular Rust:");
     println!("    1. Include {}_witness.rs in your project", circuit_name);
-    println!("    2. Call compute_witness(inputs)");
+    println!("    2. Call compute_witness(inputs), or compute_witness_from_strings(inputs) for decimal/0x hex strings");
+    println!(
+        "    3. compute_witness_with_outputs(inputs) also returns the output signals on their own"
+    );
     println!("\n  WASM (for browser):");
     println!("    1. cd {}", wasm_output_path.display());
     println!("    2. wasm-pack build --target web");
@@ -70,7 +124,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     Ok(())
 }
 
-fn generate_witness_calculator(circuit: &IRCircuit) -> Result<String, Box<dyn std::error::Error>> {
+fn generate_witness_calculator(circuit: &IRCircuit) -> Result<String, WitnessGenError> {
     use std::fmt::Write;
 
     let mut code = String::new();
@@ -106,58 +160,137 @@ fn generate_witness_calculator(circuit: &IRCircuit) -> Result<String, Box<dyn st
     writeln!(&mut code, "    let mut witness = HashMap::new();")?;
     writeln!(&mut code)?;
 
+    let outputs = circuit.output_signals();
+
     writeln!(&mut code, "    // Public inputs")?;
     for (name, _typ) in &circuit.pub_inputs {
-        writeln!(&mut code, "    witness.insert(\"{}\".to_string(), pub_inputs.get(\"{}\").ok_or(\"Missing input: {}\")?. clone());", name, name, name)?;
+        if outputs.contains(name) {
+            // outputs may be left out and are then computed from the assertion defining them
+            writeln!(
+                &mut code,
+                "    if let Some(value) = pub_inputs.get(\"{}\") {{",
+                name
+            )?;
+            writeln!(
+                &mut code,
+                "        witness.insert(\"{}\".to_string(), fmod(value.clone()));",
+                name
+            )?;
+            writeln!(&mut code, "    }}")?;
+        } else {
+            writeln!(&mut code, "    witness.insert(\"{}\".to_string(), fmod(pub_inputs.get(\"{}\").ok_or(\"Missing input: {}\")?.clone()));", name, name, name)?;
+        }
     }
     writeln!(&mut code)?;
 
     writeln!(&mut code, "    // Execute circuit logic")?;
     for (i, instruction) in circuit.instructions.iter().enumerate() {
-        write_instruction(&mut code, instruction, i)?;
+        write_instruction(&mut code, instruction, i, &outputs)?;
     }
 
     writeln!(&mut code)?;
     writeln!(&mut code, "    Ok(witness)")?;
     writeln!(&mut code, "}}")?;
 
+    writeln!(&mut code)?;
+    writeln!(
+        &mut code,
+        "/// Compute the full witness from decimal or `0x`-prefixed hex strings"
+    )?;
+    writeln!(&mut code, "pub fn compute_witness_from_strings(pub_inputs: HashMap<String, String>) -> Result<HashMap<String, BigInt>, String> {{")?;
+    writeln!(&mut code, "    let parsed = pub_inputs")?;
+    writeln!(&mut code, "        .into_iter()")?;
+    writeln!(&mut code, "        .map(|(name, value)| parse_field(&value).map(|v| (name.clone(), v)).map_err(|e| format!(\"Input {{}}: {{}}\", name, e)))")?;
+    writeln!(
+        &mut code,
+        "        .collect::<Result<HashMap<_, _>, _>>()?;"
+    )?;
+    writeln!(&mut code, "    compute_witness(parsed)")?;
+    writeln!(&mut code, "}}")?;
+
+    let output_names = outputs
+        .iter()
+        .map(|name| format!("{:?}", name))
+        .collect::<Vec<_>>();
+    writeln!(&mut code)?;
+    writeln!(
+        &mut code,
+        "/// The circuit's output signals, next to the full witness they were computed in"
+    )?;
+    writeln!(&mut code, "pub struct WitnessWithOutputs {{")?;
+    writeln!(&mut code, "    pub outputs: HashMap<String, BigInt>,")?;
+    writeln!(&mut code, "    pub witness: HashMap<String, BigInt>,")?;
+    writeln!(&mut code, "}}")?;
+    writeln!(&mut code)?;
+    writeln!(
+        &mut code,
+        "/// Compute the witness and return the output signals separately"
+    )?;
+    writeln!(&mut code, "pub fn compute_witness_with_outputs(pub_inputs: HashMap<String, BigInt>) -> Result<WitnessWithOutputs, String> {{")?;
+    writeln!(&mut code, "    let witness = compute_witness(pub_inputs)?;")?;
+    writeln!(
+        &mut code,
+        "    let names: [&str; {}] = [{}];",
+        output_names.len(),
+        output_names.join(", ")
+    )?;
+    writeln!(&mut code, "    let outputs = names")?;
+    writeln!(&mut code, "        .iter()")?;
+    writeln!(&mut code, "        .map(|name| witness.get(*name).map(|value| (name.to_string(), value.clone())).ok_or(format!(\"Output {{}} was not computed\", name)))")?;
+    writeln!(
+        &mut code,
+        "        .collect::<Result<HashMap<_, _>, _>>()?;"
+    )?;
+    writeln!(
+        &mut code,
+        "    Ok(WitnessWithOutputs {{ outputs, witness }})"
+    )?;
+    writeln!(&mut code, "}}")?;
+    writeln!(&mut code)?;
+    writeln!(
+        &mut code,
+        "/// `compute_witness_with_outputs` from decimal or `0x`-prefixed hex strings"
+    )?;
+    writeln!(&mut code, "pub fn compute_witness_with_outputs_from_strings(pub_inputs: HashMap<String, String>) -> Result<WitnessWithOutputs, String> {{")?;
+    writeln!(&mut code, "    let parsed = pub_inputs")?;
+    writeln!(&mut code, "        .into_iter()")?;
+    writeln!(&mut code, "        .map(|(name, value)| parse_field(&value).map(|v| (name.clone(), v)).map_err(|e| format!(\"Input {{}}: {{}}\", name, e)))")?;
+    writeln!(
+        &mut code,
+        "        .collect::<Result<HashMap<_, _>, _>>()?;"
+    )?;
+    writeln!(&mut code, "    compute_witness_with_outputs(parsed)")?;
+    writeln!(&mut code, "}}")?;
+
     writeln!(&mut code)?;
     writeln!(&mut code, "// Helper: Evaluate an expression")?;
     writeln!(&mut code, "fn eval_expr(expr: &IRExpr, witness: &HashMap<String, BigInt>) -> Result<BigInt, String> {{")?;
     writeln!(&mut code, "    match expr {{")?;
     writeln!(
         &mut code,
-        "        IRExpr::Constant(s) => s.parse().map_err(|e| format(\"Parse error: {{}}\", e)),"
+        "        IRExpr::Constant(s) => s.parse().map(fmod).map_err(|e| format!(\"Parse error: {{}}\", e)),"
     )?;
     writeln!(&mut code, "        IRExpr::Variable(name) => witness.get(name).ok_or(format!(\"Unknown variable: {{}}\", name)).cloned(),")?;
     writeln!(
         &mut code,
-        "        IRExpr::Add(l, r) => Ok(eval_expr(l, witness)? + eval_expr(r, witness)?),"
+        "        IRExpr::Add(l, r) => Ok(fmod(eval_expr(l, witness)? + eval_expr(r, witness)?)),"
     )?;
     writeln!(
         &mut code,
-        "        IRExpr::Sub(l, r) => Ok(eval_expr(l, witness)? - eval_expr(r, witness)?),"
+        "        IRExpr::Sub(l, r) => Ok(fmod(eval_expr(l, witness)? - eval_expr(r, witness)?)),"
     )?;
     writeln!(
         &mut code,
-        "        IRExpr::Mul(l, r) => Ok(eval_expr(l, witness)? * eval_expr(r, witness)?),"
+        "        IRExpr::Mul(l, r) => Ok(fmod(eval_expr(l, witness)? * eval_expr(r, witness)?)),"
     )?;
-    writeln!(&mut code, "        IRExpr::Div(l, r) => {{")?;
     writeln!(
         &mut code,
-        "            let divisor = eval_expr(r, witness)?;"
+        "        IRExpr::Div(l, r) => field_div(&eval_expr(l, witness)?, &eval_expr(r, witness)?),"
     )?;
-    writeln!(&mut code, "            if divisor == BigInt::from(0) {{")?;
     writeln!(
         &mut code,
-        "                return Err(\"Division by zero\".to_string());"
+        "        IRExpr::Mod(l, r) => field_rem(&eval_expr(l, witness)?, &eval_expr(r, witness)?),"
     )?;
-    writeln!(&mut code, "            }}")?;
-    writeln!(
-        &mut code,
-        "            Ok(eval_expr(l, witness)? / divisor)"
-    )?;
-    writeln!(&mut code, "        }},")?;
     writeln!(&mut code, "        // Comparisons return 0 or 1")?;
     writeln!(&mut code, "        IRExpr::Lt(l, r) => Ok(if eval_expr(l, witness)? < eval_expr(r, witness)? {{ BigInt::from(1) }} else {{ BigInt::from(0) }}),")?;
     writeln!(&mut code, "        IRExpr::Gt(l, r) => Ok(if eval_expr(l, witness)? > eval_expr(r, witness)? {{ BigInt::from(1) }} else {{ BigInt::from(0) }}),")?;
@@ -206,6 +339,8 @@ fn generate_witness_calculator(circuit: &IRCircuit) -> Result<String, Box<dyn st
     writeln!(&mut code, "    }}")?;
     writeln!(&mut code, "}}")?;
 
+    write_field_helpers(&mut code)?;
+
     writeln!(&mut code)?;
     writeln!(
         &mut code,
@@ -220,6 +355,7 @@ fn generate_witness_calculator(circuit: &IRCircuit) -> Result<String, Box<dyn st
     writeln!(&mut code, "    Sub(Box<IRExpr>, Box<IRExpr>),")?;
     writeln!(&mut code, "    Mul(Box<IRExpr>, Box<IRExpr>),")?;
     writeln!(&mut code, "    Div(Box<IRExpr>, Box<IRExpr>),")?;
+    writeln!(&mut code, "    Mod(Box<IRExpr>, Box<IRExpr>),")?;
     writeln!(&mut code, "    Lt(Box<IRExpr>, Box<IRExpr>),")?;
     writeln!(&mut code, "    Gt(Box<IRExpr>, Box<IRExpr>),")?;
     writeln!(&mut code, "    Le(Box<IRExpr>, Box<IRExpr>),")?;
@@ -242,11 +378,163 @@ fn generate_witness_calculator(circuit: &IRCircuit) -> Result<String, Box<dyn st
     Ok(code)
 }
 
+// every value the calculator produces is reduced into [0, P), the field the R1CS is proven
+// over; division has the same semantics as `lof::field_div`
+fn write_field_helpers(code: &mut String) -> Result<(), WitnessGenError> {
+    use std::fmt::Write;
+
+    writeln!(code)?;
+    writeln!(code, "// BN254 scalar field modulus")?;
+    writeln!(code, "const P: &str = \"{}\";", lof::ir::FIELD_MODULUS)?;
+    writeln!(code)?;
+    writeln!(code, "fn modulus() -> BigInt {{")?;
+    writeln!(code, "    P.parse().unwrap()")?;
+    writeln!(code, "}}")?;
+    writeln!(code)?;
+    writeln!(
+        code,
+        "// Helper: reduce into the field, so negative values wrap around"
+    )?;
+    writeln!(code, "fn fmod(x: BigInt) -> BigInt {{")?;
+    writeln!(code, "    let p = modulus();")?;
+    writeln!(code, "    ((x % &p) + &p) % &p")?;
+    writeln!(code, "}}")?;
+    writeln!(code)?;
+    writeln!(
+        code,
+        "// Helper: field division, the value the R1CS constraint `b * q = a` requires"
+    )?;
+    writeln!(
+        code,
+        "fn field_div(numerator: &BigInt, denominator: &BigInt) -> Result<BigInt, String> {{"
+    )?;
+    writeln!(code, "    let p = modulus();")?;
+    writeln!(code, "    let denominator = fmod(denominator.clone());")?;
+    writeln!(code, "    if denominator == BigInt::from(0) {{")?;
+    writeln!(
+        code,
+        "        return Err(\"Division by zero\".to_string());"
+    )?;
+    writeln!(code, "    }}")?;
+    writeln!(code, "    let inverse = denominator.modpow(&(&p - 2), &p);")?;
+    writeln!(code, "    Ok(fmod(numerator.clone() * inverse))")?;
+    writeln!(code, "}}")?;
+    writeln!(code)?;
+    writeln!(
+        code,
+        "// Helper: remainder of the canonical representatives, like `lof`'s `%`"
+    )?;
+    writeln!(code, "#[allow(dead_code)]")?;
+    writeln!(
+        code,
+        "fn field_rem(value: &BigInt, divisor: &BigInt) -> Result<BigInt, String> {{"
+    )?;
+    writeln!(code, "    let divisor = fmod(divisor.clone());")?;
+    writeln!(code, "    if divisor == BigInt::from(0) {{")?;
+    writeln!(
+        code,
+        "        return Err(\"Division by zero\".to_string());"
+    )?;
+    writeln!(code, "    }}")?;
+    writeln!(code, "    Ok(fmod(value.clone()) % divisor)")?;
+    writeln!(code, "}}")?;
+    writeln!(code)?;
+    writeln!(
+        code,
+        "// Helpers: rotl, shr and xor3 see the low `width` bits, like `lof::ir::rotate_left`"
+    )?;
+    writeln!(code, "#[allow(dead_code)]")?;
+    writeln!(code, "fn low_bits(x: &BigInt, width: usize) -> BigInt {{")?;
+    writeln!(code, "    x & ((BigInt::from(1) << width) - 1)")?;
+    writeln!(code, "}}")?;
+    writeln!(code)?;
+    writeln!(code, "#[allow(dead_code)]")?;
+    writeln!(
+        code,
+        "fn rotl(x: BigInt, shift: usize, width: usize) -> BigInt {{"
+    )?;
+    writeln!(code, "    let x = low_bits(&x, width);")?;
+    writeln!(
+        code,
+        "    low_bits(&(&x << shift), width) | (x >> (width - shift))"
+    )?;
+    writeln!(code, "}}")?;
+    writeln!(code)?;
+    writeln!(code, "#[allow(dead_code)]")?;
+    writeln!(
+        code,
+        "fn shr(x: BigInt, shift: usize, width: usize) -> BigInt {{"
+    )?;
+    writeln!(code, "    low_bits(&x, width) >> shift")?;
+    writeln!(code, "}}")?;
+    writeln!(code)?;
+    writeln!(code, "#[allow(dead_code)]")?;
+    writeln!(
+        code,
+        "fn xor3(a: BigInt, b: BigInt, c: BigInt, width: usize) -> BigInt {{"
+    )?;
+    writeln!(code, "    low_bits(&(a ^ b ^ c), width)")?;
+    writeln!(code, "}}")?;
+    writeln!(code)?;
+    // same rules as lofit's `fr_from_str`: an optional sign, a magnitude below the modulus,
+    // negatives wrap around; `0x` hex is accepted on top
+    writeln!(
+        code,
+        "// Helper: parse a decimal or `0x` hex string into a field element"
+    )?;
+    writeln!(
+        code,
+        "fn parse_field(value: &str) -> Result<BigInt, String> {{"
+    )?;
+    writeln!(code, "    let trimmed = value.trim();")?;
+    writeln!(
+        code,
+        "    let (negative, digits) = match trimmed.strip_prefix('-') {{"
+    )?;
+    writeln!(code, "        Some(rest) => (true, rest),")?;
+    writeln!(code, "        None => (false, trimmed),")?;
+    writeln!(code, "    }};")?;
+    writeln!(
+        code,
+        "    let magnitude = match digits.strip_prefix(\"0x\").or_else(|| digits.strip_prefix(\"0X\")) {{"
+    )?;
+    writeln!(
+        code,
+        "        Some(hex) => BigInt::parse_bytes(hex.as_bytes(), 16),"
+    )?;
+    writeln!(
+        code,
+        "        None => BigInt::parse_bytes(digits.as_bytes(), 10),"
+    )?;
+    writeln!(code, "    }}")?;
+    writeln!(
+        code,
+        "    .filter(|magnitude| *magnitude >= BigInt::from(0))"
+    )?;
+    writeln!(
+        code,
+        "    .ok_or_else(|| format!(\"invalid field element '{{}}'\", value))?;"
+    )?;
+    writeln!(code, "    if magnitude >= modulus() {{")?;
+    writeln!(
+        code,
+        "        return Err(format!(\"'{{}}' exceeds the BN254 field\", value));"
+    )?;
+    writeln!(code, "    }}")?;
+    writeln!(
+        code,
+        "    Ok(fmod(if negative {{ -magnitude }} else {{ magnitude }}))"
+    )?;
+    writeln!(code, "}}")?;
+    Ok(())
+}
+
 fn write_instruction(
     code: &mut String,
     instruction: &lof::IRInstruction,
     index: usize,
-) -> Result<(), Box<dyn std::error::Error>> {
+    outputs: &[String],
+) -> Result<(), WitnessGenError> {
     use std::fmt::Write;
 
     match instruction {
@@ -279,6 +567,19 @@ fn write_instruction(
             )?;
             let left_code = expr_to_code(left)?;
             let right_code = expr_to_code(right)?;
+            for (side, other) in [(left, &right_code), (right, &left_code)] {
+                if let lof::IRExpr::Variable(name) = side {
+                    if outputs.contains(name) {
+                        writeln!(code, "    if !witness.contains_key(\"{}\") {{", name)?;
+                        writeln!(
+                            code,
+                            "        witness.insert(\"{}\".to_string(), {});",
+                            name, other
+                        )?;
+                        writeln!(code, "    }}")?;
+                    }
+                }
+            }
             writeln!(
                 code,
                 "    witness.insert(\"__left_{}\".to_string(), {});",
@@ -295,17 +596,26 @@ fn write_instruction(
     Ok(())
 }
 
-fn expr_to_code(expr: &lof::IRExpr) -> Result<String, Box<dyn std::error::Error>> {
+fn expr_to_code(expr: &lof::IRExpr) -> Result<String, WitnessGenError> {
     match expr {
-        lof::IRExpr::Constant(s) => Ok(format!("BigInt::from_str(\"{}\")?", s)),
+        lof::IRExpr::Constant(s) => Ok(format!("fmod(\"{}\".parse::<BigInt>().unwrap())", s)),
         lof::IRExpr::Variable(name) => Ok(format!(
             "witness.get(\"{}\").ok_or(\"Missing: {}\")?.clone()",
             name, name
         )),
-        lof::IRExpr::Add(l, r) => Ok(format!("({} + {})", expr_to_code(l)?, expr_to_code(r)?)),
-        lof::IRExpr::Sub(l, r) => Ok(format!("({} - {})", expr_to_code(l)?, expr_to_code(r)?)),
-        lof::IRExpr::Mul(l, r) => Ok(format!("({} * {})", expr_to_code(l)?, expr_to_code(r)?)),
-        lof::IRExpr::Div(l, r) => Ok(format!("({} / {})", expr_to_code(l)?, expr_to_code(r)?)),
+        lof::IRExpr::Add(l, r) => Ok(format!("fmod({} + {})", expr_to_code(l)?, expr_to_code(r)?)),
+        lof::IRExpr::Sub(l, r) => Ok(format!("fmod({} - {})", expr_to_code(l)?, expr_to_code(r)?)),
+        lof::IRExpr::Mul(l, r) => Ok(format!("fmod({} * {})", expr_to_code(l)?, expr_to_code(r)?)),
+        lof::IRExpr::Div(l, r) => Ok(format!(
+            "field_div(&{}, &{})?",
+            expr_to_code(l)?,
+            expr_to_code(r)?
+        )),
+        lof::IRExpr::Mod(l, r) => Ok(format!(
+            "field_rem(&{}, &{})?",
+            expr_to_code(l)?,
+            expr_to_code(r)?
+        )),
         lof::IRExpr::Lt(l, r) => Ok(format!(
             "if {} < {} {{ BigInt::from(1) }} else {{ BigInt::from(0) }}",
             expr_to_code(l)?,
@@ -361,5 +671,32 @@ fn expr_to_code(expr: &lof::IRExpr) -> Result<String, Box<dyn std::error::Error>
             "witness.get(&format!(\"{}_{}\" )).ok_or(\"Missing tuple field\")?.clone()",
             tuple, index
         )),
+        lof::IRExpr::RotateLeft {
+            value,
+            shift,
+            width,
+        } => Ok(format!(
+            "rotl({}, {}, {})",
+            expr_to_code(value)?,
+            shift,
+            width
+        )),
+        lof::IRExpr::ShiftRight {
+            value,
+            shift,
+            width,
+        } => Ok(format!(
+            "shr({}, {}, {})",
+            expr_to_code(value)?,
+            shift,
+            width
+        )),
+        lof::IRExpr::Xor3 { a, b, c, width } => Ok(format!(
+            "xor3({}, {}, {}, {})",
+            expr_to_code(a)?,
+            expr_to_code(b)?,
+            expr_to_code(c)?,
+            width
+        )),
     }
 }
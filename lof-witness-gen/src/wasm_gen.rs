@@ -1,9 +1,8 @@
+use crate::WitnessGenError;
 use lof::IRCircuit;
 use std::fmt::Write;
 
-pub fn generate_wasm_witness_calculator(
-    circuit: &IRCircuit,
-) -> Result<String, Box<dyn std::error::Error>> {
+pub fn generate_wasm_witness_calculator(circuit: &IRCircuit) -> Result<String, WitnessGenError> {
     let mut code = String::new();
 
     writeln!(
@@ -18,6 +17,7 @@ pub fn generate_wasm_witness_calculator(
     writeln!(&mut code)?;
     writeln!(&mut code, "use wasm_bindgen::prelude::*;")?;
     writeln!(&mut code, "use serde::{{Serialize, Deserialize}};")?;
+    writeln!(&mut code, "use num_bigint::BigInt;")?;
     writeln!(&mut code, "use std::collections::HashMap;")?;
     writeln!(&mut code)?;
 
@@ -43,17 +43,6 @@ pub fn generate_wasm_witness_calculator(
     writeln!(&mut code, "}}")?;
     writeln!(&mut code)?;
 
-    writeln!(&mut code, "#[derive(Serialize, Deserialize)]")?;
-    writeln!(&mut code, "pub struct WitnessOutput {{")?;
-    for (name, _) in &circuit.pub_inputs {
-        writeln!(&mut code, "    pub {}: String,", name)?;
-    }
-    for (name, _) in &circuit.witnesses {
-        writeln!(&mut code, "    pub {}: String,", name)?;
-    }
-    writeln!(&mut code, "}}")?;
-    writeln!(&mut code)?;
-
     writeln!(
         &mut code,
         "/// Compute witness from inputs (WASM entry point)"
@@ -117,27 +106,136 @@ pub fn generate_wasm_witness_calculator(
     }
     writeln!(&mut code)?;
 
-    writeln!(&mut code, "    // Build output structure")?;
-    writeln!(&mut code, "    let output = WitnessOutput {{")?;
-    for (name, _) in &circuit.pub_inputs {
-        writeln!(
-            &mut code,
-            "        {}: witness.get(\"{}\").cloned().unwrap_or_else(|| \"0\".to_string()),",
-            name, name
-        )?;
-    }
-    for (name, _) in &circuit.witnesses {
-        writeln!(
-            &mut code,
-            "        {}: witness.get(\"{}\").cloned().unwrap_or_else(|| \"0\".to_string()),",
-            name, name
-        )?;
-    }
-    writeln!(&mut code, "    }};")?;
+    // a plain object keyed by signal name with decimal string values, which is what the
+    // packaged integration.js indexes into (`witnessOutput[name].toString()`)
+    let signals = circuit
+        .pub_inputs
+        .iter()
+        .chain(&circuit.witnesses)
+        .map(|(name, _)| format!("{:?}", name))
+        .collect::<Vec<_>>();
+    writeln!(&mut code, "    // Build output object")?;
+    writeln!(
+        &mut code,
+        "    let signals: [&str; {}] = [{}];",
+        signals.len(),
+        signals.join(", ")
+    )?;
+    writeln!(&mut code, "    let output = js_sys::Object::new();")?;
+    writeln!(&mut code, "    for name in signals {{")?;
+    writeln!(
+        &mut code,
+        "        let value = witness.get(name).cloned().unwrap_or_else(|| \"0\".to_string());"
+    )?;
+    writeln!(
+        &mut code,
+        "        js_sys::Reflect::set(&output, &JsValue::from_str(name), &JsValue::from_str(&value))?;"
+    )?;
+    writeln!(&mut code, "    }}")?;
     writeln!(&mut code)?;
 
     writeln!(&mut code, "    log(\"Witness computed successfully\");")?;
-    writeln!(&mut code, "    serde_wasm_bindgen::to_value(&output).map_err(|e| JsValue::from_str(&format!(\"Serialization error: {{}}\", e)))")?;
+    writeln!(&mut code, "    Ok(output.into())")?;
+    writeln!(&mut code, "}}")?;
+    writeln!(&mut code)?;
+
+    // values travel as decimal strings; arithmetic parses them, reduces modulo the BN254
+    // prime and divides through the field inverse, matching the R1CS `b * q = a`
+    writeln!(&mut code, "const P: &str = \"{}\";", lof::ir::FIELD_MODULUS)?;
+    writeln!(&mut code)?;
+    writeln!(&mut code, "fn fmod(x: BigInt) -> BigInt {{")?;
+    writeln!(&mut code, "    let p: BigInt = P.parse().unwrap();")?;
+    writeln!(&mut code, "    ((x % &p) + &p) % &p")?;
+    writeln!(&mut code, "}}")?;
+    writeln!(&mut code)?;
+    writeln!(
+        &mut code,
+        "fn field(value: &str) -> Result<BigInt, JsValue> {{"
+    )?;
+    writeln!(
+        &mut code,
+        "    value.parse::<BigInt>().map(fmod).map_err(|_| JsValue::from_str(\"Parse error\"))"
+    )?;
+    writeln!(&mut code, "}}")?;
+    writeln!(&mut code)?;
+    writeln!(&mut code, "#[allow(dead_code)]")?;
+    writeln!(
+        &mut code,
+        "fn field_div(numerator: &str, denominator: &str) -> Result<String, JsValue> {{"
+    )?;
+    writeln!(&mut code, "    let p: BigInt = P.parse().unwrap();")?;
+    writeln!(&mut code, "    let denominator = field(denominator)?;")?;
+    writeln!(&mut code, "    if denominator == BigInt::from(0) {{")?;
+    writeln!(
+        &mut code,
+        "        return Err(JsValue::from_str(\"Division by zero\"));"
+    )?;
+    writeln!(&mut code, "    }}")?;
+    writeln!(
+        &mut code,
+        "    let inverse = denominator.modpow(&(&p - 2), &p);"
+    )?;
+    writeln!(
+        &mut code,
+        "    Ok(fmod(field(numerator)? * inverse).to_string())"
+    )?;
+    writeln!(&mut code, "}}")?;
+    writeln!(&mut code)?;
+    writeln!(&mut code, "#[allow(dead_code)]")?;
+    writeln!(
+        &mut code,
+        "fn field_rem(value: &str, divisor: &str) -> Result<String, JsValue> {{"
+    )?;
+    writeln!(&mut code, "    let divisor = field(divisor)?;")?;
+    writeln!(&mut code, "    if divisor == BigInt::from(0) {{")?;
+    writeln!(
+        &mut code,
+        "        return Err(JsValue::from_str(\"Division by zero\"));"
+    )?;
+    writeln!(&mut code, "    }}")?;
+    writeln!(&mut code, "    Ok((field(value)? % divisor).to_string())")?;
+    writeln!(&mut code, "}}")?;
+    writeln!(&mut code)?;
+    writeln!(&mut code, "#[allow(dead_code)]")?;
+    writeln!(
+        &mut code,
+        "fn low_bits(x: &BigInt, width: usize) -> BigInt {{"
+    )?;
+    writeln!(&mut code, "    x & ((BigInt::from(1) << width) - 1)")?;
+    writeln!(&mut code, "}}")?;
+    writeln!(&mut code)?;
+    writeln!(&mut code, "#[allow(dead_code)]")?;
+    writeln!(
+        &mut code,
+        "fn rotl(x: &str, shift: usize, width: usize) -> Result<String, JsValue> {{"
+    )?;
+    writeln!(&mut code, "    let x = low_bits(&field(x)?, width);")?;
+    writeln!(
+        &mut code,
+        "    Ok((low_bits(&(&x << shift), width) | (x >> (width - shift))).to_string())"
+    )?;
+    writeln!(&mut code, "}}")?;
+    writeln!(&mut code)?;
+    writeln!(&mut code, "#[allow(dead_code)]")?;
+    writeln!(
+        &mut code,
+        "fn shr(x: &str, shift: usize, width: usize) -> Result<String, JsValue> {{"
+    )?;
+    writeln!(
+        &mut code,
+        "    Ok((low_bits(&field(x)?, width) >> shift).to_string())"
+    )?;
+    writeln!(&mut code, "}}")?;
+    writeln!(&mut code)?;
+    writeln!(&mut code, "#[allow(dead_code)]")?;
+    writeln!(
+        &mut code,
+        "fn xor3(a: &str, b: &str, c: &str, width: usize) -> Result<String, JsValue> {{"
+    )?;
+    writeln!(
+        &mut code,
+        "    Ok(low_bits(&(field(a)? ^ field(b)? ^ field(c)?), width).to_string())"
+    )?;
     writeln!(&mut code, "}}")?;
 
     Ok(code)
@@ -147,7 +245,7 @@ fn write_wasm_instruction(
     code: &mut String,
     instruction: &lof::IRInstruction,
     index: usize,
-) -> Result<(), Box<dyn std::error::Error>> {
+) -> Result<(), WitnessGenError> {
     match instruction {
         lof::IRInstruction::Assign { target, expr } => {
             writeln!(code, "    // Instruction {}: {} = ...", index, target)?;
@@ -189,7 +287,7 @@ fn write_wasm_instruction(
     Ok(())
 }
 
-fn expr_to_js_code(expr: &lof::IRExpr) -> Result<String, Box<dyn std::error::Error>> {
+fn expr_to_js_code(expr: &lof::IRExpr) -> Result<String, WitnessGenError> {
     Ok(match expr {
         lof::IRExpr::Constant(s) => format!("String::from(\"{}\")", s),
         lof::IRExpr::Variable(name) => format!(
@@ -199,50 +297,66 @@ fn expr_to_js_code(expr: &lof::IRExpr) -> Result<String, Box<dyn std::error::Err
         lof::IRExpr::Add(l, r) => {
             let left = expr_to_js_code(l)?;
             let right = expr_to_js_code(r)?;
-            format!(
-                "(({}).parse::<i64>().unwrap() + ({}).parse::<i64>().unwrap()).to_string()",
-                left, right
-            )
+            format!("fmod(field(&{})? + field(&{})?).to_string()", left, right)
         }
         lof::IRExpr::Sub(l, r) => {
             let left = expr_to_js_code(l)?;
             let right = expr_to_js_code(r)?;
-            format!(
-                "(({}).parse::<i64>().unwrap() - ({}).parse::<i64>().unwrap()).to_string()",
-                left, right
-            )
+            format!("fmod(field(&{})? - field(&{})?).to_string()", left, right)
         }
         lof::IRExpr::Mul(l, r) => {
             let left = expr_to_js_code(l)?;
             let right = expr_to_js_code(r)?;
-            format!(
-                "(({}).parse::<i64>().unwrap() * ({}).parse::<i64>().unwrap()).to_string()",
-                left, right
-            )
+            format!("fmod(field(&{})? * field(&{})?).to_string()", left, right)
         }
         lof::IRExpr::Div(l, r) => {
             let left = expr_to_js_code(l)?;
             let right = expr_to_js_code(r)?;
-            format!(
-                "(({}).parse::<i64>().unwrap() / ({}).parse::<i64>().unwrap()).to_string()",
-                left, right
-            )
-        }
-        lof::IRExpr::Ge(l, r) => {
-            let left = expr_to_js_code(l)?;
-            let right = expr_to_js_code(r)?;
-            format!("if ({}).parse::<i64>().unwrap() >= ({}).parse::<i64>().unwrap() {{ String::from(\"1\") }} else {{ String::from(\"0\") }}", left, right)
+            format!("field_div(&{}, &{})?", left, right)
         }
-        lof::IRExpr::Le(l, r) => {
+        lof::IRExpr::Mod(l, r) => {
             let left = expr_to_js_code(l)?;
             let right = expr_to_js_code(r)?;
-            format!("if ({}).parse::<i64>().unwrap() <= ({}).parse::<i64>().unwrap() {{ String::from(\"1\") }} else {{ String::from(\"0\") }}", left, right)
+            format!("field_rem(&{}, &{})?", left, right)
         }
+        lof::IRExpr::Lt(l, r) => compare_js(l, r, "<")?,
+        lof::IRExpr::Gt(l, r) => compare_js(l, r, ">")?,
+        lof::IRExpr::Le(l, r) => compare_js(l, r, "<=")?,
+        lof::IRExpr::Ge(l, r) => compare_js(l, r, ">=")?,
+        lof::IRExpr::Equal(l, r) => compare_js(l, r, "==")?,
+        lof::IRExpr::NotEqual(l, r) => compare_js(l, r, "!=")?,
+        lof::IRExpr::RotateLeft {
+            value,
+            shift,
+            width,
+        } => format!("rotl(&{}, {}, {})?", expr_to_js_code(value)?, shift, width),
+        lof::IRExpr::ShiftRight {
+            value,
+            shift,
+            width,
+        } => format!("shr(&{}, {}, {})?", expr_to_js_code(value)?, shift, width),
+        lof::IRExpr::Xor3 { a, b, c, width } => format!(
+            "xor3(&{}, &{}, &{}, {})?",
+            expr_to_js_code(a)?,
+            expr_to_js_code(b)?,
+            expr_to_js_code(c)?,
+            width
+        ),
         _ => "String::from(\"0\")".to_string(),
     })
 }
 
-pub fn generate_wasm_cargo_toml(circuit_name: &str) -> Result<String, Box<dyn std::error::Error>> {
+// comparisons see the reduced field values, like the native calculator
+fn compare_js(l: &lof::IRExpr, r: &lof::IRExpr, op: &str) -> Result<String, WitnessGenError> {
+    Ok(format!(
+        "if field(&{})? {} field(&{})? {{ String::from(\"1\") }} else {{ String::from(\"0\") }}",
+        expr_to_js_code(l)?,
+        op,
+        expr_to_js_code(r)?
+    ))
+}
+
+pub fn generate_wasm_cargo_toml(circuit_name: &str) -> Result<String, WitnessGenError> {
     Ok(format!(
         r#"[package]
 name = "{}_witness_wasm"
@@ -259,6 +373,8 @@ crate-type = ["cdylib", "rlib"]
 wasm-bindgen = "0.2"
 serde = {{ version = "1.0", features = ["derive"] }}
 serde-wasm-bindgen = "0.6"
+js-sys = "0.3"
+num-bigint = "0.4"
 
 [profile.release]
 opt-level = "z"     # Optimize for size
@@ -0,0 +1,309 @@
+use lof::ir_generator::IRGenerator;
+use lof::lexer::Lexer;
+use lof::parser::Parser;
+use num_bigint::BigInt;
+use std::path::Path;
+use std::process::Command;
+
+// writes the IR for `source` into `dir` and runs the generator on it
+fn generate(source: &str, dir: &Path) {
+    let ast = Parser::new(Lexer::new(source)).parse_program().unwrap();
+    let circuit = IRGenerator::new().convert_proof(&ast[0]).unwrap();
+    circuit.write_to_file(&dir.join("circuit.ir")).unwrap();
+
+    let status = Command::new(env!("CARGO_BIN_EXE_lof-witness-gen"))
+        .arg(dir.join("circuit.ir"))
+        .arg(dir)
+        .output()
+        .unwrap();
+    assert!(status.status.success(), "{:?}", status);
+}
+
+// builds the generated calculator against num-bigint and returns what it prints for
+// `signals`; the registry is already warm from building this workspace
+fn run_calculator(source: &str, inputs: &[(&str, &str)], signals: &[&str]) -> Vec<BigInt> {
+    run_calculator_with(source, "compute_witness_from_strings", "", inputs, signals)
+}
+
+// like `run_calculator`, reading `signals` from `entry(inputs).unwrap(){field}`
+fn run_calculator_with(
+    source: &str,
+    entry: &str,
+    field: &str,
+    inputs: &[(&str, &str)],
+    signals: &[&str],
+) -> Vec<BigInt> {
+    let dir = tempfile::tempdir().unwrap();
+    generate(source, dir.path());
+
+    let runner = dir.path().join("runner");
+    std::fs::create_dir_all(runner.join("src")).unwrap();
+    std::fs::write(
+        runner.join("Cargo.toml"),
+        "[package]\nname = \"runner\"\nversion = \"0.1.0\"\nedition = \"2021\"\n\n[workspace]\n\n[dependencies]\nnum-bigint = \"0.4\"\n",
+    )
+    .unwrap();
+    let inputs = inputs
+        .iter()
+        .map(|(name, value)| format!("(\"{}\".to_string(), \"{}\".to_string())", name, value))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let prints = signals
+        .iter()
+        .map(|signal| format!("    println!(\"{{}}\", witness[\"{}\"]);\n", signal))
+        .collect::<String>();
+    std::fs::write(
+        runner.join("src").join("main.rs"),
+        format!(
+            "#![allow(dead_code)]\ninclude!(\"../../circuit_witness.rs\");\n\nfn main() {{\n    let witness = {}(HashMap::from([{}])).unwrap(){};\n{}}}\n",
+            entry, inputs, field, prints
+        ),
+    )
+    .unwrap();
+
+    let output = Command::new(std::env::var("CARGO").unwrap())
+        .args(["run", "--quiet", "--offline", "--manifest-path"])
+        .arg(runner.join("Cargo.toml"))
+        .env(
+            "CARGO_TARGET_DIR",
+            Path::new(env!("CARGO_TARGET_TMPDIR")).join("calculator"),
+        )
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    String::from_utf8(output.stdout)
+        .unwrap()
+        .lines()
+        .map(|line| line.parse().unwrap())
+        .collect()
+}
+
+fn modulus() -> BigInt {
+    lof::ir::FIELD_MODULUS.parse().unwrap()
+}
+
+#[test]
+fn test_underflowing_subtraction_wraps_into_the_field() {
+    let source = r#"
+    proof Sub {
+        input a: Field;
+        input b: Field;
+        let diff = a - b in
+        let doubled = diff * 2 in
+        assert doubled === diff + diff
+    }"#;
+    let values = run_calculator(source, &[("a", "3"), ("b", "5")], &["diff", "doubled"]);
+
+    // 3 - 5 is -2 over the integers and p - 2 in the field
+    assert_eq!(values[0], modulus() - 2);
+    assert_eq!(values[1], modulus() - 4);
+    assert!(values.iter().all(|value| *value < modulus()));
+}
+
+#[test]
+fn test_modulo_takes_the_remainder_of_the_field_value() {
+    let source = r#"
+    proof Bucket {
+        input a: Field;
+        let r = a % 10 in
+        assert r === a % 10
+    }"#;
+    let values = run_calculator(source, &[("a", "1234")], &["r"]);
+    assert_eq!(values, vec![BigInt::from(4)]);
+
+    // -1 is p - 1 in the field, not -1 over the integers
+    let values = run_calculator(source, &[("a", "-1")], &["r"]);
+    assert_eq!(values, vec![(modulus() - 1) % 10]);
+}
+
+#[test]
+fn test_string_inputs_accept_hex() {
+    let source = r#"
+    proof Hex {
+        input a: Field;
+        input b: Field;
+        let sum = a + b in
+        assert sum === a + b
+    }"#;
+    let values = run_calculator(source, &[("a", "0x10"), ("b", "0X0a")], &["a", "b", "sum"]);
+    assert_eq!(
+        values,
+        vec![BigInt::from(16), BigInt::from(10), BigInt::from(26)]
+    );
+
+    // negative inputs wrap around like lofit's `fr_from_str`
+    let values = run_calculator(source, &[("a", "-0x1"), ("b", "3")], &["a", "sum"]);
+    assert_eq!(values, vec![modulus() - 1, BigInt::from(2)]);
+}
+
+#[test]
+fn test_outputs_are_computed_and_returned_separately() {
+    let source = r#"
+    proof HashFunction {
+        input preimage: Field;
+        input salt: Field;
+        output hash: Field;
+        assert hash === preimage * preimage + salt
+    }"#;
+    let inputs = [("preimage", "7"), ("salt", "5")];
+
+    let values = run_calculator_with(
+        source,
+        "compute_witness_with_outputs_from_strings",
+        ".outputs",
+        &inputs,
+        &["hash"],
+    );
+    assert_eq!(values, vec![BigInt::from(54)]);
+
+    // the full witness still carries the output next to the inputs
+    let values = run_calculator(source, &inputs, &["preimage", "hash"]);
+    assert_eq!(values, vec![BigInt::from(7), BigInt::from(54)]);
+}
+
+#[test]
+fn test_wasm_calculator_returns_a_plain_object() {
+    if Command::new("wasm-bindgen-test-runner")
+        .arg("--version")
+        .output()
+        .is_err()
+    {
+        eprintln!("skipping: wasm-bindgen-test-runner is not installed");
+        return;
+    }
+
+    let dir = tempfile::tempdir().unwrap();
+    generate(
+        r#"
+    proof Sub {
+        input a: Field;
+        input b: Field;
+        witness d: Field;
+        assert d === a - b
+    }"#,
+        dir.path(),
+    );
+
+    let crate_dir = dir.path().join("circuit_witness_wasm");
+    let mut manifest = std::fs::read_to_string(crate_dir.join("Cargo.toml")).unwrap();
+    manifest.push_str("\n[dev-dependencies]\nwasm-bindgen-test = \"0.3\"\n");
+    std::fs::write(crate_dir.join("Cargo.toml"), manifest).unwrap();
+    std::fs::create_dir_all(crate_dir.join("tests")).unwrap();
+    std::fs::write(
+        crate_dir.join("tests").join("web.rs"),
+        r##"
+use wasm_bindgen::JsCast;
+use wasm_bindgen_test::wasm_bindgen_test;
+
+#[wasm_bindgen_test]
+fn compute_witness_returns_signal_keys() {
+    let inputs = js_sys::JSON::parse(r#"{"a": "5", "b": "3", "d": "2"}"#).unwrap();
+    let output = circuit_witness_wasm::compute_witness(inputs).unwrap();
+
+    assert!(!output.is_instance_of::<js_sys::Map>());
+    let output: js_sys::Object = output.dyn_into().unwrap();
+    let keys: Vec<String> = js_sys::Object::keys(&output)
+        .iter()
+        .map(|key| key.as_string().unwrap())
+        .collect();
+    assert_eq!(keys, ["a", "b", "d"]);
+
+    let a = js_sys::Reflect::get(&output, &"a".into()).unwrap();
+    assert_eq!(a.as_string().as_deref(), Some("5"));
+}
+"##,
+    )
+    .unwrap();
+
+    let output = Command::new(std::env::var("CARGO").unwrap())
+        .args([
+            "test",
+            "--offline",
+            "--target",
+            "wasm32-unknown-unknown",
+            "--test",
+            "web",
+            "--manifest-path",
+        ])
+        .arg(crate_dir.join("Cargo.toml"))
+        .env(
+            "CARGO_TARGET_DIR",
+            Path::new(env!("CARGO_TARGET_TMPDIR")).join("wasm-calculator"),
+        )
+        .env(
+            "CARGO_TARGET_WASM32_UNKNOWN_UNKNOWN_RUNNER",
+            "wasm-bindgen-test-runner",
+        )
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "{}\n{}",
+        String::from_utf8_lossy(&output.stdout),
+        String::from_utf8_lossy(&output.stderr)
+    );
+}
+
+#[test]
+fn test_missing_ir_file_is_reported_apart_from_invalid_ir() {
+    let dir = tempfile::tempdir().unwrap();
+    let run = |path: &Path| {
+        let output = Command::new(env!("CARGO_BIN_EXE_lof-witness-gen"))
+            .arg(path)
+            .arg(dir.path())
+            .output()
+            .unwrap();
+        assert!(!output.status.success());
+        String::from_utf8_lossy(&output.stderr).into_owned()
+    };
+
+    let missing = dir.path().join("missing.ir");
+    let stderr = run(&missing);
+    assert!(
+        stderr.starts_with(&format!("Error: Could not access {}:", missing.display())),
+        "{}",
+        stderr
+    );
+
+    let garbage = dir.path().join("garbage.ir");
+    std::fs::write(&garbage, b"not an ir file").unwrap();
+    let stderr = run(&garbage);
+    assert!(stderr.starts_with("Error: Invalid IR:"), "{}", stderr);
+}
+
+#[cfg(unix)]
+#[test]
+fn test_non_utf8_file_name_is_reported_as_such() {
+    use std::os::unix::ffi::OsStrExt;
+
+    let dir = tempfile::tempdir().unwrap();
+    let source = r#"
+    proof Square {
+        input x: Field;
+        witness y: Field;
+        assert x === y * y
+    }"#;
+    generate(source, dir.path());
+    let path = dir
+        .path()
+        .join(std::ffi::OsStr::from_bytes(b"circuit\xff.ir"));
+    std::fs::rename(dir.path().join("circuit.ir"), &path).unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_lof-witness-gen"))
+        .arg(&path)
+        .arg(dir.path())
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.starts_with("Error: Cannot name the generated files after"),
+        "{}",
+        stderr
+    );
+    assert!(stderr.contains("not valid UTF-8"), "{}", stderr);
+}
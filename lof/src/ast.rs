@@ -1,3 +1,5 @@
+use num_bigint::BigInt;
+use std::collections::HashMap;
 use std::fmt;
 
 #[derive(Debug, Clone, PartialEq, Copy)]
@@ -47,6 +49,19 @@ pub enum Type {
     Tuple(Vec<Type>),
 }
 
+impl Type {
+    // `Bits<N>` is sugar for `Array<Bool, N>` whose elements are each enforced boolean
+    pub fn bits_width(&self) -> Option<usize> {
+        match self {
+            Self::Bits(size) => match size.as_ref() {
+                Expression::Number(n) => usize::try_from(*n).ok(),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+}
+
 impl fmt::Display for Type {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
@@ -73,7 +88,10 @@ impl fmt::Display for Type {
                 };
                 write!(f, "bool{}", constraint_str)
             }
-            Self::Bits(size) => write!(f, "Bits<{:?}>", size),
+            Self::Bits(size) => match self.bits_width() {
+                Some(width) => write!(f, "Bits<{}>", width),
+                None => write!(f, "Bits<{:?}>", size),
+            },
             Self::Array { element_type, size } => write!(f, "Array<{}, {}>", element_type, size),
             Self::Nat => write!(f, "Nat"),
             Self::Custom(name) => write!(f, "{}", name),
@@ -107,6 +125,7 @@ impl fmt::Display for Type {
 #[derive(Debug, Clone, PartialEq)]
 pub enum Expression {
     Number(i64),
+    BigNumber(BigInt),
     Variable(String),
     FunctionCall {
         function: String,
@@ -115,7 +134,8 @@ pub enum Expression {
     FunctionDef {
         name: String,
         params: Vec<Parameter>,
-        return_type: Type,
+        // `None` when the annotation is omitted and the type is inferred from the body
+        return_type: Option<Type>,
         body: Box<Expression>,
     },
     Let {
@@ -155,6 +175,11 @@ pub enum Expression {
         index: Box<Expression>,
     },
     ArrayLiteral(Vec<Expression>),
+    // `[] : Array<Field, 0>`, for literals whose type can't be inferred from their elements
+    Ascription {
+        value: Box<Expression>,
+        typ: Type,
+    },
     TypeAlias {
         name: String,
         typ: Type,
@@ -163,6 +188,173 @@ pub enum Expression {
         name: String,
         variants: Vec<EnumVariant>,
     },
+    Const {
+        name: String,
+        value: Box<Expression>,
+    },
+}
+
+/// Builtins spelling out an equality assertion: `assert_eq(a, b)` and `assert_zero(x)`.
+pub const ASSERTION_BUILTINS: &[&str] = &["assert_eq", "assert_zero"];
+
+impl Expression {
+    // `assert c` and `a === b` are statements: a block that ends in one has no value
+    pub fn is_assertion(&self) -> bool {
+        match self {
+            Expression::Assert(_)
+            | Expression::BinaryOp {
+                op: Operator::Assert,
+                ..
+            } => true,
+            Expression::FunctionCall { function, .. } => {
+                ASSERTION_BUILTINS.contains(&function.as_str())
+            }
+            _ => false,
+        }
+    }
+
+    // `assert_eq(a, b)` is `assert a === b` and `assert_zero(x)` is `assert x === 0`;
+    // `None` for any other call, or one with the wrong number of arguments
+    pub fn desugar_assertion(function: &str, arguments: &[Expression]) -> Option<Expression> {
+        let (left, right) = match (function, arguments) {
+            ("assert_eq", [left, right]) => (left.clone(), right.clone()),
+            ("assert_zero", [value]) => (value.clone(), Expression::Number(0)),
+            _ => return None,
+        };
+        Some(Expression::Assert(Box::new(Expression::BinaryOp {
+            left: Box::new(left),
+            op: Operator::Assert,
+            right: Box::new(right),
+        })))
+    }
+
+    // `const` initialisers may only combine literals and earlier constants with + - *
+    pub fn fold_constant(&self, constants: &HashMap<String, BigInt>) -> Option<BigInt> {
+        match self {
+            Expression::Number(n) => Some(BigInt::from(*n)),
+            Expression::BigNumber(n) => Some(n.clone()),
+            Expression::Variable(name) => constants.get(name).cloned(),
+            Expression::BinaryOp { left, op, right } => {
+                let left = left.fold_constant(constants)?;
+                let right = right.fold_constant(constants)?;
+                match op {
+                    Operator::Add => Some(left + right),
+                    Operator::Sub => Some(left - right),
+                    Operator::Mul => Some(left * right),
+                    _ => None,
+                }
+            }
+            _ => None,
+        }
+    }
+}
+
+// renders an expression back in source syntax, for messages that point at what was written
+impl fmt::Display for Expression {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Number(n) => write!(f, "{}", n),
+            Self::BigNumber(n) => write!(f, "{}", n),
+            Self::Variable(name) => write!(f, "{}", name),
+            Self::FunctionCall {
+                function,
+                arguments,
+            } => write!(f, "{}({})", function, join(arguments, ", ")),
+            Self::FunctionDef { name, params, .. } => {
+                let params = params
+                    .iter()
+                    .map(|p| p.name.clone())
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                write!(f, "fn {}({})", name, params)
+            }
+            Self::Let {
+                pattern,
+                value,
+                body,
+            } => write!(f, "let {} = {} in {}", pattern, value, body),
+            Self::BinaryOp {
+                op: Operator::Not,
+                right,
+                ..
+            } => write!(f, "!{}", Operand(right, u8::MAX)),
+            // operators bind left to right, so only a right operand at the same level needs parentheses
+            Self::BinaryOp { left, op, right } => write!(
+                f,
+                "{} {} {}",
+                Operand(left, op.precedence()),
+                op,
+                Operand(right, op.precedence() + 1)
+            ),
+            Self::Match { value, patterns } => {
+                write!(f, "match {} with", value)?;
+                for arm in patterns {
+                    write!(f, " | {}", arm.pattern)?;
+                    if let Some(guard) = &arm.guard {
+                        write!(f, " if {}", guard)?;
+                    }
+                    write!(f, " => {}", arm.body)?;
+                }
+                Ok(())
+            }
+            Self::Block {
+                statements,
+                final_expr,
+            } => {
+                write!(f, "{{")?;
+                for statement in statements {
+                    write!(f, " {};", statement)?;
+                }
+                if let Some(expr) = final_expr {
+                    write!(f, " {}", expr)?;
+                }
+                write!(f, " }}")
+            }
+            Self::Component { name, .. } => write!(f, "component {}", name),
+            Self::Proof { name, .. } => write!(f, "proof {}", name),
+            Self::Tuple(elements) => write!(f, "({})", join(elements, ", ")),
+            Self::Assert(condition) => write!(f, "assert {}", condition),
+            Self::ArrayIndex { array, index } => {
+                write!(f, "{}[{}]", Operand(array, u8::MAX), index)
+            }
+            Self::ArrayLiteral(elements) => write!(f, "[{}]", join(elements, ", ")),
+            Self::Ascription { value, typ } => write!(f, "{} : {}", Operand(value, u8::MAX), typ),
+            Self::TypeAlias { name, typ } => write!(f, "type {} = {}", name, typ),
+            Self::EnumDef { name, .. } => write!(f, "enum {}", name),
+            Self::Const { name, value } => write!(f, "const {} = {}", name, value),
+        }
+    }
+}
+
+// an operand is parenthesised when it binds looser than the precedence its position needs
+struct Operand<'a>(&'a Expression, u8);
+
+impl fmt::Display for Operand<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let needs_parens = match self.0 {
+            Expression::BinaryOp {
+                op: Operator::Not, ..
+            } => false,
+            Expression::BinaryOp { op, .. } => op.precedence() < self.1,
+            Expression::Let { .. } | Expression::Match { .. } | Expression::Ascription { .. } => {
+                true
+            }
+            _ => false,
+        };
+        if needs_parens {
+            write!(f, "({})", self.0)
+        } else {
+            write!(f, "{}", self.0)
+        }
+    }
+}
+
+fn join<T: fmt::Display>(items: &[T], separator: &str) -> String {
+    items
+        .iter()
+        .map(|item| item.to_string())
+        .collect::<Vec<_>>()
+        .join(separator)
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -178,6 +370,13 @@ pub struct Signal {
     pub typ: Type,
 }
 
+// what a diagnostic is about: a statement, or the declaration of a proof or component's signal
+#[derive(Debug, Clone, PartialEq)]
+pub enum Site {
+    Statement(Expression),
+    Signal { owner: String, name: String },
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct Parameter {
     pub name: String,
@@ -188,6 +387,7 @@ pub struct Parameter {
 pub enum Visibility {
     Input,
     Witness,
+    Output,
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -199,9 +399,23 @@ pub enum Pattern {
     Literal(i64),
 }
 
+impl fmt::Display for Pattern {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Variable(name) => write!(f, "{}", name),
+            Self::Tuple(patterns) => write!(f, "({})", join(patterns, ", ")),
+            Self::Wildcard => write!(f, "_"),
+            Self::Constructor(name, fields) if fields.is_empty() => write!(f, "{}", name),
+            Self::Constructor(name, fields) => write!(f, "{}({})", name, join(fields, ", ")),
+            Self::Literal(n) => write!(f, "{}", n),
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct MatchPattern {
     pub pattern: Pattern,
+    pub guard: Option<Box<Expression>>,
     pub body: Box<Expression>,
 }
 
@@ -220,6 +434,7 @@ pub enum Operator {
     Sub,
     Mul,
     Div,
+    Mod,
 
     // Comparison
     Equal,    // ==
@@ -238,6 +453,44 @@ pub enum Operator {
     Assert, // ===
 }
 
+impl Operator {
+    // binding strength as the parser assigns it, loosest first
+    pub fn precedence(self) -> u8 {
+        match self {
+            Self::Assert => 1,
+            Self::Or => 2,
+            Self::And => 3,
+            Self::Equal | Self::NotEqual | Self::Gt | Self::Lt | Self::Ge | Self::Le => 4,
+            Self::Add | Self::Sub => 5,
+            Self::Mul | Self::Div | Self::Mod => 6,
+            Self::Not => 7,
+        }
+    }
+}
+
+impl fmt::Display for Operator {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let symbol = match self {
+            Self::Add => "+",
+            Self::Sub => "-",
+            Self::Mul => "*",
+            Self::Div => "/",
+            Self::Mod => "%",
+            Self::Equal => "==",
+            Self::NotEqual => "!=",
+            Self::Gt => ">",
+            Self::Lt => "<",
+            Self::Ge => ">=",
+            Self::Le => "<=",
+            Self::And => "&&",
+            Self::Or => "||",
+            Self::Not => "!",
+            Self::Assert => "===",
+        };
+        write!(f, "{}", symbol)
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct GenericParam {
     pub name: String,
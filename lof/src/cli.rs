@@ -1,14 +1,19 @@
+use crate::ast::{Expression, Visibility};
 use crate::lexer::Lexer;
 use crate::parser::Parser as LofParser;
-use crate::pipeline::{CompilerError, CompilerPipeline};
+use crate::pipeline::{warning_diagnostic, CompilerError, CompilerPipeline};
+use crate::r1cs::OptLevel;
 use clap::{Parser, Subcommand, ValueEnum};
 use colored::*;
 use lofit::ConstraintSystem;
+use notify::{EventKind, RecursiveMode, Watcher};
 use serde_json::{json, to_string_pretty};
-use std::collections::BTreeMap;
+use std::collections::HashSet;
 use std::fs;
-use std::io::{BufReader, Write};
+use std::io::{BufReader, Read, Write};
 use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::time::Duration;
 use tracing::{debug, error, info, warn};
 
 struct ProjectPaths {
@@ -36,6 +41,49 @@ enum Target {
     Wasm,
 }
 
+#[derive(Clone, Copy, ValueEnum)]
+enum Emit {
+    R1cs,
+    Ir,
+    Both,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Optimize {
+    #[value(name = "0")]
+    O0,
+    #[value(name = "1")]
+    O1,
+    #[value(name = "2")]
+    O2,
+}
+
+impl From<Optimize> for OptLevel {
+    fn from(level: Optimize) -> Self {
+        match level {
+            Optimize::O0 => OptLevel::O0,
+            Optimize::O1 => OptLevel::O1,
+            Optimize::O2 => OptLevel::O2,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum MessageFormat {
+    Human,
+    Json,
+}
+
+impl Emit {
+    fn r1cs(self) -> bool {
+        matches!(self, Emit::R1cs | Emit::Both)
+    }
+
+    fn ir(self) -> bool {
+        matches!(self, Emit::Ir | Emit::Both)
+    }
+}
+
 #[derive(Subcommand)]
 enum Commands {
     Check {
@@ -46,6 +94,22 @@ enum Commands {
 
         #[arg(short, long)]
         verbose: bool,
+
+        /// Re-run the check every time the file changes
+        #[arg(short, long)]
+        watch: bool,
+
+        /// How to report errors: colored text, or one JSON object per line on stdout
+        #[arg(long, value_enum, default_value = "human")]
+        message_format: MessageFormat,
+
+        /// Report unconstrained witnesses as warnings instead of errors (unsound)
+        #[arg(long)]
+        allow_unconstrained: bool,
+
+        /// After a successful check, list every R1CS constraint with the statement it came from
+        #[arg(long)]
+        emit_constraints: bool,
     },
     Compile {
         #[arg(value_name = "FILE")]
@@ -65,6 +129,28 @@ enum Commands {
 
         #[arg(short = 'g', long)]
         generate_templates: bool,
+
+        /// Directory for the compiled artifacts (defaults to `build/` next to the source)
+        #[arg(long, value_name = "DIR")]
+        out_dir: Option<PathBuf>,
+
+        /// Which artifacts to write
+        #[arg(long, value_enum, default_value = "both")]
+        emit: Emit,
+
+        /// Artifact base name, required when FILE is `-` (stdin)
+        #[arg(long, value_name = "NAME")]
+        out_base: Option<String>,
+
+        /// Optimization level: 0 keeps the constraints as lowered, 1 folds constants and
+        /// drops copies and dead temporaries, 2 also merges repeated products
+        #[arg(short = 'O', long, value_enum, default_value = "0")]
+        optimize: Optimize,
+    },
+    /// Report constraint counts and where they come from
+    Stats {
+        #[arg(value_name = "FILE")]
+        file: PathBuf,
     },
     Parse {
         #[arg(value_name = "FILE")]
@@ -83,20 +169,56 @@ pub fn run_cli() -> Result<(), Box<dyn std::error::Error>> {
     if std::env::var("RUST_LOG").is_err() {
         std::env::set_var("RUST_LOG", "info");
     }
-    let _ = tracing_subscriber::fmt::try_init();
+    let cli = Cli::parse();
 
-    run()
-}
+    // keep stdout clean for tools reading JSON diagnostics
+    let json_output = matches!(
+        cli.command,
+        Commands::Check {
+            message_format: MessageFormat::Json,
+            ..
+        }
+    );
+    if json_output {
+        let _ = tracing_subscriber::fmt()
+            .with_writer(std::io::stderr)
+            .try_init();
+    } else {
+        let _ = tracing_subscriber::fmt::try_init();
+    }
 
-fn run() -> Result<(), Box<dyn std::error::Error>> {
-    let cli = Cli::parse();
+    run(cli)
+}
 
+fn run(cli: Cli) -> Result<(), Box<dyn std::error::Error>> {
     match cli.command {
         Commands::Version => {
             println!("{}", VERSION);
             Ok(())
         }
-        Commands::Check { file, verbose, .. } => handle_check(file, verbose),
+        Commands::Check {
+            file,
+            verbose,
+            watch,
+            message_format,
+            allow_unconstrained,
+            emit_constraints,
+            ..
+        } => match (watch, message_format) {
+            (true, MessageFormat::Json) => {
+                Err("--watch cannot be combined with --message-format json".into())
+            }
+            (false, MessageFormat::Json) if emit_constraints => {
+                Err("--emit-constraints cannot be combined with --message-format json".into())
+            }
+            (true, MessageFormat::Human) => {
+                watch_check(file, verbose, allow_unconstrained, emit_constraints)
+            }
+            (false, MessageFormat::Json) => handle_check_json(file, allow_unconstrained),
+            (false, MessageFormat::Human) => {
+                handle_check(file, verbose, allow_unconstrained, emit_constraints)
+            }
+        },
         Commands::Compile {
             file,
             verbose,
@@ -104,7 +226,25 @@ fn run() -> Result<(), Box<dyn std::error::Error>> {
             output,
             generate_templates,
             skip_wasm,
-        } => handle_compile(file, verbose, target, output, generate_templates, skip_wasm),
+            out_dir,
+            emit,
+            out_base,
+            optimize,
+        } => handle_compile(
+            file,
+            verbose,
+            target,
+            output,
+            generate_templates,
+            skip_wasm,
+            CompileOutput {
+                out_dir,
+                emit,
+                out_base,
+                opt_level: optimize.into(),
+            },
+        ),
+        Commands::Stats { file } => handle_stats(file),
         Commands::Parse {
             file,
             verbose,
@@ -124,14 +264,52 @@ fn ensure_lof_extension(file: &Path) -> Result<(), Box<dyn std::error::Error>> {
     }
 }
 
-fn handle_check(file: PathBuf, verbose: bool) -> Result<(), Box<dyn std::error::Error>> {
-    ensure_lof_extension(&file)?;
+const STDIN_PATH: &str = "-";
 
-    info!("Type checking file: {}", file.display());
-    println!("{} {}", "Type checking".blue(), file.display());
-    let source = fs::read_to_string(&file)?;
+fn is_stdin(file: &Path) -> bool {
+    file.as_os_str() == STDIN_PATH
+}
+
+fn read_stdin() -> std::io::Result<String> {
+    let mut source = String::new();
+    std::io::stdin().read_to_string(&mut source)?;
+    Ok(source)
+}
 
-    let pipeline = CompilerPipeline::new(source, verbose);
+// stdin has no path to derive artifact names from, so `<out_base>.lof` stands in for it
+fn load_source(
+    file: &Path,
+    out_base: Option<&str>,
+) -> Result<(PathBuf, String), Box<dyn std::error::Error>> {
+    if is_stdin(file) {
+        let out_base =
+            out_base.ok_or("--out-base <NAME> is required when reading source from stdin")?;
+        Ok((PathBuf::from(format!("{}.lof", out_base)), read_stdin()?))
+    } else {
+        ensure_lof_extension(file)?;
+        Ok((file.to_path_buf(), fs::read_to_string(file)?))
+    }
+}
+
+fn handle_check(
+    file: PathBuf,
+    verbose: bool,
+    allow_unconstrained: bool,
+    emit_constraints: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let source = if is_stdin(&file) {
+        info!("Type checking source from stdin");
+        println!("{} <stdin>", "Type checking".blue());
+        read_stdin()?
+    } else {
+        ensure_lof_extension(&file)?;
+        info!("Type checking file: {}", file.display());
+        println!("{} {}", "Type checking".blue(), file.display());
+        fs::read_to_string(&file)?
+    };
+
+    let mut pipeline = CompilerPipeline::new(source, verbose);
+    pipeline.allow_unconstrained = allow_unconstrained;
 
     if verbose {
         debug!("Starting type checking in verbose mode");
@@ -139,38 +317,159 @@ fn handle_check(file: PathBuf, verbose: bool) -> Result<(), Box<dyn std::error::
     }
 
     match pipeline.type_check_only(file.as_path()) {
-        Ok(_) => {
+        Ok(warnings) => {
+            for (warning, _) in warnings {
+                println!("{} {}", "Warning:".yellow(), warning);
+            }
             info!("Type checking completed successfully");
             println!("{}", "Type checking successful".green());
-            Ok(())
         }
-        Err(err) => match err {
-            CompilerError::LexerError(e) => {
-                error!("Lexer error: {}", e);
-                Err(format!("Lexer error: {}", e).into())
-            }
-            CompilerError::ParserError(e) => {
-                error!("Parser error: {}", e);
-                Err(format!("Parser error: {}", e).into())
-            }
-            CompilerError::TypeCheckerError(e) => {
-                error!("Type error: {:?}", e);
-                Err(format!("Type error: {:?}", e).into())
-            }
-            CompilerError::NoProofs => {
-                error!("No proofs found in the source file");
-                Err("No proofs found in the source file".into())
+        Err(err) => return Err(map_compiler_error(err, &pipeline.source)),
+    }
+
+    if emit_constraints {
+        let listings = pipeline
+            .annotated_constraints()
+            .map_err(|e| map_compiler_error(e, &pipeline.source))?;
+        for (proof_name, constraints) in listings {
+            println!("\n{} {}", "Constraints for proof".blue(), proof_name);
+            for (index, annotated) in constraints.iter().enumerate() {
+                println!(
+                    "  {:>3}: {}  // {}",
+                    index, annotated.constraint, annotated.source
+                );
             }
-            CompilerError::R1CSError => {
-                error!("R1CS generation failed");
-                Err("R1CS error".into())
+        }
+    }
+    Ok(())
+}
+
+fn handle_check_json(
+    file: PathBuf,
+    allow_unconstrained: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let source = if is_stdin(&file) {
+        read_stdin()?
+    } else {
+        ensure_lof_extension(&file)?;
+        fs::read_to_string(&file)?
+    };
+
+    let mut pipeline = CompilerPipeline::new(source, false);
+    pipeline.allow_unconstrained = allow_unconstrained;
+
+    // lexer errors are reported as diagnostics, not as a panic backtrace on stderr; the hook is
+    // only silenced while the source is lexed and checked
+    let previous_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(|_| {}));
+    let checked = pipeline.type_check_only(file.as_path());
+    std::panic::set_hook(previous_hook);
+
+    match checked {
+        Ok(warnings) => {
+            for (warning, span) in warnings {
+                let diagnostic = warning_diagnostic(warning, span);
+                println!("{}", serde_json::to_string(&diagnostic)?);
             }
-            CompilerError::IRError(e) => {
-                error!("IR generation failed: {}", e);
-                Err(format!("IR error: {}", e).into())
+            Ok(())
+        }
+        Err(err) => {
+            let diagnostic = err.into_diagnostic(&pipeline.source);
+            println!("{}", serde_json::to_string(&diagnostic)?);
+            Err(diagnostic.message.into())
+        }
+    }
+}
+
+fn watch_check(
+    file: PathBuf,
+    verbose: bool,
+    allow_unconstrained: bool,
+    emit_constraints: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    if is_stdin(&file) {
+        return Err("--watch needs a file path, it cannot be used with stdin".into());
+    }
+    ensure_lof_extension(&file)?;
+
+    let file_name = file
+        .file_name()
+        .ok_or_else(|| format!("Invalid file path '{}'", file.display()))?
+        .to_os_string();
+
+    // watch the directory rather than the file: editors that save atomically replace
+    // the file, which would silently drop a watch on the old inode
+    let watch_dir = match file.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent.to_path_buf(),
+        _ => PathBuf::from("."),
+    };
+
+    let (tx, rx) = mpsc::channel();
+    let mut watcher = notify::recommended_watcher(tx)?;
+    watcher.watch(&watch_dir, RecursiveMode::NonRecursive)?;
+
+    report_watch_result(handle_check(
+        file.clone(),
+        verbose,
+        allow_unconstrained,
+        emit_constraints,
+    ));
+    println!(
+        "{} {} {}",
+        "Watching".cyan(),
+        file.display(),
+        "for changes (Ctrl+C to stop)".cyan()
+    );
+
+    while let Ok(event) = rx.recv() {
+        let event = match event {
+            Ok(event) => event,
+            Err(e) => {
+                warn!("Watch error: {}", e);
+                continue;
             }
-        },
+        };
+
+        let touches_file = event
+            .paths
+            .iter()
+            .any(|p| p.file_name() == Some(file_name.as_os_str()));
+        if !touches_file || !matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_)) {
+            continue;
+        }
+
+        // a single save usually arrives as a burst of events
+        std::thread::sleep(Duration::from_millis(100));
+        while rx.try_recv().is_ok() {}
+
+        if !file.exists() {
+            debug!("{} is gone, waiting for it to be recreated", file.display());
+            continue;
+        }
+
+        println!();
+        report_watch_result(handle_check(
+            file.clone(),
+            verbose,
+            allow_unconstrained,
+            emit_constraints,
+        ));
     }
+
+    Ok(())
+}
+
+fn report_watch_result(result: Result<(), Box<dyn std::error::Error>>) {
+    if let Err(e) = result {
+        println!("{} {}", "Error:".red(), e);
+    }
+}
+
+struct CompileOutput {
+    out_dir: Option<PathBuf>,
+    emit: Emit,
+    out_base: Option<String>,
+    opt_level: OptLevel,
 }
 
 fn handle_compile(
@@ -180,15 +479,50 @@ fn handle_compile(
     output: Option<PathBuf>,
     generate_templates: bool,
     skip_wasm: bool,
+    compile_output: CompileOutput,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    ensure_lof_extension(&file)?;
+    let (file, source) = load_source(&file, compile_output.out_base.as_deref())?;
 
     match target {
-        Target::R1cs => compile_r1cs(file.as_path(), verbose, generate_templates, None).map(|_| ()),
-        Target::Wasm => compile_wasm(file.as_path(), verbose, output.as_deref(), skip_wasm),
+        Target::R1cs => compile_r1cs(
+            file.as_path(),
+            &source,
+            verbose,
+            generate_templates,
+            None,
+            &compile_output,
+        )
+        .map(|_| ()),
+        Target::Wasm => compile_wasm(
+            file.as_path(),
+            &source,
+            verbose,
+            output.as_deref(),
+            skip_wasm,
+            compile_output.opt_level,
+        ),
     }
 }
 
+fn handle_stats(file: PathBuf) -> Result<(), Box<dyn std::error::Error>> {
+    ensure_lof_extension(&file)?;
+    let source = fs::read_to_string(&file)?;
+
+    let r1cs = crate::compile_dsl_to_r1cs(&source).map_err(|e| map_compiler_error(e, &source))?;
+    let stats = r1cs.statistics();
+
+    println!("{} {}", "Circuit statistics for".blue(), file.display());
+    println!("  Constraints:          {}", stats.constraints);
+    println!("    multiplications:    {}", stats.multiplications);
+    println!("    boolean checks:     {}", stats.boolean_constraints);
+    println!("    comparison gadgets: {}", stats.comparisons);
+    println!("  Public inputs:        {}", stats.public_inputs);
+    println!("  Witnesses:            {}", stats.witnesses);
+    println!("  Temporaries:          {}", stats.temporaries);
+
+    Ok(())
+}
+
 fn handle_parse(
     file: PathBuf,
     verbose: bool,
@@ -208,7 +542,7 @@ fn handle_parse(
     }
 
     let lexer = Lexer::new(&source);
-    let mut parser = LofParser::new(lexer);
+    let mut parser = LofParser::new(lexer.spanned());
 
     match parser.parse_program() {
         Ok(ast) => {
@@ -234,27 +568,38 @@ fn handle_parse(
 
 fn compile_r1cs(
     file: &Path,
+    source: &str,
     verbose: bool,
     generate_templates: bool,
     output_root: Option<&Path>,
+    compile_output: &CompileOutput,
 ) -> Result<PathBuf, Box<dyn std::error::Error>> {
     let base_name = file
         .file_stem()
         .and_then(|s| s.to_str())
         .ok_or_else(|| format!("Unable to determine base name for '{}'", file.display()))?;
-    let paths = prepare_project_paths(file, output_root)?;
-
-    run_compiler_pipeline(file, verbose)?;
-    handle_compilation_artifacts(file, base_name, &paths, generate_templates)?;
+    let paths = prepare_project_paths(file, output_root, compile_output.out_dir.as_deref())?;
+
+    run_compiler_pipeline(file, source, verbose, compile_output.opt_level)?;
+    handle_compilation_artifacts(
+        file,
+        source,
+        base_name,
+        &paths,
+        generate_templates,
+        compile_output.emit,
+    )?;
 
     Ok(paths.base)
 }
 
 fn compile_wasm(
     file: &Path,
+    source: &str,
     verbose: bool,
     output: Option<&Path>,
     skip_wasm: bool,
+    opt_level: OptLevel,
 ) -> Result<(), Box<dyn std::error::Error>> {
     if verbose {
         println!("{}", "WASM target selected".yellow());
@@ -262,7 +607,19 @@ fn compile_wasm(
 
     // Step 1: compile to R1CS/IR into a temporary workspace
     let temp_dir = tempfile::tempdir()?;
-    let temp_output = compile_r1cs(file, verbose, false, Some(temp_dir.path()))?;
+    let temp_output = compile_r1cs(
+        file,
+        source,
+        verbose,
+        false,
+        Some(temp_dir.path()),
+        &CompileOutput {
+            out_dir: None,
+            emit: Emit::Both,
+            out_base: None,
+            opt_level,
+        },
+    )?;
 
     let base_name = file
         .file_stem()
@@ -306,6 +663,7 @@ fn compile_wasm(
 fn prepare_project_paths(
     file: &Path,
     output_root: Option<&Path>,
+    out_dir: Option<&Path>,
 ) -> Result<ProjectPaths, Box<dyn std::error::Error>> {
     let file_dir = file.parent().unwrap_or_else(|| Path::new("."));
     let base = output_root
@@ -313,7 +671,9 @@ fn prepare_project_paths(
         .unwrap_or_else(|| file_dir.to_path_buf());
 
     let paths = ProjectPaths {
-        build_dir: base.join("build"),
+        build_dir: out_dir
+            .map(|p| p.to_path_buf())
+            .unwrap_or_else(|| base.join("build")),
         keys_dir: base.join("keys"),
         inputs_dir: base.join("inputs"),
         proofs_dir: base.join("proofs"),
@@ -338,19 +698,26 @@ fn prepare_project_paths(
     Ok(paths)
 }
 
-fn run_compiler_pipeline(file: &Path, verbose: bool) -> Result<(), Box<dyn std::error::Error>> {
+fn run_compiler_pipeline(
+    file: &Path,
+    source: &str,
+    verbose: bool,
+    opt_level: OptLevel,
+) -> Result<(), Box<dyn std::error::Error>> {
     info!("Processing file: {}", file.display());
     println!("{} {}", "Processing".blue(), file.display());
 
-    let source = fs::read_to_string(file)?;
-    let pipeline = CompilerPipeline::new(source, verbose);
+    let mut pipeline = CompilerPipeline::new(source.to_string(), verbose);
+    pipeline.opt_level = opt_level;
 
     if verbose {
         debug!("Starting compilation pipeline in verbose mode");
         println!("{}", "Starting compilation pipeline...".yellow());
     }
 
-    pipeline.run(file).map_err(map_compiler_error)?;
+    pipeline
+        .run(file)
+        .map_err(|err| map_compiler_error(err, &pipeline.source))?;
 
     info!("Compilation completed successfully");
     println!("{}", "Compilation successful".green());
@@ -358,40 +725,18 @@ fn run_compiler_pipeline(file: &Path, verbose: bool) -> Result<(), Box<dyn std::
     Ok(())
 }
 
-fn map_compiler_error(err: CompilerError) -> Box<dyn std::error::Error> {
-    match err {
-        CompilerError::LexerError(e) => {
-            error!("Lexer error: {}", e);
-            format!("Lexer error: {}", e).into()
-        }
-        CompilerError::ParserError(e) => {
-            error!("Parser error: {}", e);
-            format!("Parser error: {}", e).into()
-        }
-        CompilerError::TypeCheckerError(e) => {
-            error!("Type error: {:?}", e);
-            format!("Type error: {:?}", e).into()
-        }
-        CompilerError::NoProofs => {
-            error!("No proofs found in the source file");
-            "No proofs found in the source file".into()
-        }
-        CompilerError::R1CSError => {
-            error!("R1CS generation failed");
-            "R1CS error".into()
-        }
-        CompilerError::IRError(e) => {
-            error!("IR generation failed: {}", e);
-            format!("IR error: {}", e).into()
-        }
-    }
+fn map_compiler_error(err: CompilerError, source: &str) -> Box<dyn std::error::Error> {
+    error!("{}", err);
+    err.render_with_source(source).into()
 }
 
 fn handle_compilation_artifacts(
     source_file: &Path,
+    source: &str,
     base_name: &str,
     paths: &ProjectPaths,
     generate_templates: bool,
+    emit: Emit,
 ) -> Result<(), Box<dyn std::error::Error>> {
     let r1cs_file = source_file.with_extension("r1cs");
     if !r1cs_file.exists() {
@@ -400,23 +745,45 @@ fn handle_compilation_artifacts(
         return Ok(());
     }
 
-    let build_r1cs = paths.build_dir.join(format!("{}.r1cs", base_name));
-    copy_artifact(&r1cs_file, &build_r1cs, "Generated R1CS")?;
+    // read signal names before the R1CS is moved or discarded
+    let signal_names = if generate_templates {
+        Some(load_template_signals(source, &r1cs_file)?)
+    } else {
+        None
+    };
+
+    let sym_file = source_file.with_extension("sym");
+    if emit.r1cs() {
+        let build_r1cs = paths.build_dir.join(format!("{}.r1cs", base_name));
+        copy_artifact(&r1cs_file, &build_r1cs, "Generated R1CS")?;
+        if sym_file.exists() {
+            let build_sym = paths.build_dir.join(format!("{}.sym", base_name));
+            copy_artifact(&sym_file, &build_sym, "Generated symbols")?;
+        }
+    } else {
+        fs::remove_file(&r1cs_file)?;
+        if sym_file.exists() {
+            fs::remove_file(&sym_file)?;
+        }
+    }
 
     let ir_file = source_file.with_extension("ir");
     if ir_file.exists() {
-        let build_ir = paths.build_dir.join(format!("{}.ir", base_name));
-        copy_artifact(&ir_file, &build_ir, "Generated IR")?;
-    } else {
+        if emit.ir() {
+            let build_ir = paths.build_dir.join(format!("{}.ir", base_name));
+            copy_artifact(&ir_file, &build_ir, "Generated IR")?;
+        } else {
+            fs::remove_file(&ir_file)?;
+        }
+    } else if emit.ir() {
         warn!("IR file not found after compilation");
         println!("{} IR file not generated", "Warning:".yellow());
     }
 
-    if generate_templates {
-        let (public_inputs, witness_inputs) = load_signal_names(&build_r1cs)?;
+    if let Some((public_inputs, witness_inputs)) = signal_names {
         info!("Generating JSON templates for proof: {}", base_name);
         generate_json_templates(&paths.base, base_name, &public_inputs, &witness_inputs)?;
-        print_next_steps(&paths.base, base_name)?;
+        print_next_steps(paths, base_name)?;
     }
 
     Ok(())
@@ -438,6 +805,44 @@ fn load_signal_names(
     Ok((r1cs.public_inputs.clone(), r1cs.witnesses.clone()))
 }
 
+/// Public inputs come straight from the R1CS. Witnesses are limited to the ones
+/// declared in the source, since intermediate values are solved by the prover and
+/// a placeholder for them would be fed in as a real assignment.
+fn load_template_signals(
+    source: &str,
+    r1cs_path: &Path,
+) -> Result<(Vec<String>, Vec<String>), Box<dyn std::error::Error>> {
+    let (public_inputs, witnesses) = load_signal_names(r1cs_path)?;
+
+    let mut parser = LofParser::new(Lexer::new(source));
+    let ast = parser
+        .parse_program()
+        .map_err(|e| format!("Parser error: {}", e))?;
+
+    // every proof is written to the same artifact, so the last one is what the R1CS holds
+    let declared: HashSet<String> = ast
+        .iter()
+        .rev()
+        .find_map(|expr| match expr {
+            Expression::Proof { signals, .. } => Some(
+                signals
+                    .iter()
+                    .filter(|s| s.visibility == Visibility::Witness)
+                    .map(|s| s.name.clone())
+                    .collect(),
+            ),
+            _ => None,
+        })
+        .unwrap_or_default();
+
+    let witnesses = witnesses
+        .into_iter()
+        .filter(|name| declared.contains(name))
+        .collect();
+
+    Ok((public_inputs, witnesses))
+}
+
 fn generate_json_templates(
     base_dir: &Path,
     proof_name: &str,
@@ -470,12 +875,7 @@ fn write_template_file(
     signals: &[String],
     label: &str,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    let mut placeholders = BTreeMap::new();
-    for name in signals {
-        placeholders.insert(name.clone(), "0".to_string());
-    }
-
-    let json_body = json!(placeholders);
+    let json_body = json!(lofit::inputs::input_template(signals));
     let file_path = base_dir
         .join("inputs")
         .join(format!("{}_{}.json", proof_name, template_type));
@@ -489,10 +889,13 @@ fn write_template_file(
     Ok(())
 }
 
-fn print_next_steps(base_dir: &Path, proof_name: &str) -> Result<(), Box<dyn std::error::Error>> {
+fn print_next_steps(
+    project: &ProjectPaths,
+    proof_name: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
     println!("\n{}", "Next steps:".bold().green());
 
-    let paths = NextStepPaths::new(base_dir, proof_name);
+    let paths = NextStepPaths::new(project, proof_name);
 
     println!("1. Generate keys:");
     println!(
@@ -534,7 +937,8 @@ fn print_next_steps(base_dir: &Path, proof_name: &str) -> Result<(), Box<dyn std
     println!(
         "   {}",
         format!(
-            "lofit verify --verification-key {} --proof {} --public-inputs {}",
+            "lofit verify --input {} --verification-key {} --proof {} --public-inputs {}",
+            paths.r1cs.display(),
             paths.verification_key.display(),
             paths.proof_file.display(),
             paths.public_inputs.display()
@@ -555,20 +959,18 @@ struct NextStepPaths {
 }
 
 impl NextStepPaths {
-    fn new(base_dir: &Path, proof_name: &str) -> Self {
+    fn new(project: &ProjectPaths, proof_name: &str) -> Self {
         Self {
-            r1cs: base_dir.join("build").join(format!("{}.r1cs", proof_name)),
-            proving_key: base_dir.join("keys").join(format!("{}_pk.bin", proof_name)),
-            verification_key: base_dir.join("keys").join(format!("{}_vk.bin", proof_name)),
-            public_inputs: base_dir
-                .join("inputs")
+            r1cs: project.build_dir.join(format!("{}.r1cs", proof_name)),
+            proving_key: project.keys_dir.join(format!("{}_pk.bin", proof_name)),
+            verification_key: project.keys_dir.join(format!("{}_vk.bin", proof_name)),
+            public_inputs: project
+                .inputs_dir
                 .join(format!("{}_public.json", proof_name)),
-            witness_inputs: base_dir
-                .join("inputs")
+            witness_inputs: project
+                .inputs_dir
                 .join(format!("{}_witness.json", proof_name)),
-            proof_file: base_dir
-                .join("proofs")
-                .join(format!("{}_proof.bin", proof_name)),
+            proof_file: project.proofs_dir.join(format!("{}_proof.bin", proof_name)),
         }
     }
 }
@@ -0,0 +1,287 @@
+use crate::ast::Expression;
+use crate::ir::{
+    field_div, ir_constant_to_bigint, rotate_left, shift_right, xor3, IRExpr, IRInstruction,
+    FIELD_MODULUS,
+};
+use crate::ir_generator::IRGenerator;
+use num_bigint::BigInt;
+use std::collections::HashMap;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum InterpreterError {
+    #[error("Could not lower the proof to IR: {0}")]
+    IRGeneration(String),
+    #[error("No value provided for '{0}'")]
+    MissingValue(String),
+    #[error("Invalid constant: {0}")]
+    InvalidConstant(String),
+    #[error("Division by zero in the field")]
+    DivisionByZero,
+    #[error("Assertion {index} does not hold: {assertion}")]
+    AssertionFailed { index: usize, assertion: String },
+}
+
+/// The result of running a proof body: its `output` signals, and every signal the body saw.
+#[derive(Debug, Clone)]
+pub struct Evaluation {
+    pub outputs: HashMap<String, BigInt>,
+    pub witness: HashMap<String, BigInt>,
+}
+
+/// Runs the body of `proof` over `values` (its inputs and witnesses by signal name, with
+/// array elements as `name[i]`), once all assertions hold. Outputs left out of `values` are
+/// computed from the assertion that defines them, `assert out === ...`.
+pub fn evaluate_proof(
+    proof: &Expression,
+    values: &HashMap<String, BigInt>,
+) -> Result<Evaluation, InterpreterError> {
+    evaluate_proof_with(&mut IRGenerator::new(), proof, values)
+}
+
+/// Like [`evaluate_proof`], with a generator that already has the program's functions,
+/// components and constants registered.
+pub fn evaluate_proof_with(
+    generator: &mut IRGenerator,
+    proof: &Expression,
+    values: &HashMap<String, BigInt>,
+) -> Result<Evaluation, InterpreterError> {
+    let circuit = generator
+        .convert_proof(proof)
+        .map_err(|e| InterpreterError::IRGeneration(format!("{:?}", e)))?;
+
+    let mut env: HashMap<String, BigInt> = values
+        .iter()
+        .map(|(name, value)| (name.clone(), reduce(value.clone())))
+        .collect();
+
+    // assertions are numbered in source order, the way they read in the proof body;
+    // `assert a === b` lowers to a constraint followed by the matching equality check,
+    // which counts as the same assertion
+    let output_signals = circuit.output_signals();
+    let mut assertion_index = 0;
+    let mut last_constraint: Option<String> = None;
+    for instruction in &circuit.instructions {
+        match instruction {
+            IRInstruction::Assign { target, expr } => {
+                let value = eval(expr, &env)?;
+                env.insert(target.clone(), value);
+            }
+            IRInstruction::Assert { condition } => {
+                let restates_constraint = match condition {
+                    IRExpr::Equal(l, r) => {
+                        last_constraint.take() == Some(format!("{} === {}", render(l), render(r)))
+                    }
+                    _ => false,
+                };
+                if restates_constraint {
+                    continue;
+                }
+                last_constraint = None;
+                assertion_index += 1;
+                if eval(condition, &env)? == BigInt::from(0) {
+                    return Err(InterpreterError::AssertionFailed {
+                        index: assertion_index,
+                        assertion: format!("assert {}", render(condition)),
+                    });
+                }
+            }
+            IRInstruction::Constrain { left, right } => {
+                assertion_index += 1;
+                for (side, other) in [(left, right), (right, left)] {
+                    if let IRExpr::Variable(name) = side {
+                        if output_signals.contains(name) && !env.contains_key(name) {
+                            let value = eval(other, &env)?;
+                            env.insert(name.clone(), value);
+                        }
+                    }
+                }
+                let assertion = format!("{} === {}", render(left), render(right));
+                if eval(left, &env)? != eval(right, &env)? {
+                    return Err(InterpreterError::AssertionFailed {
+                        index: assertion_index,
+                        assertion,
+                    });
+                }
+                last_constraint = Some(assertion);
+            }
+        }
+    }
+
+    let outputs = output_signals
+        .into_iter()
+        .map(|name| {
+            let value = lookup(&env, &name)?;
+            Ok((name, value))
+        })
+        .collect::<Result<_, InterpreterError>>()?;
+
+    Ok(Evaluation {
+        outputs,
+        witness: env,
+    })
+}
+
+fn modulus() -> BigInt {
+    FIELD_MODULUS.parse().unwrap()
+}
+
+fn reduce(value: BigInt) -> BigInt {
+    let modulus = modulus();
+    ((value % &modulus) + &modulus) % &modulus
+}
+
+fn flag(value: bool) -> BigInt {
+    BigInt::from(value as u8)
+}
+
+fn lookup(env: &HashMap<String, BigInt>, name: &str) -> Result<BigInt, InterpreterError> {
+    env.get(name)
+        .cloned()
+        .ok_or_else(|| InterpreterError::MissingValue(name.to_string()))
+}
+
+fn eval(expr: &IRExpr, env: &HashMap<String, BigInt>) -> Result<BigInt, InterpreterError> {
+    let zero = BigInt::from(0);
+    let binary = |l: &IRExpr, r: &IRExpr| -> Result<(BigInt, BigInt), InterpreterError> {
+        Ok((eval(l, env)?, eval(r, env)?))
+    };
+
+    Ok(match expr {
+        IRExpr::Constant(s) => reduce(
+            ir_constant_to_bigint(s)
+                .map_err(|e| InterpreterError::InvalidConstant(e.to_string()))?,
+        ),
+        IRExpr::Variable(name) => lookup(env, name)?,
+        IRExpr::Add(l, r) => {
+            let (l, r) = binary(l, r)?;
+            reduce(l + r)
+        }
+        IRExpr::Sub(l, r) => {
+            let (l, r) = binary(l, r)?;
+            reduce(l - r)
+        }
+        IRExpr::Mul(l, r) => {
+            let (l, r) = binary(l, r)?;
+            reduce(l * r)
+        }
+        IRExpr::Div(l, r) => {
+            let (l, r) = binary(l, r)?;
+            field_div(&l, &r).ok_or(InterpreterError::DivisionByZero)?
+        }
+        // the remainder of the canonical representatives, as the R1CS lowering proves it
+        IRExpr::Mod(l, r) => {
+            let (l, r) = binary(l, r)?;
+            if r == zero {
+                return Err(InterpreterError::DivisionByZero);
+            }
+            l % r
+        }
+        IRExpr::Lt(l, r) => {
+            let (l, r) = binary(l, r)?;
+            flag(l < r)
+        }
+        IRExpr::Gt(l, r) => {
+            let (l, r) = binary(l, r)?;
+            flag(l > r)
+        }
+        IRExpr::Le(l, r) => {
+            let (l, r) = binary(l, r)?;
+            flag(l <= r)
+        }
+        IRExpr::Ge(l, r) => {
+            let (l, r) = binary(l, r)?;
+            flag(l >= r)
+        }
+        IRExpr::Equal(l, r) => {
+            let (l, r) = binary(l, r)?;
+            flag(l == r)
+        }
+        IRExpr::NotEqual(l, r) => {
+            let (l, r) = binary(l, r)?;
+            flag(l != r)
+        }
+        IRExpr::And(l, r) => {
+            let (l, r) = binary(l, r)?;
+            flag(l != zero && r != zero)
+        }
+        IRExpr::Or(l, r) => {
+            let (l, r) = binary(l, r)?;
+            flag(l != zero || r != zero)
+        }
+        IRExpr::Not(e) => flag(eval(e, env)? == zero),
+        IRExpr::ArrayIndex { array, index } => lookup(env, &format!("{}[{}]", array, index))?,
+        IRExpr::TupleField { tuple, index } => lookup(env, &format!("{}_{}", tuple, index))?,
+        IRExpr::RotateLeft {
+            value,
+            shift,
+            width,
+        } => rotate_left(&eval(value, env)?, *shift, *width),
+        IRExpr::ShiftRight {
+            value,
+            shift,
+            width,
+        } => shift_right(&eval(value, env)?, *shift, *width),
+        IRExpr::Xor3 { a, b, c, width } => {
+            xor3(&eval(a, env)?, &eval(b, env)?, &eval(c, env)?, *width)
+        }
+    })
+}
+
+fn render(expr: &IRExpr) -> String {
+    let binary = |l: &IRExpr, op: &str, r: &IRExpr| {
+        format!("{} {} {}", render_operand(l), op, render_operand(r))
+    };
+
+    match expr {
+        IRExpr::Constant(s) | IRExpr::Variable(s) => s.clone(),
+        IRExpr::Add(l, r) => binary(l, "+", r),
+        IRExpr::Sub(l, r) => binary(l, "-", r),
+        IRExpr::Mul(l, r) => binary(l, "*", r),
+        IRExpr::Div(l, r) => binary(l, "/", r),
+        IRExpr::Mod(l, r) => binary(l, "%", r),
+        IRExpr::Lt(l, r) => binary(l, "<", r),
+        IRExpr::Gt(l, r) => binary(l, ">", r),
+        IRExpr::Le(l, r) => binary(l, "<=", r),
+        IRExpr::Ge(l, r) => binary(l, ">=", r),
+        IRExpr::Equal(l, r) => binary(l, "==", r),
+        IRExpr::NotEqual(l, r) => binary(l, "!=", r),
+        IRExpr::And(l, r) => binary(l, "&&", r),
+        IRExpr::Or(l, r) => binary(l, "||", r),
+        IRExpr::Not(e) => format!("!{}", render_operand(e)),
+        IRExpr::ArrayIndex { array, index } => format!("{}[{}]", array, index),
+        IRExpr::TupleField { tuple, index } => format!("{}.{}", tuple, index),
+        IRExpr::RotateLeft {
+            value,
+            shift,
+            width,
+        } => format!("rotl({}, {}, {})", render(value), shift, width),
+        IRExpr::ShiftRight {
+            value,
+            shift,
+            width,
+        } => format!("shr({}, {}, {})", render(value), shift, width),
+        IRExpr::Xor3 { a, b, c, width } => format!(
+            "xor3({}, {}, {}, {})",
+            render(a),
+            render(b),
+            render(c),
+            width
+        ),
+    }
+}
+
+// nested operators are parenthesised so the rendered assertion reads unambiguously
+fn render_operand(expr: &IRExpr) -> String {
+    match expr {
+        IRExpr::Constant(_)
+        | IRExpr::Variable(_)
+        | IRExpr::ArrayIndex { .. }
+        | IRExpr::TupleField { .. }
+        | IRExpr::Not(_)
+        | IRExpr::RotateLeft { .. }
+        | IRExpr::ShiftRight { .. }
+        | IRExpr::Xor3 { .. } => render(expr),
+        _ => format!("({})", render(expr)),
+    }
+}
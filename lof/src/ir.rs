@@ -21,6 +21,7 @@ pub enum IRExpr {
     Sub(Box<IRExpr>, Box<IRExpr>),
     Mul(Box<IRExpr>, Box<IRExpr>),
     Div(Box<IRExpr>, Box<IRExpr>),
+    Mod(Box<IRExpr>, Box<IRExpr>),
 
     Lt(Box<IRExpr>, Box<IRExpr>),
     Gt(Box<IRExpr>, Box<IRExpr>),
@@ -33,9 +34,32 @@ pub enum IRExpr {
     Or(Box<IRExpr>, Box<IRExpr>),
     Not(Box<IRExpr>),
 
-    ArrayIndex { array: String, index: usize },
+    ArrayIndex {
+        array: String,
+        index: usize,
+    },
+
+    TupleField {
+        tuple: String,
+        index: usize,
+    },
 
-    TupleField { tuple: String, index: usize },
+    RotateLeft {
+        value: Box<IRExpr>,
+        shift: usize,
+        width: usize,
+    },
+    ShiftRight {
+        value: Box<IRExpr>,
+        shift: usize,
+        width: usize,
+    },
+    Xor3 {
+        a: Box<IRExpr>,
+        b: Box<IRExpr>,
+        c: Box<IRExpr>,
+        width: usize,
+    },
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -59,14 +83,36 @@ pub struct IRCircuit {
     pub functions: HashMap<String, (Vec<String>, Vec<IRInstruction>)>,
 }
 
+// `.ir` files are this header followed by the circuit as JSON; the version is bumped whenever
+// the instruction set changes, so stale files are rejected instead of misread
+const IR_MAGIC: &[u8; 8] = b"lof-ir\x00\x00";
+const IR_VERSION: u32 = 2;
+const IR_HEADER_LEN: usize = 8 + 4;
+
 impl IRCircuit {
+    /// The wires of the `output` signals, flattened the way `pub_inputs` lists them.
+    pub fn output_signals(&self) -> Vec<String> {
+        self.outputs
+            .iter()
+            .flat_map(|(name, typ)| match typ {
+                IRType::Array { size, .. } => {
+                    (0..*size).map(|i| format!("{}[{}]", name, i)).collect()
+                }
+                IRType::Tuple(fields) => (0..fields.len())
+                    .map(|i| format!("{}_{}", name, i))
+                    .collect(),
+                _ => vec![name.clone()],
+            })
+            .collect()
+    }
+
     pub fn write_to_file(&self, path: &std::path::Path) -> std::io::Result<()> {
         let file = std::fs::File::create(path)?;
         let mut writer = std::io::BufWriter::new(file);
 
-        writer.write_all(b"lof-ir\x00\x00")?;
+        writer.write_all(IR_MAGIC)?;
 
-        writer.write_all(&1u32.to_le_bytes())?;
+        writer.write_all(&IR_VERSION.to_le_bytes())?;
 
         let json = serde_json::to_string_pretty(self)
             .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
@@ -77,35 +123,85 @@ impl IRCircuit {
     }
 
     pub fn read_from_file(path: &std::path::Path) -> std::io::Result<Self> {
-        let file = std::fs::File::open(path)?;
-        let mut reader = std::io::BufReader::new(file);
+        let mut bytes = Vec::new();
+        std::fs::File::open(path)?.read_to_end(&mut bytes)?;
+        Self::from_bytes(&bytes)
+            .map_err(|e| std::io::Error::new(e.kind(), format!("{}: {}", path.display(), e)))
+    }
 
-        let mut magic = [0u8; 8];
-        reader.read_exact(&mut magic)?;
-        if &magic != b"lof-ir\x00\x00" {
+    pub fn from_bytes(bytes: &[u8]) -> std::io::Result<Self> {
+        let invalid =
+            |message: String| std::io::Error::new(std::io::ErrorKind::InvalidData, message);
+
+        if !bytes.starts_with(IR_MAGIC) {
+            let found = &bytes[..bytes.len().min(IR_MAGIC.len())];
+            return Err(invalid(format!(
+                "Invalid magic bytes - not a lof-ir file (found {:?})",
+                String::from_utf8_lossy(found)
+            )));
+        }
+        if bytes.len() < IR_HEADER_LEN {
             return Err(std::io::Error::new(
-                std::io::ErrorKind::InvalidData,
-                "Invalid magic bytes - not a lof-ir file",
+                std::io::ErrorKind::UnexpectedEof,
+                "Truncated IR header",
             ));
         }
 
-        let mut version = [0u8; 4];
-        reader.read_exact(&mut version)?;
-        if u32::from_le_bytes(version) != 1 {
-            return Err(std::io::Error::new(
-                std::io::ErrorKind::InvalidData,
-                "Unsupported IR version",
-            ));
+        let version = u32::from_le_bytes(bytes[8..12].try_into().unwrap());
+        if version != IR_VERSION {
+            return Err(invalid(format!(
+                "Unsupported IR version {} (expected {}); recompile the circuit with `lof compile`",
+                version, IR_VERSION
+            )));
         }
 
-        let mut json = String::new();
-        reader.read_to_string(&mut json)?;
+        serde_json::from_slice(&bytes[IR_HEADER_LEN..])
+            .map_err(|e| invalid(format!("Corrupt IR body: {}", e)))
+    }
+}
 
-        let circuit: IRCircuit = serde_json::from_str(&json)
-            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+// BN254 scalar field, the field every lof circuit is proven over
+pub const FIELD_MODULUS: &str =
+    "21888242871839275222246405745257275088548364400416034343698204186575808495617";
+
+// bits in the modulus, the widest a value can be decomposed into
+pub fn field_bits() -> usize {
+    FIELD_MODULUS.parse::<BigInt>().unwrap().bits() as usize
+}
 
-        Ok(circuit)
+// `xor3(a, b, c)` without a width works on 32-bit words, the SHA-256 word size
+pub const XOR3_DEFAULT_WIDTH: usize = 32;
+
+// `a / b` lowers to the constraint `b * q = a`, so the quotient a witness calculator must
+// produce is `a * b^(p-2)` in the field; integer division only agrees when b divides a
+pub fn field_div(numerator: &BigInt, denominator: &BigInt) -> Option<BigInt> {
+    let modulus: BigInt = FIELD_MODULUS.parse().unwrap();
+    let reduce = |value: &BigInt| ((value % &modulus) + &modulus) % &modulus;
+
+    let denominator = reduce(denominator);
+    if denominator == BigInt::from(0) {
+        return None;
     }
+    let inverse = denominator.modpow(&(&modulus - 2), &modulus);
+    Some(reduce(numerator) * inverse % &modulus)
+}
+
+// the bitwise builtins see the low `width` bits of a value; the R1CS rejects anything wider
+fn low_bits(value: &BigInt, width: usize) -> BigInt {
+    value & ((BigInt::from(1) << width) - 1)
+}
+
+pub fn rotate_left(value: &BigInt, shift: usize, width: usize) -> BigInt {
+    let value = low_bits(value, width);
+    low_bits(&(&value << shift), width) | (value >> (width - shift))
+}
+
+pub fn shift_right(value: &BigInt, shift: usize, width: usize) -> BigInt {
+    low_bits(value, width) >> shift
+}
+
+pub fn xor3(a: &BigInt, b: &BigInt, c: &BigInt, width: usize) -> BigInt {
+    low_bits(&(a ^ b ^ c), width)
 }
 
 pub fn bigint_to_ir_constant(value: &BigInt) -> String {
@@ -22,6 +22,9 @@ pub struct IRGenerator {
     function_defs: HashMap<String, (Vec<Parameter>, Expression)>,
     component_defs: HashMap<String, (Vec<Parameter>, Expression)>,
     variable_substitutions: HashMap<String, IRExpr>,
+    constants: HashMap<String, BigInt>,
+    // `Bits<N>` and other boolean array signals of the proof being converted, by width
+    bit_widths: HashMap<String, usize>,
 }
 
 impl IRGenerator {
@@ -31,6 +34,8 @@ impl IRGenerator {
             function_defs: HashMap::new(),
             component_defs: HashMap::new(),
             variable_substitutions: HashMap::new(),
+            constants: HashMap::new(),
+            bit_widths: HashMap::new(),
         }
     }
 
@@ -38,6 +43,21 @@ impl IRGenerator {
         self.function_defs.insert(name, (params, body));
     }
 
+    pub fn register_constant(
+        &mut self,
+        name: String,
+        value: &Expression,
+    ) -> Result<(), IRGenError> {
+        let folded = value.fold_constant(&self.constants).ok_or_else(|| {
+            IRGenError::UnsupportedExpression(format!(
+                "constant '{}' is not a constant expression",
+                name
+            ))
+        })?;
+        self.constants.insert(name, folded);
+        Ok(())
+    }
+
     pub fn register_component(&mut self, name: String, params: Vec<Parameter>, body: Expression) {
         self.component_defs.insert(name, (params, body));
     }
@@ -57,9 +77,16 @@ impl IRGenerator {
 
                 let mut pub_inputs = Vec::new();
                 let mut witnesses = Vec::new();
+                let mut outputs = Vec::new();
 
+                self.bit_widths.clear();
                 for signal in signals {
                     let ir_type = self.convert_type(&signal.typ)?;
+                    if let IRType::Array { element_type, size } = &ir_type {
+                        if matches!(**element_type, IRType::Bool) {
+                            self.bit_widths.insert(signal.name.clone(), *size);
+                        }
+                    }
 
                     match signal.visibility {
                         Visibility::Input => {
@@ -68,6 +95,11 @@ impl IRGenerator {
                         Visibility::Witness => {
                             witnesses.push((signal.name.clone(), ir_type));
                         }
+                        // outputs are public wires, kept in `pub_inputs` so the order matches the R1CS
+                        Visibility::Output => {
+                            self.flatten_signal_to_inputs(&signal.name, &ir_type, &mut pub_inputs);
+                            outputs.push((signal.name.clone(), ir_type));
+                        }
                     }
                 }
 
@@ -77,7 +109,7 @@ impl IRGenerator {
                     name: name.clone(),
                     pub_inputs,
                     witnesses,
-                    outputs: Vec::new(),
+                    outputs,
                     instructions: self.instructions.clone(),
                     functions: HashMap::new(),
                 };
@@ -123,9 +155,20 @@ impl IRGenerator {
         match typ {
             Type::Field { .. } => Ok(IRType::Field),
             Type::Bool { .. } => Ok(IRType::Bool),
+            Type::Nat => Ok(IRType::Field),
             Type::Array { element_type, size } => self.convert_array_type(element_type, *size),
             Type::Tuple(types) => self.convert_tuple_type(types),
             Type::Refined(base_type, _predicate) => self.convert_type(base_type),
+            Type::Bits(_) => match typ.bits_width() {
+                Some(width) => Ok(IRType::Array {
+                    element_type: Box::new(IRType::Bool),
+                    size: width,
+                }),
+                None => Err(IRGenError::TypeError(format!(
+                    "Unsupported type in IR: {:?}",
+                    typ
+                ))),
+            },
             _ => Err(IRGenError::TypeError(format!(
                 "Unsupported type in IR: {:?}",
                 typ
@@ -155,9 +198,13 @@ impl IRGenerator {
                 Ok(Some(IRExpr::Constant(bigint_to_ir_constant(&bigint))))
             }
 
+            Expression::BigNumber(n) => Ok(Some(IRExpr::Constant(bigint_to_ir_constant(n)))),
+
             Expression::Variable(name) => {
                 if let Some(subst) = self.variable_substitutions.get(name) {
                     Ok(Some(subst.clone()))
+                } else if let Some(value) = self.constants.get(name) {
+                    Ok(Some(IRExpr::Constant(bigint_to_ir_constant(value))))
                 } else {
                     Ok(Some(IRExpr::Variable(name.clone())))
                 }
@@ -176,6 +223,7 @@ impl IRGenerator {
                     Operator::Sub => IRExpr::Sub(Box::new(left_expr), Box::new(right_expr)),
                     Operator::Mul => IRExpr::Mul(Box::new(left_expr), Box::new(right_expr)),
                     Operator::Div => IRExpr::Div(Box::new(left_expr), Box::new(right_expr)),
+                    Operator::Mod => IRExpr::Mod(Box::new(left_expr), Box::new(right_expr)),
 
                     Operator::Lt => IRExpr::Lt(Box::new(left_expr), Box::new(right_expr)),
                     Operator::Gt => IRExpr::Gt(Box::new(left_expr), Box::new(right_expr)),
@@ -307,6 +355,39 @@ impl IRGenerator {
                     .cloned()
                     .or_else(|| self.component_defs.get(function).cloned());
 
+                if def.is_none() && function == "decompose" {
+                    return self.convert_decompose(arguments);
+                }
+
+                if def.is_none() && matches!(function.as_str(), "rotl" | "shr" | "xor3") {
+                    return self.convert_bitwise(function, arguments);
+                }
+
+                if def.is_none() {
+                    if let Some(assertion) = Expression::desugar_assertion(function, arguments) {
+                        return self.convert_expression_to_ir(&assertion);
+                    }
+                }
+
+                if def.is_none() && function == "compose" {
+                    return match arguments.as_slice() {
+                        [Expression::ArrayLiteral(elements)] => {
+                            let bits = elements
+                                .iter()
+                                .map(|element| {
+                                    self.convert_expression_to_ir(element)?.ok_or_else(|| {
+                                        IRGenError::UnsupportedExpression(
+                                            "Empty compose element".to_string(),
+                                        )
+                                    })
+                                })
+                                .collect::<Result<Vec<_>, _>>()?;
+                            Ok(Self::weighted_sum(bits))
+                        }
+                        _ => self.convert_decompose(arguments),
+                    };
+                }
+
                 if let Some((params, body)) = def {
                     let saved_substitutions = self.variable_substitutions.clone();
 
@@ -348,17 +429,28 @@ impl IRGenerator {
                 let mut accumulated: Option<IRExpr> = None;
 
                 for (idx, pattern_arm) in patterns.iter().enumerate() {
-                    let (branch_guard, bindings) =
+                    let (mut branch_guard, bindings) =
                         self.match_pattern_condition(&pattern_arm.pattern, &match_value)?;
 
-                    let selector =
-                        IRExpr::Mul(Box::new(remaining_selector.clone()), Box::new(branch_guard));
-
                     let saved_substitutions = self.variable_substitutions.clone();
                     for (name, binding_expr) in bindings {
                         self.variable_substitutions.insert(name, binding_expr);
                     }
 
+                    if let Some(guard) = &pattern_arm.guard {
+                        let guard_value =
+                            self.convert_expression_to_ir(guard)?.ok_or_else(|| {
+                                IRGenError::UnsupportedExpression(format!(
+                                    "Guard of match arm {} did not produce a value",
+                                    idx
+                                ))
+                            })?;
+                        branch_guard = IRExpr::Mul(Box::new(branch_guard), Box::new(guard_value));
+                    }
+
+                    let selector =
+                        IRExpr::Mul(Box::new(remaining_selector.clone()), Box::new(branch_guard));
+
                     let branch_value = self
                         .convert_expression_to_ir(&pattern_arm.body)?
                         .ok_or_else(|| {
@@ -393,6 +485,8 @@ impl IRGenerator {
                 }
             }
 
+            Expression::Ascription { value, .. } => self.convert_expression_to_ir(value),
+
             Expression::ArrayLiteral(_elements) => Err(IRGenError::UnsupportedExpression(
                 "Array literals are not yet supported in IR generation".to_string(),
             )),
@@ -404,6 +498,100 @@ impl IRGenerator {
         }
     }
 
+    // the bits recompose to the value itself, so only `Bits<N>` signals need the weighted sum.
+    // `compose` shares it: `compose(decompose(x))` is `x`, `compose(bits)` the weighted sum
+    fn convert_decompose(
+        &mut self,
+        arguments: &[Expression],
+    ) -> Result<Option<IRExpr>, IRGenError> {
+        let value = arguments.first().ok_or_else(|| {
+            IRGenError::UnsupportedExpression("decompose expects a value".to_string())
+        })?;
+        let width = match value {
+            Expression::Variable(name) => self.bit_widths.get(name).map(|w| (name, *w)),
+            _ => None,
+        };
+        let Some((name, width)) = width else {
+            return self.convert_expression_to_ir(value);
+        };
+
+        let bits = (0..width)
+            .map(|index| IRExpr::ArrayIndex {
+                array: name.clone(),
+                index,
+            })
+            .collect();
+        Ok(Self::weighted_sum(bits))
+    }
+
+    fn convert_bitwise(
+        &mut self,
+        function: &str,
+        arguments: &[Expression],
+    ) -> Result<Option<IRExpr>, IRGenError> {
+        let literal = |expr: &Expression| match expr {
+            Expression::Number(n) => usize::try_from(*n).map_err(|_| {
+                IRGenError::TypeError(format!("{} expects non-negative literals", function))
+            }),
+            _ => Err(IRGenError::TypeError(format!(
+                "{} expects literal shifts and widths",
+                function
+            ))),
+        };
+        let mut operand = |expr: &Expression| {
+            self.convert_expression_to_ir(expr)?
+                .map(Box::new)
+                .ok_or_else(|| {
+                    IRGenError::UnsupportedExpression(format!("Empty {} operand", function))
+                })
+        };
+
+        let expr = match (function, arguments) {
+            ("rotl", [value, shift, width]) => IRExpr::RotateLeft {
+                value: operand(value)?,
+                shift: literal(shift)?,
+                width: literal(width)?,
+            },
+            ("shr", [value, shift, width]) => IRExpr::ShiftRight {
+                value: operand(value)?,
+                shift: literal(shift)?,
+                width: literal(width)?,
+            },
+            ("xor3", [a, b, c]) | ("xor3", [a, b, c, _]) => IRExpr::Xor3 {
+                a: operand(a)?,
+                b: operand(b)?,
+                c: operand(c)?,
+                width: match arguments.get(3) {
+                    Some(width) => literal(width)?,
+                    None => crate::ir::XOR3_DEFAULT_WIDTH,
+                },
+            },
+            _ => {
+                return Err(IRGenError::TypeError(format!(
+                    "{} called with {} arguments",
+                    function,
+                    arguments.len()
+                )))
+            }
+        };
+        Ok(Some(expr))
+    }
+
+    fn weighted_sum(bits: Vec<IRExpr>) -> Option<IRExpr> {
+        bits.into_iter().enumerate().fold(None, |sum, (i, bit)| {
+            let term = IRExpr::Mul(
+                Box::new(IRExpr::Constant(bigint_to_ir_constant(
+                    &(BigInt::from(1) << i),
+                ))),
+                Box::new(bit),
+            );
+            Some(match sum {
+                Some(acc) => IRExpr::Add(Box::new(acc), Box::new(term)),
+                None => term,
+            })
+        })
+    }
+
     fn ir_constant(value: i64) -> IRExpr {
         let bigint = BigInt::from(value);
         IRExpr::Constant(bigint_to_ir_constant(&bigint))
@@ -1,7 +1,11 @@
+use num_bigint::BigInt;
+
 #[derive(Debug, Clone, PartialEq)]
 pub enum Token {
     Identifier(String),
     Number(i64),
+    // literals that do not fit in an i64, e.g. field moduli
+    BigNumber(BigInt),
     Keyword(Keyword),
     Symbol(Symbol),
     Comment(String),
@@ -16,6 +20,8 @@ pub enum Keyword {
     Type,
     Input,
     Witness,
+    Output,
+    Const,
     With,
     Field,
     Bits,
@@ -29,6 +35,9 @@ pub enum Keyword {
     Where,
     Let,
     In,
+    If,
+    Else,
+    For,
 }
 
 #[derive(Debug, Clone, PartialEq, Copy)]
@@ -56,6 +65,7 @@ pub enum Symbol {
     Minus,       // -
     Star,        // *
     Slash,       // /
+    Percent,     // %
 
     Range,      // ..
     Underscore, // _
@@ -71,6 +81,16 @@ pub enum Symbol {
     Or,        // ||
 }
 
+// where a token sits in the source: byte offsets `start..end`, plus 1-based line and column
+// of its first character
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+    pub line: usize,
+    pub column: usize,
+}
+
 pub struct Lexer {
     input: Vec<char>,
     position: usize,
@@ -88,6 +108,24 @@ impl Lexer {
         }
     }
 
+    // 1-based line and column of the next unread character
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
+    pub fn column(&self) -> usize {
+        self.column
+    }
+
+    // same tokens as the plain iterator, each paired with its span
+    pub fn spanned(self) -> SpannedTokens {
+        SpannedTokens {
+            lexer: self,
+            char_offset: 0,
+            byte_offset: 0,
+        }
+    }
+
     fn peek_token(&mut self) -> Token {
         let saved_position = self.position;
         let saved_line = self.line;
@@ -187,19 +225,22 @@ impl Lexer {
             '+' => self.advance_with(Token::Symbol(Symbol::Plus)),
             '-' => self.advance_with(Token::Symbol(Symbol::Minus)),
             '*' => self.advance_with(Token::Symbol(Symbol::Star)),
+            '%' => self.advance_with(Token::Symbol(Symbol::Percent)),
             '/' => {
                 if self.peek() == Some('/') {
                     self.position += 2;
                     self.column += 2;
 
                     let start = self.position;
-                    while self.position < self.input.len() && self.input[self.position] != '\n' {
+                    while self.position < self.input.len() && !self.at_line_break() {
                         self.position += 1;
                         self.column += 1;
                     }
 
                     let comment_text: String = self.input[start..self.position].iter().collect();
                     Token::Comment(comment_text)
+                } else if self.peek() == Some('*') {
+                    self.read_block_comment()
                 } else {
                     self.advance_with(Token::Symbol(Symbol::Slash))
                 }
@@ -223,6 +264,7 @@ impl Lexer {
                     panic!("Unexpected character '&' at {:?}", error_pos);
                 }
             }
+            '_' if self.starts_underscore_identifier() => self.read_identifier(),
             c if c.is_alphabetic() => self.read_identifier(),
             c if c.is_numeric() => self.read_number(),
             c => {
@@ -249,10 +291,11 @@ impl Lexer {
             "type" => Token::Keyword(Keyword::Type),
             "input" => Token::Keyword(Keyword::Input),
             "witness" => Token::Keyword(Keyword::Witness),
+            "output" => Token::Keyword(Keyword::Output),
+            "const" => Token::Keyword(Keyword::Const),
             "field" => Token::Keyword(Keyword::Field),
             "Field" => Token::Keyword(Keyword::Field),
             "Bits" => Token::Keyword(Keyword::Bits),
-            "bits" => Token::Keyword(Keyword::Bits),
             "array" => Token::Keyword(Keyword::Array),
             "Array" => Token::Keyword(Keyword::Array),
             "nat" => Token::Keyword(Keyword::Nat),
@@ -266,6 +309,9 @@ impl Lexer {
             "where" => Token::Keyword(Keyword::Where),
             "let" => Token::Keyword(Keyword::Let),
             "in" => Token::Keyword(Keyword::In),
+            "if" => Token::Keyword(Keyword::If),
+            "else" => Token::Keyword(Keyword::Else),
+            "for" => Token::Keyword(Keyword::For),
             "refined" => Token::Keyword(Keyword::Refined),
             "Refined" => Token::Keyword(Keyword::Refined),
 
@@ -275,13 +321,55 @@ impl Lexer {
 
     fn read_number(&mut self) -> Token {
         let start = self.position;
-        while self.position < self.input.len() && self.input[self.position].is_numeric() {
+        let start_pos = (self.line, self.column);
+
+        let radix = if self.current_char() == '0' && matches!(self.peek(), Some('x') | Some('X')) {
+            self.position += 2;
+            self.column += 2;
+            16
+        } else {
+            10
+        };
+
+        let digits_start = self.position;
+        while self.position < self.input.len()
+            && (self.input[self.position].is_digit(radix) || self.input[self.position] == '_')
+        {
             self.position += 1;
             self.column += 1;
         }
 
-        let number: String = self.input[start..self.position].iter().collect();
-        Token::Number(number.parse().unwrap())
+        let literal: String = self.input[start..self.position].iter().collect();
+        let digits: String = self.input[digits_start..self.position].iter().collect();
+
+        // underscores are only allowed as separators between digits
+        if digits.is_empty()
+            || digits.starts_with('_')
+            || digits.ends_with('_')
+            || digits.contains("__")
+        {
+            panic!(
+                "Malformed numeric literal '{}' at {:?}: underscores may only appear between digits",
+                literal, start_pos
+            );
+        }
+
+        let cleaned = digits.replace('_', "");
+        match i64::from_str_radix(&cleaned, radix) {
+            Ok(value) => Token::Number(value),
+            Err(e) => match BigInt::parse_bytes(cleaned.as_bytes(), radix) {
+                Some(value) => Token::BigNumber(value),
+                None => panic!(
+                    "Invalid numeric literal '{}' at {:?}: {}",
+                    literal, start_pos, e
+                ),
+            },
+        }
+    }
+
+    // `_5` and `_tmp` are identifiers, a lone `_` is the wildcard
+    fn starts_underscore_identifier(&self) -> bool {
+        matches!(self.peek(), Some(c) if c.is_alphanumeric() || c == '_')
     }
 
     fn current_char(&self) -> char {
@@ -304,6 +392,44 @@ impl Lexer {
         }
     }
 
+    fn at_line_break(&self) -> bool {
+        matches!(self.input[self.position], '\n' | '\r')
+    }
+
+    // `/* ... */`, which may span lines; they do not nest
+    fn read_block_comment(&mut self) -> Token {
+        let error_pos = (self.line, self.column);
+        self.position += 2;
+        self.column += 2;
+
+        let start = self.position;
+        while self.position + 1 < self.input.len()
+            && !(self.input[self.position] == '*' && self.input[self.position + 1] == '/')
+        {
+            match self.input[self.position] {
+                '\n' => {
+                    self.line += 1;
+                    self.column = 1;
+                }
+                '\r' if self.peek() != Some('\n') => {
+                    self.line += 1;
+                    self.column = 1;
+                }
+                '\r' => {}
+                _ => self.column += 1,
+            }
+            self.position += 1;
+        }
+        if self.position + 1 >= self.input.len() {
+            panic!("Unterminated block comment at {:?}", error_pos);
+        }
+
+        let comment_text: String = self.input[start..self.position].iter().collect();
+        self.position += 2;
+        self.column += 2;
+        Token::Comment(comment_text)
+    }
+
     fn advance_with(&mut self, token: Token) -> Token {
         self.position += 1;
         self.column += 1;
@@ -322,8 +448,14 @@ impl Lexer {
                     self.line += 1;
                     self.column = 1;
                 }
+                // `\r\n` counts once, on its `\n`; a lone `\r` is a line break of its own
+                '\r' if self.peek() == Some('\n') => {
+                    self.position += 1;
+                }
                 '\r' => {
                     self.position += 1;
+                    self.line += 1;
+                    self.column = 1;
                 }
                 _ => break,
             }
@@ -409,19 +541,22 @@ impl Iterator for Lexer {
             '+' => self.advance_with(Token::Symbol(Symbol::Plus)),
             '-' => self.advance_with(Token::Symbol(Symbol::Minus)),
             '*' => self.advance_with(Token::Symbol(Symbol::Star)),
+            '%' => self.advance_with(Token::Symbol(Symbol::Percent)),
             '/' => {
                 if self.peek() == Some('/') {
                     self.position += 2;
                     self.column += 2;
 
                     let start = self.position;
-                    while self.position < self.input.len() && self.input[self.position] != '\n' {
+                    while self.position < self.input.len() && !self.at_line_break() {
                         self.position += 1;
                         self.column += 1;
                     }
 
                     let comment_text: String = self.input[start..self.position].iter().collect();
                     Token::Comment(comment_text)
+                } else if self.peek() == Some('*') {
+                    self.read_block_comment()
                 } else {
                     self.advance_with(Token::Symbol(Symbol::Slash))
                 }
@@ -445,6 +580,7 @@ impl Iterator for Lexer {
                     panic!("Unexpected character '&' at {:?}", error_pos);
                 }
             }
+            '_' if self.starts_underscore_identifier() => self.read_identifier(),
             '_' => self.advance_with(Token::Symbol(Symbol::Underscore)),
             c if c.is_alphabetic() => self.read_identifier(),
             c if c.is_numeric() => self.read_number(),
@@ -456,3 +592,44 @@ impl Iterator for Lexer {
         Some(token)
     }
 }
+
+pub struct SpannedTokens {
+    lexer: Lexer,
+    // the lexer works on chars; this caches the byte offset of `char_offset` so converting
+    // positions stays linear in the input
+    char_offset: usize,
+    byte_offset: usize,
+}
+
+impl SpannedTokens {
+    fn byte_offset_of(&mut self, position: usize) -> usize {
+        self.byte_offset += self.lexer.input[self.char_offset..position]
+            .iter()
+            .map(|c| c.len_utf8())
+            .sum::<usize>();
+        self.char_offset = position;
+        self.byte_offset
+    }
+}
+
+impl Iterator for SpannedTokens {
+    type Item = (Token, Span);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.lexer.skip_whitespace();
+        let (line, column) = (self.lexer.line, self.lexer.column);
+        let start = self.byte_offset_of(self.lexer.position);
+
+        let token = self.lexer.next()?;
+        let end = self.byte_offset_of(self.lexer.position);
+        Some((
+            token,
+            Span {
+                start,
+                end,
+                line,
+                column,
+            },
+        ))
+    }
+}
@@ -1,5 +1,6 @@
 pub mod ast;
 pub mod cli;
+pub mod interpreter;
 pub mod ir;
 pub mod ir_generator;
 pub mod lexer;
@@ -9,18 +10,53 @@ pub mod r1cs;
 pub mod typechecker;
 
 pub use ast::Expression;
-pub use ir::{IRCircuit, IRExpr, IRInstruction, IRType};
+pub use interpreter::{evaluate_proof, evaluate_proof_with, Evaluation, InterpreterError};
+pub use ir::{field_div, IRCircuit, IRExpr, IRInstruction, IRType};
 pub use ir_generator::{IRGenError, IRGenerator};
-pub use pipeline::{CompilerError, CompilerPipeline};
-pub use r1cs::{R1CSError, R1CSGenerator};
+pub use pipeline::{
+    AnnotatedConstraint, CompilerError, CompilerPipeline, Diagnostic, DiagnosticKind,
+};
+pub use r1cs::{CircuitStats, OptLevel, OptimizationReport, R1CSError, R1CSGenerator};
 
 pub fn compile_dsl_to_r1cs(source: &str) -> Result<R1CSGenerator, CompilerError> {
-    let lexer = lexer::Lexer::new(source);
-    let mut parser = parser::Parser::new(lexer);
+    match compile_with_diagnostics(source) {
+        (Some(r1cs_generator), _) => Ok(r1cs_generator),
+        (None, diagnostics) => Err(diagnostics
+            .into_iter()
+            .find_map(|d| match d.kind {
+                DiagnosticKind::Error(e) => Some(e),
+                DiagnosticKind::Warning(_) => None,
+            })
+            .unwrap_or(CompilerError::R1CSError(None))),
+    }
+}
+
+/// Compiles `source` into the constraint system `lofit` proves over, entirely in memory.
+pub fn compile_dsl_to_constraint_system(
+    source: &str,
+) -> Result<lofit::ConstraintSystem, CompilerError> {
+    compile_dsl_to_r1cs(source).map(|r1cs_generator| r1cs_generator.to_constraint_system())
+}
+
+/// Compiles `source`, reporting failures and warnings as diagnostics that keep the
+/// typed `ParseError`/`TypeError` alongside their source position.
+pub fn compile_with_diagnostics(source: &str) -> (Option<R1CSGenerator>, Vec<Diagnostic>) {
+    match compile_program(source) {
+        Ok((r1cs_generator, warnings)) => (
+            Some(r1cs_generator),
+            warnings
+                .into_iter()
+                .map(|(warning, span)| pipeline::warning_diagnostic(warning, span))
+                .collect(),
+        ),
+        Err(e) => (None, vec![e.into_diagnostic(source)]),
+    }
+}
+
+type LocatedWarning = (typechecker::TypeWarning, Option<lexer::Span>);
 
-    let ast = parser
-        .parse_program()
-        .map_err(|e| CompilerError::ParserError(format!("{:?}", e)))?;
+fn compile_program(source: &str) -> Result<(R1CSGenerator, Vec<LocatedWarning>), CompilerError> {
+    let (ast, source_map) = pipeline::parse_source(source)?;
 
     if ast.is_empty() {
         return Err(CompilerError::NoProofs);
@@ -29,16 +65,30 @@ pub fn compile_dsl_to_r1cs(source: &str) -> Result<R1CSGenerator, CompilerError>
     let mut type_checker = typechecker::TypeChecker::new();
     type_checker
         .check_program(&ast)
-        .map_err(CompilerError::TypeCheckerError)?;
+        .map_err(|e| pipeline::type_error(&type_checker, &source_map, e))?;
 
     let mut r1cs_generator = r1cs::R1CSGenerator::new();
 
     for item in &ast {
-        if let Expression::FunctionDef {
-            name, params, body, ..
-        } = item
-        {
-            r1cs_generator.register_function(name.clone(), params.clone(), *body.clone());
+        match item {
+            Expression::FunctionDef {
+                name, params, body, ..
+            } => {
+                r1cs_generator.register_function(name.clone(), params.clone(), *body.clone());
+            }
+            Expression::Component {
+                name,
+                signals,
+                body,
+                ..
+            } => {
+                r1cs_generator.register_component(name.clone(), signals.clone(), *body.clone());
+            }
+            Expression::EnumDef { variants, .. } => r1cs_generator.register_enum(variants),
+            Expression::Const { name, value } => r1cs_generator
+                .register_constant(name.clone(), value)
+                .map_err(|_| CompilerError::R1CSError(None))?,
+            _ => {}
         }
     }
 
@@ -46,18 +96,18 @@ pub fn compile_dsl_to_r1cs(source: &str) -> Result<R1CSGenerator, CompilerError>
         if let Expression::Proof { .. } = proof {
             r1cs_generator
                 .convert_proof(proof)
-                .map_err(|_| CompilerError::R1CSError)?;
+                .map_err(|_| pipeline::r1cs_error(&r1cs_generator, &source_map))?;
+            r1cs_generator.dedup_constraints();
+            r1cs_generator
+                .validate_quadratic()
+                .map_err(|_| CompilerError::R1CSError(None))?;
         }
     }
 
-    Ok(r1cs_generator)
+    let warnings = pipeline::located_warnings(&type_checker, &source_map);
+    Ok((r1cs_generator, warnings))
 }
 
 pub fn parse_dsl(source: &str) -> Result<Vec<Expression>, CompilerError> {
-    let lexer = lexer::Lexer::new(source);
-    let mut parser = parser::Parser::new(lexer);
-
-    parser
-        .parse_program()
-        .map_err(|e| CompilerError::ParserError(format!("{:?}", e)))
+    pipeline::parse_source(source).map(|(ast, _)| ast)
 }
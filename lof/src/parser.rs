@@ -1,14 +1,94 @@
 use crate::ast::{
-    EnumVariant, Expression, GenericParam, MatchPattern, Operator, Parameter, Pattern, Signal,
-    Type, Visibility,
+    EnumVariant, Expression, GenericParam, MatchPattern, Operator, Parameter, Pattern, Refinement,
+    Signal, Site, Type, Visibility,
 };
-use crate::lexer::{Keyword, Symbol, Token};
+use crate::lexer::{Keyword, Span, Symbol, Token};
 use std::fmt;
 use std::iter::Peekable;
 use tracing::debug;
 
-pub struct Parser<T: Iterator<Item = Token>> {
+/// What the parser reads: bare tokens, or tokens paired with where they sit in the source
+/// (`Lexer::spanned`), which lets errors point at a line.
+pub trait SourceToken {
+    fn token(&self) -> &Token;
+    fn into_token(self) -> Token;
+    fn span(&self) -> Option<Span>;
+}
+
+impl SourceToken for Token {
+    fn token(&self) -> &Token {
+        self
+    }
+
+    fn into_token(self) -> Token {
+        self
+    }
+
+    fn span(&self) -> Option<Span> {
+        None
+    }
+}
+
+impl SourceToken for (Token, Span) {
+    fn token(&self) -> &Token {
+        &self.0
+    }
+
+    fn into_token(self) -> Token {
+        self.0
+    }
+
+    fn span(&self) -> Option<Span> {
+        Some(self.1)
+    }
+}
+
+pub struct Parser<T: Iterator>
+where
+    T::Item: SourceToken,
+{
     tokens: Peekable<T>,
+    // where the last token taken sits, for placing errors that are not about the next one
+    last_span: Option<Span>,
+    source_map: SourceMap,
+    // the proof or component whose signals are being declared
+    owner: String,
+}
+
+/// Where the statements and signal declarations of a parsed program sit, so later phases can
+/// report their errors there. Empty when the tokens carried no spans.
+#[derive(Debug, Default)]
+pub struct SourceMap {
+    statements: Vec<(Expression, Span)>,
+    signals: Vec<(String, String, Span)>,
+}
+
+impl SourceMap {
+    /// The span of the first of `sites` that names exactly one place in the program; a
+    /// statement repeated word for word elsewhere cannot be placed.
+    pub fn locate(&self, sites: &[Site]) -> Option<Span> {
+        sites.iter().find_map(|site| {
+            let mut spans: Vec<Span> = match site {
+                Site::Statement(statement) => self
+                    .statements
+                    .iter()
+                    .filter(|(recorded, _)| recorded == statement)
+                    .map(|(_, span)| *span)
+                    .collect(),
+                Site::Signal { owner, name } => self
+                    .signals
+                    .iter()
+                    .filter(|(o, n, _)| o == owner && n == name)
+                    .map(|(_, _, span)| *span)
+                    .collect(),
+            };
+            spans.dedup();
+            match spans.as_slice() {
+                [span] => Some(*span),
+                _ => None,
+            }
+        })
+    }
 }
 
 #[derive(Debug)]
@@ -17,26 +97,85 @@ pub enum ParseError {
     UnexpectedEOF,
     InvalidType,
     InvalidExpression,
+    // the input ended, or the next declaration began, before the closing `}`
+    Unterminated {
+        kind: &'static str,
+        name: String,
+        opened_at: Option<Span>,
+    },
 }
 
 type ParseResult<T> = Result<T, ParseError>;
 
-impl<T: Iterator<Item = Token>> Parser<T> {
+impl<T: Iterator> Parser<T>
+where
+    T::Item: SourceToken,
+{
     pub fn new(tokens: T) -> Self {
         Parser {
             tokens: tokens.peekable(),
+            last_span: None,
+            source_map: SourceMap::default(),
+            owner: String::new(),
         }
     }
 
+    pub fn into_source_map(self) -> SourceMap {
+        self.source_map
+    }
+
     fn peek(&mut self) -> Option<&Token> {
-        while matches!(self.tokens.peek(), Some(Token::Comment(_))) {
+        while matches!(
+            self.tokens.peek().map(SourceToken::token),
+            Some(Token::Comment(_))
+        ) {
             self.tokens.next();
         }
-        self.tokens.peek()
+        self.tokens.peek().map(SourceToken::token)
+    }
+
+    fn peek_span(&mut self) -> Option<Span> {
+        self.peek();
+        self.tokens.peek().and_then(SourceToken::span)
+    }
+
+    // every token is taken through here, so a comment is never mistaken for the token after it
+    fn next_token(&mut self) -> Option<Token> {
+        self.peek();
+        let token = self.tokens.next()?;
+        self.last_span = token.span().or(self.last_span);
+        Some(token.into_token())
+    }
+
+    /// Where parsing stopped with `error`: the unread token it names, the opening of an
+    /// unterminated declaration, or else the last token taken. `None` for unspanned tokens.
+    pub fn error_span(&mut self, error: &ParseError) -> Option<Span> {
+        match error {
+            ParseError::Unterminated { opened_at, .. } => *opened_at,
+            ParseError::UnexpectedToken(token) if self.peek() == Some(token) => self.peek_span(),
+            _ => self.last_span,
+        }
+    }
+
+    // a statement spans from its first token to its last, not counting the `;`
+    fn parse_statement(
+        &mut self,
+        parse: impl FnOnce(&mut Self) -> ParseResult<Expression>,
+    ) -> ParseResult<Expression> {
+        let start = self.peek_span();
+        let statement = parse(self)?;
+        if let (Some(start), Some(end)) = (start, self.last_span) {
+            let span = Span {
+                end: end.end,
+                ..start
+            };
+            self.source_map.statements.push((statement.clone(), span));
+        }
+        Ok(statement)
     }
 
     fn expect(&mut self, expected: Token) -> ParseResult<()> {
-        match self.tokens.next() {
+        match self.next_token() {
             Some(token) if token == expected => Ok(()),
             Some(token) => Err(ParseError::UnexpectedToken(token)),
             None => Err(ParseError::UnexpectedEOF),
@@ -58,15 +197,38 @@ impl<T: Iterator<Item = Token>> Parser<T> {
             Some(Token::Keyword(Keyword::Let)) => self.parse_function_definition(),
             Some(Token::Keyword(Keyword::Type)) => self.parse_type_or_enum_definition(),
             Some(Token::Keyword(Keyword::Enum)) => self.parse_enum_definition(),
+            Some(Token::Keyword(Keyword::Const)) => self.parse_const_definition(),
             Some(other) => Err(ParseError::UnexpectedToken(other.clone())),
             None => Err(ParseError::UnexpectedEOF),
         }
     }
 
+    fn parse_const_definition(&mut self) -> ParseResult<Expression> {
+        self.expect(Token::Keyword(Keyword::Const))?;
+
+        let name = match self.next_token() {
+            Some(Token::Identifier(name)) => name,
+            Some(token) => return Err(ParseError::UnexpectedToken(token)),
+            None => return Err(ParseError::UnexpectedEOF),
+        };
+
+        self.expect(Token::Symbol(Symbol::Equals))?;
+        let value = self.parse_expression()?;
+
+        if self.peek() == Some(&Token::Symbol(Symbol::Semi)) {
+            self.next_token();
+        }
+
+        Ok(Expression::Const {
+            name,
+            value: Box::new(value),
+        })
+    }
+
     fn parse_function_definition(&mut self) -> ParseResult<Expression> {
         self.expect(Token::Keyword(Keyword::Let))?;
 
-        let name = match self.tokens.next() {
+        let name = match self.next_token() {
             Some(Token::Identifier(name)) => name,
             Some(token) => return Err(ParseError::UnexpectedToken(token)),
             None => return Err(ParseError::UnexpectedEOF),
@@ -74,9 +236,9 @@ impl<T: Iterator<Item = Token>> Parser<T> {
 
         let mut params = Vec::new();
         while let Some(&Token::Symbol(Symbol::LParen)) = self.peek() {
-            self.tokens.next();
+            self.next_token();
 
-            let param_name = match self.tokens.next() {
+            let param_name = match self.next_token() {
                 Some(Token::Identifier(name)) => name,
                 Some(token) => return Err(ParseError::UnexpectedToken(token)),
                 None => return Err(ParseError::UnexpectedEOF),
@@ -92,8 +254,17 @@ impl<T: Iterator<Item = Token>> Parser<T> {
             });
         }
 
-        self.expect(Token::Symbol(Symbol::Colon))?;
-        let return_type = self.parse_type()?;
+        // a top-level `let x = 5` is not a function, constants are declared with `const`
+        let return_type = match self.peek() {
+            Some(Token::Symbol(Symbol::Colon)) => {
+                self.next_token();
+                Some(self.parse_type()?)
+            }
+            Some(token) if params.is_empty() => {
+                return Err(ParseError::UnexpectedToken(token.clone()))
+            }
+            _ => None,
+        };
 
         self.expect(Token::Symbol(Symbol::Equals))?;
 
@@ -110,7 +281,7 @@ impl<T: Iterator<Item = Token>> Parser<T> {
     fn parse_proof(&mut self) -> ParseResult<Expression> {
         self.expect(Token::Keyword(Keyword::Proof))?;
 
-        let name = match self.tokens.next() {
+        let name = match self.next_token() {
             Some(Token::Identifier(name)) => name,
             Some(token) => return Err(ParseError::UnexpectedToken(token)),
             None => return Err(ParseError::UnexpectedEOF),
@@ -122,17 +293,24 @@ impl<T: Iterator<Item = Token>> Parser<T> {
             vec![]
         };
 
+        let opened_at = self.peek_span();
         self.expect(Token::Symbol(Symbol::LBrace))?;
+        self.owner = name.clone();
+        let (signals, body) = self
+            .parse_proof_contents()
+            .map_err(|e| unterminated(e, "proof", &name, opened_at))?;
 
-        let mut signals = Vec::new();
-        while let Some(Token::Keyword(kw)) = self.peek() {
-            match kw {
-                Keyword::Input | Keyword::Witness => {
-                    signals.push(self.parse_signal()?);
-                }
-                _ => break,
-            }
-        }
+        Ok(Expression::Proof {
+            name,
+            generics,
+            signals,
+            body: Box::new(body),
+        })
+    }
+
+    // everything after a proof's `{`, up to and including its `}`
+    fn parse_proof_contents(&mut self) -> ParseResult<(Vec<Signal>, Expression)> {
+        let signals = self.parse_signals()?;
 
         let mut statements = Vec::new();
 
@@ -141,12 +319,12 @@ impl<T: Iterator<Item = Token>> Parser<T> {
                 break;
             }
 
-            let expr = self.parse_expression()?;
+            let expr = self.parse_statement(Self::parse_expression)?;
             statements.push(expr);
 
             // Handle optional semicolon
             if let Some(Token::Symbol(Symbol::Semi)) = self.peek() {
-                self.tokens.next();
+                self.next_token();
             }
 
             if let Some(Token::Symbol(Symbol::RBrace)) = self.peek() {
@@ -154,27 +332,26 @@ impl<T: Iterator<Item = Token>> Parser<T> {
             }
         }
 
-        let body = if statements.is_empty() {
+        if statements.is_empty() {
             return Err(ParseError::InvalidExpression);
-        } else if statements.len() == 1 {
-            statements.into_iter().next().unwrap()
-        } else {
-            let mut stmts = statements;
-            let final_expr = stmts.pop().map(Box::new);
-            Expression::Block {
-                statements: stmts,
-                final_expr,
-            }
-        };
+        }
+        let body = block_of(statements);
 
         self.expect(Token::Symbol(Symbol::RBrace))?;
+        Ok((signals, body))
+    }
 
-        Ok(Expression::Proof {
-            name,
-            generics,
-            signals,
-            body: Box::new(body),
-        })
+    fn parse_signals(&mut self) -> ParseResult<Vec<Signal>> {
+        let mut signals = Vec::new();
+        while let Some(Token::Keyword(kw)) = self.peek() {
+            match kw {
+                Keyword::Input | Keyword::Witness | Keyword::Output => {
+                    signals.push(self.parse_signal()?);
+                }
+                _ => break,
+            }
+        }
+        Ok(signals)
     }
 
     fn parse_block(&mut self) -> ParseResult<Expression> {
@@ -187,41 +364,27 @@ impl<T: Iterator<Item = Token>> Parser<T> {
             debug!("Block parsing token: {:?}", token);
 
             if token == &Token::Symbol(Symbol::RBrace) {
-                self.tokens.next();
+                self.next_token();
                 debug!("Exiting block on RBrace");
                 break;
             }
 
             match token {
                 Token::Keyword(Keyword::Let) => {
-                    let let_expr = self.parse_let_binding()?;
+                    let let_expr = self.parse_statement(Self::parse_let_binding)?;
                     statements.push(let_expr);
                 }
                 _ => {
-                    let expr = self.parse_expression()?;
+                    let expr = self.parse_statement(Self::parse_expression)?;
                     if let Some(Token::Symbol(Symbol::Semi)) = self.peek() {
-                        self.tokens.next();
+                        self.next_token();
                     }
                     statements.push(expr);
                 }
             };
         }
 
-        if statements.is_empty() {
-            Ok(Expression::Block {
-                statements: vec![],
-                final_expr: None,
-            })
-        } else if statements.len() == 1 {
-            Ok(statements.remove(0))
-        } else {
-            let mut stmts = statements;
-            let final_expr = stmts.pop().map(Box::new);
-            Ok(Expression::Block {
-                statements: stmts,
-                final_expr,
-            })
-        }
+        Ok(block_of(statements))
     }
 
     fn parse_generic_params(&mut self) -> ParseResult<Vec<GenericParam>> {
@@ -231,14 +394,14 @@ impl<T: Iterator<Item = Token>> Parser<T> {
         while let Some(token) = self.peek() {
             match token {
                 Token::Symbol(Symbol::RAngle) => {
-                    self.tokens.next();
+                    self.next_token();
                     break;
                 }
                 Token::Identifier(_) => {
                     params.push(self.parse_generic_param()?);
                     match self.peek() {
                         Some(Token::Symbol(Symbol::Comma)) => {
-                            self.tokens.next();
+                            self.next_token();
                         }
                         Some(Token::Symbol(Symbol::RAngle)) => continue,
                         _ => return Err(ParseError::InvalidType),
@@ -252,14 +415,14 @@ impl<T: Iterator<Item = Token>> Parser<T> {
     }
 
     fn parse_generic_param(&mut self) -> ParseResult<GenericParam> {
-        let name = match self.tokens.next() {
+        let name = match self.next_token() {
             Some(Token::Identifier(name)) => name,
             Some(token) => return Err(ParseError::UnexpectedToken(token)),
             None => return Err(ParseError::UnexpectedEOF),
         };
 
         let bound = if let Some(Token::Symbol(Symbol::Colon)) = self.peek() {
-            self.tokens.next();
+            self.next_token();
             Some(self.parse_type()?)
         } else {
             None
@@ -269,18 +432,24 @@ impl<T: Iterator<Item = Token>> Parser<T> {
     }
 
     fn parse_signal(&mut self) -> ParseResult<Signal> {
-        let visibility = match self.tokens.next() {
+        let visibility = match self.next_token() {
             Some(Token::Keyword(Keyword::Input)) => Visibility::Input,
             Some(Token::Keyword(Keyword::Witness)) => Visibility::Witness,
+            Some(Token::Keyword(Keyword::Output)) => Visibility::Output,
             Some(token) => return Err(ParseError::UnexpectedToken(token)),
             None => return Err(ParseError::UnexpectedEOF),
         };
 
-        let name = match self.tokens.next() {
+        let name = match self.next_token() {
             Some(Token::Identifier(name)) => name,
             Some(token) => return Err(ParseError::UnexpectedToken(token)),
             None => return Err(ParseError::UnexpectedEOF),
         };
+        if let Some(span) = self.last_span {
+            self.source_map
+                .signals
+                .push((self.owner.clone(), name.clone(), span));
+        }
 
         self.expect(Token::Symbol(Symbol::Colon))?;
         let typ = self.parse_type()?;
@@ -301,24 +470,29 @@ impl<T: Iterator<Item = Token>> Parser<T> {
 
         match next_token {
             Token::Keyword(Keyword::Field) => {
-                self.tokens.next();
+                self.next_token();
+                let refinement = if self.peek() == Some(&Token::Symbol(Symbol::LAngle)) {
+                    Some(self.parse_field_range()?)
+                } else {
+                    None
+                };
                 Ok(Type::Field {
                     constraint: crate::ast::ConstraintStatus::Unconstrained,
-                    refinement: None,
+                    refinement,
                 })
             }
             Token::Keyword(Keyword::Bool) => {
-                self.tokens.next();
+                self.next_token();
                 Ok(Type::Bool {
                     constraint: crate::ast::ConstraintStatus::Unconstrained,
                 })
             }
             Token::Keyword(Keyword::Nat) => {
-                self.tokens.next();
+                self.next_token();
                 Ok(Type::Nat)
             }
             Token::Keyword(Keyword::Refined) => {
-                self.tokens.next();
+                self.next_token();
                 self.expect(Token::Symbol(Symbol::LBrace))?;
                 let base_type = Box::new(self.parse_type()?);
                 self.expect(Token::Symbol(Symbol::Comma))?;
@@ -326,12 +500,16 @@ impl<T: Iterator<Item = Token>> Parser<T> {
                 self.expect(Token::Symbol(Symbol::RBrace))?;
                 Ok(Type::Refined(base_type, predicate))
             }
+            Token::Keyword(Keyword::Bits) => {
+                self.next_token();
+                self.parse_bits_width()
+            }
             Token::Keyword(Keyword::Array) => {
-                self.tokens.next();
+                self.next_token();
                 self.expect(Token::Symbol(Symbol::LAngle))?;
                 let element_type = Box::new(self.parse_type()?);
                 self.expect(Token::Symbol(Symbol::Comma))?;
-                let size = match self.tokens.next() {
+                let size = match self.next_token() {
                     Some(Token::Number(n)) => n as usize,
                     Some(other) => return Err(ParseError::UnexpectedToken(other)),
                     None => return Err(ParseError::UnexpectedEOF),
@@ -340,22 +518,26 @@ impl<T: Iterator<Item = Token>> Parser<T> {
                 Ok(Type::Array { element_type, size })
             }
             Token::Identifier(name) => {
-                self.tokens.next();
+                self.next_token();
+                // lowercase `bits` stays usable as a signal name, so it is only a type here
+                if name == "bits" && self.peek() == Some(&Token::Symbol(Symbol::LAngle)) {
+                    return self.parse_bits_width();
+                }
                 Ok(Type::Identifier(name))
             }
             Token::Symbol(Symbol::LParen) => {
-                self.tokens.next();
+                self.next_token();
                 let mut types = Vec::new();
 
                 if self.peek() == Some(&Token::Symbol(Symbol::RParen)) {
-                    self.tokens.next();
+                    self.next_token();
                     return Ok(Type::Tuple(types));
                 }
 
                 loop {
                     types.push(self.parse_type()?);
                     if self.peek() == Some(&Token::Symbol(Symbol::Comma)) {
-                        self.tokens.next();
+                        self.next_token();
                     } else {
                         break;
                     }
@@ -368,6 +550,17 @@ impl<T: Iterator<Item = Token>> Parser<T> {
         }
     }
 
+    fn parse_bits_width(&mut self) -> ParseResult<Type> {
+        self.expect(Token::Symbol(Symbol::LAngle))?;
+        let width = match self.next_token() {
+            Some(Token::Number(n)) => n,
+            Some(other) => return Err(ParseError::UnexpectedToken(other)),
+            None => return Err(ParseError::UnexpectedEOF),
+        };
+        self.expect(Token::Symbol(Symbol::RAngle))?;
+        Ok(Type::Bits(Box::new(Expression::Number(width))))
+    }
+
     fn parse_expression(&mut self) -> ParseResult<Expression> {
         match self.peek() {
             Some(Token::Keyword(Keyword::Match)) => self.parse_match_expression(),
@@ -377,7 +570,10 @@ impl<T: Iterator<Item = Token>> Parser<T> {
     }
 
     fn parse_binary_expression(&mut self) -> ParseResult<Expression> {
-        let mut expr_stack = vec![self.parse_unary_expression()?];
+        // each operand carries the right-hand side of the ordering comparison it ends with,
+        // so `a <= b < c` can be reduced to `(a <= b) && (b < c)`
+        let mut expr_stack: Vec<(Expression, Option<Expression>)> =
+            vec![(self.parse_unary_expression()?, None)];
         let mut op_stack = Vec::new();
 
         while let Some(token) = self.peek() {
@@ -395,10 +591,11 @@ impl<T: Iterator<Item = Token>> Parser<T> {
                 Token::Symbol(Symbol::Minus) => (Operator::Sub, 5),
                 Token::Symbol(Symbol::Star) => (Operator::Mul, 6),
                 Token::Symbol(Symbol::Slash) => (Operator::Div, 6),
+                Token::Symbol(Symbol::Percent) => (Operator::Mod, 6),
                 _ => break,
             };
 
-            self.tokens.next();
+            self.next_token();
 
             let right = self.parse_unary_expression()?;
 
@@ -414,7 +611,7 @@ impl<T: Iterator<Item = Token>> Parser<T> {
                     | Operator::Gt
                     | Operator::Lt => 4,
                     Operator::Add | Operator::Sub => 5,
-                    Operator::Mul | Operator::Div => 6,
+                    Operator::Mul | Operator::Div | Operator::Mod => 6,
                     _ => 0,
                 };
 
@@ -424,39 +621,81 @@ impl<T: Iterator<Item = Token>> Parser<T> {
 
                 let right_expr = expr_stack.pop().unwrap();
                 let left_expr = expr_stack.pop().unwrap();
-                expr_stack.push(Expression::BinaryOp {
-                    left: Box::new(left_expr),
-                    op: op_stack.pop().unwrap(),
-                    right: Box::new(right_expr),
-                });
+                let top_op = op_stack.pop().unwrap();
+                expr_stack.push(Self::reduce_binary(left_expr, top_op, right_expr.0));
             }
 
             op_stack.push(op);
-            expr_stack.push(right);
+            expr_stack.push((right, None));
         }
 
         while let Some(op) = op_stack.pop() {
             let right_expr = expr_stack.pop().unwrap();
             let left_expr = expr_stack.pop().unwrap();
-            expr_stack.push(Expression::BinaryOp {
-                left: Box::new(left_expr),
-                op,
-                right: Box::new(right_expr),
-            });
+            expr_stack.push(Self::reduce_binary(left_expr, op, right_expr.0));
+        }
+
+        Ok(expr_stack.pop().unwrap().0)
+    }
+
+    fn reduce_binary(
+        (left, chain_tail): (Expression, Option<Expression>),
+        op: Operator,
+        right: Expression,
+    ) -> (Expression, Option<Expression>) {
+        let is_ordering = matches!(
+            op,
+            Operator::Lt | Operator::Le | Operator::Gt | Operator::Ge
+        );
+        if !is_ordering {
+            return (
+                Expression::BinaryOp {
+                    left: Box::new(left),
+                    op,
+                    right: Box::new(right),
+                },
+                None,
+            );
         }
 
-        Ok(expr_stack.pop().unwrap())
+        let comparison = |left: Expression| Expression::BinaryOp {
+            left: Box::new(left),
+            op,
+            right: Box::new(right.clone()),
+        };
+        let expr = match chain_tail {
+            Some(middle) => Expression::BinaryOp {
+                left: Box::new(left),
+                op: Operator::And,
+                right: Box::new(comparison(middle)),
+            },
+            None => comparison(left),
+        };
+
+        (expr, Some(right))
     }
 
     fn parse_unary_expression(&mut self) -> ParseResult<Expression> {
         if let Some(Token::Symbol(Symbol::Not)) = self.peek() {
-            self.tokens.next();
+            self.next_token();
             let expr = self.parse_unary_expression()?;
             Ok(Expression::BinaryOp {
                 left: Box::new(Expression::Number(0)),
                 op: Operator::Not,
                 right: Box::new(expr),
             })
+        } else if let Some(Token::Symbol(Symbol::Minus)) = self.peek() {
+            self.next_token();
+            // literals are negated in place so the R1CS sees a single `ONE`-scaled term
+            match self.parse_unary_expression()? {
+                Expression::Number(n) => Ok(Expression::Number(-n)),
+                Expression::BigNumber(n) => Ok(Expression::BigNumber(-n)),
+                expr => Ok(Expression::BinaryOp {
+                    left: Box::new(Expression::Number(0)),
+                    op: Operator::Sub,
+                    right: Box::new(expr),
+                }),
+            }
         } else {
             self.parse_postfix_expression()
         }
@@ -466,7 +705,7 @@ impl<T: Iterator<Item = Token>> Parser<T> {
         let mut expr = self.parse_primary_expression()?;
 
         while let Some(Token::Symbol(Symbol::LBracket)) = self.peek() {
-            self.tokens.next();
+            self.next_token();
             let index = self.parse_expression()?;
             self.expect(Token::Symbol(Symbol::RBracket))?;
             expr = Expression::ArrayIndex {
@@ -485,13 +724,14 @@ impl<T: Iterator<Item = Token>> Parser<T> {
         };
 
         match next_token {
-            Token::Number(_) | Token::Identifier(_) | Token::Symbol(Symbol::LParen) => {
-                self.parse_simple_primary()
-            }
+            Token::Number(_)
+            | Token::BigNumber(_)
+            | Token::Identifier(_)
+            | Token::Symbol(Symbol::LParen) => self.parse_simple_primary(),
             Token::Symbol(Symbol::LBrace) => self.parse_block(),
             Token::Symbol(Symbol::LBracket) => self.parse_array_literal(),
             Token::Keyword(Keyword::Assert) => {
-                self.tokens.next();
+                self.next_token();
                 let condition = self.parse_expression()?;
                 Ok(Expression::Assert(Box::new(condition)))
             }
@@ -505,32 +745,40 @@ impl<T: Iterator<Item = Token>> Parser<T> {
 
         let mut elements = Vec::new();
 
-        if self.peek() == Some(&Token::Symbol(Symbol::RBracket)) {
-            self.tokens.next();
-            return Ok(Expression::ArrayLiteral(elements));
-        }
-
-        loop {
-            elements.push(self.parse_expression()?);
-            if self.peek() == Some(&Token::Symbol(Symbol::Comma)) {
-                self.tokens.next();
-            } else {
-                break;
+        if self.peek() != Some(&Token::Symbol(Symbol::RBracket)) {
+            loop {
+                elements.push(self.parse_expression()?);
+                if self.peek() == Some(&Token::Symbol(Symbol::Comma)) {
+                    self.next_token();
+                } else {
+                    break;
+                }
             }
         }
 
         self.expect(Token::Symbol(Symbol::RBracket))?;
-        Ok(Expression::ArrayLiteral(elements))
+        let literal = Expression::ArrayLiteral(elements);
+
+        if self.peek() == Some(&Token::Symbol(Symbol::Colon)) {
+            self.next_token();
+            let typ = self.parse_type()?;
+            return Ok(Expression::Ascription {
+                value: Box::new(literal),
+                typ,
+            });
+        }
+        Ok(literal)
     }
 
     fn parse_simple_primary(&mut self) -> ParseResult<Expression> {
-        match self.tokens.next() {
+        match self.next_token() {
             Some(Token::Number(n)) => Ok(Expression::Number(n)),
+            Some(Token::BigNumber(n)) => Ok(Expression::BigNumber(n)),
             Some(Token::Identifier(name)) => {
                 let mut expr = Expression::Variable(name.clone());
 
                 if self.peek() == Some(&Token::Symbol(Symbol::LParen)) {
-                    self.tokens.next();
+                    self.next_token();
 
                     let mut first_call_args = Vec::new();
 
@@ -539,7 +787,7 @@ impl<T: Iterator<Item = Token>> Parser<T> {
 
                         if self.peek() == Some(&Token::Symbol(Symbol::Comma)) {
                             while self.peek() == Some(&Token::Symbol(Symbol::Comma)) {
-                                self.tokens.next();
+                                self.next_token();
                                 first_call_args.push(self.parse_expression()?);
                             }
                             self.expect(Token::Symbol(Symbol::RParen))?;
@@ -560,7 +808,7 @@ impl<T: Iterator<Item = Token>> Parser<T> {
                     };
 
                     while self.peek() == Some(&Token::Symbol(Symbol::LParen)) {
-                        self.tokens.next();
+                        self.next_token();
                         let arg = self.parse_expression()?;
                         self.expect(Token::Symbol(Symbol::RParen))?;
 
@@ -595,20 +843,20 @@ impl<T: Iterator<Item = Token>> Parser<T> {
 
     fn parse_tuple_or_grouped_expr(&mut self) -> ParseResult<Expression> {
         if self.peek() == Some(&Token::Symbol(Symbol::RParen)) {
-            self.tokens.next();
+            self.next_token();
             return Ok(Expression::Tuple(vec![]));
         }
 
         let first_expr = self.parse_expression()?;
 
         if self.peek() == Some(&Token::Symbol(Symbol::Comma)) {
-            self.tokens.next();
+            self.next_token();
             let mut elements = vec![first_expr];
 
             while self.peek() != Some(&Token::Symbol(Symbol::RParen)) {
                 elements.push(self.parse_expression()?);
                 if self.peek() == Some(&Token::Symbol(Symbol::Comma)) {
-                    self.tokens.next();
+                    self.next_token();
                 } else {
                     break;
                 }
@@ -630,19 +878,31 @@ impl<T: Iterator<Item = Token>> Parser<T> {
         let mut patterns = Vec::new();
 
         if let Some(Token::Symbol(Symbol::Pipe)) = self.peek() {
-            self.tokens.next();
+            self.next_token();
         }
 
         while self.is_pattern_start() {
             let pattern = self.parse_pattern()?;
+
+            let guard = if let Some(Token::Keyword(Keyword::If)) = self.peek() {
+                self.next_token();
+                Some(Box::new(self.parse_expression()?))
+            } else {
+                None
+            };
+
             self.expect(Token::Symbol(Symbol::FatArrow))?;
 
             let body = Box::new(self.parse_expression()?);
 
-            patterns.push(MatchPattern { pattern, body });
+            patterns.push(MatchPattern {
+                pattern,
+                guard,
+                body,
+            });
 
             if let Some(Token::Symbol(Symbol::Pipe)) = self.peek() {
-                self.tokens.next();
+                self.next_token();
                 continue;
             } else if self.is_pattern_start() {
                 continue;
@@ -671,20 +931,20 @@ impl<T: Iterator<Item = Token>> Parser<T> {
     fn parse_pattern(&mut self) -> ParseResult<Pattern> {
         match self.peek().cloned() {
             Some(Token::Number(n)) => {
-                self.tokens.next();
+                self.next_token();
                 Ok(Pattern::Literal(n))
             }
             Some(Token::Identifier(name)) => {
-                self.tokens.next();
+                self.next_token();
                 if let Some(Token::Symbol(Symbol::LParen)) = self.peek() {
-                    self.tokens.next();
+                    self.next_token();
                     let mut subpatterns = Vec::new();
 
                     if self.peek() != Some(&Token::Symbol(Symbol::RParen)) {
                         loop {
                             subpatterns.push(self.parse_pattern()?);
                             if self.peek() == Some(&Token::Symbol(Symbol::Comma)) {
-                                self.tokens.next();
+                                self.next_token();
                             } else {
                                 break;
                             }
@@ -698,20 +958,20 @@ impl<T: Iterator<Item = Token>> Parser<T> {
                 }
             }
             Some(Token::Symbol(Symbol::Underscore)) => {
-                self.tokens.next();
+                self.next_token();
                 Ok(Pattern::Wildcard)
             }
             Some(Token::Symbol(Symbol::LParen)) => {
-                self.tokens.next();
+                self.next_token();
                 let mut patterns = Vec::new();
                 if self.peek() == Some(&Token::Symbol(Symbol::RParen)) {
-                    self.tokens.next();
+                    self.next_token();
                     return Ok(Pattern::Tuple(patterns));
                 }
                 loop {
                     patterns.push(self.parse_pattern()?);
                     if self.peek() == Some(&Token::Symbol(Symbol::Comma)) {
-                        self.tokens.next();
+                        self.next_token();
                     } else {
                         break;
                     }
@@ -727,7 +987,7 @@ impl<T: Iterator<Item = Token>> Parser<T> {
     fn parse_component(&mut self) -> ParseResult<Expression> {
         self.expect(Token::Keyword(Keyword::Component))?;
 
-        let name = match self.tokens.next() {
+        let name = match self.next_token() {
             Some(Token::Identifier(name)) => name,
             Some(token) => return Err(ParseError::UnexpectedToken(token)),
             None => return Err(ParseError::UnexpectedEOF),
@@ -739,21 +999,12 @@ impl<T: Iterator<Item = Token>> Parser<T> {
             vec![]
         };
 
+        let opened_at = self.peek_span();
         self.expect(Token::Symbol(Symbol::LBrace))?;
-
-        let mut signals = Vec::new();
-        while let Some(Token::Keyword(kw)) = self.peek() {
-            match kw {
-                Keyword::Input | Keyword::Witness => {
-                    signals.push(self.parse_signal()?);
-                }
-                _ => break,
-            }
-        }
-
-        let body = self.parse_expression()?;
-
-        self.expect(Token::Symbol(Symbol::RBrace))?;
+        self.owner = name.clone();
+        let (signals, body) = self
+            .parse_component_contents()
+            .map_err(|e| unterminated(e, "component", &name, opened_at))?;
 
         Ok(Expression::Component {
             name,
@@ -763,6 +1014,13 @@ impl<T: Iterator<Item = Token>> Parser<T> {
         })
     }
 
+    fn parse_component_contents(&mut self) -> ParseResult<(Vec<Signal>, Expression)> {
+        let signals = self.parse_signals()?;
+        let body = self.parse_expression()?;
+        self.expect(Token::Symbol(Symbol::RBrace))?;
+        Ok((signals, body))
+    }
+
     fn parse_let_binding(&mut self) -> ParseResult<Expression> {
         self.expect(Token::Keyword(Keyword::Let))?;
 
@@ -783,10 +1041,32 @@ impl<T: Iterator<Item = Token>> Parser<T> {
         })
     }
 
+    // `Field<min..max>`, an inclusive bound on the values a signal may take
+    fn parse_field_range(&mut self) -> ParseResult<Refinement> {
+        self.expect(Token::Symbol(Symbol::LAngle))?;
+        let min = self.parse_range_bound()?;
+        self.expect(Token::Symbol(Symbol::Range))?;
+        let max = self.parse_range_bound()?;
+        self.expect(Token::Symbol(Symbol::RAngle))?;
+
+        if min > max {
+            return Err(ParseError::InvalidType);
+        }
+        Ok(Refinement::Range { min, max })
+    }
+
+    fn parse_range_bound(&mut self) -> ParseResult<i64> {
+        match self.next_token() {
+            Some(Token::Number(n)) => Ok(n),
+            Some(other) => Err(ParseError::UnexpectedToken(other)),
+            None => Err(ParseError::UnexpectedEOF),
+        }
+    }
+
     fn parse_type_or_enum_definition(&mut self) -> ParseResult<Expression> {
         self.expect(Token::Keyword(Keyword::Type))?;
 
-        let name = match self.tokens.next() {
+        let name = match self.next_token() {
             Some(Token::Identifier(name)) => name,
             Some(token) => return Err(ParseError::UnexpectedToken(token)),
             None => return Err(ParseError::UnexpectedEOF),
@@ -795,12 +1075,12 @@ impl<T: Iterator<Item = Token>> Parser<T> {
         self.expect(Token::Symbol(Symbol::Equals))?;
 
         if let Some(Token::Symbol(Symbol::Pipe)) = self.peek() {
-            self.tokens.next(); // consume pipe
+            self.next_token(); // consume pipe
 
             let mut variants = Vec::new();
 
             loop {
-                let variant_name = match self.tokens.next() {
+                let variant_name = match self.next_token() {
                     Some(Token::Identifier(name)) => name,
                     Some(token) => return Err(ParseError::UnexpectedToken(token)),
                     None => return Err(ParseError::UnexpectedEOF),
@@ -809,13 +1089,13 @@ impl<T: Iterator<Item = Token>> Parser<T> {
                 let mut fields = Vec::new();
 
                 if let Some(Token::Symbol(Symbol::LParen)) = self.peek() {
-                    self.tokens.next();
+                    self.next_token();
 
                     if self.peek() != Some(&Token::Symbol(Symbol::RParen)) {
                         loop {
                             fields.push(self.parse_type()?);
                             if self.peek() == Some(&Token::Symbol(Symbol::Comma)) {
-                                self.tokens.next();
+                                self.next_token();
                             } else {
                                 break;
                             }
@@ -831,15 +1111,22 @@ impl<T: Iterator<Item = Token>> Parser<T> {
                 });
 
                 if let Some(Token::Symbol(Symbol::Pipe)) = self.peek() {
-                    self.tokens.next();
+                    self.next_token();
                 } else {
                     break;
                 }
             }
 
+            if let Some(Token::Symbol(Symbol::Semi)) = self.peek() {
+                self.next_token();
+            }
+
             Ok(Expression::EnumDef { name, variants })
         } else {
             let typ = self.parse_type()?;
+            if let Some(Token::Symbol(Symbol::Semi)) = self.peek() {
+                self.next_token();
+            }
             Ok(Expression::TypeAlias { name, typ })
         }
     }
@@ -847,7 +1134,7 @@ impl<T: Iterator<Item = Token>> Parser<T> {
     fn parse_enum_definition(&mut self) -> ParseResult<Expression> {
         self.expect(Token::Keyword(Keyword::Enum))?;
 
-        let name = match self.tokens.next() {
+        let name = match self.next_token() {
             Some(Token::Identifier(name)) => name,
             Some(token) => return Err(ParseError::UnexpectedToken(token)),
             None => return Err(ParseError::UnexpectedEOF),
@@ -858,7 +1145,7 @@ impl<T: Iterator<Item = Token>> Parser<T> {
         let mut variants = Vec::new();
 
         if let Some(Token::Symbol(Symbol::Pipe)) = self.peek() {
-            self.tokens.next();
+            self.next_token();
         }
 
         while let Some(token) = self.peek() {
@@ -866,7 +1153,7 @@ impl<T: Iterator<Item = Token>> Parser<T> {
                 break;
             }
 
-            let variant_name = match self.tokens.next() {
+            let variant_name = match self.next_token() {
                 Some(Token::Identifier(name)) => name,
                 Some(token) => return Err(ParseError::UnexpectedToken(token)),
                 None => return Err(ParseError::UnexpectedEOF),
@@ -875,13 +1162,13 @@ impl<T: Iterator<Item = Token>> Parser<T> {
             let mut fields = Vec::new();
 
             if let Some(Token::Symbol(Symbol::LParen)) = self.peek() {
-                self.tokens.next();
+                self.next_token();
 
                 if self.peek() != Some(&Token::Symbol(Symbol::RParen)) {
                     loop {
                         fields.push(self.parse_type()?);
                         if self.peek() == Some(&Token::Symbol(Symbol::Comma)) {
-                            self.tokens.next();
+                            self.next_token();
                         } else {
                             break;
                         }
@@ -897,7 +1184,7 @@ impl<T: Iterator<Item = Token>> Parser<T> {
             });
 
             if let Some(Token::Symbol(Symbol::Pipe)) = self.peek() {
-                self.tokens.next();
+                self.next_token();
             }
         }
 
@@ -907,6 +1194,53 @@ impl<T: Iterator<Item = Token>> Parser<T> {
     }
 }
 
+// only a trailing expression gives the block a value; when the last item is an assertion
+// the block is unit. a lone item stands for itself, unless it is a bare `===` whose own
+// type would otherwise leak out as the block's
+fn block_of(mut items: Vec<Expression>) -> Expression {
+    let lone_value = items.len() == 1
+        && !matches!(
+            items[0],
+            Expression::BinaryOp {
+                op: Operator::Assert,
+                ..
+            }
+        );
+    if lone_value {
+        return items.remove(0);
+    }
+
+    let final_expr = match items.last() {
+        Some(last) if !last.is_assertion() => items.pop().map(Box::new),
+        _ => None,
+    };
+    Expression::Block {
+        statements: items,
+        final_expr,
+    }
+}
+
+// running out of input inside a declaration, or meeting the next one, means its `}` is missing
+fn unterminated(
+    error: ParseError,
+    kind: &'static str,
+    name: &str,
+    opened_at: Option<Span>,
+) -> ParseError {
+    match error {
+        ParseError::UnexpectedEOF
+        | ParseError::UnexpectedToken(Token::EOF)
+        | ParseError::UnexpectedToken(Token::Keyword(Keyword::Proof | Keyword::Component)) => {
+            ParseError::Unterminated {
+                kind,
+                name: name.to_string(),
+                opened_at,
+            }
+        }
+        other => other,
+    }
+}
+
 impl fmt::Display for ParseError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
@@ -914,6 +1248,22 @@ impl fmt::Display for ParseError {
             ParseError::UnexpectedEOF => write!(f, "Unexpected end of file"),
             ParseError::InvalidType => write!(f, "Invalid type"),
             ParseError::InvalidExpression => write!(f, "Invalid expression"),
+            ParseError::Unterminated {
+                kind,
+                name,
+                opened_at: Some(span),
+            } => write!(
+                f,
+                "Unterminated {} '{}' starting at line {}: missing its closing '}}'",
+                kind, name, span.line
+            ),
+            ParseError::Unterminated { kind, name, .. } => {
+                write!(
+                    f,
+                    "Unterminated {} '{}': missing its closing '}}'",
+                    kind, name
+                )
+            }
         }
     }
 }
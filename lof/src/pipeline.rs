@@ -1,41 +1,250 @@
 use crate::ast::{Expression, Parameter, Visibility};
 use crate::ir::IRCircuit;
 use crate::ir_generator::IRGenerator;
-use crate::lexer::Lexer;
-use crate::parser::Parser;
-use crate::r1cs::R1CSGenerator;
-use crate::typechecker::{TypeChecker, TypeError};
+use crate::lexer::{Lexer, Span};
+use crate::parser::{ParseError, Parser, SourceMap};
+use crate::r1cs::{OptLevel, R1CSConstraint, R1CSGenerator};
+use crate::typechecker::{TypeChecker, TypeError, TypeWarning};
+use serde::Serialize;
+use std::fmt;
+use std::panic::{self, AssertUnwindSafe};
 use tracing::{debug, error, info, instrument, warn};
 
 #[derive(Debug)]
 pub enum CompilerError {
     LexerError(String),
-    ParserError(String),
-    TypeCheckerError(TypeError),
-    R1CSError,
+    ParserError(ParseError),
+    // the statement or declaration the error was raised in, where it is known
+    TypeCheckerError(Box<TypeError>, Option<Span>),
+    R1CSError(Option<Span>),
     IRError(String),
     NoProofs,
 }
 
+impl fmt::Display for CompilerError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CompilerError::LexerError(e) => write!(f, "Lexer error: {}", e),
+            CompilerError::ParserError(e) => write!(f, "Parser error: {}", e),
+            CompilerError::TypeCheckerError(e, _) => write!(f, "Type error: {}", e),
+            CompilerError::R1CSError(_) => write!(f, "R1CS error"),
+            CompilerError::IRError(e) => write!(f, "IR error: {}", e),
+            CompilerError::NoProofs => write!(f, "No proofs found in the source file"),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct Diagnostic {
+    pub severity: &'static str,
+    pub message: String,
+    pub line: Option<usize>,
+    pub col: Option<usize>,
+    pub code: &'static str,
+    // the typed error or warning, for consumers that want to match on it rather than on `code`
+    #[serde(skip)]
+    pub kind: DiagnosticKind,
+}
+
+#[derive(Debug)]
+pub enum DiagnosticKind {
+    Error(CompilerError),
+    Warning(TypeWarning),
+}
+
+impl CompilerError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            CompilerError::LexerError(_) => "lexer_error",
+            CompilerError::ParserError(_) => "parse_error",
+            CompilerError::TypeCheckerError(e, _) => e.code(),
+            CompilerError::R1CSError(_) => "r1cs_error",
+            CompilerError::IRError(_) => "ir_error",
+            CompilerError::NoProofs => "no_proofs",
+        }
+    }
+
+    /// Renders the error message followed by the offending source line with a caret under
+    /// the statement or declaration the error was raised in, when that is known.
+    pub fn render_with_source(&self, source: &str) -> String {
+        let snippet = match self {
+            CompilerError::TypeCheckerError(_, Some(span))
+            | CompilerError::R1CSError(Some(span)) => source_snippet(source, *span),
+            _ => None,
+        };
+
+        match snippet {
+            Some(snippet) => format!("{}\n\n{}", self, snippet),
+            None => self.to_string(),
+        }
+    }
+
+    /// Machine-readable form of the error; `line` and `col` are 1-based and absent
+    /// when the error cannot be tied to a place in `source`.
+    pub fn into_diagnostic(self, source: &str) -> Diagnostic {
+        let position = match &self {
+            CompilerError::LexerError(message) => lexer_error_position(message),
+            CompilerError::ParserError(_) => parse_error_position(source),
+            CompilerError::TypeCheckerError(_, span) | CompilerError::R1CSError(span) => {
+                span.map(|span| (span.line, span.column))
+            }
+            _ => None,
+        };
+
+        Diagnostic {
+            severity: "error",
+            message: self.to_string(),
+            line: position.map(|(line, _)| line),
+            col: position.map(|(_, col)| col),
+            code: self.code(),
+            kind: DiagnosticKind::Error(self),
+        }
+    }
+}
+
+/// A warning as a diagnostic, placed at `span`, the statement or declaration it was raised in.
+pub fn warning_diagnostic(warning: TypeWarning, span: Option<Span>) -> Diagnostic {
+    Diagnostic {
+        severity: "warning",
+        message: warning.to_string(),
+        line: span.map(|span| span.line),
+        col: span.map(|span| span.column),
+        code: warning.code(),
+        kind: DiagnosticKind::Warning(warning),
+    }
+}
+
+// lexer panics end their position as `at (line, col)`
+fn lexer_error_position(message: &str) -> Option<(usize, usize)> {
+    let start = message.find(" at (")? + " at (".len();
+    let end = start + message[start..].find(')')?;
+    let (line, col) = message[start..end].split_once(',')?;
+    Some((line.trim().parse().ok()?, col.trim().parse().ok()?))
+}
+
+// parsing is deterministic, so running the parser over `source` again stops at the same token
+fn parse_error_position(source: &str) -> Option<(usize, usize)> {
+    let mut parser = Parser::new(Lexer::new(source).spanned());
+    let error = parser.parse_program().err()?;
+    parser
+        .error_span(&error)
+        .map(|span| (span.line, span.column))
+}
+
+// underlines the span on its first line; the rest of a multi-line statement is left out
+fn source_snippet(source: &str, span: Span) -> Option<String> {
+    let line = source.lines().nth(span.line.checked_sub(1)?)?;
+    let width = source
+        .get(span.start..span.end)?
+        .lines()
+        .next()
+        .map_or(0, |first| first.chars().count())
+        .max(1);
+
+    let line_no = span.line.to_string();
+    let gutter = " ".repeat(line_no.len());
+    let caret_offset = span.column - 1;
+
+    Some(format!(
+        "{gutter}--> line {}, column {}\n{gutter} |\n{} | {}\n{gutter} | {}{}",
+        line_no,
+        span.column,
+        line_no,
+        line,
+        " ".repeat(caret_offset),
+        "^".repeat(width),
+        gutter = gutter
+    ))
+}
+
+// the lexer reports bad input by panicking, surface that as a regular error
+pub(crate) fn parse_source(source: &str) -> Result<(Vec<Expression>, SourceMap), CompilerError> {
+    let (parsed, source_map) = panic::catch_unwind(AssertUnwindSafe(|| {
+        let mut parser = Parser::new(Lexer::new(source).spanned());
+        let parsed = parser.parse_program();
+        (parsed, parser.into_source_map())
+    }))
+    .map_err(|payload| {
+        let message = payload
+            .downcast_ref::<String>()
+            .cloned()
+            .or_else(|| payload.downcast_ref::<&str>().map(|s| s.to_string()))
+            .unwrap_or_else(|| "Lexer failed".to_string());
+        CompilerError::LexerError(message)
+    })?;
+
+    Ok((parsed.map_err(CompilerError::ParserError)?, source_map))
+}
+
+pub(crate) fn type_error(
+    type_checker: &TypeChecker,
+    source_map: &SourceMap,
+    error: TypeError,
+) -> CompilerError {
+    CompilerError::TypeCheckerError(
+        Box::new(error),
+        source_map.locate(type_checker.error_sites()),
+    )
+}
+
+pub(crate) fn r1cs_error(r1cs_generator: &R1CSGenerator, source_map: &SourceMap) -> CompilerError {
+    CompilerError::R1CSError(source_map.locate(r1cs_generator.error_sites()))
+}
+
+// each warning with the statement or declaration it was raised in
+pub(crate) fn located_warnings(
+    type_checker: &TypeChecker,
+    source_map: &SourceMap,
+) -> Vec<(TypeWarning, Option<Span>)> {
+    type_checker
+        .warnings()
+        .iter()
+        .cloned()
+        .zip(
+            type_checker
+                .warning_sites()
+                .iter()
+                .map(|sites| source_map.locate(sites)),
+        )
+        .collect()
+}
+
+/// A constraint as lowered, with the source statement that produced it.
+#[derive(Debug, Clone)]
+pub struct AnnotatedConstraint {
+    pub constraint: R1CSConstraint,
+    pub source: String,
+}
+
 pub struct CompilerPipeline {
     pub source: String,
+    // downgrades unconstrained witnesses from an error to a warning; unsound, so off by default
+    pub allow_unconstrained: bool,
+    pub opt_level: OptLevel,
 }
 
 impl CompilerPipeline {
     pub fn new(source: String, _verbose: bool) -> Self {
-        Self { source }
+        Self {
+            source,
+            allow_unconstrained: false,
+            opt_level: OptLevel::O0,
+        }
     }
 
     #[instrument(skip(self, _source_path))]
-    pub fn type_check_only(&self, _source_path: &std::path::Path) -> Result<(), CompilerError> {
+    pub fn type_check_only(
+        &self,
+        _source_path: &std::path::Path,
+    ) -> Result<Vec<(TypeWarning, Option<Span>)>, CompilerError> {
         info!("Starting type checking process");
 
-        let ast = self.parse_program()?;
+        let (ast, source_map) = self.parse_program()?;
         if ast.is_empty() {
             error!("No proofs found in source code");
             return Err(CompilerError::NoProofs);
         }
-        self.type_check_ast(&ast)?;
+        let warnings = self.type_check_ast(&ast, &source_map)?;
         self.log_typecheck_summary(&ast);
 
         let proof_count = ast
@@ -47,50 +256,100 @@ impl CompilerPipeline {
             proof_count
         );
 
-        Ok(())
+        Ok(warnings)
+    }
+
+    /// Lowers every proof without writing artifacts, listing each proof's constraints
+    /// alongside the statement they came from.
+    pub fn annotated_constraints(
+        &self,
+    ) -> Result<Vec<(String, Vec<AnnotatedConstraint>)>, CompilerError> {
+        let (ast, source_map) = self.parse_program()?;
+        self.ensure_proofs_present(&ast)?;
+        self.type_check_ast(&ast, &source_map)?;
+
+        let mut r1cs_generator = R1CSGenerator::new();
+        self.register_items(&ast, &mut r1cs_generator, &mut IRGenerator::new())?;
+
+        let mut listings = Vec::new();
+        for proof in &ast {
+            if let Expression::Proof { name, .. } = proof {
+                r1cs_generator.convert_proof(proof).map_err(|e| {
+                    error!("R1CS generation failed for proof '{}': {}", name, e);
+                    r1cs_error(&r1cs_generator, &source_map)
+                })?;
+                r1cs_generator.dedup_constraints();
+
+                let constraints = r1cs_generator
+                    .constraints
+                    .iter()
+                    .enumerate()
+                    .map(|(index, constraint)| AnnotatedConstraint {
+                        constraint: constraint.clone(),
+                        source: r1cs_generator
+                            .constraint_source(index)
+                            .unwrap_or_default()
+                            .to_string(),
+                    })
+                    .collect();
+                listings.push((name.clone(), constraints));
+            }
+        }
+        Ok(listings)
     }
 
     #[instrument(skip(self, source_path))]
     pub fn run(&self, source_path: &std::path::Path) -> Result<(), CompilerError> {
         info!("Starting compilation process");
-        let ast = self.parse_program()?;
+        let (ast, source_map) = self.parse_program()?;
         self.ensure_proofs_present(&ast)?;
-        self.type_check_ast(&ast)?;
+        self.type_check_ast(&ast, &source_map)?;
 
         info!("Generating R1CS constraints and IR...");
         let mut r1cs_generator = R1CSGenerator::new();
         let mut ir_generator = IRGenerator::new();
-        self.register_items(&ast, &mut r1cs_generator, &mut ir_generator);
+        self.register_items(&ast, &mut r1cs_generator, &mut ir_generator)?;
 
         let file_stem = source_path
             .file_stem()
             .and_then(|s| s.to_str())
             .unwrap_or("output");
 
-        self.convert_proofs(source_path, file_stem, &ast, r1cs_generator, ir_generator)
+        self.convert_proofs(
+            source_path,
+            file_stem,
+            &ast,
+            &source_map,
+            r1cs_generator,
+            ir_generator,
+        )
     }
 
-    fn parse_program(&self) -> Result<Vec<Expression>, CompilerError> {
+    fn parse_program(&self) -> Result<(Vec<Expression>, SourceMap), CompilerError> {
         info!("Parsing source code");
-        let lexer = Lexer::new(&self.source);
-        let mut parser = Parser::new(lexer);
-        let ast = parser.parse_program().map_err(|e| {
-            error!("Parsing failed: {}", e);
-            CompilerError::ParserError(format!("{:?}", e))
-        })?;
+        let parsed = parse_source(&self.source).inspect_err(|e| error!("Parsing failed: {}", e))?;
         info!("Parsing completed successfully");
-        Ok(ast)
+        Ok(parsed)
     }
 
-    fn type_check_ast(&self, ast: &[Expression]) -> Result<(), CompilerError> {
+    fn type_check_ast(
+        &self,
+        ast: &[Expression],
+        source_map: &SourceMap,
+    ) -> Result<Vec<(TypeWarning, Option<Span>)>, CompilerError> {
         info!("Performing type checking...");
         let mut type_checker = TypeChecker::new();
-        type_checker.check_program(ast).map_err(|e| {
+        type_checker.set_allow_unconstrained(self.allow_unconstrained);
+        let (result, warnings) = type_checker.check_program_with_warnings(ast);
+        for warning in &warnings {
+            warn!("{}", warning);
+        }
+        result.map_err(|e| {
             error!("Type checking failed: {}", e);
-            CompilerError::TypeCheckerError(e)
+            type_error(&type_checker, source_map, e)
         })?;
         info!("Type checking completed successfully");
-        Ok(())
+        Ok(located_warnings(&type_checker, source_map))
     }
 
     fn ensure_proofs_present(&self, ast: &[Expression]) -> Result<(), CompilerError> {
@@ -118,7 +377,7 @@ impl CompilerPipeline {
         ast: &[Expression],
         r1cs_generator: &mut R1CSGenerator,
         ir_generator: &mut IRGenerator,
-    ) {
+    ) -> Result<(), CompilerError> {
         for item in ast {
             match item {
                 Expression::FunctionDef {
@@ -143,12 +402,29 @@ impl CompilerPipeline {
                             typ: s.typ.clone(),
                         })
                         .collect();
-                    r1cs_generator.register_function(name.clone(), params.clone(), *body.clone());
-                    ir_generator.register_component(name.clone(), params.clone(), *body.clone());
+                    r1cs_generator.register_component(name.clone(), signals.clone(), *body.clone());
+                    ir_generator.register_component(name.clone(), params, *body.clone());
+                }
+                Expression::EnumDef { name, variants } => {
+                    debug!("Registering enum '{}'", name);
+                    r1cs_generator.register_enum(variants);
+                }
+                Expression::Const { name, value } => {
+                    debug!("Registering constant '{}'", name);
+                    r1cs_generator
+                        .register_constant(name.clone(), value)
+                        .map_err(|e| {
+                            error!("Failed to register constant '{}': {:?}", name, e);
+                            CompilerError::R1CSError(None)
+                        })?;
+                    ir_generator
+                        .register_constant(name.clone(), value)
+                        .map_err(|e| CompilerError::IRError(format!("{:?}", e)))?;
                 }
                 _ => {}
             }
         }
+        Ok(())
     }
 
     fn convert_proofs(
@@ -156,6 +432,7 @@ impl CompilerPipeline {
         source_path: &std::path::Path,
         file_stem: &str,
         ast: &[Expression],
+        source_map: &SourceMap,
         mut r1cs_generator: R1CSGenerator,
         mut ir_generator: IRGenerator,
     ) -> Result<(), CompilerError> {
@@ -163,8 +440,14 @@ impl CompilerPipeline {
         for proof in ast.iter().filter(|e| matches!(e, Expression::Proof { .. })) {
             if let Expression::Proof { name, .. } = proof {
                 debug!("Converting proof '{}' to R1CS and IR", name);
-                total_constraints +=
-                    self.generate_r1cs(source_path, file_stem, name, proof, &mut r1cs_generator)?;
+                total_constraints += self.generate_r1cs(
+                    source_path,
+                    file_stem,
+                    name,
+                    proof,
+                    source_map,
+                    &mut r1cs_generator,
+                )?;
                 self.generate_ir(source_path, file_stem, name, proof, &mut ir_generator)?;
             }
         }
@@ -180,11 +463,17 @@ impl CompilerPipeline {
         file_stem: &str,
         proof_name: &str,
         proof: &Expression,
+        source_map: &SourceMap,
         r1cs_generator: &mut R1CSGenerator,
     ) -> Result<usize, CompilerError> {
         r1cs_generator.convert_proof(proof).map_err(|e| {
             error!("R1CS generation failed for proof '{}': {}", proof_name, e);
-            CompilerError::R1CSError
+            r1cs_error(r1cs_generator, source_map)
+        })?;
+        r1cs_generator.optimize(self.opt_level);
+        r1cs_generator.validate_quadratic().map_err(|e| {
+            error!("R1CS generation failed for proof '{}': {}", proof_name, e);
+            CompilerError::R1CSError(None)
         })?;
 
         let constraint_count = r1cs_generator.constraints.len();
@@ -221,10 +510,6 @@ impl CompilerPipeline {
     ) -> Result<(), CompilerError> {
         let constraint_count = r1cs_generator.constraints.len();
 
-        if constraint_count == 0 {
-            warn!("Proof '{}' generated no constraints", proof_name);
-        }
-
         debug!(
             "Statistics for proof '{}': pub_inputs={:?}, witnesses={:?}, constraints={}",
             proof_name, r1cs_generator.pub_inputs, r1cs_generator.witnesses, constraint_count
@@ -233,7 +518,11 @@ impl CompilerPipeline {
         info!("Writing R1CS file to: {}", r1cs_path.display());
         r1cs_generator.write_r1cs_file(r1cs_path).map_err(|e| {
             error!("Failed to write R1CS file: {}", e);
-            CompilerError::R1CSError
+            CompilerError::R1CSError(None)
+        })?;
+        r1cs_generator.write_sym_file(r1cs_path).map_err(|e| {
+            error!("Failed to write symbol file: {}", e);
+            CompilerError::R1CSError(None)
         })?;
 
         info!(
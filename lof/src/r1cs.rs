@@ -1,23 +1,36 @@
-use crate::ast::{Expression, Operator, Parameter, Pattern, Type, Visibility};
+use crate::ast::{
+    EnumVariant, Expression, Operator, Parameter, Pattern, Refinement, Signal, Site, Type,
+    Visibility,
+};
 use num_bigint::BigInt;
 use std::fmt;
 use std::{
-    collections::{HashMap, HashSet},
+    collections::{BTreeMap, HashMap, HashSet},
     io::{Read, Seek, Write},
     path::PathBuf,
 };
 use tracing::{debug, info, warn};
 
+// comparisons shift `left - right` by 2^252 before decomposing it into bits, unless both
+// operands have a known bit width, in which case the offset shrinks to 2^width
+const COMPARISON_OFFSET_BITS: usize = 252;
+
+const BITWISE_BUILTINS: &[&str] = &["rotl", "shr", "xor3"];
+
 #[derive(Debug)]
 pub enum R1CSError {
     UnsupportedOperation(String),
     InvalidFunction(String),
     InvalidArgument(String),
-    NonQuadratic,
+    NonQuadratic(usize),
     InvalidExpression,
     FileError,
     TypeError(String),
     UnknownVariable(String),
+    NoConstraints(String),
+    RecursionLimitExceeded(String),
+    UnsatisfiableConstant(String),
+    NonAffineOperand(String),
 }
 
 #[derive(Debug, Clone)]
@@ -37,17 +50,77 @@ pub struct R1CSContext {
     pub variables: HashMap<String, Type>,
 }
 
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CircuitStats {
+    pub constraints: usize,
+    pub multiplications: usize,
+    pub boolean_constraints: usize,
+    pub comparisons: usize,
+    pub public_inputs: usize,
+    pub witnesses: usize,
+    pub temporaries: usize,
+}
+
+// calls nested deeper than this are treated as runaway recursion
+pub const DEFAULT_MAX_INLINE_DEPTH: usize = 64;
+
+// -O0 only drops exact duplicates, which keeps the constraints in the shape the lowering
+// produced; higher levels rewrite the system and remove compiler temporaries
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub enum OptLevel {
+    #[default]
+    O0,
+    // constant folding, copy propagation and dead temporaries
+    O1,
+    // plus merging temporaries defined by the same product
+    O2,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OptimizationReport {
+    pub constraints_before: usize,
+    pub constraints_after: usize,
+    pub witnesses_before: usize,
+    pub witnesses_after: usize,
+}
+
+// the function, component, enum and constant definitions and `max_inline_depth` belong to
+// the session and are shared by every proof lowered with the generator; everything else is
+// per-proof and starts over in `convert_proof`. temporaries are named after their proof, so
+// proofs lowered one after another never share a wire even though the counter restarts
 pub struct R1CSGenerator {
     pub constraints: Vec<R1CSConstraint>,
     pub temp_var_counter: usize,
+    // name of the proof being lowered, appended to temporaries so circuits can be combined
+    pub temp_scope: String,
     pub symbol_map: HashMap<String, usize>,
     pub variable_substitutions: HashMap<String, LinearCombination>,
     pub pub_inputs: Vec<String>,
     pub witnesses: Vec<String>,
     pub context: R1CSContext,
     pub function_defs: HashMap<String, (Vec<Parameter>, Expression)>,
+    pub component_defs: HashMap<String, (Vec<Signal>, Expression)>,
     pub arrays: HashMap<String, Vec<String>>,
     pub boolean_vars: HashSet<String>,
+    // wires the prover fills in that only the constraint using them pins down
+    hints: HashSet<String>,
+    // signal -> bits it has been decomposed into, so repeated comparisons reuse one range check
+    pub range_checked: HashMap<String, usize>,
+    // (signal, width) -> weighted bits, so decomposing the same signal again reuses its bits
+    pub decompositions: HashMap<(String, usize), LinearCombination>,
+    pub enum_constructors: HashMap<String, (usize, usize)>,
+    pub enum_values: HashMap<String, Vec<String>>,
+    pub constants: HashMap<String, BigInt>,
+    pub max_inline_depth: usize,
+    // functions and components currently being inlined, innermost last
+    inline_stack: Vec<String>,
+    // wire index of every input and witness, fixed by `finalize_layout`
+    wire_indices: HashMap<String, usize>,
+    // source statement each constraint was lowered from, parallel to `constraints`
+    constraint_sources: Vec<String>,
+    current_source: Option<String>,
+    // where the error being returned was raised, innermost first
+    error_sites: Vec<Site>,
 }
 
 impl Default for R1CSGenerator {
@@ -61,6 +134,7 @@ impl R1CSGenerator {
         Self {
             constraints: Vec::new(),
             temp_var_counter: 0,
+            temp_scope: String::new(),
             symbol_map: HashMap::new(),
             variable_substitutions: HashMap::new(),
             pub_inputs: Vec::new(),
@@ -69,8 +143,21 @@ impl R1CSGenerator {
                 variables: HashMap::new(),
             },
             function_defs: HashMap::new(),
+            component_defs: HashMap::new(),
             arrays: HashMap::new(),
             boolean_vars: HashSet::new(),
+            hints: HashSet::new(),
+            range_checked: HashMap::new(),
+            decompositions: HashMap::new(),
+            enum_constructors: HashMap::new(),
+            enum_values: HashMap::new(),
+            constants: HashMap::new(),
+            max_inline_depth: DEFAULT_MAX_INLINE_DEPTH,
+            inline_stack: Vec::new(),
+            wire_indices: HashMap::new(),
+            constraint_sources: Vec::new(),
+            current_source: None,
+            error_sites: Vec::new(),
         }
     }
 
@@ -78,9 +165,45 @@ impl R1CSGenerator {
         self.function_defs.insert(name, (params, body));
     }
 
+    pub fn register_component(&mut self, name: String, signals: Vec<Signal>, body: Expression) {
+        self.component_defs.insert(name, (signals, body));
+    }
+
+    pub fn register_enum(&mut self, variants: &[EnumVariant]) {
+        for (tag, variant) in variants.iter().enumerate() {
+            self.enum_constructors
+                .insert(variant.name.clone(), (tag, variant.fields.len()));
+        }
+    }
+
+    pub fn register_constant(&mut self, name: String, value: &Expression) -> Result<(), R1CSError> {
+        let folded = value.fold_constant(&self.constants).ok_or_else(|| {
+            R1CSError::InvalidArgument(format!("constant '{}' is not a constant expression", name))
+        })?;
+        self.constants.insert(name, folded);
+        Ok(())
+    }
+
+    /// A fresh generator that shares only this one's session state: its registered functions,
+    /// components, enums and constants, and its inline depth limit.
+    pub fn new_scoped(&self) -> Self {
+        Self {
+            function_defs: self.function_defs.clone(),
+            component_defs: self.component_defs.clone(),
+            enum_constructors: self.enum_constructors.clone(),
+            constants: self.constants.clone(),
+            max_inline_depth: self.max_inline_depth,
+            ..Self::new()
+        }
+    }
+
     fn reset_for_new_proof(&mut self) {
         self.constraints.clear();
+        self.constraint_sources.clear();
+        self.current_source = None;
+        self.error_sites.clear();
         self.temp_var_counter = 0;
+        self.temp_scope.clear();
         self.symbol_map.clear();
         self.variable_substitutions.clear();
         self.pub_inputs.clear();
@@ -88,6 +211,46 @@ impl R1CSGenerator {
         self.context.variables.clear();
         self.arrays.clear();
         self.boolean_vars.clear();
+        self.hints.clear();
+        self.range_checked.clear();
+        self.decompositions.clear();
+        self.enum_values.clear();
+        self.inline_stack.clear();
+        self.wire_indices.clear();
+    }
+
+    // ONE is wire 0, then the public inputs sorted by name, then the named witnesses sorted by
+    // name, then temporaries and bits in the order they were created. none of it depends on
+    // the order signals were declared or lowered, so indices survive unrelated compiler changes
+    pub fn finalize_layout(&mut self) {
+        self.pub_inputs.sort_by(|a, b| wire_name_order(a, b));
+        let (mut named, generated): (Vec<String>, Vec<String>) = self
+            .witnesses
+            .drain(..)
+            .partition(|w| !is_generated_witness(w));
+        named.sort_by(|a, b| wire_name_order(a, b));
+        named.extend(generated);
+        self.witnesses = named;
+
+        self.wire_indices = self
+            .pub_inputs
+            .iter()
+            .chain(&self.witnesses)
+            .enumerate()
+            .map(|(index, name)| (name.clone(), index + 1))
+            .collect();
+    }
+
+    pub fn wire_index(&self, name: &str) -> Option<usize> {
+        if name == "ONE" {
+            return Some(0);
+        }
+        self.wire_indices.get(name).copied()
+    }
+
+    // the proof last converted, recorded in the r1cs header
+    pub fn circuit_name(&self) -> Option<&str> {
+        Some(self.temp_scope.as_str()).filter(|name| !name.is_empty())
     }
 
     pub fn write_r1cs_file(&self, source_path: &std::path::Path) -> std::io::Result<u64> {
@@ -108,11 +271,19 @@ impl R1CSGenerator {
         let mut writer = std::io::BufWriter::new(file);
 
         writer.write_all(b"lof-r1cs")?;
-        writer.write_all(&1u32.to_le_bytes())?;
+        writer.write_all(&lofit::r1cs::R1CS_VERSION.to_le_bytes())?;
+
+        let modulus = field_modulus().to_bytes_le().1;
+        writer.write_all(&(modulus.len() as u32).to_le_bytes())?;
+        writer.write_all(&modulus)?;
+        let name = self.circuit_name().unwrap_or_default();
+        writer.write_all(&(name.len() as u32).to_le_bytes())?;
+        writer.write_all(name.as_bytes())?;
 
         writer.write_all(&(self.pub_inputs.len() as u32).to_le_bytes())?;
         writer.write_all(&(self.witnesses.len() as u32).to_le_bytes())?;
         writer.write_all(&(self.constraints.len() as u32).to_le_bytes())?;
+        writer.write_all(&(self.declared_witness_count() as u32).to_le_bytes())?;
 
         for input in &self.pub_inputs {
             writer.write_all(&(input.len() as u32).to_le_bytes())?;
@@ -141,14 +312,65 @@ impl R1CSGenerator {
         Ok(metadata.len())
     }
 
+    // the layout puts the user's witnesses ahead of every wire the compiler generates, and the
+    // typechecker keeps the generated names out of user code, so the prefix is exact
+    fn declared_witness_count(&self) -> usize {
+        self.witnesses
+            .iter()
+            .take_while(|name| !is_generated_witness(name))
+            .count()
+    }
+
+    // the wires and constraints `write_r1cs_file` serializes, for proving without a disk round-trip
+    pub fn to_constraint_system(&self) -> lofit::ConstraintSystem {
+        let convert = |lc: &LinearCombination| lofit::r1cs::LinearCombination {
+            terms: lc
+                .significant_terms()
+                .map(|(var, coeff)| (self.get_variable_index(var) as u32, coeff.clone()))
+                .collect(),
+        };
+
+        lofit::ConstraintSystem {
+            name: self.circuit_name().map(str::to_string),
+            public_inputs: self.pub_inputs.clone(),
+            witnesses: self.witnesses.clone(),
+            declared_witness_count: Some(self.declared_witness_count()),
+            constraints: self
+                .constraints
+                .iter()
+                .map(|constraint| lofit::r1cs::Constraint {
+                    a: convert(&constraint.a),
+                    b: convert(&constraint.b),
+                    c: convert(&constraint.c),
+                })
+                .collect(),
+        }
+    }
+
+    // companion `<stem>.sym` listing `wire_index,signal_name` for every wire in r1cs order
+    pub fn write_sym_file(&self, source_path: &std::path::Path) -> std::io::Result<PathBuf> {
+        let sym_path = source_path.with_extension("sym");
+        info!("Writing symbol file to: {}", sym_path.display());
+
+        let file = std::fs::File::create(&sym_path)?;
+        let mut writer = std::io::BufWriter::new(file);
+        writeln!(writer, "0,ONE")?;
+        for name in self.pub_inputs.iter().chain(&self.witnesses) {
+            writeln!(writer, "{},{}", self.get_variable_index(name), name)?;
+        }
+        writer.flush()?;
+
+        Ok(sym_path)
+    }
+
     fn write_linear_combination<W: Write + Seek>(
         &self,
         writer: &mut W,
         lc: &LinearCombination,
     ) -> std::io::Result<()> {
-        writer.write_all(&(lc.terms.len() as u32).to_le_bytes())?;
+        writer.write_all(&(lc.significant_terms().count() as u32).to_le_bytes())?;
 
-        for (var, coeff) in &lc.terms {
+        for (var, coeff) in lc.significant_terms() {
             let idx = self.get_variable_index(var);
             writer.write_all(&(idx as u32).to_le_bytes())?;
 
@@ -160,8 +382,14 @@ impl R1CSGenerator {
         Ok(())
     }
 
+    // temporaries are prefixed with the proof they belong to, `Proof::t_<n>`; `::` cannot
+    // appear in an identifier, so they never collide with a signal or another proof's wires
     fn new_temp_var(&mut self) -> String {
-        let var = format!("t_{}", self.temp_var_counter);
+        let var = if self.temp_scope.is_empty() {
+            format!("t_{}", self.temp_var_counter)
+        } else {
+            format!("{}::t_{}", self.temp_scope, self.temp_var_counter)
+        };
         self.temp_var_counter += 1;
         var
     }
@@ -178,11 +406,476 @@ impl R1CSGenerator {
                         (var.to_string(), BigInt::from(-1)),
                     ],
                 },
-                c: LinearCombination { terms: vec![] },
+                c: LinearCombination::zero(),
             });
         }
     }
 
+    /// The source statement the constraint at `index` was lowered from, as long as the
+    /// constraints are still in their lowered shape (`-O0`).
+    pub fn constraint_source(&self, index: usize) -> Option<&str> {
+        match self.constraint_sources.get(index) {
+            Some(source) if self.constraint_sources.len() == self.constraints.len() => Some(source),
+            _ => None,
+        }
+    }
+
+    /// Where the last failed conversion was raised, innermost first; see `SourceMap::locate`.
+    pub fn error_sites(&self) -> &[Site] {
+        &self.error_sites
+    }
+
+    // constraints emitted while lowering `expr` are tagged with it, unless a statement nested
+    // inside it claims them first. a failure is reported at `expr` too
+    fn with_source<T>(
+        &mut self,
+        expr: &Expression,
+        convert: impl FnOnce(&mut Self) -> Result<T, R1CSError>,
+    ) -> Result<T, R1CSError> {
+        let source = match expr {
+            Expression::Let { pattern, value, .. } => format!("let {} = {}", pattern, value),
+            _ => expr.to_string(),
+        };
+        self.tag_new_constraints();
+        let outer = self.current_source.replace(source);
+        let result = convert(self);
+        self.tag_new_constraints();
+        self.current_source = outer;
+        if result.is_err() {
+            self.error_sites.push(Site::Statement(expr.clone()));
+        }
+        result
+    }
+
+    // a signal of the proof being lowered, `temp_scope`, that failed to lower
+    fn declaration_failed(&mut self, signal: &Signal) {
+        self.error_sites.push(Site::Signal {
+            owner: self.temp_scope.clone(),
+            name: signal.name.clone(),
+        });
+    }
+
+    fn tag_new_constraints(&mut self) {
+        let source = self.current_source.clone().unwrap_or_default();
+        self.constraint_sources
+            .resize(self.constraints.len(), source);
+    }
+
+    // drops constraints whose normalized A/B/C match an earlier one; repeated gadgets such as
+    // `decompose` on the same variable re-emit identical bit constraints. temps get fresh names,
+    // so constraints over different temps never compare equal
+    pub fn dedup_constraints(&mut self) -> usize {
+        let before = self.constraints.len();
+        let mut seen = HashSet::new();
+        let keep: Vec<bool> = self
+            .constraints
+            .iter()
+            .map(|constraint| {
+                seen.insert((
+                    constraint.a.normalized(),
+                    constraint.b.normalized(),
+                    constraint.c.normalized(),
+                ))
+            })
+            .collect();
+        let mut kept = keep.iter();
+        self.constraints.retain(|_| *kept.next().unwrap());
+        if self.constraint_sources.len() == keep.len() {
+            let mut kept = keep.iter();
+            self.constraint_sources.retain(|_| *kept.next().unwrap());
+        }
+
+        let removed = before - self.constraints.len();
+        if removed > 0 {
+            info!(
+                "Removed {} duplicate constraints ({} -> {})",
+                removed,
+                before,
+                self.constraints.len()
+            );
+        }
+        removed
+    }
+
+    pub fn optimize(&mut self, level: OptLevel) -> OptimizationReport {
+        let constraints_before = self.constraints.len();
+        let witnesses_before = self.witnesses.len();
+
+        self.dedup_constraints();
+        if level >= OptLevel::O1 {
+            // rewritten constraints no longer come from a single statement
+            self.constraint_sources.clear();
+            loop {
+                let mut changed = self.fold_constants();
+                changed |= self.propagate_copies();
+                if level >= OptLevel::O2 {
+                    changed |= self.merge_common_products();
+                }
+                changed |= self.dedup_constraints() > 0;
+                if !changed {
+                    break;
+                }
+            }
+            self.remove_dead_witnesses();
+            self.finalize_layout();
+        }
+
+        let report = OptimizationReport {
+            constraints_before,
+            constraints_after: self.constraints.len(),
+            witnesses_before,
+            witnesses_after: self.witnesses.len(),
+        };
+        if level > OptLevel::O0 {
+            info!(
+                "Optimization -{:?}: {} -> {} constraints, {} -> {} witnesses",
+                level,
+                report.constraints_before,
+                report.constraints_after,
+                report.witnesses_before,
+                report.witnesses_after
+            );
+        }
+        report
+    }
+
+    // `t * 1 = c` pins a temporary to a constant, which is substituted everywhere; constraints
+    // left with only constants are dropped when they hold. failing ones are kept so the
+    // circuit stays unsatisfiable
+    fn fold_constants(&mut self) -> bool {
+        let modulus = field_modulus();
+        let mut changed = false;
+
+        while let Some((temp, value)) = self.constraints.iter().find_map(|constraint| {
+            let [(temp, coeff)] = constraint.a.terms.as_slice() else {
+                return None;
+            };
+            (lofit::r1cs::is_computed_witness(temp)
+                && *coeff == BigInt::from(1)
+                && is_one(&constraint.b)
+                && constraint.c.is_constant()
+                && !self.is_decomposition_target(temp))
+            .then(|| (temp.clone(), constant_value(&constraint.c)))
+        }) {
+            debug!("Folding '{}' to the constant {}", temp, value);
+            self.substitute(
+                &temp,
+                &LinearCombination {
+                    terms: vec![("ONE".to_string(), value)],
+                },
+            );
+            changed = true;
+        }
+
+        let before = self.constraints.len();
+        self.constraints.retain(|constraint| {
+            let constant = constraint.a.is_constant()
+                && constraint.b.is_constant()
+                && constraint.c.is_constant();
+            let holds = || {
+                let lhs = constant_value(&constraint.a) * constant_value(&constraint.b);
+                ((lhs - constant_value(&constraint.c)) % &modulus) == BigInt::from(0)
+            };
+            !(constant && holds())
+        });
+        changed || self.constraints.len() != before
+    }
+
+    // `u * 1 = v` between two wires makes one of them a copy; a temporary is replaced by the
+    // other side (a declared signal, or the older temporary) and the constraint goes away
+    fn propagate_copies(&mut self) -> bool {
+        let mut changed = false;
+
+        while let Some((index, from, to)) =
+            self.constraints
+                .iter()
+                .enumerate()
+                .find_map(|(index, constraint)| {
+                    let ([(u, u_coeff)], [(v, v_coeff)]) =
+                        (constraint.a.terms.as_slice(), constraint.c.terms.as_slice())
+                    else {
+                        return None;
+                    };
+                    if u == v
+                        || u == "ONE"
+                        || v == "ONE"
+                        || *u_coeff != BigInt::from(1)
+                        || *v_coeff != BigInt::from(1)
+                        || !is_one(&constraint.b)
+                    {
+                        return None;
+                    }
+                    let (from, to) = self.copy_direction(u, v)?;
+                    Some((index, from, to))
+                })
+        {
+            debug!("Replacing copy '{}' with '{}'", from, to);
+            self.constraints.remove(index);
+            self.substitute(
+                &from,
+                &LinearCombination {
+                    terms: vec![(to, BigInt::from(1))],
+                },
+            );
+            changed = true;
+        }
+        changed
+    }
+
+    // the temporary to drop and the wire that replaces it
+    fn copy_direction(&self, u: &str, v: &str) -> Option<(String, String)> {
+        let position = |var: &str| self.witnesses.iter().position(|w| w == var);
+        let replaceable = |var: &str| lofit::r1cs::is_computed_witness(var);
+        match (replaceable(u), replaceable(v)) {
+            (true, true) if position(u) < position(v) => Some((v.to_string(), u.to_string())),
+            (true, _) => Some((u.to_string(), v.to_string())),
+            (false, true) => Some((v.to_string(), u.to_string())),
+            (false, false) => None,
+        }
+    }
+
+    // two temporaries defined as the same product are the same value
+    fn merge_common_products(&mut self) -> bool {
+        let mut definitions: HashMap<_, String> = HashMap::new();
+        let mut merges = Vec::new();
+
+        for constraint in &self.constraints {
+            let [(temp, coeff)] = constraint.c.terms.as_slice() else {
+                continue;
+            };
+            if !lofit::r1cs::is_computed_witness(temp) || *coeff != BigInt::from(1) {
+                continue;
+            }
+            let mut sides = [constraint.a.normalized(), constraint.b.normalized()];
+            sides.sort();
+            match definitions.get(&sides) {
+                Some(existing) if existing != temp => merges.push((temp.clone(), existing.clone())),
+                Some(_) => {}
+                None => {
+                    definitions.insert(sides, temp.clone());
+                }
+            }
+        }
+
+        let changed = !merges.is_empty();
+        for (from, to) in merges {
+            debug!("Merging '{}' into '{}'", from, to);
+            self.substitute(
+                &from,
+                &LinearCombination {
+                    terms: vec![(to, BigInt::from(1))],
+                },
+            );
+        }
+        changed
+    }
+
+    // temporaries no constraint mentions any more
+    fn remove_dead_witnesses(&mut self) {
+        let used: HashSet<&str> = self
+            .constraints
+            .iter()
+            .flat_map(|c| c.a.terms.iter().chain(&c.b.terms).chain(&c.c.terms))
+            .map(|(var, _)| var.as_str())
+            .collect();
+        let live: Vec<String> = self
+            .witnesses
+            .iter()
+            .filter(|w| !is_generated_witness(w) || used.contains(w.as_str()))
+            .cloned()
+            .collect();
+        self.witnesses = live;
+    }
+
+    // the witness solver reads bits off a decomposition only when its C side is a single wire
+    fn is_decomposition_target(&self, var: &str) -> bool {
+        self.constraints.iter().any(|constraint| {
+            constraint.a.terms.len() > 1
+                && matches!(constraint.c.terms.as_slice(), [(c, _)] if c == var)
+        })
+    }
+
+    fn substitute(&mut self, var: &str, replacement: &LinearCombination) {
+        let rewrite = |lc: &mut LinearCombination| {
+            if !lc.terms.iter().any(|(v, _)| v == var) {
+                return;
+            }
+            let mut rewritten = LinearCombination::zero();
+            for (v, coeff) in &lc.terms {
+                if v == var {
+                    rewritten.add(&LinearCombination {
+                        terms: replacement
+                            .terms
+                            .iter()
+                            .map(|(r, c)| (r.clone(), c * coeff))
+                            .collect(),
+                    });
+                } else {
+                    rewritten.add(&LinearCombination {
+                        terms: vec![(v.clone(), coeff.clone())],
+                    });
+                }
+            }
+            *lc = rewritten;
+        };
+        for constraint in &mut self.constraints {
+            rewrite(&mut constraint.a);
+            rewrite(&mut constraint.b);
+            rewrite(&mut constraint.c);
+        }
+    }
+
+    // a product feeding into A or B has to have been given a wire by a constraint of its own;
+    // a temporary that no constraint defines is a sub-expression that was never flattened.
+    // wires count as defined by a linear constraint (`lc * ONE = c`) or by appearing in a C,
+    // and the inverses of the zero checks are hints the prover fills in
+    pub fn validate_quadratic(&self) -> Result<(), R1CSError> {
+        let mut defined: HashSet<&str> = HashSet::from(["ONE"]);
+        defined.extend(self.pub_inputs.iter().map(String::as_str));
+        defined.extend(
+            self.witnesses
+                .iter()
+                .map(String::as_str)
+                .filter(|w| !is_generated_witness(w)),
+        );
+        defined.extend(self.hints.iter().map(String::as_str));
+        for constraint in &self.constraints {
+            defined.extend(constraint.c.terms.iter().map(|(var, _)| var.as_str()));
+            let linear = match (is_one(&constraint.a), is_one(&constraint.b)) {
+                (_, true) => Some(&constraint.a),
+                (true, _) => Some(&constraint.b),
+                _ => None,
+            };
+            if let Some(lc) = linear {
+                defined.extend(lc.terms.iter().map(|(var, _)| var.as_str()));
+            }
+        }
+
+        for (index, constraint) in self.constraints.iter().enumerate() {
+            let flattened = constraint
+                .a
+                .terms
+                .iter()
+                .chain(&constraint.b.terms)
+                .all(|(var, _)| defined.contains(var.as_str()));
+            if !flattened {
+                return Err(R1CSError::NonQuadratic(index));
+            }
+        }
+        Ok(())
+    }
+
+    fn is_wire(&self, var: &str) -> bool {
+        var == "ONE"
+            || self.pub_inputs.iter().any(|x| x == var)
+            || self.witnesses.iter().any(|x| x == var)
+            || self.symbol_map.contains_key(var)
+    }
+
+    fn is_boolean_lc(&self, lc: &LinearCombination) -> bool {
+        match lc.terms.as_slice() {
+            [] => true,
+            [(var, coeff)] if var == "ONE" => {
+                *coeff == BigInt::from(0) || *coeff == BigInt::from(1)
+            }
+            [(var, coeff)] => *coeff == BigInt::from(1) && self.boolean_vars.contains(var),
+            _ => false,
+        }
+    }
+
+    // the AND/OR products are only boolean logic on 0/1 operands: with a = b = 2, OR gives
+    // 2 + 2 - 4 = 0, so each operand is pinned to {0, 1} unless it already is
+    fn enforce_boolean_operand(&mut self, lc: &LinearCombination) -> Result<(), R1CSError> {
+        if lc.is_constant() {
+            let value: BigInt = lc.terms.iter().map(|(_, coeff)| coeff).sum();
+            if value != BigInt::from(0) && value != BigInt::from(1) {
+                return Err(R1CSError::InvalidArgument(format!(
+                    "logical operand {} is not a boolean",
+                    value
+                )));
+            }
+            return Ok(());
+        }
+
+        if let [(var, coeff)] = lc.terms.as_slice() {
+            if *coeff == BigInt::from(1) {
+                self.enforce_boolean(var);
+                return Ok(());
+            }
+        }
+
+        let mut one_minus = lc.negate();
+        one_minus.terms.push(("ONE".to_string(), BigInt::from(1)));
+        self.constraints.push(R1CSConstraint {
+            a: lc.clone(),
+            b: one_minus,
+            c: LinearCombination::zero(),
+        });
+        Ok(())
+    }
+
+    // `Field<min..max>` signals are decomposed into enough bits to hold `max`, which is what
+    // lets comparisons on them use a narrow decomposition. those bits alone allow anything up to
+    // the next power of two, so `max - x` and `x - min` must fit in them too where they matter
+    fn enforce_declared_range(&mut self, name: &str, typ: &Type) -> Result<(), R1CSError> {
+        let Some(bits) = declared_range_bits(typ) else {
+            return Ok(());
+        };
+        let prefix = self.new_temp_var();
+        self.decompose_into_bits(name, &prefix, bits);
+        self.range_checked.insert(name.to_string(), bits);
+
+        let Type::Field {
+            refinement: Some(Refinement::Range { min, max }),
+            ..
+        } = typ
+        else {
+            return Ok(());
+        };
+        let value = LinearCombination {
+            terms: vec![(name.to_string(), BigInt::from(1))],
+        };
+        // a `max` of 2^k - 1 is exactly what the bits hold
+        if bits_for(*max as u64 + 1) == bits {
+            let mut below_max = LinearCombination {
+                terms: vec![("ONE".to_string(), BigInt::from(*max))],
+            };
+            below_max.add(&value.negate());
+            self.enforce_operand_range(&below_max, bits)?;
+        }
+        if *min > 0 {
+            let mut above_min = value;
+            above_min
+                .terms
+                .push(("ONE".to_string(), BigInt::from(-*min)));
+            self.enforce_operand_range(&above_min, bits)?;
+        }
+        Ok(())
+    }
+
+    // one boolean wire per bit, addressed as `name[i]` like any other array element
+    fn declare_bits(&mut self, signal: &Signal, public: bool) -> Result<(), R1CSError> {
+        let width = signal.typ.bits_width().ok_or_else(|| {
+            R1CSError::InvalidArgument(format!("'{}' has an invalid bit width", signal.name))
+        })?;
+        let mut element_vars = Vec::new();
+        for i in 0..width {
+            let element = format!("{}[{}]", signal.name, i);
+            if public {
+                self.pub_inputs.push(element.clone());
+            } else {
+                self.witnesses.push(element.clone());
+            }
+            self.enforce_boolean(&element);
+            element_vars.push(element);
+        }
+        self.arrays.insert(signal.name.clone(), element_vars);
+        self.context
+            .variables
+            .insert(signal.name.clone(), signal.typ.clone());
+        Ok(())
+    }
+
     pub fn convert_proof(&mut self, expr: &Expression) -> Result<(), R1CSError> {
         match expr {
             Expression::Proof {
@@ -194,10 +887,16 @@ impl R1CSGenerator {
                 debug!("Converting proof '{}' to R1CS", name);
 
                 self.reset_for_new_proof();
+                self.temp_scope = name.clone();
 
                 for signal in signals {
+                    self.current_source = Some(signal_declaration(signal));
                     match signal.visibility {
-                        Visibility::Input => match &signal.typ {
+                        // outputs are public wires too, the verifier receives them with the inputs
+                        Visibility::Input | Visibility::Output => match &signal.typ {
+                            Type::Bits(_) => self
+                                .declare_bits(signal, true)
+                                .inspect_err(|_| self.declaration_failed(signal))?,
                             Type::Array {
                                 element_type: _,
                                 size,
@@ -241,6 +940,9 @@ impl R1CSGenerator {
                                 }
                             }
                         },
+                        Visibility::Witness if matches!(signal.typ, Type::Bits(_)) => self
+                            .declare_bits(signal, false)
+                            .inspect_err(|_| self.declaration_failed(signal))?,
                         Visibility::Witness => {
                             self.witnesses.push(signal.name.clone());
                             self.context
@@ -251,15 +953,31 @@ impl R1CSGenerator {
                             }
                         }
                     }
+                    self.tag_new_constraints();
+                }
+
+                // after every declared witness, so the bits land among the computed wires
+                for signal in signals {
+                    self.current_source = Some(signal_declaration(signal));
+                    self.enforce_declared_range(&signal.name, &signal.typ)
+                        .inspect_err(|_| self.declaration_failed(signal))?;
+                    self.tag_new_constraints();
                 }
+                self.current_source = None;
 
-                let _result = self.convert_to_linear_combination(body)?;
+                let _result =
+                    self.with_source(body, |this| this.convert_to_linear_combination(body))?;
+
+                if self.constraints.is_empty() {
+                    return Err(R1CSError::NoConstraints(name.clone()));
+                }
 
                 debug!(
                     "Generated {} constraints for proof '{}'",
                     self.constraints.len(),
                     name
                 );
+                self.finalize_layout();
                 warn!(
                     "Witnesses list ({} total): {:?}",
                     self.witnesses.len(),
@@ -283,6 +1001,18 @@ impl R1CSGenerator {
                     return Ok(subst.clone());
                 }
 
+                let is_signal = self.pub_inputs.contains(name) || self.witnesses.contains(name);
+
+                if self.enum_constructors.contains_key(name) && !is_signal {
+                    return self.convert_enum_constructor(name, &[]);
+                }
+
+                if let Some(value) = self.constants.get(name).filter(|_| !is_signal) {
+                    return Ok(LinearCombination {
+                        terms: vec![("ONE".to_string(), value.clone())],
+                    });
+                }
+
                 Ok(LinearCombination {
                     terms: vec![(name.clone(), BigInt::from(1))],
                 })
@@ -292,11 +1022,46 @@ impl R1CSGenerator {
                 terms: vec![("ONE".to_string(), BigInt::from(*n))],
             }),
 
+            Expression::BigNumber(n) => Ok(LinearCombination {
+                terms: vec![("ONE".to_string(), n.clone())],
+            }),
+
             Expression::BinaryOp { left, op, right } => self.convert_binary_op(left, op, right),
 
             Expression::Assert(condition) => {
+                // `assert a === b` is the equality constraint itself and yields no boolean, so
+                // only a bare condition goes through the `cond * 1 = 1` lowering below
+                if matches!(
+                    condition.as_ref(),
+                    Expression::BinaryOp {
+                        op: Operator::Assert,
+                        ..
+                    }
+                ) {
+                    return self.convert_to_linear_combination(condition);
+                }
+
                 let cond_lc = self.convert_to_linear_combination(condition)?;
 
+                // `cond * 1 = 1` only reads as "cond holds" for a 0/1 value; on any other field
+                // element it would silently mean `cond == 1`
+                if !self.is_boolean_lc(&cond_lc) {
+                    return Err(R1CSError::TypeError(format!(
+                        "assert needs a boolean condition, found {:?}; use `===` to constrain a field value",
+                        condition
+                    )));
+                }
+                if cond_lc.is_constant() {
+                    if !same_field_value(constant_value(&cond_lc), BigInt::from(1)) {
+                        return Err(R1CSError::UnsatisfiableConstant(format!(
+                            "`assert {:?}`",
+                            condition
+                        )));
+                    }
+                    debug!("Dropping assertion of a condition that always holds");
+                    return Ok(LinearCombination::zero());
+                }
+
                 self.constraints.push(R1CSConstraint {
                     a: cond_lc.clone(),
                     b: LinearCombination {
@@ -307,14 +1072,14 @@ impl R1CSGenerator {
                     },
                 });
 
-                Ok(LinearCombination { terms: vec![] })
+                Ok(LinearCombination::zero())
             }
 
             Expression::Let {
                 pattern,
                 value,
                 body,
-            } => self.convert_let_binding(pattern, value, body),
+            } => self.with_source(expr, |this| this.convert_let_binding(pattern, value, body)),
 
             Expression::Match { value, patterns } => self.convert_match_expression(value, patterns),
 
@@ -325,13 +1090,13 @@ impl R1CSGenerator {
                 let saved_variables = self.context.variables.clone();
 
                 for stmt in statements {
-                    self.convert_to_linear_combination(stmt)?;
+                    self.with_source(stmt, |this| this.convert_to_linear_combination(stmt))?;
                 }
 
                 let result = if let Some(expr) = final_expr {
-                    self.convert_to_linear_combination(expr)?
+                    self.with_source(expr, |this| this.convert_to_linear_combination(expr))?
                 } else {
-                    LinearCombination { terms: vec![] }
+                    LinearCombination::zero()
                 };
 
                 self.context.variables = saved_variables;
@@ -345,7 +1110,7 @@ impl R1CSGenerator {
                 for elem in elements {
                     self.convert_to_linear_combination(elem)?;
                 }
-                Ok(LinearCombination { terms: vec![] })
+                Ok(LinearCombination::zero())
             }
 
             Expression::FunctionCall {
@@ -353,6 +1118,8 @@ impl R1CSGenerator {
                 arguments,
             } => self.convert_function_call(function, arguments),
 
+            Expression::Ascription { value, .. } => self.convert_to_linear_combination(value),
+
             Expression::ArrayLiteral(_elements) => Err(R1CSError::UnsupportedOperation(
                 "Array literals are not supported in R1CS generation yet".to_string(),
             )),
@@ -363,7 +1130,7 @@ impl R1CSGenerator {
                     Expression::Variable(name) => name,
                     _ => {
                         warn!("Array indexing only supported for simple variables");
-                        return Ok(LinearCombination { terms: vec![] });
+                        return Ok(LinearCombination::zero());
                     }
                 };
 
@@ -404,13 +1171,13 @@ impl R1CSGenerator {
                 }
             }
 
-            Expression::TypeAlias { .. } | Expression::EnumDef { .. } => {
-                Ok(LinearCombination { terms: vec![] })
-            }
+            Expression::TypeAlias { .. }
+            | Expression::EnumDef { .. }
+            | Expression::Const { .. } => Ok(LinearCombination::zero()),
 
             _ => {
                 warn!("Unsupported expression type in R1CS conversion: {:?}", expr);
-                Ok(LinearCombination { terms: vec![] })
+                Ok(LinearCombination::zero())
             }
         }
     }
@@ -493,6 +1260,8 @@ impl R1CSGenerator {
                 })
             }
 
+            Operator::Mod => self.convert_modulo(left, right),
+
             Operator::Assert => {
                 let left_lc = self.convert_to_linear_combination(left)?;
                 let right_lc = self.convert_to_linear_combination(right)?;
@@ -501,6 +1270,25 @@ impl R1CSGenerator {
                 let resolved_left = self.resolve_symbol_map_variables(&left_lc);
                 let resolved_right = self.resolve_symbol_map_variables(&right_lc);
 
+                // between constants the constraint is decided here: vacuous or never satisfiable
+                if resolved_left.is_constant() && resolved_right.is_constant() {
+                    let (left_value, right_value) = (
+                        constant_value(&resolved_left),
+                        constant_value(&resolved_right),
+                    );
+                    if !same_field_value(left_value.clone(), right_value.clone()) {
+                        return Err(R1CSError::UnsatisfiableConstant(format!(
+                            "`{} === {}`",
+                            left_value, right_value
+                        )));
+                    }
+                    debug!(
+                        "Dropping vacuous constraint {} === {}",
+                        left_value, right_value
+                    );
+                    return Ok(LinearCombination::zero());
+                }
+
                 warn!(
                     "ASSERTION CONSTRAINT: {:?} * 1 = {:?}",
                     resolved_left, resolved_right
@@ -521,7 +1309,7 @@ impl R1CSGenerator {
                 );
                 self.constraints.push(constraint);
 
-                Ok(LinearCombination { terms: vec![] })
+                Ok(LinearCombination::zero())
             }
 
             Operator::Equal => {
@@ -534,6 +1322,7 @@ impl R1CSGenerator {
                 let inv = self.new_temp_var();
                 let out = self.new_temp_var();
                 self.witnesses.push(inv.clone());
+                self.hints.insert(inv.clone());
                 self.witnesses.push(out.clone());
 
                 // constraint 1: out = -diff * inv + 1
@@ -573,7 +1362,7 @@ impl R1CSGenerator {
                     b: LinearCombination {
                         terms: vec![(out.clone(), BigInt::from(1))],
                     },
-                    c: LinearCombination { terms: vec![] },
+                    c: LinearCombination::zero(),
                 });
 
                 self.enforce_boolean(&out);
@@ -594,6 +1383,7 @@ impl R1CSGenerator {
                 let inv = self.new_temp_var();
                 let eq_result = self.new_temp_var();
                 self.witnesses.push(inv.clone());
+                self.hints.insert(inv.clone());
                 self.witnesses.push(eq_result.clone());
 
                 // constraint 1: eq_result = -diff * inv + 1 (this is the IsZero check)
@@ -633,7 +1423,7 @@ impl R1CSGenerator {
                     b: LinearCombination {
                         terms: vec![(eq_result.clone(), BigInt::from(1))],
                     },
-                    c: LinearCombination { terms: vec![] },
+                    c: LinearCombination::zero(),
                 });
 
                 // constraint 3: neq_result = 1 - eq_result
@@ -672,6 +1462,8 @@ impl R1CSGenerator {
                 // result = a * b
                 let left_lc = self.convert_to_linear_combination(left)?;
                 let right_lc = self.convert_to_linear_combination(right)?;
+                self.enforce_boolean_operand(&left_lc)?;
+                self.enforce_boolean_operand(&right_lc)?;
 
                 let temp = self.new_temp_var();
                 self.witnesses.push(temp.clone());
@@ -696,6 +1488,8 @@ impl R1CSGenerator {
                 // result = a + b - a*b
                 let left_lc = self.convert_to_linear_combination(left)?;
                 let right_lc = self.convert_to_linear_combination(right)?;
+                self.enforce_boolean_operand(&left_lc)?;
+                self.enforce_boolean_operand(&right_lc)?;
 
                 // first compute a * b
                 let product_temp = self.new_temp_var();
@@ -782,6 +1576,10 @@ impl R1CSGenerator {
 
         let saved_context = self.context.clone();
 
+        let value = match value {
+            Expression::Ascription { value, .. } => value.as_ref(),
+            value => value,
+        };
         if let Expression::ArrayLiteral(elements) = value {
             if let Pattern::Variable(array_name) = pattern {
                 debug!("Binding array literal: {} = [...]", array_name);
@@ -808,7 +1606,8 @@ impl R1CSGenerator {
 
                 self.arrays.insert(array_name.clone(), element_vars);
 
-                let result = self.convert_to_linear_combination(body)?;
+                let result =
+                    self.with_source(body, |this| this.convert_to_linear_combination(body))?;
 
                 self.context = saved_context;
 
@@ -949,9 +1748,7 @@ impl R1CSGenerator {
                     b: LinearCombination {
                         terms: vec![("ONE".to_string(), BigInt::from(1))],
                     },
-                    c: LinearCombination {
-                        terms: vec![("ONE".to_string(), BigInt::from(0))],
-                    },
+                    c: LinearCombination::zero(),
                 });
             }
             Pattern::Literal(lit) => {
@@ -970,7 +1767,8 @@ impl R1CSGenerator {
         }
 
         debug!("Processing let body: {:?}", body);
-        let body_result = self.convert_to_linear_combination(body)?;
+        let body_result =
+            self.with_source(body, |this| this.convert_to_linear_combination(body))?;
 
         debug!("Restoring context after let binding (preserving symbol map)");
         debug!("Symbol map before restore: {:?}", self.symbol_map);
@@ -1021,11 +1819,14 @@ impl R1CSGenerator {
             )
         });
 
+        // guarded arms can overlap, so each arm must also be gated on no earlier arm firing
+        let has_guards = patterns.iter().any(|p| p.guard.is_some());
+
         if all_simple {
             let result_var = self.new_temp_var();
             self.witnesses.push(result_var.clone());
 
-            let mut accumulated_result = LinearCombination { terms: vec![] };
+            let mut accumulated_result = LinearCombination::zero();
             let mut remaining_probability = LinearCombination {
                 terms: vec![("ONE".to_string(), BigInt::from(1))],
             };
@@ -1043,6 +1844,7 @@ impl R1CSGenerator {
                         let inv = self.new_temp_var();
                         let is_equal = self.new_temp_var();
                         self.witnesses.push(inv.clone());
+                        self.hints.insert(inv.clone());
                         self.witnesses.push(is_equal.clone());
 
                         let neg_product = self.new_temp_var();
@@ -1080,20 +1882,30 @@ impl R1CSGenerator {
                             b: LinearCombination {
                                 terms: vec![(is_equal.clone(), BigInt::from(1))],
                             },
-                            c: LinearCombination { terms: vec![] },
+                            c: LinearCombination::zero(),
                         });
 
+                        let mut selector = LinearCombination {
+                            terms: vec![(is_equal, BigInt::from(1))],
+                        };
+                        if let Some(guard) = &match_pattern.guard {
+                            let guard_lc = self.convert_to_linear_combination(guard)?;
+                            selector = self.multiply_selectors(selector, guard_lc);
+                        }
+                        if has_guards {
+                            selector =
+                                self.multiply_selectors(remaining_probability.clone(), selector);
+                        }
+
                         let branch_result =
                             self.convert_to_linear_combination(&match_pattern.body)?;
 
-                        // result += is_equal * branch_result
+                        // result += selector * branch_result
                         let weighted_result = self.new_temp_var();
                         self.witnesses.push(weighted_result.clone());
 
                         self.constraints.push(R1CSConstraint {
-                            a: LinearCombination {
-                                terms: vec![(is_equal.clone(), BigInt::from(1))],
-                            },
+                            a: selector.clone(),
                             b: branch_result,
                             c: LinearCombination {
                                 terms: vec![(weighted_result.clone(), BigInt::from(1))],
@@ -1112,9 +1924,7 @@ impl R1CSGenerator {
                             let mut not_eq_expr = LinearCombination {
                                 terms: vec![("ONE".to_string(), BigInt::from(1))],
                             };
-                            not_eq_expr.add(&LinearCombination {
-                                terms: vec![(is_equal, BigInt::from(-1))],
-                            });
+                            not_eq_expr.add(&selector.negate());
 
                             self.constraints.push(R1CSConstraint {
                                 a: LinearCombination {
@@ -1153,6 +1963,14 @@ impl R1CSGenerator {
                                 .insert(var_name.clone(), value_lc.clone());
                         }
 
+                        let selector = match &match_pattern.guard {
+                            Some(guard) => {
+                                let guard_lc = self.convert_to_linear_combination(guard)?;
+                                self.multiply_selectors(remaining_probability.clone(), guard_lc)
+                            }
+                            None => remaining_probability.clone(),
+                        };
+
                         let branch_result =
                             self.convert_to_linear_combination(&match_pattern.body)?;
 
@@ -1162,7 +1980,7 @@ impl R1CSGenerator {
                         self.witnesses.push(weighted_result.clone());
 
                         self.constraints.push(R1CSConstraint {
-                            a: remaining_probability.clone(),
+                            a: selector.clone(),
                             b: branch_result,
                             c: LinearCombination {
                                 terms: vec![(weighted_result.clone(), BigInt::from(1))],
@@ -1173,7 +1991,12 @@ impl R1CSGenerator {
                             terms: vec![(weighted_result, BigInt::from(1))],
                         });
 
-                        break;
+                        if match_pattern.guard.is_none() {
+                            break;
+                        }
+
+                        // a failed guard falls through: remaining -= selector
+                        remaining_probability.add(&selector.negate());
                     }
                     _ => {}
                 }
@@ -1196,7 +2019,28 @@ impl R1CSGenerator {
         } else {
             // complex patterns (tuples, constructors) not yet supported
             warn!("Complex match patterns (tuples, constructors) not yet fully supported in R1CS");
-            Ok(LinearCombination { terms: vec![] })
+            Ok(LinearCombination::zero())
+        }
+    }
+
+    fn multiply_selectors(
+        &mut self,
+        left: LinearCombination,
+        right: LinearCombination,
+    ) -> LinearCombination {
+        let product = self.new_temp_var();
+        self.witnesses.push(product.clone());
+
+        self.constraints.push(R1CSConstraint {
+            a: left,
+            b: right,
+            c: LinearCombination {
+                terms: vec![(product.clone(), BigInt::from(1))],
+            },
+        });
+
+        LinearCombination {
+            terms: vec![(product, BigInt::from(1))],
         }
     }
 
@@ -1209,6 +2053,32 @@ impl R1CSGenerator {
             return self.convert_decompose(arguments);
         }
 
+        if function == "compose" && !self.function_defs.contains_key(function) {
+            return self.convert_compose(arguments);
+        }
+
+        if BITWISE_BUILTINS.contains(&function) && !self.function_defs.contains_key(function) {
+            return self.convert_bitwise(function, arguments);
+        }
+
+        if !self.function_defs.contains_key(function) {
+            if let Some(assertion) = Expression::desugar_assertion(function, arguments) {
+                return self.convert_to_linear_combination(&assertion);
+            }
+        }
+
+        if self.enum_constructors.contains_key(function)
+            && !self.function_defs.contains_key(function)
+        {
+            return self.convert_enum_constructor(function, arguments);
+        }
+
+        if let Some((signals, body)) = self.component_defs.get(function).cloned() {
+            return self.inlining(function, |this| {
+                this.inline_component(function, &signals, &body, arguments)
+            });
+        }
+
         if let Some((params, body)) = self.function_defs.get(function).cloned() {
             debug!(
                 "Inlining function '{}' with {} arguments",
@@ -1225,23 +2095,169 @@ impl R1CSGenerator {
                 )));
             }
 
-            let saved_substitutions = self.variable_substitutions.clone();
-
+            let mut bindings = Vec::with_capacity(params.len());
             for (param, arg) in params.iter().zip(arguments.iter()) {
-                let arg_lc = self.convert_to_linear_combination(arg)?;
-                self.variable_substitutions
-                    .insert(param.name.clone(), arg_lc);
+                bindings.push((param.name.clone(), self.convert_to_linear_combination(arg)?));
             }
 
-            let result = self.convert_to_linear_combination(&body)?;
+            self.inlining(function, |this| {
+                let saved_substitutions = this.variable_substitutions.clone();
+                this.variable_substitutions.extend(bindings);
 
-            self.variable_substitutions = saved_substitutions;
+                let result = this.convert_to_linear_combination(&body);
 
-            Ok(result)
-        } else {
+                this.variable_substitutions = saved_substitutions;
+
+                result
+            })
+        } else {
             warn!("Function '{}' not found in function definitions", function);
-            Ok(LinearCombination { terms: vec![] })
+            Ok(LinearCombination::zero())
+        }
+    }
+
+    fn inlining<T>(
+        &mut self,
+        function: &str,
+        inline: impl FnOnce(&mut Self) -> Result<T, R1CSError>,
+    ) -> Result<T, R1CSError> {
+        if self.inline_stack.iter().any(|f| f == function)
+            || self.inline_stack.len() >= self.max_inline_depth
+        {
+            return Err(R1CSError::RecursionLimitExceeded(function.to_string()));
         }
+
+        self.inline_stack.push(function.to_string());
+        let result = inline(self);
+        self.inline_stack.pop();
+        result
+    }
+
+    // inputs are bound to the arguments in declaration order; every other signal becomes a
+    // fresh witness of the calling proof, so each call gets its own copy of the internals
+    fn inline_component(
+        &mut self,
+        component: &str,
+        signals: &[Signal],
+        body: &Expression,
+        arguments: &[Expression],
+    ) -> Result<LinearCombination, R1CSError> {
+        let inputs: Vec<&Signal> = signals
+            .iter()
+            .filter(|s| s.visibility == Visibility::Input)
+            .collect();
+
+        if arguments.len() != inputs.len() {
+            return Err(R1CSError::InvalidArgument(format!(
+                "Component '{}' expects {} inputs, got {}",
+                component,
+                inputs.len(),
+                arguments.len()
+            )));
+        }
+
+        debug!(
+            "Inlining component '{}' with {} inputs",
+            component,
+            inputs.len()
+        );
+
+        let mut bindings = Vec::with_capacity(signals.len());
+        for (input, arg) in inputs.iter().zip(arguments) {
+            bindings.push((input.name.clone(), self.convert_to_linear_combination(arg)?));
+        }
+
+        for signal in signals.iter().filter(|s| s.visibility != Visibility::Input) {
+            if matches!(signal.typ, Type::Array { .. }) {
+                return Err(R1CSError::InvalidArgument(format!(
+                    "Component '{}' declares array signal '{}', which cannot be inlined",
+                    component, signal.name
+                )));
+            }
+            let var = self.new_temp_var();
+            self.witnesses.push(var.clone());
+            if matches!(signal.typ, Type::Bool { .. }) {
+                self.enforce_boolean(&var);
+            }
+            bindings.push((
+                signal.name.clone(),
+                LinearCombination {
+                    terms: vec![(var, BigInt::from(1))],
+                },
+            ));
+        }
+
+        let saved_substitutions = self.variable_substitutions.clone();
+        self.variable_substitutions.extend(bindings);
+
+        let result = self.convert_to_linear_combination(body);
+
+        self.variable_substitutions = saved_substitutions;
+
+        result
+    }
+
+    // an enum value is a tag witness pinned to the variant index, with one witness per field
+    fn convert_enum_constructor(
+        &mut self,
+        constructor: &str,
+        arguments: &[Expression],
+    ) -> Result<LinearCombination, R1CSError> {
+        let (tag, arity) = self.enum_constructors[constructor];
+
+        if arguments.len() != arity {
+            return Err(R1CSError::InvalidArgument(format!(
+                "Constructor '{}' expects {} arguments, got {}",
+                constructor,
+                arity,
+                arguments.len()
+            )));
+        }
+
+        let tag_var = self.new_temp_var();
+        self.witnesses.push(tag_var.clone());
+
+        self.constraints.push(R1CSConstraint {
+            a: LinearCombination {
+                terms: vec![(tag_var.clone(), BigInt::from(1))],
+            },
+            b: LinearCombination {
+                terms: vec![("ONE".to_string(), BigInt::from(1))],
+            },
+            c: LinearCombination {
+                terms: vec![("ONE".to_string(), BigInt::from(tag))],
+            },
+        });
+
+        let mut field_vars = Vec::with_capacity(arity);
+        for (i, argument) in arguments.iter().enumerate() {
+            let arg_lc = self.convert_to_linear_combination(argument)?;
+
+            let field_var = format!("{}_field_{}", tag_var, i);
+            self.witnesses.push(field_var.clone());
+
+            self.constraints.push(R1CSConstraint {
+                a: LinearCombination {
+                    terms: vec![(field_var.clone(), BigInt::from(1))],
+                },
+                b: LinearCombination {
+                    terms: vec![("ONE".to_string(), BigInt::from(1))],
+                },
+                c: arg_lc,
+            });
+
+            field_vars.push(field_var);
+        }
+
+        debug!(
+            "Constructed '{}' as tag {} in '{}' with {} fields",
+            constructor, tag, tag_var, arity
+        );
+        self.enum_values.insert(tag_var.clone(), field_vars);
+
+        Ok(LinearCombination {
+            terms: vec![(tag_var, BigInt::from(1))],
+        })
     }
 
     fn convert_decompose(
@@ -1254,18 +2270,25 @@ impl R1CSGenerator {
             ));
         }
 
-        let input_var = match &arguments[0] {
-            Expression::Variable(name) => name,
-            _ => {
-                return Err(R1CSError::InvalidArgument(
-                    "decompose expects a variable".to_string(),
-                ))
+        if let Expression::Variable(name) = &arguments[0] {
+            if let Some(bits) = self.array_bits(name) {
+                if let Some(width) = arguments.get(1) {
+                    if *width != Expression::Number(bits.len() as i64) {
+                        return Err(R1CSError::InvalidArgument(format!(
+                            "'{}' has {} bits, so the decompose width must be {}",
+                            name,
+                            bits.len(),
+                            bits.len()
+                        )));
+                    }
+                }
+                return self.recompose_bits(&bits);
             }
-        };
+        }
 
         let bit_width = if arguments.len() == 2 {
             match &arguments[1] {
-                Expression::Number(n) => *n as usize,
+                Expression::Number(n) => *n,
                 _ => {
                     return Err(R1CSError::InvalidArgument(
                         "decompose bit width must be a number".to_string(),
@@ -1276,10 +2299,502 @@ impl R1CSGenerator {
             8
         };
 
+        let field_bits = crate::ir::field_bits();
+        let bit_width = usize::try_from(bit_width)
+            .ok()
+            .filter(|width| (1..=field_bits).contains(width))
+            .ok_or_else(|| {
+                R1CSError::InvalidArgument(format!(
+                    "decompose bit width must be between 1 and {}, got {}",
+                    field_bits, bit_width
+                ))
+            })?;
+
+        // a computed value is first placed on a wire of its own, which is what gets decomposed
+        let value = self.convert_to_linear_combination(&arguments[0])?;
+        let input_var = self.bound_var(value);
+
+        let key = (input_var.clone(), bit_width);
+        if let Some(bits) = self.decompositions.get(&key) {
+            return Ok(bits.clone());
+        }
+
+        let bits = self.decompose_into_bits(&input_var, &input_var, bit_width);
+        if bit_width == field_bits {
+            self.enforce_below_modulus(&input_var, &bits);
+        }
+        if self
+            .range_checked
+            .get(&input_var)
+            .is_none_or(|bits| *bits > bit_width)
+        {
+            self.range_checked.insert(input_var, bit_width);
+        }
+        Ok(bits)
+    }
+
+    // `Bits<N>` elements are already boolean, so enforcing them again adds nothing
+    fn recompose_bits(
+        &mut self,
+        bits: &[LinearCombination],
+    ) -> Result<LinearCombination, R1CSError> {
+        let field_bits = crate::ir::field_bits();
+        if bits.is_empty() || bits.len() > field_bits {
+            return Err(R1CSError::InvalidArgument(format!(
+                "a bit array must have between 1 and {} bits, got {}",
+                field_bits,
+                bits.len()
+            )));
+        }
+        let mut sum = LinearCombination::zero();
+        for (i, bit) in bits.iter().enumerate() {
+            self.enforce_boolean_operand(bit)?;
+            let weight = BigInt::from(1) << i;
+            sum.add(&LinearCombination {
+                terms: bit
+                    .terms
+                    .iter()
+                    .map(|(var, coeff)| (var.clone(), coeff * &weight))
+                    .collect(),
+            });
+        }
+        // a full-width array could otherwise spell out x + p and compose to x
+        if bits.len() == field_bits {
+            self.enforce_bits_below_modulus("compose", bits);
+        }
+        Ok(sum)
+    }
+
+    fn array_bits(&self, name: &str) -> Option<Vec<LinearCombination>> {
+        self.arrays.get(name).map(|elements| {
+            elements
+                .iter()
+                .map(|element| LinearCombination {
+                    terms: vec![(element.clone(), BigInt::from(1))],
+                })
+                .collect()
+        })
+    }
+
+    // `compose(bits)` is the integer the bits spell out, least significant first, on a wire
+    // of its own. `compose(decompose(x))` reuses the bits `decompose` already allocated
+    fn convert_compose(
+        &mut self,
+        arguments: &[Expression],
+    ) -> Result<LinearCombination, R1CSError> {
+        let [bits] = arguments else {
+            return Err(R1CSError::InvalidArgument(
+                "compose expects 1 argument".to_string(),
+            ));
+        };
+
+        let sum = match bits {
+            Expression::Variable(name) => {
+                let bits = self.array_bits(name).ok_or_else(|| {
+                    R1CSError::InvalidArgument(format!(
+                        "compose expects a bit array, got '{}'",
+                        name
+                    ))
+                })?;
+                self.recompose_bits(&bits)?
+            }
+            Expression::ArrayLiteral(elements) => {
+                let bits = elements
+                    .iter()
+                    .map(|element| self.convert_to_linear_combination(element))
+                    .collect::<Result<Vec<_>, _>>()?;
+                self.recompose_bits(&bits)?
+            }
+            Expression::FunctionCall {
+                function,
+                arguments,
+            } if function == "decompose" => self.convert_decompose(arguments)?,
+            _ => {
+                return Err(R1CSError::InvalidArgument(
+                    "compose expects a bit array or a decompose call".to_string(),
+                ))
+            }
+        };
+
+        let value = self.new_temp_var();
+        self.witnesses.push(value.clone());
+        let value = LinearCombination {
+            terms: vec![(value, BigInt::from(1))],
+        };
+        self.constraints.push(R1CSConstraint {
+            a: sum,
+            b: LinearCombination {
+                terms: vec![("ONE".to_string(), BigInt::from(1))],
+            },
+            c: value.clone(),
+        });
+        Ok(value)
+    }
+
+    // `rotl(x, k, width)`, `shr(x, k, width)` and `xor3(a, b, c)` (words of 32 bits unless a
+    // fourth width argument says otherwise) read their operands' bits the way `decompose(x,
+    // width)` does, which also confines each operand to `width` bits. rotations and shifts only
+    // move bit weights around; each xor3 bit costs two products. the result gets a wire of its own
+    fn convert_bitwise(
+        &mut self,
+        function: &str,
+        arguments: &[Expression],
+    ) -> Result<LinearCombination, R1CSError> {
+        let literal = |expr: &Expression, what: &str| match expr {
+            Expression::Number(n) => usize::try_from(*n).map_err(|_| {
+                R1CSError::InvalidArgument(format!("{} {} must not be negative", function, what))
+            }),
+            _ => Err(R1CSError::InvalidArgument(format!(
+                "{} {} must be a number",
+                function, what
+            ))),
+        };
+        let check_width = |width: usize| {
+            let field_bits = crate::ir::field_bits();
+            if (1..=field_bits).contains(&width) {
+                Ok(width)
+            } else {
+                Err(R1CSError::InvalidArgument(format!(
+                    "{} bit width must be between 1 and {}, got {}",
+                    function, field_bits, width
+                )))
+            }
+        };
+
+        let sum = match (function, arguments) {
+            ("rotl" | "shr", [value, shift, width]) => {
+                let width = check_width(literal(width, "bit width")?)?;
+                let shift = literal(shift, "shift")?;
+                if shift >= width {
+                    return Err(R1CSError::InvalidArgument(format!(
+                        "{} shift must be below the bit width {}, got {}",
+                        function, width, shift
+                    )));
+                }
+                let bits = self.word_bits(value, width)?;
+                let mut sum = LinearCombination::zero();
+                for (i, bit) in bits.iter().enumerate() {
+                    let position = match function {
+                        "rotl" => (i + shift) % width,
+                        _ if i >= shift => i - shift,
+                        _ => continue,
+                    };
+                    sum.add(&scaled(bit, &(BigInt::from(1) << position)));
+                }
+                sum
+            }
+            ("xor3", [a, b, c]) | ("xor3", [a, b, c, _]) => {
+                let width = match arguments.get(3) {
+                    Some(width) => check_width(literal(width, "bit width")?)?,
+                    None => crate::ir::XOR3_DEFAULT_WIDTH,
+                };
+                let a = self.word_bits(a, width)?;
+                let b = self.word_bits(b, width)?;
+                let c = self.word_bits(c, width)?;
+                let mut sum = LinearCombination::zero();
+                for (i, ((a, b), c)) in a.iter().zip(&b).zip(&c).enumerate() {
+                    // a ^ b = a + b - 2ab, then (a ^ b) ^ c the same way
+                    let mut ab = a.clone();
+                    ab.add(b);
+                    let product = self.bit_product(a, b);
+                    ab.add(&scaled(&product, &BigInt::from(-2)));
+
+                    let mut abc = ab.clone();
+                    abc.add(c);
+                    let product = self.bit_product(&ab, c);
+                    abc.add(&scaled(&product, &BigInt::from(-2)));
+
+                    sum.add(&scaled(&abc, &(BigInt::from(1) << i)));
+                }
+                sum
+            }
+            ("xor3", _) => {
+                return Err(R1CSError::InvalidArgument(
+                    "xor3 expects 3 operands and an optional bit width".to_string(),
+                ))
+            }
+            _ => {
+                return Err(R1CSError::InvalidArgument(format!(
+                    "{} expects a value, a shift and a bit width",
+                    function
+                )))
+            }
+        };
+
+        let value = self.new_temp_var();
+        self.witnesses.push(value.clone());
+        let value = LinearCombination {
+            terms: vec![(value, BigInt::from(1))],
+        };
+        self.constraints.push(R1CSConstraint {
+            a: sum,
+            b: LinearCombination {
+                terms: vec![("ONE".to_string(), BigInt::from(1))],
+            },
+            c: value.clone(),
+        });
+        Ok(value)
+    }
+
+    // the `width` bits of a bitwise operand, least significant first. constants contribute
+    // constant bits, anything other than a plain signal is bound to a temp and decomposed
+    fn word_bits(
+        &mut self,
+        expr: &Expression,
+        width: usize,
+    ) -> Result<Vec<LinearCombination>, R1CSError> {
+        let lc = self.convert_to_linear_combination(expr)?;
+        if lc.is_constant() {
+            let value = constant_value(&lc);
+            if value < BigInt::from(0) || value.bits() > width as u64 {
+                return Err(R1CSError::InvalidArgument(format!(
+                    "bitwise operand {} does not fit in {} bits",
+                    value, width
+                )));
+            }
+            return Ok((0..width)
+                .map(|i| {
+                    if value.bit(i as u64) {
+                        LinearCombination {
+                            terms: vec![("ONE".to_string(), BigInt::from(1))],
+                        }
+                    } else {
+                        LinearCombination::zero()
+                    }
+                })
+                .collect());
+        }
+
+        let var = self.bound_var(lc);
+
+        let bits = self.decompose_into_bits(&var, &var, width);
+        if width == crate::ir::field_bits() {
+            self.enforce_below_modulus(&var, &bits);
+        }
+        if self
+            .range_checked
+            .get(&var)
+            .is_none_or(|bits| *bits > width)
+        {
+            self.range_checked.insert(var, width);
+        }
+        Ok(bits
+            .terms
+            .into_iter()
+            .map(|(bit, _)| LinearCombination {
+                terms: vec![(bit, BigInt::from(1))],
+            })
+            .collect())
+    }
+
+    // `x % n` for a constant n. the bits of x weighted by 2^i mod n sum to a small s congruent to
+    // x, so `n * q + r = s` cannot wrap the field once r is below n and q fits the quotient
+    // of the largest s; when every s is already below n, s is the remainder itself
+    fn convert_modulo(
+        &mut self,
+        left: &Expression,
+        right: &Expression,
+    ) -> Result<LinearCombination, R1CSError> {
+        let divisor = self.convert_to_linear_combination(right)?;
+        let divisor = Some(&divisor)
+            .filter(|lc| lc.is_constant())
+            .map(constant_value)
+            .filter(|n| *n > BigInt::from(0))
+            .ok_or_else(|| {
+                R1CSError::InvalidArgument(format!(
+                    "the right side of `%` must be a positive constant, got {}",
+                    right
+                ))
+            })?;
+
+        let value = self.convert_to_linear_combination(left)?;
+        if value.is_constant() {
+            let modulus = field_modulus();
+            let value = ((constant_value(&value) % &modulus) + &modulus) % &modulus;
+            return Ok(LinearCombination {
+                terms: vec![("ONE".to_string(), value % &divisor)],
+            });
+        }
+
+        let var = self.bound_var(value);
+        let field_bits = crate::ir::field_bits();
+        let width = self.range_checked.get(&var).copied().unwrap_or(field_bits);
+        let bits = match self.decompositions.get(&(var.clone(), width)) {
+            Some(bits) => bits.clone(),
+            None => {
+                let bits = self.decompose_into_bits(&var, &var, width);
+                if width == field_bits {
+                    self.enforce_below_modulus(&var, &bits);
+                }
+                self.range_checked.insert(var.clone(), width);
+                bits
+            }
+        };
+
+        let mut sum = LinearCombination::zero();
+        let mut max_sum = BigInt::from(0);
+        for (bit, weight) in &bits.terms {
+            let weight = weight % &divisor;
+            if weight != BigInt::from(0) {
+                max_sum += &weight;
+                sum.terms.push((bit.clone(), weight));
+            }
+        }
+        if max_sum < divisor {
+            return Ok(sum);
+        }
+
+        let quotient = self.new_temp_var();
+        let remainder = self.new_temp_var();
+        self.witnesses.push(quotient.clone());
+        self.witnesses.push(remainder.clone());
+        self.constraints.push(R1CSConstraint {
+            a: LinearCombination {
+                terms: vec![
+                    (quotient.clone(), divisor.clone()),
+                    (remainder.clone(), BigInt::from(1)),
+                ],
+            },
+            b: LinearCombination {
+                terms: vec![("ONE".to_string(), BigInt::from(1))],
+            },
+            c: sum,
+        });
+
+        let remainder_lc = LinearCombination {
+            terms: vec![(remainder, BigInt::from(1))],
+        };
+        let quotient_bits = (&max_sum / &divisor).bits() as usize;
+        let largest_remainder = &divisor - BigInt::from(1);
+        let remainder_bits = largest_remainder.bits() as usize;
+        self.enforce_operand_range(
+            &LinearCombination {
+                terms: vec![(quotient, BigInt::from(1))],
+            },
+            quotient_bits,
+        )?;
+        self.enforce_operand_range(&remainder_lc, remainder_bits)?;
+        // a power of two never gets here, so r < 2^k alone would still let r reach n
+        let mut headroom = LinearCombination {
+            terms: vec![("ONE".to_string(), largest_remainder)],
+        };
+        headroom.add(&remainder_lc.negate());
+        self.enforce_operand_range(&headroom, remainder_bits)?;
+        Ok(remainder_lc)
+    }
+
+    // the signal `lc` is, or a temp constrained to equal it
+    fn bound_var(&mut self, lc: LinearCombination) -> String {
+        if let [(var, coeff)] = lc.terms.as_slice() {
+            if *coeff == BigInt::from(1) && var != "ONE" {
+                return var.clone();
+            }
+        }
+        let bound = self.new_temp_var();
+        self.witnesses.push(bound.clone());
+        self.constraints.push(R1CSConstraint {
+            a: LinearCombination {
+                terms: vec![(bound.clone(), BigInt::from(1))],
+            },
+            b: LinearCombination {
+                terms: vec![("ONE".to_string(), BigInt::from(1))],
+            },
+            c: lc,
+        });
+        bound
+    }
+
+    // a product of two bits, which only needs a constraint when neither is a constant
+    fn bit_product(&mut self, a: &LinearCombination, b: &LinearCombination) -> LinearCombination {
+        let constant = |lc: &LinearCombination| lc.is_constant().then(|| constant_value(lc));
+        match (constant(a), constant(b)) {
+            (Some(k), _) => scaled(b, &k),
+            (_, Some(k)) => scaled(a, &k),
+            _ => self.multiply_selectors(a.clone(), b.clone()),
+        }
+    }
+
+    // with as many bits as the modulus, x + p still fits, so the bits must also spell out an
+    // integer below p: walking down from the top bit, `equal` stays 1 while the bits match
+    // p's, and the first bit that differs has to be a 0 where p has a 1
+    fn enforce_below_modulus(&mut self, input_var: &str, bits: &LinearCombination) {
+        let bits: Vec<_> = bits
+            .terms
+            .iter()
+            .map(|(bit, _)| LinearCombination {
+                terms: vec![(bit.clone(), BigInt::from(1))],
+            })
+            .collect();
+        self.enforce_bits_below_modulus(input_var, &bits);
+    }
+
+    // the same for bits that are linear combinations already constrained to be boolean, as
+    // `compose` receives them
+    fn enforce_bits_below_modulus(&mut self, input_var: &str, bits: &[LinearCombination]) {
+        let modulus = field_modulus();
+        let mut equal = LinearCombination {
+            terms: vec![("ONE".to_string(), BigInt::from(1))],
+        };
+        let mut less = LinearCombination::zero();
+
+        for (i, bit) in bits.iter().enumerate().rev() {
+            let next = self.new_temp_var();
+            self.witnesses.push(next.clone());
+            let next_lc = LinearCombination {
+                terms: vec![(next.clone(), BigInt::from(1))],
+            };
+
+            let b = if modulus.bit(i as u64) {
+                // a 0 here while everything above matched puts the value below p
+                less.add(&equal);
+                less.add(&next_lc.negate());
+                bit.clone()
+            } else {
+                let mut flipped = LinearCombination {
+                    terms: vec![("ONE".to_string(), BigInt::from(1))],
+                };
+                flipped.add(&bit.negate());
+                flipped
+            };
+            self.constraints.push(R1CSConstraint {
+                a: equal,
+                b,
+                c: next_lc.clone(),
+            });
+            equal = next_lc;
+        }
+
+        debug!(
+            "Enforcing '{}' decomposes below the field modulus",
+            input_var
+        );
+        self.constraints.push(R1CSConstraint {
+            a: less,
+            b: LinearCombination {
+                terms: vec![("ONE".to_string(), BigInt::from(1))],
+            },
+            c: LinearCombination {
+                terms: vec![("ONE".to_string(), BigInt::from(1))],
+            },
+        });
+    }
+
+    // booleanity for each `<bit_prefix>_bit_i` plus the weighted sum equal to `input_var`
+    fn decompose_into_bits(
+        &mut self,
+        input_var: &str,
+        bit_prefix: &str,
+        bit_width: usize,
+    ) -> LinearCombination {
+        let key = (input_var.to_string(), bit_width);
+        if let Some(bits) = self.decompositions.get(&key) {
+            return bits.clone();
+        }
+
         let mut sum_terms = Vec::new();
 
         for i in 0..bit_width {
-            let bit = format!("{}_bit_{}", input_var, i);
+            let bit = format!("{}_bit_{}", bit_prefix, i);
 
             if !self.witnesses.contains(&bit) {
                 self.witnesses.push(bit.clone());
@@ -1296,7 +2811,7 @@ impl R1CSGenerator {
                         (bit.clone(), BigInt::from(-1)),
                     ],
                 },
-                c: LinearCombination { terms: vec![] },
+                c: LinearCombination::zero(),
             });
 
             sum_terms.push((bit, BigInt::from(1) << i));
@@ -1311,11 +2826,83 @@ impl R1CSGenerator {
                 terms: vec![("ONE".to_string(), BigInt::from(1))],
             },
             c: LinearCombination {
-                terms: vec![(input_var.clone(), BigInt::from(1))],
+                terms: vec![(input_var.to_string(), BigInt::from(1))],
             },
         });
 
-        Ok(LinearCombination { terms: sum_terms })
+        let bits = LinearCombination { terms: sum_terms };
+        self.decompositions.insert(key, bits.clone());
+        bits
+    }
+
+    // bits needed for any value an operand can take, when its range is known
+    fn operand_bits(&self, expr: &Expression) -> Option<usize> {
+        match expr {
+            Expression::Number(n) => u64::try_from(*n).ok().map(bits_for),
+            Expression::Variable(name) if !self.variable_substitutions.contains_key(name) => {
+                match self.context.variables.get(name) {
+                    Some(Type::Bool { .. }) => Some(1),
+                    Some(typ) => declared_range_bits(typ),
+                    None => self
+                        .constants
+                        .get(name)
+                        .and_then(|value| u64::try_from(value).ok())
+                        .map(bits_for),
+                }
+            }
+            _ => None,
+        }
+    }
+
+    // constants are checked here, signals already ranged to at most `width` bits are reused,
+    // anything else is bound to a temp and decomposed
+    fn enforce_operand_range(
+        &mut self,
+        lc: &LinearCombination,
+        width: usize,
+    ) -> Result<(), R1CSError> {
+        if lc.is_constant() {
+            let value: BigInt = lc.terms.iter().map(|(_, coeff)| coeff).sum();
+            if value < BigInt::from(0) || value.bits() > width as u64 {
+                return Err(R1CSError::InvalidArgument(format!(
+                    "comparison operand {} does not fit in {} bits",
+                    value, width
+                )));
+            }
+            return Ok(());
+        }
+
+        let var = self.bound_var(lc.clone());
+
+        if self
+            .range_checked
+            .get(&var)
+            .is_some_and(|bits| *bits <= width)
+        {
+            return Ok(());
+        }
+        let prefix = self.new_temp_var();
+        self.decompose_into_bits(&var, &prefix, width);
+        self.range_checked.insert(var, width);
+        Ok(())
+    }
+
+    fn comparison_width(&self, left: &Expression, right: &Expression) -> usize {
+        match (self.operand_bits(left), self.operand_bits(right)) {
+            (Some(l), Some(r)) => l.max(r).clamp(1, COMPARISON_OFFSET_BITS),
+            _ => COMPARISON_OFFSET_BITS,
+        }
+    }
+
+    // the comparison gadget decomposes `left - right` into bits, which only means something
+    // when both sides are affine over the circuit's wires; a tuple, an assertion or a block
+    // without a value lowers to the zero combination and would silently compare as 0
+    fn comparison_operand(&mut self, expr: &Expression) -> Result<LinearCombination, R1CSError> {
+        let lc = self.convert_to_linear_combination(expr)?;
+        if !yields_value(expr) || !lc.terms.iter().all(|(var, _)| self.is_wire(var)) {
+            return Err(R1CSError::NonAffineOperand(expr.to_string()));
+        }
+        Ok(lc)
     }
 
     fn convert_comparison(
@@ -1326,51 +2913,40 @@ impl R1CSGenerator {
     ) -> Result<LinearCombination, R1CSError> {
         debug!("Converting comparison: {:?} {:?} {:?}", left, op, right);
 
+        let width = self.comparison_width(left, right);
         let diff_var = self.new_temp_var();
         let result_var = self.new_temp_var();
 
         self.witnesses.push(diff_var.clone());
         self.witnesses.push(result_var.clone());
 
+        let a = self.comparison_operand(left)?;
+        let b = self.comparison_operand(right)?;
+
+        // the shifted difference only has the right sign bit when both operands fit in
+        // `width` bits, so a prover could otherwise pick e.g. p - 1 to pass `x < 10`
+        self.enforce_operand_range(&a, width)?;
+        self.enforce_operand_range(&b, width)?;
+
+        let plus_one = |mut lc: LinearCombination| {
+            lc.terms.push(("ONE".to_string(), BigInt::from(1)));
+            lc
+        };
         let (left_lc, right_lc) = match op {
-            Operator::Gt => {
-                // for a > b, swap to compute b < a
-                (
-                    self.convert_to_linear_combination(right)?,
-                    self.convert_to_linear_combination(left)?,
-                )
-            }
-            Operator::Ge => {
-                // for a >= b, compute b < a+1, i.e., b < (a+1)
-                // so diff = 2^252 + b - (a+1) = 2^252 + b - a - 1
-                let mut right_tmp = self.convert_to_linear_combination(left)?;
-                // add 1 to right (which represents 'a'), so right becomes a+1
-                right_tmp.terms.push(("ONE".to_string(), BigInt::from(1)));
-                (self.convert_to_linear_combination(right)?, right_tmp)
-            }
-            Operator::Lt => {
-                // for a < b, compute as-is
-                (
-                    self.convert_to_linear_combination(left)?,
-                    self.convert_to_linear_combination(right)?,
-                )
-            }
-            Operator::Le => {
-                // for a <= b, compute a < b+1
-                // so diff = 2^252 + a - (b+1) = 2^252 + a - b - 1
-                let mut right_tmp = self.convert_to_linear_combination(right)?;
-                // add 1 to right (which represents 'b'), so right becomes b+1
-                right_tmp.terms.push(("ONE".to_string(), BigInt::from(1)));
-                (self.convert_to_linear_combination(left)?, right_tmp)
-            }
+            // for a > b, swap to compute b < a
+            Operator::Gt => (b, a),
+            // for a >= b, compute b < a+1, so diff = 2^width + b - a - 1
+            Operator::Ge => (b, plus_one(a)),
+            Operator::Lt => (a, b),
+            // for a <= b, compute a < b+1, so diff = 2^width + a - b - 1
+            Operator::Le => (a, plus_one(b)),
             _ => unreachable!(),
         };
 
-        // cnstraint: diff = 2^252 + left - right
-        // the 2^252 offset is crucial for proper strict < handling (like Circom's LessThan)
-        // this shifts the range so that equality gives bit 252 = 1 (not 0)
-        const OFFSET_BITS: i64 = 252;
-        let offset = BigInt::from(1) << OFFSET_BITS; // 2^252
+        // cnstraint: diff = 2^width + left - right
+        // the 2^width offset is crucial for proper strict < handling (like Circom's LessThan)
+        // this shifts the range so that equality gives bit `width` = 1 (not 0)
+        let offset = BigInt::from(1) << width;
 
         let mut diff_lc = left_lc;
         diff_lc.add(&right_lc.negate());
@@ -1386,13 +2962,9 @@ impl R1CSGenerator {
             c: diff_lc,
         });
 
-        const COMPARISON_BITS: i64 = 253;
-        self.convert_decompose(&[
-            Expression::Variable(diff_var.clone()),
-            Expression::Number(COMPARISON_BITS),
-        ])?;
+        self.decompose_into_bits(&diff_var, &diff_var, width + 1);
 
-        let sign_bit = format!("{}_bit_{}", diff_var, COMPARISON_BITS - 1);
+        let sign_bit = format!("{}_bit_{}", diff_var, width);
 
         self.constraints.push(R1CSConstraint {
             a: LinearCombination {
@@ -1451,10 +3023,11 @@ impl R1CSGenerator {
     }
 
     fn get_variable_index(&self, var: &str) -> usize {
-        if var == "ONE" {
-            return 0;
+        if let Some(index) = self.wire_index(var) {
+            return index;
         }
 
+        // wires added by hand after (or without) `finalize_layout` keep their push order
         if let Some(pos) = self.pub_inputs.iter().position(|x| x == var) {
             return pos + 1;
         }
@@ -1499,11 +3072,231 @@ impl R1CSGenerator {
     pub fn get_constraints(&self) -> &Vec<R1CSConstraint> {
         &self.constraints
     }
+
+    // classified from the constraint shapes alone, so it works on circuits read back from disk
+    pub fn statistics(&self) -> CircuitStats {
+        let copies: HashMap<&str, &LinearCombination> = self
+            .constraints
+            .iter()
+            .filter_map(|c| c.scaled_copy_target().map(|target| (target, &c.c)))
+            .collect();
+        let mut stats = CircuitStats {
+            constraints: self.constraints.len(),
+            public_inputs: self.pub_inputs.len(),
+            ..Default::default()
+        };
+
+        for constraint in &self.constraints {
+            if constraint.is_boolean_enforcement() {
+                stats.boolean_constraints += 1;
+            } else if !constraint.a.is_constant() && !constraint.b.is_constant() {
+                stats.multiplications += 1;
+            }
+
+            // an `n`-bit recomposition of `diff`, where `diff * 1 = left - right + 2^(n-1)`
+            if let Some((diff, bits)) = constraint.recomposition_target() {
+                let offset = BigInt::from(1) << (bits - 1);
+                if copies.get(diff).is_some_and(|lc| {
+                    lc.terms
+                        .iter()
+                        .any(|(var, coeff)| var == "ONE" && *coeff == offset)
+                }) {
+                    stats.comparisons += 1;
+                }
+            }
+        }
+
+        stats.temporaries = self
+            .witnesses
+            .iter()
+            .filter(|w| is_generated_witness(w))
+            .count();
+        stats.witnesses = self.witnesses.len() - stats.temporaries;
+
+        stats
+    }
+}
+
+// temporaries and anything derived from them, plus bits produced by `decompose`
+pub(crate) fn is_generated_witness(name: &str) -> bool {
+    lofit::r1cs::is_computed_witness(name) || name.contains("_bit_")
+}
+
+// names compare piecewise, with digit runs compared as numbers so `a[2]` sorts before `a[10]`
+fn wire_name_order(a: &str, b: &str) -> std::cmp::Ordering {
+    fn chunks(name: &str) -> Vec<(bool, usize, &str)> {
+        let mut chunks = Vec::new();
+        let mut rest = name;
+        while let Some(first) = rest.chars().next() {
+            let digits = first.is_ascii_digit();
+            let end = rest
+                .find(|c: char| c.is_ascii_digit() != digits)
+                .unwrap_or(rest.len());
+            let (chunk, tail) = rest.split_at(end);
+            if digits {
+                let number = chunk.trim_start_matches('0');
+                chunks.push((true, number.len(), number));
+            } else {
+                chunks.push((false, 0, chunk));
+            }
+            rest = tail;
+        }
+        chunks
+    }
+    chunks(a).cmp(&chunks(b)).then_with(|| a.cmp(b))
+}
+
+fn is_one(lc: &LinearCombination) -> bool {
+    matches!(lc.terms.as_slice(), [(var, coeff)] if var == "ONE" && *coeff == BigInt::from(1))
+}
+
+fn scaled(lc: &LinearCombination, factor: &BigInt) -> LinearCombination {
+    let mut scaled = LinearCombination {
+        terms: lc
+            .terms
+            .iter()
+            .map(|(var, coeff)| (var.clone(), coeff * factor))
+            .collect(),
+    };
+    scaled.terms.retain(|(_, coeff)| *coeff != BigInt::from(0));
+    scaled
+}
+
+// value of a combination made only of `ONE` terms
+fn constant_value(lc: &LinearCombination) -> BigInt {
+    lc.terms.iter().map(|(_, coeff)| coeff).sum()
+}
+
+// equal as field elements, so `p === 0` holds
+fn same_field_value(left: BigInt, right: BigInt) -> bool {
+    (left - right) % field_modulus() == BigInt::from(0)
+}
+
+fn field_modulus() -> BigInt {
+    crate::ir::FIELD_MODULUS.parse().unwrap()
+}
+
+fn bits_for(value: u64) -> usize {
+    (u64::BITS - value.leading_zeros()) as usize
+}
+
+// width of a non-negative `Field<min..max>` bound
+fn declared_range_bits(typ: &Type) -> Option<usize> {
+    match typ {
+        Type::Field {
+            refinement: Some(Refinement::Range { min, max }),
+            ..
+        } if *min >= 0 => Some(bits_for(*max as u64)),
+        _ => None,
+    }
+}
+
+// whether lowering `expr` produces its value rather than the zero combination
+fn yields_value(expr: &Expression) -> bool {
+    match expr {
+        Expression::Tuple(_) => false,
+        Expression::Block { final_expr, .. } => final_expr.as_deref().is_some_and(yields_value),
+        Expression::Ascription { value, .. } => yields_value(value),
+        _ => !expr.is_assertion(),
+    }
+}
+
+impl R1CSConstraint {
+    // `x * 1 = ...`, the shape used to bind a fresh wire to a linear combination
+    fn scaled_copy_target(&self) -> Option<&str> {
+        match (self.a.terms.as_slice(), self.b.terms.as_slice()) {
+            ([(var, a)], [(one, b)])
+                if var != "ONE"
+                    && one == "ONE"
+                    && *a == BigInt::from(1)
+                    && *b == BigInt::from(1) =>
+            {
+                Some(var)
+            }
+            _ => None,
+        }
+    }
+
+    // `(b_0 + 2*b_1 + 4*b_2 + ...) * 1 = x`, returning `x` and the bit count
+    fn recomposition_target(&self) -> Option<(&str, usize)> {
+        let target = match (self.b.terms.as_slice(), self.c.terms.as_slice()) {
+            ([(one, b)], [(var, c)])
+                if one == "ONE" && *b == BigInt::from(1) && *c == BigInt::from(1) =>
+            {
+                var.as_str()
+            }
+            _ => return None,
+        };
+        let weighted = self
+            .a
+            .terms
+            .iter()
+            .enumerate()
+            .all(|(i, (var, coeff))| var != "ONE" && *coeff == BigInt::from(1) << i);
+        (self.a.terms.len() > 1 && weighted).then_some((target, self.a.terms.len()))
+    }
+
+    // x * (1 - x) = 0
+    fn is_boolean_enforcement(&self) -> bool {
+        let single = |lc: &LinearCombination| match lc.terms.as_slice() {
+            [(var, coeff)] if var != "ONE" && *coeff == BigInt::from(1) => Some(var.clone()),
+            _ => None,
+        };
+        let one_minus = |lc: &LinearCombination| match lc.terms.as_slice() {
+            [(one, c1), (var, c2)] | [(var, c2), (one, c1)]
+                if one == "ONE" && *c1 == BigInt::from(1) && *c2 == BigInt::from(-1) =>
+            {
+                Some(var.clone())
+            }
+            _ => None,
+        };
+
+        self.c.terms.is_empty()
+            && ((single(&self.a).is_some() && single(&self.a) == one_minus(&self.b))
+                || (single(&self.b).is_some() && single(&self.b) == one_minus(&self.a)))
+    }
 }
 
 impl LinearCombination {
+    // zero is the empty sum, never `ONE * 0`
+    pub fn zero() -> Self {
+        LinearCombination { terms: Vec::new() }
+    }
+
+    // the terms that get written out: a zero-coefficient `ONE` term contributes nothing
+    fn significant_terms(&self) -> impl Iterator<Item = &(String, BigInt)> {
+        self.terms
+            .iter()
+            .filter(|(var, coeff)| !(var == "ONE" && *coeff == BigInt::from(0)))
+    }
+
+    fn is_constant(&self) -> bool {
+        self.terms.iter().all(|(var, _)| var == "ONE")
+    }
+
+    // like terms are merged in place, so `a - b + b` is just `a`; term order is kept because
+    // the statistics read bit decompositions by position
     fn add(&mut self, other: &LinearCombination) {
-        self.terms.extend(other.terms.clone());
+        for (var, coeff) in &other.terms {
+            match self.terms.iter_mut().find(|(existing, _)| existing == var) {
+                Some((_, existing)) => *existing += coeff,
+                None => self.terms.push((var.clone(), coeff.clone())),
+            }
+        }
+        self.terms.retain(|(_, coeff)| *coeff != BigInt::from(0));
+    }
+
+    // terms sorted by variable with like terms merged and zero coefficients dropped
+    fn normalized(&self) -> Vec<(String, BigInt)> {
+        let mut merged: BTreeMap<&str, BigInt> = BTreeMap::new();
+        for (var, coeff) in &self.terms {
+            *merged.entry(var.as_str()).or_default() += coeff;
+        }
+        merged
+            .into_iter()
+            .filter(|(_, coeff)| *coeff != BigInt::from(0))
+            .map(|(var, coeff)| (var.to_string(), coeff))
+            .collect()
     }
 
     fn negate(&self) -> LinearCombination {
@@ -1517,6 +3310,16 @@ impl LinearCombination {
     }
 }
 
+// `input x`, naming the signal a declared range or boolean check comes from
+fn signal_declaration(signal: &Signal) -> String {
+    let keyword = match signal.visibility {
+        Visibility::Input => "input",
+        Visibility::Witness => "witness",
+        Visibility::Output => "output",
+    };
+    format!("{} {}", keyword, signal.name)
+}
+
 impl std::fmt::Display for R1CSConstraint {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "(")?;
@@ -1547,7 +3350,11 @@ impl std::fmt::Display for R1CSConstraint {
 impl fmt::Display for R1CSError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
-            R1CSError::NonQuadratic => write!(f, "Non-quadratic constraint"),
+            R1CSError::NonQuadratic(index) => write!(
+                f,
+                "Non-quadratic constraint {}: A and B must be linear combinations of circuit wires",
+                index
+            ),
             R1CSError::InvalidExpression => write!(f, "Invalid expression in R1CS"),
             R1CSError::FileError => write!(f, "Error writing R1CS file"),
             R1CSError::UnsupportedOperation(op) => write!(f, "Unsupported operation: {}", op),
@@ -1555,6 +3362,26 @@ impl fmt::Display for R1CSError {
             R1CSError::InvalidArgument(arg) => write!(f, "Invalid argument: {}", arg),
             R1CSError::TypeError(msg) => write!(f, "Type error: {}", msg),
             R1CSError::UnknownVariable(var) => write!(f, "Unknown variable: {}", var),
+            R1CSError::NoConstraints(proof) => write!(
+                f,
+                "Proof '{}' generated no constraints, so it would accept any inputs",
+                proof
+            ),
+            R1CSError::RecursionLimitExceeded(function) => write!(
+                f,
+                "Function '{}' is recursive or nested too deeply to be inlined",
+                function
+            ),
+            R1CSError::UnsatisfiableConstant(constraint) => write!(
+                f,
+                "Constraint {} compares constants that differ, so no proof can satisfy it",
+                constraint
+            ),
+            R1CSError::NonAffineOperand(operand) => write!(
+                f,
+                "Comparison operand `{}` is not an affine combination of circuit wires",
+                operand
+            ),
         }
     }
 }
@@ -1576,7 +3403,8 @@ pub fn read_r1cs_file(path: &PathBuf) -> std::io::Result<R1CSGenerator> {
 
     let mut version = [0u8; 4];
     reader.read_exact(&mut version)?;
-    if u32::from_le_bytes(version) != 1 {
+    let version = u32::from_le_bytes(version);
+    if version == 0 || version > lofit::r1cs::R1CS_VERSION {
         return Err(std::io::Error::new(
             std::io::ErrorKind::InvalidData,
             "Unsupported r1cs version",
@@ -1584,6 +3412,22 @@ pub fn read_r1cs_file(path: &PathBuf) -> std::io::Result<R1CSGenerator> {
     }
 
     let mut buf = [0u8; 4];
+
+    // version 1 files predate the modulus and name and were always BN254
+    let mut temp_scope = String::new();
+    if version >= 2 {
+        let modulus = BigInt::from_bytes_le(num_bigint::Sign::Plus, &read_bytes(&mut reader)?);
+        if modulus != field_modulus() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!(
+                    "R1CS file targets the field with modulus {}, not BN254",
+                    modulus
+                ),
+            ));
+        }
+        temp_scope = read_string(&mut reader)?;
+    }
     reader.read_exact(&mut buf)?;
     let pub_inputs_count = u32::from_le_bytes(buf);
 
@@ -1593,28 +3437,19 @@ pub fn read_r1cs_file(path: &PathBuf) -> std::io::Result<R1CSGenerator> {
     reader.read_exact(&mut buf)?;
     let constraints_count = u32::from_le_bytes(buf);
 
+    // the declared witness count follows from the names here, the generator doesn't keep it
+    if version >= 3 {
+        reader.read_exact(&mut buf)?;
+    }
+
     let mut pub_inputs = Vec::new();
     for _ in 0..pub_inputs_count {
-        reader.read_exact(&mut buf)?;
-        let len = u32::from_le_bytes(buf) as usize;
-        let mut name = vec![0u8; len];
-        reader.read_exact(&mut name)?;
-        pub_inputs.push(
-            String::from_utf8(name)
-                .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?,
-        );
+        pub_inputs.push(read_string(&mut reader)?);
     }
 
     let mut witnesses = Vec::new();
     for _ in 0..witnesses_count {
-        reader.read_exact(&mut buf)?;
-        let len = u32::from_le_bytes(buf) as usize;
-        let mut name = vec![0u8; len];
-        reader.read_exact(&mut name)?;
-        witnesses.push(
-            String::from_utf8(name)
-                .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?,
-        );
+        witnesses.push(read_string(&mut reader)?);
     }
 
     let mut constraints = Vec::new();
@@ -1628,6 +3463,7 @@ pub fn read_r1cs_file(path: &PathBuf) -> std::io::Result<R1CSGenerator> {
     Ok(R1CSGenerator {
         constraints,
         temp_var_counter: 0,
+        temp_scope,
         symbol_map: HashMap::new(),
         variable_substitutions: HashMap::new(),
         pub_inputs,
@@ -1636,11 +3472,46 @@ pub fn read_r1cs_file(path: &PathBuf) -> std::io::Result<R1CSGenerator> {
             variables: HashMap::new(),
         },
         function_defs: HashMap::new(),
+        component_defs: HashMap::new(),
         arrays: HashMap::new(),
         boolean_vars: HashSet::new(),
+        hints: HashSet::new(),
+        range_checked: HashMap::new(),
+        decompositions: HashMap::new(),
+        enum_constructors: HashMap::new(),
+        enum_values: HashMap::new(),
+        constants: HashMap::new(),
+        max_inline_depth: DEFAULT_MAX_INLINE_DEPTH,
+        inline_stack: Vec::new(),
+        // the file already fixes the layout, so wires keep the order they were read in
+        wire_indices: HashMap::new(),
+        constraint_sources: Vec::new(),
+        current_source: None,
+        error_sites: Vec::new(),
     })
 }
 
+// the length prefix only caps the read, so a corrupt one cannot size a huge allocation
+fn read_bytes<R: Read>(reader: &mut R) -> std::io::Result<Vec<u8>> {
+    let mut buf = [0u8; 4];
+    reader.read_exact(&mut buf)?;
+    let len = u64::from(u32::from_le_bytes(buf));
+    let mut bytes = Vec::new();
+    reader.take(len).read_to_end(&mut bytes)?;
+    if (bytes.len() as u64) < len {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::UnexpectedEof,
+            "R1CS file ends inside a length-prefixed field",
+        ));
+    }
+    Ok(bytes)
+}
+
+fn read_string<R: Read>(reader: &mut R) -> std::io::Result<String> {
+    String::from_utf8(read_bytes(reader)?)
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))
+}
+
 fn read_linear_combination<R: Read>(reader: &mut R) -> std::io::Result<LinearCombination> {
     let mut buf = [0u8; 4];
     reader.read_exact(&mut buf)?;
@@ -1651,12 +3522,7 @@ fn read_linear_combination<R: Read>(reader: &mut R) -> std::io::Result<LinearCom
         reader.read_exact(&mut buf)?;
         let var_idx = u32::from_le_bytes(buf);
 
-        reader.read_exact(&mut buf)?;
-        let bytes_len = u32::from_le_bytes(buf) as usize;
-        let mut bytes = vec![0u8; bytes_len];
-        reader.read_exact(&mut bytes)?;
-
-        let coeff = BigInt::from_signed_bytes_le(&bytes);
+        let coeff = BigInt::from_signed_bytes_le(&read_bytes(reader)?);
 
         terms.push((format!("var_{}", var_idx), coeff));
     }
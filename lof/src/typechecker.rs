@@ -1,11 +1,112 @@
-use crate::ast::{ConstraintStatus, Expression, Operator, Pattern, Refinement, Type, Visibility};
+use crate::ast::{
+    ConstraintStatus, Expression, Operator, Parameter, Pattern, Refinement, Signal, Site, Type,
+    Visibility, ASSERTION_BUILTINS,
+};
+use crate::r1cs::is_generated_witness;
+use num_bigint::BigInt;
 use std::collections::{HashMap, HashSet};
 use std::fmt;
 
+// builtins and the constant wire; a user binding with one of these names would be silently
+// replaced (or, for `ONE`, merged into the constant 1) when lowering to R1CS
+const RESERVED_NAMES: &[&str] = &[
+    "ONE",
+    "decompose",
+    "compose",
+    "rotl",
+    "shr",
+    "xor3",
+    "assert_eq",
+    "assert_zero",
+];
+
 pub struct TypeChecker {
     symbols: HashMap<String, Type>,
     witnesses: HashSet<String>,
     dependencies: HashMap<String, HashSet<String>>,
+    type_aliases: HashMap<String, Type>,
+    enum_types: HashSet<String>,
+    constructors: HashMap<String, (String, Vec<Type>)>,
+    used_variables: HashSet<String>,
+    warnings: Vec<TypeWarning>,
+    // where each warning was raised, innermost first, alongside `warnings`
+    warning_sites: Vec<Vec<Site>>,
+    // the statements being checked, outermost first
+    statements: Vec<Expression>,
+    // where the error being returned was raised, innermost first
+    error_sites: Vec<Site>,
+    constants: HashMap<String, BigInt>,
+    allow_unconstrained: bool,
+}
+
+// reported without failing the check
+#[derive(Debug, Clone, PartialEq)]
+pub enum TypeWarning {
+    UnusedInput(String),
+    UnconstrainedWitness(String),
+    // both sides of an equality are the same expression, naming its variables
+    TrivialAssertion(String),
+    // the variables a zero literal is multiplied with
+    MultipliedByZero(String),
+    // a `refined` signal whose predicate the proof body never asserts
+    UncheckedRefinement(String),
+}
+
+impl fmt::Display for TypeWarning {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TypeWarning::UnusedInput(name) => write!(
+                f,
+                "Public input '{}' is never used in the proof body, \
+                 but still has to be supplied to the verifier",
+                name
+            ),
+            TypeWarning::UnconstrainedWitness(name) => write!(
+                f,
+                "Witness '{}' is unconstrained; a malicious prover can set it to anything",
+                name
+            ),
+            TypeWarning::TrivialAssertion(names) => write!(
+                f,
+                "Assertion compares '{}' with itself, so it always holds and constrains nothing",
+                names
+            ),
+            TypeWarning::MultipliedByZero(names) => write!(
+                f,
+                "'{}' is multiplied by zero, so the result never depends on it",
+                names
+            ),
+            TypeWarning::UncheckedRefinement(name) => write!(
+                f,
+                "The refinement on '{}' is never asserted in the proof body; \
+                 add an assertion of its predicate or a prover can violate it",
+                name
+            ),
+        }
+    }
+}
+
+impl TypeWarning {
+    pub fn subject(&self) -> Option<&str> {
+        match self {
+            TypeWarning::UnusedInput(name)
+            | TypeWarning::UnconstrainedWitness(name)
+            | TypeWarning::UncheckedRefinement(name) => Some(name),
+            TypeWarning::TrivialAssertion(names) | TypeWarning::MultipliedByZero(names) => {
+                names.split(", ").next()
+            }
+        }
+    }
+
+    pub fn code(&self) -> &'static str {
+        match self {
+            TypeWarning::UnusedInput(_) => "unused_input",
+            TypeWarning::UnconstrainedWitness(_) => "unconstrained_witness",
+            TypeWarning::TrivialAssertion(_) => "trivial_assertion",
+            TypeWarning::MultipliedByZero(_) => "multiplied_by_zero",
+            TypeWarning::UncheckedRefinement(_) => "unchecked_refinement",
+        }
+    }
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -13,15 +114,50 @@ pub enum TypeError {
     UndefinedVariable(String),
     UndefinedFunction(String),
     UndefinedType(String),
-    TypeMismatch { expected: Type, found: Type },
-    ArgumentCountMismatch { expected: usize, found: usize },
-    PatternMismatch { expected: Type, found: Pattern },
+    TypeMismatch {
+        expected: Type,
+        found: Type,
+    },
+    // `arm` counts from 1, in source order
+    MatchArmTypeMismatch {
+        arm: usize,
+        expected: Type,
+        found: Type,
+    },
+    ArrayLengthMismatch {
+        expected: usize,
+        found: usize,
+    },
+    ArgumentCountMismatch {
+        expected: usize,
+        found: usize,
+    },
+    PatternMismatch {
+        expected: Type,
+        found: Pattern,
+    },
     NonBooleanInAssert(Type),
     EmptyMatchExpression,
     DuplicatePatternVariable(String),
     InvalidExpression,
-    UnconstrainedWitness { name: String, witness_type: Type },
-    NonZeroRequired { found: Type },
+    UnconstrainedWitness {
+        name: String,
+        witness_type: Type,
+    },
+    NonZeroRequired {
+        found: Type,
+    },
+    CyclicTypeAlias(String),
+    NonConstantInitializer(String),
+    CannotInferReturnType(String),
+    InvalidBuiltinArgument {
+        builtin: String,
+        reason: String,
+    },
+    InvalidBitsWidth(Type),
+    ReservedName(String),
+    RecursiveComponent(String),
+    InvalidModulus(String),
 }
 
 impl fmt::Display for TypeError {
@@ -30,9 +166,60 @@ impl fmt::Display for TypeError {
             TypeError::UndefinedVariable(name) => write!(f, "Undefined variable: {}", name),
             TypeError::UndefinedFunction(name) => write!(f, "Undefined function: {}", name),
             TypeError::UndefinedType(name) => write!(f, "Undefined type: {}", name),
+            TypeError::CyclicTypeAlias(name) => {
+                write!(f, "Type alias '{}' is defined in terms of itself", name)
+            }
+            TypeError::CannotInferReturnType(name) => write!(
+                f,
+                "Cannot infer the return type of '{}', add a `: Type` annotation",
+                name
+            ),
+            TypeError::InvalidBuiltinArgument { builtin, reason } => {
+                write!(f, "Invalid argument to '{}': {}", builtin, reason)
+            }
+            TypeError::ReservedName(name) => write!(
+                f,
+                "'{}' is reserved by the compiler and cannot be used as a name",
+                name
+            ),
+            TypeError::RecursiveComponent(name) => write!(
+                f,
+                "Component '{}' uses itself through other components, so it cannot be inlined",
+                name
+            ),
+            TypeError::InvalidModulus(divisor) => write!(
+                f,
+                "The right side of `%` must be a positive constant below 2^63, found {}",
+                divisor
+            ),
+            TypeError::InvalidBitsWidth(typ) => write!(
+                f,
+                "{} must have between 1 and {} bits",
+                typ,
+                crate::ir::field_bits()
+            ),
+            TypeError::NonConstantInitializer(name) => write!(
+                f,
+                "Constant '{}' must be built from literals and earlier constants using +, - and *",
+                name
+            ),
             TypeError::TypeMismatch { expected, found } => {
                 write!(f, "Type mismatch: expected {}, found {}", expected, found)
             }
+            TypeError::MatchArmTypeMismatch {
+                arm,
+                expected,
+                found,
+            } => write!(
+                f,
+                "Match arm {} has type {}, but the first arm has type {}",
+                arm, found, expected
+            ),
+            TypeError::ArrayLengthMismatch { expected, found } => write!(
+                f,
+                "Array length mismatch: expected {} elements, found {}",
+                expected, found
+            ),
             TypeError::EmptyMatchExpression => {
                 write!(f, "Match expression must have at least one pattern")
             }
@@ -52,7 +239,7 @@ impl fmt::Display for TypeError {
             TypeError::PatternMismatch { expected, found } => {
                 write!(
                     f,
-                    "Pattern mismatch: pattern {:?} does not match type {}",
+                    "Pattern mismatch: pattern {} does not match type {}",
                     found, expected
                 )
             }
@@ -83,6 +270,54 @@ impl fmt::Display for TypeError {
     }
 }
 
+impl TypeError {
+    /// Name of the identifier the error is about, used to locate it in the source.
+    pub fn subject(&self) -> Option<&str> {
+        match self {
+            TypeError::UndefinedVariable(name)
+            | TypeError::UndefinedFunction(name)
+            | TypeError::UndefinedType(name)
+            | TypeError::DuplicatePatternVariable(name)
+            | TypeError::CyclicTypeAlias(name)
+            | TypeError::NonConstantInitializer(name)
+            | TypeError::CannotInferReturnType(name)
+            | TypeError::ReservedName(name)
+            | TypeError::RecursiveComponent(name)
+            | TypeError::UnconstrainedWitness { name, .. } => Some(name),
+            TypeError::InvalidBuiltinArgument { builtin, .. } => Some(builtin),
+            _ => None,
+        }
+    }
+
+    /// Stable identifier for the error kind, for tools that consume diagnostics.
+    pub fn code(&self) -> &'static str {
+        match self {
+            TypeError::UndefinedVariable(_) => "undefined_variable",
+            TypeError::UndefinedFunction(_) => "undefined_function",
+            TypeError::UndefinedType(_) => "undefined_type",
+            TypeError::TypeMismatch { .. } => "type_mismatch",
+            TypeError::MatchArmTypeMismatch { .. } => "match_arm_type_mismatch",
+            TypeError::ArrayLengthMismatch { .. } => "array_length_mismatch",
+            TypeError::ArgumentCountMismatch { .. } => "argument_count_mismatch",
+            TypeError::PatternMismatch { .. } => "pattern_mismatch",
+            TypeError::NonBooleanInAssert(_) => "non_boolean_assert",
+            TypeError::EmptyMatchExpression => "empty_match",
+            TypeError::DuplicatePatternVariable(_) => "duplicate_pattern_variable",
+            TypeError::InvalidExpression => "invalid_expression",
+            TypeError::UnconstrainedWitness { .. } => "unconstrained_witness",
+            TypeError::NonZeroRequired { .. } => "non_zero_required",
+            TypeError::CyclicTypeAlias(_) => "cyclic_type_alias",
+            TypeError::NonConstantInitializer(_) => "non_constant_initializer",
+            TypeError::CannotInferReturnType(_) => "cannot_infer_return_type",
+            TypeError::InvalidBuiltinArgument { .. } => "invalid_builtin_argument",
+            TypeError::InvalidBitsWidth(_) => "invalid_bits_width",
+            TypeError::ReservedName(_) => "reserved_name",
+            TypeError::RecursiveComponent(_) => "recursive_component",
+            TypeError::InvalidModulus(_) => "invalid_modulus",
+        }
+    }
+}
+
 impl Default for TypeChecker {
     fn default() -> Self {
         Self::new()
@@ -95,9 +330,39 @@ impl TypeChecker {
             symbols: HashMap::new(),
             witnesses: HashSet::new(),
             dependencies: HashMap::new(),
+            type_aliases: HashMap::new(),
+            enum_types: HashSet::new(),
+            constructors: HashMap::new(),
+            used_variables: HashSet::new(),
+            warnings: Vec::new(),
+            warning_sites: Vec::new(),
+            statements: Vec::new(),
+            error_sites: Vec::new(),
+            constants: HashMap::new(),
+            allow_unconstrained: false,
         }
     }
 
+    pub fn warnings(&self) -> &[TypeWarning] {
+        &self.warnings
+    }
+
+    /// The statements or signals each warning was raised in, innermost first.
+    pub fn warning_sites(&self) -> &[Vec<Site>] {
+        &self.warning_sites
+    }
+
+    /// Where the last failed check was raised, innermost first; see `SourceMap::locate`.
+    pub fn error_sites(&self) -> &[Site] {
+        &self.error_sites
+    }
+
+    // report unconstrained witnesses as warnings instead of failing, for circuits whose
+    // witnesses are constrained outside the proof
+    pub fn set_allow_unconstrained(&mut self, allow: bool) {
+        self.allow_unconstrained = allow;
+    }
+
     fn field_type(constraint: ConstraintStatus, refinement: Option<Refinement>) -> Type {
         Type::Field {
             constraint,
@@ -117,8 +382,60 @@ impl TypeChecker {
         matches!(typ, Type::Bool { .. })
     }
 
+    fn is_nat_type(typ: &Type) -> bool {
+        matches!(typ, Type::Nat)
+    }
+
     fn is_numeric_type(typ: &Type) -> bool {
-        matches!(typ, Type::Field { .. } | Type::Bool { .. })
+        matches!(typ, Type::Field { .. } | Type::Bool { .. } | Type::Nat)
+    }
+
+    fn is_ordered_type(typ: &Type) -> bool {
+        matches!(typ, Type::Field { .. } | Type::Nat)
+    }
+
+    fn is_nat_literal(expr: &Expression) -> bool {
+        matches!(expr, Expression::Number(n) if *n >= 0)
+    }
+
+    // a non-negative literal next to a Nat operand is read as a Nat, so `i + 1` stays a Nat
+    fn coerce_nat_literal(expr: &Expression, typ: Type, other: &Type) -> Type {
+        if Self::is_nat_type(other) && Self::is_nat_literal(expr) {
+            Type::Nat
+        } else {
+            typ
+        }
+    }
+
+    fn is_unconstrained(typ: &Type) -> bool {
+        matches!(
+            typ,
+            Type::Field {
+                constraint: ConstraintStatus::Unconstrained,
+                ..
+            } | Type::Bool {
+                constraint: ConstraintStatus::Unconstrained
+            }
+        )
+    }
+
+    fn report_unconstrained(
+        &mut self,
+        proof: &str,
+        name: String,
+        witness_type: Type,
+    ) -> Result<(), TypeError> {
+        let site = Site::Signal {
+            owner: proof.to_string(),
+            name: name.clone(),
+        };
+        if self.allow_unconstrained {
+            self.record_warning(TypeWarning::UnconstrainedWitness(name), vec![site]);
+            Ok(())
+        } else {
+            self.error_sites.push(site);
+            Err(TypeError::UnconstrainedWitness { name, witness_type })
+        }
     }
 
     fn promote_to_constrained_direct(&mut self, var_name: &str) {
@@ -238,6 +555,152 @@ impl TypeChecker {
         }
     }
 
+    fn describe_vars(&self, expr: &Expression) -> Option<String> {
+        let mut vars = HashSet::new();
+        self.extract_vars(expr, &mut vars);
+        let mut vars: Vec<String> = vars
+            .into_iter()
+            .filter(|v| !self.constants.contains_key(v))
+            .collect();
+        vars.sort();
+        (!vars.is_empty()).then(|| vars.join(", "))
+    }
+
+    // `x === x` and friends hold for every assignment, yet still count as constraining `x`
+    fn warn_trivial_assertion(&mut self, condition: &Expression) {
+        if let Expression::BinaryOp { left, op, right } = condition {
+            if matches!(
+                op,
+                Operator::Assert | Operator::Equal | Operator::Ge | Operator::Le
+            ) && left == right
+            {
+                if let Some(names) = self.describe_vars(left) {
+                    self.push_warning(TypeWarning::TrivialAssertion(names));
+                }
+            }
+        }
+    }
+
+    fn warn_multiplied_by_zero(&mut self, left: &Expression, right: &Expression) {
+        let other = match (left, right) {
+            (_, Expression::Number(0)) => left,
+            (Expression::Number(0), _) => right,
+            _ => return,
+        };
+        if let Some(names) = self.describe_vars(other) {
+            self.push_warning(TypeWarning::MultipliedByZero(names));
+        }
+    }
+
+    // component and function bodies are checked more than once
+    fn push_warning(&mut self, warning: TypeWarning) {
+        let sites = self.statement_sites();
+        self.push_warning_at(warning, sites);
+    }
+
+    fn push_warning_at(&mut self, warning: TypeWarning, sites: Vec<Site>) {
+        if !self.warnings.contains(&warning) {
+            self.record_warning(warning, sites);
+        }
+    }
+
+    fn record_warning(&mut self, warning: TypeWarning, sites: Vec<Site>) {
+        self.warnings.push(warning);
+        self.warning_sites.push(sites);
+    }
+
+    fn signal_site(proof: &str, signal: &Signal) -> Site {
+        Site::Signal {
+            owner: proof.to_string(),
+            name: signal.name.clone(),
+        }
+    }
+
+    fn statement_sites(&self) -> Vec<Site> {
+        self.statements
+            .iter()
+            .rev()
+            .map(|statement| Site::Statement(statement.clone()))
+            .collect()
+    }
+
+    // an error raised while checking `statement` is reported at the innermost statement
+    // around it that the source map can place
+    fn check_statement(&mut self, statement: &Expression) -> Result<Type, TypeError> {
+        if self.statements.is_empty() {
+            self.error_sites.clear();
+        }
+        self.statements.push(statement.clone());
+        let result = self.check_expression(statement);
+        if result.is_err() && self.error_sites.is_empty() {
+            self.error_sites = self.statement_sites();
+        }
+        self.statements.pop();
+        result
+    }
+
+    // conditions asserted unconditionally: at the top level, in blocks and under `let`
+    fn collect_assertions<'a>(expr: &'a Expression, asserted: &mut Vec<&'a Expression>) {
+        match expr {
+            Expression::Assert(condition) => Self::conjuncts(condition, asserted),
+            Expression::Block {
+                statements,
+                final_expr,
+            } => {
+                for stmt in statements.iter().chain(final_expr.as_deref()) {
+                    Self::collect_assertions(stmt, asserted);
+                }
+            }
+            Expression::Let { body, .. } => Self::collect_assertions(body, asserted),
+            _ => {}
+        }
+    }
+
+    fn conjuncts<'a>(expr: &'a Expression, out: &mut Vec<&'a Expression>) {
+        match expr {
+            Expression::BinaryOp {
+                left,
+                op: Operator::And,
+                right,
+            } => {
+                Self::conjuncts(left, out);
+                Self::conjuncts(right, out);
+            }
+            _ => out.push(expr),
+        }
+    }
+
+    // equal up to swapping the sides of a comparison, so `0 < x` discharges `x > 0`
+    fn same_condition(a: &Expression, b: &Expression) -> bool {
+        if a == b {
+            return true;
+        }
+        let (
+            Expression::BinaryOp {
+                left: l1,
+                op: op1,
+                right: r1,
+            },
+            Expression::BinaryOp {
+                left: l2,
+                op: op2,
+                right: r2,
+            },
+        ) = (a, b)
+        else {
+            return false;
+        };
+        let flipped = match op1 {
+            Operator::Gt => Operator::Lt,
+            Operator::Lt => Operator::Gt,
+            Operator::Ge => Operator::Le,
+            Operator::Le => Operator::Ge,
+            Operator::Equal | Operator::NotEqual | Operator::Assert => *op1,
+            _ => return false,
+        };
+        *op2 == flipped && l1 == r2 && r1 == l2
+    }
+
     fn read_variable(&self, name: &str) -> Result<Type, TypeError> {
         let var_type = self
             .symbols
@@ -253,6 +716,8 @@ impl TypeChecker {
         match (pattern, typ) {
             (Pattern::Variable(_), _) | (Pattern::Wildcard, _) => Ok(()),
             (Pattern::Literal(_), Type::Field { .. }) => Ok(()),
+            // a bool is the field value 0 or 1, so those two literals are its only patterns
+            (Pattern::Literal(0 | 1), Type::Bool { .. }) => Ok(()),
             (Pattern::Tuple(patterns), Type::Tuple(types)) => {
                 if patterns.len() != types.len() {
                     return Err(TypeError::PatternMismatch {
@@ -302,6 +767,8 @@ impl TypeChecker {
         match (type1, type2) {
             (Type::Field { .. }, Type::Field { .. }) => true,
             (Type::Bool { .. }, Type::Bool { .. }) => true,
+            (Type::Nat, Type::Nat) => true,
+            (Type::Custom(a), Type::Custom(b)) => a == b,
             (Type::Tuple(t1), Type::Tuple(t2)) => {
                 t1.len() == t2.len()
                     && t1
@@ -341,19 +808,93 @@ impl TypeChecker {
     }
 
     pub fn check_program(&mut self, program: &[Expression]) -> Result<(), TypeError> {
+        self.check_program_with_warnings(program).0
+    }
+
+    // warnings raised before a failure are returned with the error, not dropped
+    pub fn check_program_with_warnings(
+        &mut self,
+        program: &[Expression],
+    ) -> (Result<(), TypeError>, Vec<TypeWarning>) {
+        let result = self.check_items(program);
+        (result, self.warnings.clone())
+    }
+
+    fn check_items(&mut self, program: &[Expression]) -> Result<(), TypeError> {
+        self.error_sites.clear();
+        Self::check_declared_names(program)?;
+
+        for expr in program {
+            match expr {
+                Expression::TypeAlias { name, typ } => {
+                    self.type_aliases.insert(name.clone(), typ.clone());
+                }
+                Expression::EnumDef { name, .. } => {
+                    self.enum_types.insert(name.clone());
+                }
+                _ => {}
+            }
+        }
+
+        // variant fields may name enums declared later, so register constructors after all names
+        for expr in program {
+            if let Expression::EnumDef { name, variants } = expr {
+                for variant in variants {
+                    let fields = variant
+                        .fields
+                        .iter()
+                        .map(|t| self.resolve_type(t))
+                        .collect::<Result<Vec<_>, _>>()?;
+                    self.constructors
+                        .insert(variant.name.clone(), (name.clone(), fields));
+                }
+            }
+        }
+
+        // constants are visible to every proof and function, in declaration order
+        for expr in program {
+            if let Expression::Const { name, value } = expr {
+                let folded = value
+                    .fold_constant(&self.constants)
+                    .ok_or_else(|| TypeError::NonConstantInitializer(name.clone()))?;
+                let refinement = if folded == BigInt::from(0) {
+                    None
+                } else {
+                    Some(Refinement::NonZero)
+                };
+                self.symbols.insert(
+                    name.clone(),
+                    Self::field_type(ConstraintStatus::Constrained, refinement),
+                );
+                self.constants.insert(name.clone(), folded);
+            }
+        }
+
+        let component_names: HashSet<&str> = program
+            .iter()
+            .filter_map(|expr| match expr {
+                Expression::Component { name, .. } => Some(name.as_str()),
+                _ => None,
+            })
+            .collect();
+        let mut deferred = Vec::new();
+
         for expr in program {
             match expr {
                 Expression::FunctionDef {
                     name,
                     params,
                     return_type,
-                    ..
+                    body,
                 } => {
                     let resolved_params = params
                         .iter()
                         .map(|p| self.resolve_type(&p.typ))
                         .collect::<Result<Vec<_>, _>>()?;
-                    let resolved_return_type = self.resolve_type(return_type)?;
+                    let resolved_return_type = match return_type {
+                        Some(return_type) => self.resolve_type(return_type)?,
+                        None => self.infer_return_type(name, params, body)?,
+                    };
 
                     let function_type = if resolved_params.is_empty() {
                         resolved_return_type
@@ -368,41 +909,149 @@ impl TypeChecker {
                     signals,
                     body,
                     ..
-                } => {
-                    let input_params: Vec<Type> = signals
-                        .iter()
-                        .filter(|s| s.visibility == Visibility::Input)
-                        .map(|s| self.resolve_type(&s.typ))
-                        .collect::<Result<Vec<_>, _>>()?;
-
-                    let saved_symbols = self.symbols.clone();
+                } => match self.register_component(name, signals, body) {
+                    // a component may use one declared after it, so it waits for that one
+                    Err(TypeError::UndefinedFunction(callee))
+                        if component_names.contains(callee.as_str()) =>
+                    {
+                        self.error_sites.clear();
+                        deferred.push((name, signals, body));
+                    }
+                    result => result?,
+                },
+                _ => {}
+            }
+        }
 
-                    for signal in signals {
-                        let resolved_type = self.resolve_type(&signal.typ)?;
-                        self.symbols.insert(signal.name.clone(), resolved_type);
+        while !deferred.is_empty() {
+            let waiting = deferred.len();
+            for (name, signals, body) in std::mem::take(&mut deferred) {
+                match self.register_component(name, signals, body) {
+                    Err(TypeError::UndefinedFunction(callee))
+                        if component_names.contains(callee.as_str()) =>
+                    {
+                        self.error_sites.clear();
+                        deferred.push((name, signals, body));
                     }
+                    result => result?,
+                }
+            }
+            // what is still waiting only waits on itself, through other components
+            if deferred.len() == waiting {
+                let (name, ..) = deferred[0];
+                return Err(TypeError::RecursiveComponent(name.clone()));
+            }
+        }
 
-                    let return_type = self.check_expression(body)?;
+        for expr in program {
+            self.check_expression(expr)?;
+        }
+        Ok(())
+    }
 
-                    self.symbols = saved_symbols;
+    // a component's type is its inputs curried into the type of its body
+    fn register_component(
+        &mut self,
+        name: &str,
+        signals: &[Signal],
+        body: &Expression,
+    ) -> Result<(), TypeError> {
+        let input_params: Vec<Type> = signals
+            .iter()
+            .filter(|s| s.visibility == Visibility::Input)
+            .map(|s| self.resolve_type(&s.typ))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let saved_symbols = self.symbols.clone();
+        let return_type = signals
+            .iter()
+            .try_for_each(|signal| {
+                let resolved_type = self.resolve_type(&signal.typ)?;
+                self.symbols.insert(signal.name.clone(), resolved_type);
+                Ok(())
+            })
+            .and_then(|()| self.check_expression(body));
+        self.symbols = saved_symbols;
 
-                    let component_type = if input_params.is_empty() {
-                        return_type
-                    } else {
-                        self.build_curried_function_type(input_params, return_type)
-                    };
+        let return_type = return_type?;
+        let component_type = if input_params.is_empty() {
+            return_type
+        } else {
+            self.build_curried_function_type(input_params, return_type)
+        };
+        self.symbols.insert(name.to_string(), component_type);
+        Ok(())
+    }
 
-                    self.symbols.insert(name.clone(), component_type);
+    // besides the builtins, the names the compiler gives its own wires: a witness called `t_9`
+    // or `x_bit_0` would be taken for a temporary or a decomposition bit
+    fn ensure_not_reserved(name: &str) -> Result<(), TypeError> {
+        if RESERVED_NAMES.contains(&name) || is_generated_witness(name) {
+            return Err(TypeError::ReservedName(name.to_string()));
+        }
+        Ok(())
+    }
+
+    // top-level names and the parameters and signals they declare; `let` and `match`
+    // bindings are checked where they are bound
+    fn check_declared_names(program: &[Expression]) -> Result<(), TypeError> {
+        for expr in program {
+            match expr {
+                Expression::Const { name, .. } => Self::ensure_not_reserved(name)?,
+                Expression::FunctionDef { name, params, .. } => {
+                    Self::ensure_not_reserved(name)?;
+                    for param in params {
+                        Self::ensure_not_reserved(&param.name)?;
+                    }
+                }
+                Expression::Component { name, signals, .. } => {
+                    Self::ensure_not_reserved(name)?;
+                    for signal in signals {
+                        Self::ensure_not_reserved(&signal.name)?;
+                    }
+                }
+                Expression::Proof { signals, .. } => {
+                    for signal in signals {
+                        Self::ensure_not_reserved(&signal.name)?;
+                    }
+                }
+                Expression::EnumDef { variants, .. } => {
+                    for variant in variants {
+                        Self::ensure_not_reserved(&variant.name)?;
+                    }
                 }
                 _ => {}
             }
         }
+        Ok(())
+    }
 
-        for expr in program {
-            self.check_expression(expr)?;
+    // functions without an annotation take their body's type, so they can only call
+    // functions declared before them
+    fn infer_return_type(
+        &mut self,
+        name: &str,
+        params: &[Parameter],
+        body: &Expression,
+    ) -> Result<Type, TypeError> {
+        let saved_symbols = self.symbols.clone();
+        for param in params {
+            let resolved_type = self.resolve_type(&param.typ)?;
+            self.symbols.insert(param.name.clone(), resolved_type);
+        }
+
+        let result = self.check_expression(body);
+        self.symbols = saved_symbols;
+
+        match result {
+            Err(TypeError::UndefinedFunction(callee)) if callee == name => {
+                Err(TypeError::CannotInferReturnType(name.to_string()))
+            }
+            Ok(Type::GenericType(_)) => Err(TypeError::CannotInferReturnType(name.to_string())),
+            other => other,
         }
-        Ok(())
     }
+
     fn build_curried_function_type(&self, params: Vec<Type>, return_type: Type) -> Type {
         if params.is_empty() {
             return_type
@@ -423,6 +1072,198 @@ impl TypeChecker {
         }
     }
 
+    // `decompose(x)` or `decompose(x, width)`: the generator splits a value into `width` bits
+    // (8 by default), so the width has to be a literal that fits the field
+    fn check_decompose(&mut self, arguments: &[Expression]) -> Result<Type, TypeError> {
+        let invalid = |reason: String| TypeError::InvalidBuiltinArgument {
+            builtin: "decompose".to_string(),
+            reason,
+        };
+
+        let (value, width) = match arguments {
+            [value] => (value, None),
+            [value, width] => (value, Some(width)),
+            _ => {
+                return Err(TypeError::ArgumentCountMismatch {
+                    expected: if arguments.is_empty() { 1 } else { 2 },
+                    found: arguments.len(),
+                })
+            }
+        };
+
+        let value_type = self.check_expression(value)?;
+
+        // `decompose(bits)` on a `Bits<N>` signal reads the integer its bits spell out
+        if let (Type::Array { element_type, size }, Expression::Variable(name)) =
+            (&value_type, value)
+        {
+            if Self::is_bool_type(element_type) {
+                return match width {
+                    None => Ok(Self::field_type(ConstraintStatus::Constrained, None)),
+                    Some(Expression::Number(n)) if *n == *size as i64 => {
+                        Ok(Self::field_type(ConstraintStatus::Constrained, None))
+                    }
+                    Some(_) => Err(invalid(format!(
+                        "'{}' already has {} bits, so the width must be {} or left out",
+                        name, size, size
+                    ))),
+                };
+            }
+        }
+        if !Self::is_field_type(&value_type) {
+            return Err(self.mismatch(
+                Self::field_type(ConstraintStatus::Unconstrained, None),
+                value_type,
+            ));
+        }
+
+        let field_bits = crate::ir::field_bits();
+        if let Some(width) = width {
+            match width {
+                Expression::Number(n) if (1..=field_bits as i64).contains(n) => {}
+                _ => {
+                    return Err(invalid(format!(
+                        "the bit width must be a literal between 1 and {}",
+                        field_bits
+                    )))
+                }
+            }
+        }
+
+        // the recomposition constraint ties the value to its bits
+        let mut vars = HashSet::new();
+        self.extract_vars(value, &mut vars);
+        for var in vars {
+            self.promote_to_constrained(&var);
+        }
+        Ok(Self::field_type(ConstraintStatus::Constrained, None))
+    }
+
+    fn check_compose(&mut self, arguments: &[Expression]) -> Result<Type, TypeError> {
+        let [bits] = arguments else {
+            return Err(TypeError::ArgumentCountMismatch {
+                expected: 1,
+                found: arguments.len(),
+            });
+        };
+
+        let bits_type = self.check_expression(bits)?;
+        let is_decompose = matches!(
+            bits,
+            Expression::FunctionCall { function, .. }
+                if function == "decompose" && !self.symbols.contains_key(function)
+        );
+        let field_bits = crate::ir::field_bits();
+        match &bits_type {
+            // field elements are enforced boolean when the bits are summed
+            Type::Array { element_type, size }
+                if (Self::is_bool_type(element_type) || Self::is_field_type(element_type))
+                    && (1..=field_bits).contains(size) => {}
+            Type::Field { .. } if is_decompose => {}
+            _ => {
+                return Err(TypeError::InvalidBuiltinArgument {
+                    builtin: "compose".to_string(),
+                    reason: format!(
+                        "expected an array of 1 to {} bits or a decompose call, found {}",
+                        field_bits, bits_type
+                    ),
+                })
+            }
+        }
+
+        let mut vars = HashSet::new();
+        self.extract_vars(bits, &mut vars);
+        for var in vars {
+            self.promote_to_constrained(&var);
+        }
+        Ok(Self::field_type(ConstraintStatus::Constrained, None))
+    }
+
+    // `rotl(x, k, width)`, `shr(x, k, width)` and `xor3(a, b, c)` (optionally with a width)
+    // take field operands and literal shifts and widths; decomposing an operand constrains it
+    fn check_bitwise(
+        &mut self,
+        function: &str,
+        arguments: &[Expression],
+    ) -> Result<Type, TypeError> {
+        let invalid = |reason: String| TypeError::InvalidBuiltinArgument {
+            builtin: function.to_string(),
+            reason,
+        };
+
+        let (operands, shift, width) = match (function, arguments) {
+            ("xor3", [_, _, _]) => (arguments, None, None),
+            ("xor3", [_, _, _, width]) => (&arguments[..3], None, Some(width)),
+            ("xor3", _) => {
+                return Err(TypeError::ArgumentCountMismatch {
+                    expected: if arguments.len() < 3 { 3 } else { 4 },
+                    found: arguments.len(),
+                })
+            }
+            (_, [value, shift, width]) => (std::slice::from_ref(value), Some(shift), Some(width)),
+            _ => {
+                return Err(TypeError::ArgumentCountMismatch {
+                    expected: 3,
+                    found: arguments.len(),
+                })
+            }
+        };
+
+        let field_bits = crate::ir::field_bits();
+        let width = match width {
+            Some(Expression::Number(n)) if (1..=field_bits as i64).contains(n) => *n,
+            Some(_) => {
+                return Err(invalid(format!(
+                    "the bit width must be a literal between 1 and {}",
+                    field_bits
+                )))
+            }
+            None => crate::ir::XOR3_DEFAULT_WIDTH as i64,
+        };
+        match shift {
+            Some(Expression::Number(n)) if (0..width).contains(n) => {}
+            Some(_) => {
+                return Err(invalid(format!(
+                    "the shift must be a literal below the bit width {}",
+                    width
+                )))
+            }
+            None => {}
+        }
+
+        for operand in operands {
+            let operand_type = self.check_expression(operand)?;
+            if !Self::is_field_type(&operand_type) {
+                return Err(self.mismatch(
+                    Self::field_type(ConstraintStatus::Unconstrained, None),
+                    operand_type,
+                ));
+            }
+            let mut vars = HashSet::new();
+            self.extract_vars(operand, &mut vars);
+            for var in vars {
+                self.promote_to_constrained(&var);
+            }
+        }
+        Ok(Self::field_type(ConstraintStatus::Constrained, None))
+    }
+
+    // checked as the `assert a === b` they stand for, so operands follow the `===` rules
+    fn check_assertion_builtin(
+        &mut self,
+        function: &str,
+        arguments: &[Expression],
+    ) -> Result<Type, TypeError> {
+        let expected = if function == "assert_eq" { 2 } else { 1 };
+        let assertion = Expression::desugar_assertion(function, arguments).ok_or(
+            TypeError::ArgumentCountMismatch {
+                expected,
+                found: arguments.len(),
+            },
+        )?;
+        self.check_expression(&assertion)
+    }
+
     fn apply_function(
         &mut self,
         mut function_type: Type,
@@ -453,10 +1294,7 @@ impl TypeChecker {
                     let argument_type = self.check_expression(argument)?;
 
                     if !self.types_compatible(&expected_param, &argument_type) {
-                        return Err(TypeError::TypeMismatch {
-                            expected: expected_param,
-                            found: argument_type,
-                        });
+                        return Err(self.mismatch(expected_param, argument_type));
                     }
 
                     function_type = *return_type;
@@ -473,6 +1311,34 @@ impl TypeChecker {
         Ok(function_type)
     }
 
+    fn check_constructor(
+        &mut self,
+        constructor: &str,
+        arguments: &[Expression],
+    ) -> Result<Type, TypeError> {
+        let (enum_name, field_types) = self
+            .constructors
+            .get(constructor)
+            .cloned()
+            .ok_or_else(|| TypeError::UndefinedFunction(constructor.to_string()))?;
+
+        if arguments.len() != field_types.len() {
+            return Err(TypeError::ArgumentCountMismatch {
+                expected: field_types.len(),
+                found: arguments.len(),
+            });
+        }
+
+        for (argument, field_type) in arguments.iter().zip(field_types.iter()) {
+            let arg_type = self.check_expression(argument)?;
+            if !self.types_compatible(field_type, &arg_type) {
+                return Err(self.mismatch(field_type.clone(), arg_type));
+            }
+        }
+
+        Ok(Type::Custom(enum_name))
+    }
+
     pub fn check_expression(&mut self, expr: &Expression) -> Result<Type, TypeError> {
         match expr {
             Expression::Number(value) => {
@@ -483,7 +1349,22 @@ impl TypeChecker {
                 };
                 Ok(Self::field_type(ConstraintStatus::Constrained, refinement))
             }
-            Expression::Variable(name) => self.read_variable(name),
+            // only literals outside the i64 range reach here, so they are never zero
+            Expression::BigNumber(_) => Ok(Self::field_type(
+                ConstraintStatus::Constrained,
+                Some(Refinement::NonZero),
+            )),
+            Expression::Variable(name) => {
+                self.used_variables.insert(name.clone());
+                match self.read_variable(name) {
+                    Err(TypeError::UndefinedVariable(_))
+                        if self.constructors.contains_key(name) =>
+                    {
+                        self.check_constructor(name, &[])
+                    }
+                    result => result,
+                }
+            }
             Expression::Let {
                 pattern,
                 value,
@@ -493,6 +1374,9 @@ impl TypeChecker {
 
                 let mut bound_variables = Vec::new();
                 Self::collect_pattern_variables(pattern, &mut bound_variables);
+                for var in &bound_variables {
+                    Self::ensure_not_reserved(var)?;
+                }
 
                 let mut previous_symbols = HashMap::new();
                 let mut previous_dependencies = HashMap::new();
@@ -546,7 +1430,14 @@ impl TypeChecker {
                 let left_type = self.check_expression(left)?;
                 let right_type = self.check_expression(right)?;
 
-                if matches!(op, Operator::Mul | Operator::Div | Operator::Assert) {
+                if *op == Operator::Mul {
+                    self.warn_multiplied_by_zero(left, right);
+                }
+
+                if matches!(
+                    op,
+                    Operator::Mul | Operator::Div | Operator::Mod | Operator::Assert
+                ) {
                     let mut vars = HashSet::new();
                     self.extract_vars(left, &mut vars);
                     self.extract_vars(right, &mut vars);
@@ -559,7 +1450,22 @@ impl TypeChecker {
                     self.ensure_nonzero_field(right, &right_type)?;
                 }
 
-                self.check_operator(op, &left_type, &right_type)
+                // `%` lowers to a range-checked remainder, which needs the divisor at compile time
+                let divisor = if matches!(op, Operator::Mod) {
+                    let divisor = right
+                        .fold_constant(&self.constants)
+                        .and_then(|n| i64::try_from(n).ok())
+                        .filter(|n| *n > 0)
+                        .ok_or_else(|| TypeError::InvalidModulus(right.to_string()))?;
+                    Some(divisor)
+                } else {
+                    None
+                };
+
+                let left_type = Self::coerce_nat_literal(left, left_type, &right_type);
+                let right_type = Self::coerce_nat_literal(right, right_type, &left_type);
+
+                self.check_operator(op, &left_type, &right_type, divisor)
             }
             Expression::Tuple(elements) => {
                 let types = elements
@@ -574,6 +1480,8 @@ impl TypeChecker {
                     return Err(TypeError::NonBooleanInAssert(cond_type));
                 }
 
+                self.warn_trivial_assertion(condition);
+
                 // promote all variables in the condition to constrained
                 // for constraint equality (===), promote transitively because they create R1CS constraints
                 // for comparison assertions (>, <, ==, etc.), only promote direct variables
@@ -615,15 +1523,15 @@ impl TypeChecker {
                 let body_type = self.check_expression(body)?;
                 self.symbols = original_symbols;
 
+                let Some(return_type) = return_type else {
+                    return Ok(Type::Unit);
+                };
                 let expected_return_type = self.resolve_type(return_type)?;
 
                 // use compatibility check instead of exact equality
                 // this allows field^constrained to match field^unconstrained in return types
                 if !self.types_compatible(&body_type, &expected_return_type) {
-                    return Err(TypeError::TypeMismatch {
-                        expected: expected_return_type,
-                        found: body_type,
-                    });
+                    return Err(self.mismatch(expected_return_type, body_type));
                 }
                 Ok(Type::Unit)
             }
@@ -631,6 +1539,31 @@ impl TypeChecker {
                 function,
                 arguments,
             } => {
+                if !self.symbols.contains_key(function) && self.constructors.contains_key(function)
+                {
+                    return self.check_constructor(function, arguments);
+                }
+
+                if !self.symbols.contains_key(function) && function == "decompose" {
+                    return self.check_decompose(arguments);
+                }
+
+                if !self.symbols.contains_key(function) && function == "compose" {
+                    return self.check_compose(arguments);
+                }
+
+                if !self.symbols.contains_key(function)
+                    && matches!(function.as_str(), "rotl" | "shr" | "xor3")
+                {
+                    return self.check_bitwise(function, arguments);
+                }
+
+                if !self.symbols.contains_key(function)
+                    && ASSERTION_BUILTINS.contains(&function.as_str())
+                {
+                    return self.check_assertion_builtin(function, arguments);
+                }
+
                 let function_type = self
                     .symbols
                     .get(function)
@@ -639,17 +1572,28 @@ impl TypeChecker {
 
                 self.apply_function(function_type, arguments)
             }
-            Expression::Proof { signals, body, .. } => {
+            Expression::Proof {
+                name: proof,
+                signals,
+                body,
+                ..
+            } => {
                 let saved_symbols = self.symbols.clone();
                 let saved_dependencies = self.dependencies.clone();
                 let saved_witnesses = std::mem::take(&mut self.witnesses);
+                let saved_used_variables = std::mem::take(&mut self.used_variables);
 
                 let result = (|| -> Result<Type, TypeError> {
                     for signal in signals {
-                        let resolved_type = self.resolve_type(&signal.typ)?;
-
-                        // inputs are inherently constrained (they're public)
-                        let final_type = if signal.visibility == Visibility::Input {
+                        let resolved_type = self.resolve_type(&signal.typ).inspect_err(|_| {
+                            self.error_sites = vec![Site::Signal {
+                                owner: proof.clone(),
+                                name: signal.name.clone(),
+                            }]
+                        })?;
+
+                        // inputs and outputs are inherently constrained (they're public)
+                        let final_type = if signal.visibility != Visibility::Witness {
                             match resolved_type {
                                 Type::Field { refinement, .. } => {
                                     Self::field_type(ConstraintStatus::Constrained, refinement)
@@ -668,68 +1612,59 @@ impl TypeChecker {
                         }
                     }
 
-                    let body_type = self.check_expression(body)?;
-
-                    // validate all witnesses are constrained
-                    for witness_name in &self.witnesses {
-                        if let Some(typ) = self.symbols.get(witness_name) {
-                            let is_unconstrained = matches!(
-                                typ,
-                                Type::Field {
-                                    constraint: ConstraintStatus::Unconstrained,
-                                    ..
-                                } | Type::Bool {
-                                    constraint: ConstraintStatus::Unconstrained
-                                }
-                            );
+                    let body_type = self.check_statement(body)?;
 
-                            if is_unconstrained {
-                                return Err(TypeError::UnconstrainedWitness {
-                                    name: witness_name.clone(),
-                                    witness_type: typ.clone(),
-                                });
+                    // refinements generate no constraints, so the body has to assert them
+                    let mut asserted = Vec::new();
+                    Self::collect_assertions(body, &mut asserted);
+                    for signal in signals {
+                        if let Type::Refined(_, predicate) = &signal.typ {
+                            let mut required = Vec::new();
+                            Self::conjuncts(predicate, &mut required);
+                            if !required.iter().all(|needed| {
+                                asserted
+                                    .iter()
+                                    .any(|held| Self::same_condition(needed, held))
+                            }) {
+                                self.push_warning_at(
+                                    TypeWarning::UncheckedRefinement(signal.name.clone()),
+                                    vec![Self::signal_site(proof, signal)],
+                                );
                             }
                         }
                     }
 
+                    for signal in signals {
+                        if signal.visibility == Visibility::Input
+                            && !self.used_variables.contains(&signal.name)
+                        {
+                            self.record_warning(
+                                TypeWarning::UnusedInput(signal.name.clone()),
+                                vec![Self::signal_site(proof, signal)],
+                            );
+                        }
+                    }
+
+                    // validate all witnesses are constrained
                     let mut body_vars = HashSet::new();
                     self.extract_vars(body, &mut body_vars);
 
-                    for var_name in &body_vars {
-                        if let Some(typ) = self.symbols.get(var_name) {
-                            let is_unconstrained = matches!(
-                                typ,
-                                Type::Field {
-                                    constraint: ConstraintStatus::Unconstrained,
-                                    ..
-                                } | Type::Bool {
-                                    constraint: ConstraintStatus::Unconstrained
-                                }
-                            );
-
-                            if is_unconstrained {
-                                return Err(TypeError::UnconstrainedWitness {
-                                    name: var_name.clone(),
-                                    witness_type: typ.clone(),
-                                });
+                    let mut unconstrained: Vec<(String, Type)> = Vec::new();
+                    for name in self.witnesses.iter().chain(&body_vars) {
+                        if let Some(typ) = self.symbols.get(name) {
+                            if Self::is_unconstrained(typ)
+                                && !unconstrained.iter().any(|(seen, _)| seen == name)
+                            {
+                                unconstrained.push((name.clone(), typ.clone()));
                             }
                         }
                     }
+                    if Self::is_unconstrained(&body_type) {
+                        unconstrained.push(("<proof body result>".to_string(), body_type));
+                    }
 
-                    match body_type {
-                        Type::Field {
-                            constraint: ConstraintStatus::Unconstrained,
-                            ..
-                        }
-                        | Type::Bool {
-                            constraint: ConstraintStatus::Unconstrained,
-                        } => {
-                            return Err(TypeError::UnconstrainedWitness {
-                                name: "<proof body result>".to_string(),
-                                witness_type: body_type,
-                            });
-                        }
-                        _ => {}
+                    for (name, witness_type) in unconstrained {
+                        self.report_unconstrained(proof, name, witness_type)?;
                     }
 
                     Ok(Type::Unit)
@@ -738,6 +1673,7 @@ impl TypeChecker {
                 self.symbols = saved_symbols;
                 self.dependencies = saved_dependencies;
                 self.witnesses = saved_witnesses;
+                self.used_variables = saved_used_variables;
 
                 result
             }
@@ -746,12 +1682,16 @@ impl TypeChecker {
                 final_expr,
             } => {
                 for stmt in statements {
-                    self.check_expression(stmt)?;
+                    self.check_statement(stmt)?;
                 }
-                let result_type = if let Some(expr) = final_expr {
-                    self.check_expression(expr)?
-                } else {
-                    Type::Unit
+                // an assertion in final position is still a statement, the block has no value
+                let result_type = match final_expr {
+                    Some(expr) if expr.is_assertion() => {
+                        self.check_statement(expr)?;
+                        Type::Unit
+                    }
+                    Some(expr) => self.check_statement(expr)?,
+                    None => Type::Unit,
                 };
 
                 Ok(result_type)
@@ -777,16 +1717,34 @@ impl TypeChecker {
                     self.check_pattern_compatibility(&pattern_arm.pattern, &scrutinee_type)?;
                     self.bind_pattern(&pattern_arm.pattern, &scrutinee_type)?;
 
+                    let mut guard_vars = HashSet::new();
+                    if let Some(guard) = &pattern_arm.guard {
+                        let guard_type = self.check_expression(guard)?;
+                        if !Self::is_bool_type(&guard_type) {
+                            return Err(TypeError::TypeMismatch {
+                                expected: Self::bool_type(ConstraintStatus::Unconstrained),
+                                found: guard_type,
+                            });
+                        }
+                        self.extract_vars(guard, &mut guard_vars);
+                    }
+
                     let arm_type = self.check_expression(&pattern_arm.body)?;
                     arm_types.push(arm_type);
 
                     self.symbols = original_symbols;
+
+                    // the guard feeds the arm selector, so its free variables are constrained
+                    for var in guard_vars {
+                        self.promote_to_constrained(&var);
+                    }
                 }
 
                 if let Some(first_type) = arm_types.first() {
-                    for arm_type in arm_types.iter().skip(1) {
+                    for (arm, arm_type) in arm_types.iter().enumerate().skip(1) {
                         if !self.types_compatible(first_type, arm_type) {
-                            return Err(TypeError::TypeMismatch {
+                            return Err(TypeError::MatchArmTypeMismatch {
+                                arm: arm + 1,
                                 expected: first_type.clone(),
                                 found: arm_type.clone(),
                             });
@@ -797,6 +1755,35 @@ impl TypeChecker {
                     Err(TypeError::EmptyMatchExpression)
                 }
             }
+            Expression::Ascription { value, typ } => {
+                let expected = self.resolve_type(typ)?;
+                match value.as_ref() {
+                    // nothing to infer from, the annotation is the type
+                    Expression::ArrayLiteral(elements) if elements.is_empty() => match expected {
+                        Type::Array { size: 0, .. } => Ok(expected),
+                        Type::Array { size, .. } => Err(TypeError::ArrayLengthMismatch {
+                            expected: size,
+                            found: 0,
+                        }),
+                        _ => Err(TypeError::TypeMismatch {
+                            expected,
+                            found: Type::Array {
+                                element_type: Box::new(Type::Unit),
+                                size: 0,
+                            },
+                        }),
+                    },
+                    _ => {
+                        let found = self.check_expression(value)?;
+                        if self.types_compatible(&expected, &found) {
+                            Ok(found)
+                        } else {
+                            Err(self.mismatch(expected, found))
+                        }
+                    }
+                }
+            }
+
             Expression::ArrayLiteral(elements) => {
                 if elements.is_empty() {
                     return Err(TypeError::InvalidExpression);
@@ -823,9 +1810,9 @@ impl TypeChecker {
                 let array_type = self.check_expression(array)?;
                 let index_type = self.check_expression(index)?;
 
-                if !Self::is_field_type(&index_type) {
+                if !Self::is_nat_type(&index_type) && !Self::is_nat_literal(index) {
                     return Err(TypeError::TypeMismatch {
-                        expected: Self::field_type(ConstraintStatus::Constrained, None),
+                        expected: Type::Nat,
                         found: index_type,
                     });
                 }
@@ -851,6 +1838,8 @@ impl TypeChecker {
 
             Expression::TypeAlias { .. } => Ok(Type::Unit),
 
+            Expression::Const { .. } => Ok(Type::Unit),
+
             Expression::EnumDef { .. } => Ok(Type::Unit),
 
             Expression::Component { signals, body, .. } => {
@@ -892,23 +1881,63 @@ impl TypeChecker {
         }
     }
 
-    #[allow(clippy::only_used_in_recursion)]
     fn resolve_type(&self, typ: &Type) -> Result<Type, TypeError> {
+        self.resolve_type_through_aliases(typ, &mut Vec::new())
+    }
+
+    fn resolve_type_through_aliases(
+        &self,
+        typ: &Type,
+        expanding: &mut Vec<String>,
+    ) -> Result<Type, TypeError> {
         match typ {
             Type::Identifier(name) => match name.as_str() {
                 "field" => Ok(Self::field_type(ConstraintStatus::Unconstrained, None)),
                 "bool" => Ok(Self::bool_type(ConstraintStatus::Unconstrained)),
+                "nat" => Ok(Type::Nat),
                 "unit" => Ok(Type::Unit),
-                _ => Err(TypeError::UndefinedType(name.clone())),
+                _ if self.enum_types.contains(name) => Ok(Type::Custom(name.clone())),
+                _ => {
+                    let aliased = self
+                        .type_aliases
+                        .get(name)
+                        .ok_or_else(|| TypeError::UndefinedType(name.clone()))?;
+
+                    if expanding.contains(name) {
+                        return Err(TypeError::CyclicTypeAlias(name.clone()));
+                    }
+
+                    expanding.push(name.clone());
+                    let resolved = self.resolve_type_through_aliases(aliased, expanding)?;
+                    expanding.pop();
+
+                    Ok(resolved)
+                }
             },
             Type::Tuple(elements) => {
                 let resolved_elements = elements
                     .iter()
-                    .map(|t| self.resolve_type(t))
+                    .map(|t| self.resolve_type_through_aliases(t, expanding))
                     .collect::<Result<Vec<_>, _>>()?;
                 Ok(Type::Tuple(resolved_elements))
             }
-            Type::Refined(base_type, _predicate) => self.resolve_type(base_type),
+            Type::Array { element_type, size } => Ok(Type::Array {
+                element_type: Box::new(self.resolve_type_through_aliases(element_type, expanding)?),
+                size: *size,
+            }),
+            Type::Refined(base_type, _predicate) => {
+                self.resolve_type_through_aliases(base_type, expanding)
+            }
+            Type::Bits(_) => {
+                let size = typ
+                    .bits_width()
+                    .filter(|width| (1..=crate::ir::field_bits()).contains(width))
+                    .ok_or_else(|| TypeError::InvalidBitsWidth(typ.clone()))?;
+                Ok(Type::Array {
+                    element_type: Box::new(Self::bool_type(ConstraintStatus::Unconstrained)),
+                    size,
+                })
+            }
             _ => Ok(typ.clone()),
         }
     }
@@ -933,6 +1962,11 @@ impl TypeChecker {
     fn bind_pattern(&mut self, pattern: &Pattern, typ: &Type) -> Result<(), TypeError> {
         let mut bound_vars = HashSet::new();
         self.check_pattern_duplicates(pattern, &mut bound_vars)?;
+        let mut names = Vec::new();
+        Self::collect_pattern_variables(pattern, &mut names);
+        for name in &names {
+            Self::ensure_not_reserved(name)?;
+        }
 
         match (pattern, typ) {
             (Pattern::Variable(name), _) => {
@@ -958,13 +1992,19 @@ impl TypeChecker {
         op: &Operator,
         left: &Type,
         right: &Type,
+        divisor: Option<i64>,
     ) -> Result<Type, TypeError> {
         match op {
             Operator::Add | Operator::Sub => {
                 let left_numeric = Self::is_numeric_type(left);
                 let right_numeric = Self::is_numeric_type(right);
 
-                if left_numeric && right_numeric {
+                if matches!(op, Operator::Add)
+                    && Self::is_nat_type(left)
+                    && Self::is_nat_type(right)
+                {
+                    Ok(Type::Nat)
+                } else if left_numeric && right_numeric {
                     Ok(Self::field_type(ConstraintStatus::Unconstrained, None))
                 } else {
                     Err(TypeError::TypeMismatch {
@@ -987,7 +2027,12 @@ impl TypeChecker {
                     true
                 };
 
-                if left_numeric && right_numeric && denominator_valid {
+                if matches!(op, Operator::Mul)
+                    && Self::is_nat_type(left)
+                    && Self::is_nat_type(right)
+                {
+                    Ok(Type::Nat)
+                } else if left_numeric && right_numeric && denominator_valid {
                     Ok(Self::field_type(ConstraintStatus::Constrained, None))
                 } else {
                     let offending = if !left_numeric {
@@ -1002,6 +2047,19 @@ impl TypeChecker {
                 }
             }
 
+            // the remainder is proven to lie in 0..=n-1, so it carries that bound
+            Operator::Mod => match divisor {
+                Some(n) if Self::is_numeric_type(left) => Ok(Self::field_type(
+                    ConstraintStatus::Constrained,
+                    Some(Refinement::Range { min: 0, max: n - 1 }),
+                )),
+                Some(_) => Err(TypeError::TypeMismatch {
+                    expected: Self::field_type(ConstraintStatus::Constrained, None),
+                    found: left.clone(),
+                }),
+                None => Err(TypeError::InvalidModulus(right.to_string())),
+            },
+
             Operator::Equal | Operator::NotEqual => {
                 if self.types_compatible(left, right) {
                     Ok(Self::bool_type(ConstraintStatus::Unconstrained))
@@ -1014,15 +2072,15 @@ impl TypeChecker {
             }
 
             Operator::Lt | Operator::Gt | Operator::Le | Operator::Ge => {
-                let left_is_field = Self::is_field_type(left);
-                let right_is_field = Self::is_field_type(right);
+                let left_ordered = Self::is_ordered_type(left);
+                let right_ordered = Self::is_ordered_type(right);
 
-                if left_is_field && right_is_field {
+                if left_ordered && right_ordered {
                     Ok(Self::bool_type(ConstraintStatus::Unconstrained))
                 } else {
                     Err(TypeError::TypeMismatch {
                         expected: Self::field_type(ConstraintStatus::Constrained, None),
-                        found: if !left_is_field {
+                        found: if !left_ordered {
                             left.clone()
                         } else {
                             right.clone()
@@ -1060,17 +2118,46 @@ impl TypeChecker {
             Operator::Assert => {
                 let compatible = self.types_compatible(left, right)
                     || (Self::is_bool_type(left) && Self::is_field_type(right))
-                    || (Self::is_field_type(left) && Self::is_bool_type(right));
+                    || (Self::is_field_type(left) && Self::is_bool_type(right))
+                    || (Self::is_nat_type(left) && Self::is_field_type(right))
+                    || (Self::is_field_type(left) && Self::is_nat_type(right));
 
                 if compatible {
                     Ok(Self::bool_type(ConstraintStatus::Constrained))
                 } else {
-                    Err(TypeError::TypeMismatch {
-                        expected: left.clone(),
-                        found: right.clone(),
-                    })
+                    Err(self.mismatch(left.clone(), right.clone()))
+                }
+            }
+        }
+    }
+
+    // arrays that only disagree on length get a dedicated error, anything else is a plain mismatch
+    fn mismatch(&self, expected: Type, found: Type) -> TypeError {
+        match self.array_length_mismatch(&expected, &found) {
+            Some((expected, found)) => TypeError::ArrayLengthMismatch { expected, found },
+            None => TypeError::TypeMismatch { expected, found },
+        }
+    }
+
+    fn array_length_mismatch(&self, expected: &Type, found: &Type) -> Option<(usize, usize)> {
+        match (expected, found) {
+            (
+                Type::Array {
+                    element_type: e1,
+                    size: s1,
+                },
+                Type::Array {
+                    element_type: e2,
+                    size: s2,
+                },
+            ) => {
+                if self.types_compatible(e1, e2) {
+                    (s1 != s2).then_some((*s1, *s2))
+                } else {
+                    self.array_length_mismatch(e1, e2)
                 }
             }
+            _ => None,
         }
     }
 }
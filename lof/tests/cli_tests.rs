@@ -0,0 +1,691 @@
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+const SOURCE: &str = r#"
+proof Square {
+    input x: Field;
+    witness y: Field;
+    assert y === x * x;
+}
+"#;
+
+fn write_source(dir: &Path) -> std::path::PathBuf {
+    let file = dir.join("square.lof");
+    fs::write(&file, SOURCE).expect("write source");
+    file
+}
+
+fn lof(args: &[&str]) -> std::process::Output {
+    Command::new(env!("CARGO_BIN_EXE_lof"))
+        .args(args)
+        .env("RUST_LOG", "error")
+        .output()
+        .expect("run lof binary")
+}
+
+#[test]
+fn test_compile_writes_both_artifacts_to_out_dir() {
+    let dir = tempfile::tempdir().unwrap();
+    let file = write_source(dir.path());
+    let out_dir = dir.path().join("artifacts");
+
+    let output = lof(&[
+        "compile",
+        file.to_str().unwrap(),
+        "--out-dir",
+        out_dir.to_str().unwrap(),
+    ]);
+    assert!(output.status.success(), "{:?}", output);
+
+    assert!(out_dir.join("square.r1cs").exists());
+    assert!(out_dir.join("square.ir").exists());
+    assert!(!dir.path().join("square.r1cs").exists());
+    assert!(!dir.path().join("square.ir").exists());
+}
+
+#[test]
+fn test_compile_emit_ir_only() {
+    let dir = tempfile::tempdir().unwrap();
+    let file = write_source(dir.path());
+    let out_dir = dir.path().join("artifacts");
+
+    let output = lof(&[
+        "compile",
+        file.to_str().unwrap(),
+        "--out-dir",
+        out_dir.to_str().unwrap(),
+        "--emit",
+        "ir",
+    ]);
+    assert!(output.status.success(), "{:?}", output);
+
+    assert!(out_dir.join("square.ir").exists());
+    assert!(!out_dir.join("square.r1cs").exists());
+    assert!(!dir.path().join("square.r1cs").exists());
+}
+
+#[test]
+fn test_check_writes_no_artifacts() {
+    let dir = tempfile::tempdir().unwrap();
+    let file = write_source(dir.path());
+
+    let output = lof(&["check", file.to_str().unwrap()]);
+    assert!(output.status.success(), "{:?}", output);
+
+    let entries: Vec<_> = fs::read_dir(dir.path())
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name())
+        .collect();
+    assert_eq!(entries, vec!["square.lof"]);
+}
+
+#[test]
+fn test_check_emit_constraints_annotates_each_constraint() {
+    let dir = tempfile::tempdir().unwrap();
+    let file = dir.path().join("multiply.lof");
+    fs::write(
+        &file,
+        "proof Multiply {\n    input a: field;\n    input b: field;\n    witness c: field;\n    assert c === a * b;\n}\n",
+    )
+    .unwrap();
+
+    let output = lof(&["check", file.to_str().unwrap(), "--emit-constraints"]);
+    assert!(output.status.success(), "{:?}", output);
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains("Constraints for proof Multiply"),
+        "{}",
+        stdout
+    );
+    assert!(
+        stdout.contains("(1*a) * (1*b) = (1*Multiply::t_0)  // assert c === a * b"),
+        "{}",
+        stdout
+    );
+    assert!(!dir.path().join("build").exists());
+}
+
+#[test]
+fn test_check_allow_unconstrained_warns_instead_of_failing() {
+    let dir = tempfile::tempdir().unwrap();
+    let file = dir.path().join("buggy.lof");
+    fs::write(
+        &file,
+        "proof Buggy {\n    input x: Field;\n    witness forgotten: Field;\n    assert x > 0\n}\n",
+    )
+    .unwrap();
+
+    let output = lof(&["check", file.to_str().unwrap()]);
+    assert!(!output.status.success());
+
+    let output = lof(&["check", file.to_str().unwrap(), "--allow-unconstrained"]);
+    assert!(output.status.success(), "{:?}", output);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains("Witness 'forgotten' is unconstrained"),
+        "{}",
+        stdout
+    );
+}
+
+#[test]
+fn test_templates_use_declared_signal_names() {
+    let dir = tempfile::tempdir().unwrap();
+    let file = dir.path().join("product.lof");
+    fs::write(
+        &file,
+        r#"
+proof Product {
+    input a: Field;
+    input b: Field;
+    witness c: Field;
+    assert c === a * b + a * a;
+}
+"#,
+    )
+    .unwrap();
+
+    let output = lof(&["compile", file.to_str().unwrap(), "-g"]);
+    assert!(output.status.success(), "{:?}", output);
+
+    let read_keys = |name: &str| -> Vec<String> {
+        let text = fs::read_to_string(dir.path().join("inputs").join(name)).unwrap();
+        let map: std::collections::BTreeMap<String, String> = serde_json::from_str(&text).unwrap();
+        assert!(map.values().all(|v| v == "0"));
+        map.into_keys().collect()
+    };
+
+    assert_eq!(read_keys("product_public.json"), vec!["a", "b"]);
+    assert_eq!(read_keys("product_witness.json"), vec!["c"]);
+}
+
+#[test]
+fn test_filled_templates_prove_and_verify() {
+    use lofit::inputs::{parse_inputs_in_order, parse_partial_witness, InputsJson};
+    use lofit::{generate_full_witness_with_provided, ConstraintSystem, LofCircuit, ProverKey};
+
+    let dir = tempfile::tempdir().unwrap();
+    let file = write_source(dir.path());
+
+    let output = lof(&["compile", file.to_str().unwrap(), "-g"]);
+    assert!(output.status.success(), "{:?}", output);
+
+    let inputs_dir = dir.path().join("inputs");
+    let fill = |name: &str, values: &[(&str, &str)]| -> InputsJson {
+        let path = inputs_dir.join(name);
+        let mut template: InputsJson =
+            serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        for (key, value) in values {
+            let slot = template
+                .get_mut(*key)
+                .unwrap_or_else(|| panic!("template {} has no '{}'", name, key));
+            *slot = value.to_string();
+        }
+        fs::write(&path, serde_json::to_string_pretty(&template).unwrap()).unwrap();
+        template
+    };
+    let public = fill("square_public.json", &[("x", "3")]);
+    let witness = fill("square_witness.json", &[("y", "9")]);
+
+    let r1cs_file = fs::File::open(dir.path().join("build").join("square.r1cs")).unwrap();
+    let r1cs = ConstraintSystem::from_file(r1cs_file).unwrap();
+
+    let pub_values = parse_inputs_in_order(&public, &r1cs.public_inputs).unwrap();
+    let provided = parse_partial_witness(&witness, &r1cs);
+    let wit_values = generate_full_witness_with_provided(&r1cs, &pub_values, &provided).unwrap();
+
+    let (pk, vk) = ProverKey::setup(LofCircuit {
+        public_inputs: vec![Default::default(); pub_values.len()],
+        witness: vec![Default::default(); wit_values.len()],
+        constraints: r1cs.constraints.clone(),
+    })
+    .unwrap();
+
+    let proof = pk
+        .prove(LofCircuit {
+            public_inputs: pub_values.clone(),
+            witness: wit_values,
+            constraints: r1cs.constraints.clone(),
+        })
+        .unwrap();
+    assert!(vk.verify(&proof, &pub_values).unwrap());
+}
+
+#[test]
+fn test_multiplication_temporaries_are_derived_during_proving() {
+    use lofit::inputs::{parse_inputs_in_order, parse_partial_witness, InputsJson};
+    use lofit::{generate_full_witness_with_provided, ConstraintSystem, LofCircuit, ProverKey};
+
+    let dir = tempfile::tempdir().unwrap();
+    let file = dir.path().join("cube.lof");
+    fs::write(
+        &file,
+        "proof Cube {\n    input out: Field;\n    witness y: Field;\n    assert out === y * y * y;\n}\n",
+    )
+    .unwrap();
+
+    let output = lof(&["compile", file.to_str().unwrap()]);
+    assert!(output.status.success(), "{:?}", output);
+
+    let r1cs_file = fs::File::open(dir.path().join("build").join("cube.r1cs")).unwrap();
+    let r1cs = ConstraintSystem::from_file(r1cs_file).unwrap();
+    assert!(r1cs
+        .witnesses
+        .iter()
+        .any(|name| name.starts_with("Cube::t_")));
+
+    let public: InputsJson = [("out".to_string(), "27".to_string())].into();
+    // a stale value for a temporary must not be trusted over the derived one
+    let witness: InputsJson = [
+        ("y".to_string(), "3".to_string()),
+        ("Cube::t_0".to_string(), "999".to_string()),
+    ]
+    .into();
+
+    let pub_values = parse_inputs_in_order(&public, &r1cs.public_inputs).unwrap();
+    let provided = parse_partial_witness(&witness, &r1cs);
+    assert_eq!(provided.len(), 1);
+    let wit_values = generate_full_witness_with_provided(&r1cs, &pub_values, &provided).unwrap();
+
+    let (pk, vk) = ProverKey::setup(LofCircuit {
+        public_inputs: vec![Default::default(); pub_values.len()],
+        witness: vec![Default::default(); wit_values.len()],
+        constraints: r1cs.constraints.clone(),
+    })
+    .unwrap();
+
+    let proof = pk
+        .prove(LofCircuit {
+            public_inputs: pub_values.clone(),
+            witness: wit_values,
+            constraints: r1cs.constraints.clone(),
+        })
+        .unwrap();
+    assert!(vk.verify(&proof, &pub_values).unwrap());
+}
+
+#[test]
+fn test_bounded_comparison_is_satisfied_only_in_range() {
+    use lofit::inputs::parse_inputs_in_order;
+    use lofit::{generate_full_witness, ConstraintSystem};
+
+    let dir = tempfile::tempdir().unwrap();
+    let file = dir.path().join("bounded.lof");
+    fs::write(
+        &file,
+        "proof Bounded {\n    input x: Field<0..255>;\n    assert x < 200;\n}\n",
+    )
+    .unwrap();
+
+    let output = lof(&["compile", file.to_str().unwrap()]);
+    assert!(output.status.success(), "{:?}", output);
+
+    let r1cs_file = fs::File::open(dir.path().join("build").join("bounded.r1cs")).unwrap();
+    let r1cs = ConstraintSystem::from_file(r1cs_file).unwrap();
+
+    let satisfied = |x: &str| {
+        let public = [("x".to_string(), x.to_string())].into();
+        let pub_values = parse_inputs_in_order(&public, &r1cs.public_inputs).unwrap();
+        let wit_values = generate_full_witness(&r1cs, &pub_values).unwrap();
+        r1cs.find_unsatisfied(&ConstraintSystem::full_assignment(&pub_values, &wit_values))
+            .is_none()
+    };
+
+    assert!(satisfied("0"));
+    assert!(satisfied("199"));
+    assert!(!satisfied("200"));
+    assert!(!satisfied("255"));
+    // outside the declared bound the range check on x itself fails
+    assert!(!satisfied("300"));
+}
+
+#[test]
+fn test_comparison_rejects_operand_outside_decomposition_range() {
+    use lofit::inputs::parse_inputs_in_order;
+    use lofit::{generate_full_witness, ConstraintSystem};
+
+    let dir = tempfile::tempdir().unwrap();
+    let file = dir.path().join("small.lof");
+    fs::write(
+        &file,
+        "proof Small {\n    input x: Field;\n    assert x < 10;\n}\n",
+    )
+    .unwrap();
+
+    let output = lof(&["compile", file.to_str().unwrap()]);
+    assert!(output.status.success(), "{:?}", output);
+
+    let r1cs_file = fs::File::open(dir.path().join("build").join("small.r1cs")).unwrap();
+    let r1cs = ConstraintSystem::from_file(r1cs_file).unwrap();
+
+    let satisfied = |x: &str| {
+        let public = [("x".to_string(), x.to_string())].into();
+        let pub_values = parse_inputs_in_order(&public, &r1cs.public_inputs).unwrap();
+        let wit_values = generate_full_witness(&r1cs, &pub_values).unwrap();
+        r1cs.find_unsatisfied(&ConstraintSystem::full_assignment(&pub_values, &wit_values))
+            .is_none()
+    };
+
+    assert!(satisfied("3"));
+    // p - 1 used to wrap the shifted difference back under 2^252 and pass as "less than 10"
+    assert!(!satisfied(
+        "21888242871839275222246405745257275088548364400416034343698204186575808495616"
+    ));
+}
+
+#[test]
+fn test_named_witness_keeps_comparison_temporaries_in_place() {
+    use lofit::inputs::{parse_inputs_in_order, parse_partial_witness};
+    use lofit::{generate_named_witness, ConstraintSystem};
+
+    let r1cs = lof::compile_dsl_to_constraint_system(
+        "proof Window {\n    input x: Field;\n    witness y: Field;\n    assert x < 10;\n    assert y === x * x;\n}\n",
+    )
+    .unwrap();
+    // the comparison adds bits and temporaries on top of the one declared witness
+    assert!(r1cs.witnesses.len() > 2);
+
+    let public = [("x".to_string(), "3".to_string())].into();
+    let witness = [("y".to_string(), "9".to_string())].into();
+    let pub_values = parse_inputs_in_order(&public, &r1cs.public_inputs).unwrap();
+    let provided = parse_partial_witness(&witness, &r1cs);
+    let named = generate_named_witness(&r1cs, &pub_values, &provided).unwrap();
+
+    assert_eq!(
+        named.keys().cloned().collect::<Vec<_>>(),
+        r1cs.witnesses,
+        "every wire keeps its own name"
+    );
+    let wit_values: Vec<_> = named.values().copied().collect();
+    assert!(r1cs
+        .find_unsatisfied(&ConstraintSystem::full_assignment(&pub_values, &wit_values))
+        .is_none());
+    assert_eq!(named["y"], lofit::fr_from_str("9").unwrap());
+}
+
+#[test]
+fn test_compile_writes_symbol_file() {
+    let dir = tempfile::tempdir().unwrap();
+    let file = write_source(dir.path());
+
+    let output = lof(&["compile", file.to_str().unwrap()]);
+    assert!(output.status.success(), "{:?}", output);
+
+    let sym_path = dir.path().join("build").join("square.sym");
+    let symbols = lofit::SymbolTable::from_file(&sym_path).unwrap();
+    assert_eq!(symbols.name(0), Some("ONE"));
+    assert_eq!(symbols.name(1), Some("x"));
+    assert_eq!(symbols.name(2), Some("y"));
+    assert!(!dir.path().join("square.sym").exists());
+}
+
+#[test]
+fn test_package_web_regenerates_missing_ir_from_source() {
+    let dir = tempfile::tempdir().unwrap();
+    let file = write_source(dir.path());
+
+    let output = lof(&["compile", file.to_str().unwrap(), "--emit", "r1cs"]);
+    assert!(output.status.success(), "{:?}", output);
+
+    let r1cs = dir.path().join("build").join("square.r1cs");
+    let ir = dir.path().join("build").join("square.ir");
+    assert!(r1cs.exists());
+    assert!(!ir.exists());
+
+    let resolved = lofit::package_web::ensure_ir_exists(&r1cs).unwrap();
+    assert_eq!(resolved, ir);
+    assert!(ir.exists());
+
+    // with neither IR nor source there is nothing to fall back on
+    fs::remove_file(&ir).unwrap();
+    fs::remove_file(&file).unwrap();
+    assert!(lofit::package_web::ensure_ir_exists(&r1cs).is_err());
+}
+
+#[test]
+fn test_check_watch_rechecks_on_change() {
+    use std::io::{BufRead, BufReader};
+    use std::process::Stdio;
+    use std::sync::mpsc;
+    use std::time::{Duration, Instant};
+
+    let dir = tempfile::tempdir().unwrap();
+    let file = write_source(dir.path());
+
+    let mut child = Command::new(env!("CARGO_BIN_EXE_lof"))
+        .args(["check", "--watch", file.to_str().unwrap()])
+        .env("RUST_LOG", "error")
+        .stdout(Stdio::piped())
+        .spawn()
+        .expect("spawn lof --watch");
+
+    let stdout = child.stdout.take().unwrap();
+    let (tx, rx) = mpsc::channel();
+    std::thread::spawn(move || {
+        for line in BufReader::new(stdout).lines().map_while(Result::ok) {
+            if tx.send(line).is_err() {
+                break;
+            }
+        }
+    });
+
+    let wait_for = |needle: &str| -> bool {
+        let deadline = Instant::now() + Duration::from_secs(20);
+        while let Some(remaining) = deadline.checked_duration_since(Instant::now()) {
+            match rx.recv_timeout(remaining) {
+                Ok(line) if line.contains(needle) => return true,
+                Ok(_) => continue,
+                Err(_) => return false,
+            }
+        }
+        false
+    };
+
+    assert!(wait_for("Watching"), "watcher never started");
+
+    fs::write(
+        &file,
+        "proof Square {\n    input x: Field;\n    witness y: Field;\n    assert x === x;\n}\n",
+    )
+    .unwrap();
+    let rechecked = wait_for("Unconstrained witness 'y'");
+
+    child.kill().ok();
+    child.wait().ok();
+
+    assert!(
+        rechecked,
+        "modifying the source should trigger a second check"
+    );
+}
+
+fn lof_with_stdin(args: &[&str], input: &str, cwd: &Path) -> std::process::Output {
+    use std::io::Write;
+    use std::process::Stdio;
+
+    let mut child = Command::new(env!("CARGO_BIN_EXE_lof"))
+        .args(args)
+        .current_dir(cwd)
+        .env("RUST_LOG", "error")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("spawn lof");
+    // lof may exit before reading stdin (e.g. on bad arguments), so a broken pipe is fine
+    let _ = child.stdin.take().unwrap().write_all(input.as_bytes());
+    child.wait_with_output().expect("wait for lof")
+}
+
+#[test]
+fn test_check_reads_source_from_stdin() {
+    let dir = tempfile::tempdir().unwrap();
+
+    let output = lof_with_stdin(&["check", "-"], SOURCE, dir.path());
+    assert!(output.status.success(), "{:?}", output);
+    assert!(String::from_utf8_lossy(&output.stdout).contains("<stdin>"));
+}
+
+#[test]
+fn test_compile_from_stdin_requires_out_base() {
+    let dir = tempfile::tempdir().unwrap();
+
+    let output = lof_with_stdin(&["compile", "-"], SOURCE, dir.path());
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("--out-base"));
+
+    let output = lof_with_stdin(
+        &["compile", "-", "--out-base", "piped", "--out-dir", "out"],
+        SOURCE,
+        dir.path(),
+    );
+    assert!(output.status.success(), "{:?}", output);
+    assert!(dir.path().join("out").join("piped.r1cs").exists());
+    assert!(dir.path().join("out").join("piped.ir").exists());
+}
+
+#[test]
+fn test_check_json_diagnostics_for_type_error() {
+    let dir = tempfile::tempdir().unwrap();
+    let file = dir.path().join("bad.lof");
+    fs::write(
+        &file,
+        "proof Bad {\n    input x: Field;\n    assert y === x;\n}\n",
+    )
+    .unwrap();
+
+    let output = lof(&["check", "--message-format", "json", file.to_str().unwrap()]);
+    assert!(!output.status.success());
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let lines: Vec<&str> = stdout.lines().collect();
+    assert_eq!(lines.len(), 1, "expected one diagnostic, got {:?}", lines);
+
+    let diagnostic: serde_json::Value = serde_json::from_str(lines[0]).unwrap();
+    assert_eq!(diagnostic["severity"], "error");
+    assert_eq!(diagnostic["code"], "undefined_variable");
+    assert_eq!(diagnostic["line"], 3);
+    assert_eq!(diagnostic["col"], 5);
+}
+
+#[test]
+fn test_check_json_diagnostics_for_lexer_error() {
+    let dir = tempfile::tempdir().unwrap();
+    let file = dir.path().join("bad.lof");
+    fs::write(
+        &file,
+        "proof Bad {\n    input x: Field;\n    let y = x $ 2;\n}\n",
+    )
+    .unwrap();
+
+    let output = lof(&["check", "--message-format", "json", file.to_str().unwrap()]);
+    assert!(!output.status.success());
+
+    let diagnostic: serde_json::Value =
+        serde_json::from_str(String::from_utf8_lossy(&output.stdout).trim()).unwrap();
+    assert_eq!(diagnostic["code"], "lexer_error");
+    assert_eq!(diagnostic["line"], 3);
+    assert_eq!(diagnostic["col"], 15);
+}
+
+#[test]
+fn test_check_json_diagnostics_for_parse_error() {
+    let dir = tempfile::tempdir().unwrap();
+    let file = dir.path().join("bad.lof");
+    fs::write(
+        &file,
+        "proof Bad {\n    input x: Field;\n    assert x === ;\n}\n",
+    )
+    .unwrap();
+
+    let output = lof(&["check", "--message-format", "json", file.to_str().unwrap()]);
+    assert!(!output.status.success());
+
+    let diagnostic: serde_json::Value =
+        serde_json::from_str(String::from_utf8_lossy(&output.stdout).trim()).unwrap();
+    assert_eq!(diagnostic["code"], "parse_error");
+    assert_eq!(diagnostic["line"], 3);
+    assert_eq!(diagnostic["col"], 18);
+}
+
+#[test]
+fn test_stats_reports_constraint_counts() {
+    let dir = tempfile::tempdir().unwrap();
+    let file = write_source(dir.path());
+
+    let output = lof(&["stats", file.to_str().unwrap()]);
+    assert!(output.status.success(), "{:?}", output);
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("Constraints:          2"), "{}", stdout);
+    assert!(stdout.contains("multiplications:    1"), "{}", stdout);
+    assert!(!dir.path().join("build").exists());
+}
+
+#[test]
+fn test_array_inputs_prove_from_nested_json() {
+    use lofit::inputs::{parse_inputs_in_order, parse_partial_witness, read_inputs_json};
+    use lofit::{generate_full_witness_with_provided, ConstraintSystem, LofCircuit, ProverKey};
+
+    let dir = tempfile::tempdir().unwrap();
+    let file = dir.path().join("sum.lof");
+    fs::write(
+        &file,
+        "proof Sum {\n    input xs: Array<Field, 3>;\n    witness total: Field;\n    assert total === (xs[0] + xs[1]) * xs[2];\n}\n",
+    )
+    .unwrap();
+
+    let output = lof(&["compile", file.to_str().unwrap()]);
+    assert!(output.status.success(), "{:?}", output);
+
+    let r1cs_file = fs::File::open(dir.path().join("build").join("sum.r1cs")).unwrap();
+    let r1cs = ConstraintSystem::from_file(r1cs_file).unwrap();
+    assert_eq!(r1cs.public_inputs, vec!["xs[0]", "xs[1]", "xs[2]"]);
+
+    let public = read_inputs_json(r#"{"xs": ["1", 2, "3"]}"#.as_bytes()).unwrap();
+    let witness = read_inputs_json(r#"{"total": "9"}"#.as_bytes()).unwrap();
+    let pub_values = parse_inputs_in_order(&public, &r1cs.public_inputs).unwrap();
+    let provided = parse_partial_witness(&witness, &r1cs);
+    let wit_values = generate_full_witness_with_provided(&r1cs, &pub_values, &provided).unwrap();
+
+    let (pk, vk) = ProverKey::setup(LofCircuit {
+        public_inputs: vec![Default::default(); pub_values.len()],
+        witness: vec![Default::default(); wit_values.len()],
+        constraints: r1cs.constraints.clone(),
+    })
+    .unwrap();
+    let proof = pk
+        .prove(LofCircuit {
+            public_inputs: pub_values.clone(),
+            witness: wit_values,
+            constraints: r1cs.constraints.clone(),
+        })
+        .unwrap();
+    assert!(vk.verify(&proof, &pub_values).unwrap());
+
+    assert!(read_inputs_json(r#"{"xs": [true]}"#.as_bytes()).is_err());
+}
+
+#[test]
+fn test_tuple_inputs_prove_from_a_list() {
+    use lofit::inputs::{
+        parse_inputs_in_order, parse_partial_witness, read_inputs_json, split_inputs,
+    };
+    use lofit::{generate_full_witness_with_provided, ConstraintSystem, LofCircuit, ProverKey};
+
+    let dir = tempfile::tempdir().unwrap();
+    let file = dir.path().join("point.lof");
+    fs::write(
+        &file,
+        "proof Point {\n    input pt: (Field, Field);\n    witness product: Field;\n    let (x, y) = pt in\n    product === x * y\n}\n",
+    )
+    .unwrap();
+
+    let output = lof(&["compile", file.to_str().unwrap(), "-g"]);
+    assert!(output.status.success(), "{:?}", output);
+
+    let r1cs_file = fs::File::open(dir.path().join("build").join("point.r1cs")).unwrap();
+    let r1cs = ConstraintSystem::from_file(r1cs_file).unwrap();
+    assert_eq!(r1cs.public_inputs, vec!["pt_0", "pt_1"]);
+
+    // the template spells the components out, the same names a list fills
+    let template = fs::read_to_string(dir.path().join("inputs").join("point_public.json")).unwrap();
+    let template: std::collections::BTreeMap<String, String> =
+        serde_json::from_str(&template).unwrap();
+    assert_eq!(
+        template.into_keys().collect::<Vec<_>>(),
+        vec!["pt_0", "pt_1"]
+    );
+
+    let merged = read_inputs_json(r#"{"pt": ["3", "4"], "product": "12"}"#.as_bytes()).unwrap();
+    let (public, witness) = split_inputs(&merged, &r1cs.public_inputs, &r1cs.witnesses);
+    assert_eq!(public.get("pt_0").map(String::as_str), Some("3"));
+    assert_eq!(public.get("pt_1").map(String::as_str), Some("4"));
+
+    let listed = read_inputs_json(r#"{"pt": ["3", "4"]}"#.as_bytes()).unwrap();
+    let pub_values = parse_inputs_in_order(&listed, &r1cs.public_inputs).unwrap();
+    assert_eq!(
+        pub_values,
+        parse_inputs_in_order(&public, &r1cs.public_inputs).unwrap()
+    );
+
+    let provided = parse_partial_witness(&witness, &r1cs);
+    let wit_values = generate_full_witness_with_provided(&r1cs, &pub_values, &provided).unwrap();
+
+    let (pk, vk) = ProverKey::setup(LofCircuit {
+        public_inputs: vec![Default::default(); pub_values.len()],
+        witness: vec![Default::default(); wit_values.len()],
+        constraints: r1cs.constraints.clone(),
+    })
+    .unwrap();
+    let proof = pk
+        .prove(LofCircuit {
+            public_inputs: pub_values.clone(),
+            witness: wit_values,
+            constraints: r1cs.constraints.clone(),
+        })
+        .unwrap();
+    assert!(vk.verify(&proof, &pub_values).unwrap());
+}
@@ -90,3 +90,219 @@ fn test_ir_serialization() {
     assert_eq!(circuit.pub_inputs.len(), deserialized.pub_inputs.len());
     assert_eq!(circuit.instructions.len(), deserialized.instructions.len());
 }
+
+#[test]
+fn test_ir_file_header_is_validated() {
+    let circuit = IRCircuit {
+        name: "Header".to_string(),
+        pub_inputs: vec![("x".to_string(), IRType::Field)],
+        witnesses: vec![],
+        outputs: vec![],
+        instructions: vec![],
+        functions: HashMap::new(),
+    };
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("header.ir");
+    circuit.write_to_file(&path).unwrap();
+    assert_eq!(IRCircuit::read_from_file(&path).unwrap().name, "Header");
+    let valid = std::fs::read(&path).unwrap();
+
+    // an r1cs file handed over by mistake
+    let mut wrong_magic = b"lof-r1cs".to_vec();
+    wrong_magic.extend_from_slice(&valid[8..]);
+    std::fs::write(&path, &wrong_magic).unwrap();
+    let err = IRCircuit::read_from_file(&path).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+    assert!(err.to_string().contains("not a lof-ir file"));
+    assert!(err.to_string().contains("header.ir"));
+
+    let mut stale = valid.clone();
+    stale[8..12].copy_from_slice(&7u32.to_le_bytes());
+    let err = IRCircuit::from_bytes(&stale).unwrap_err();
+    assert!(err.to_string().contains("Unsupported IR version 7"));
+
+    assert!(IRCircuit::from_bytes(&valid[..10]).is_err());
+    assert!(IRCircuit::from_bytes(&valid[..valid.len() - 2]).is_err());
+}
+
+#[test]
+fn test_division_witness_satisfies_the_r1cs_constraint() {
+    use lofit::ConstraintSystem;
+    use num_bigint::BigInt;
+
+    let source = r#"
+    proof Div {
+        input a: Field;
+        input b: Field;
+        witness q: Field;
+        assert b != 0;
+        assert q === a / b;
+    }"#;
+    let ast = Parser::new(Lexer::new(source)).parse_program().unwrap();
+    let circuit = IRGenerator::new().convert_proof(&ast[0]).unwrap();
+    assert!(circuit.instructions.iter().any(|instruction| matches!(
+        instruction,
+        IRInstruction::Constrain {
+            right: IRExpr::Div(..),
+            ..
+        }
+    )));
+
+    // 7 / 2 is not exact: integer division gives 3, the field gives 7 * 2^-1
+    let quotient = lof::field_div(&BigInt::from(7), &BigInt::from(2)).unwrap();
+    assert_ne!(quotient, BigInt::from(3));
+    let modulus: BigInt = lof::ir::FIELD_MODULUS.parse().unwrap();
+    assert_eq!(BigInt::from(2) * &quotient % &modulus, BigInt::from(7));
+    assert_eq!(
+        lof::field_div(&BigInt::from(12), &BigInt::from(4)),
+        Some(BigInt::from(3))
+    );
+    assert_eq!(lof::field_div(&BigInt::from(1), &modulus), None);
+
+    let cs = lof::compile_dsl_to_constraint_system(source).unwrap();
+    let satisfied = |q: &BigInt| {
+        let public: lofit::inputs::InputsJson = [
+            ("a".to_string(), "7".to_string()),
+            ("b".to_string(), "2".to_string()),
+        ]
+        .into();
+        let witness: lofit::inputs::InputsJson = [("q".to_string(), q.to_string())].into();
+        let public = lofit::inputs::parse_inputs_in_order(&public, &cs.public_inputs).unwrap();
+        let provided = lofit::inputs::parse_partial_witness(&witness, &cs);
+        let values = lofit::generate_full_witness_with_provided(&cs, &public, &provided).unwrap();
+        cs.find_unsatisfied(&ConstraintSystem::full_assignment(&public, &values))
+            .is_none()
+    };
+    assert!(satisfied(&quotient));
+    assert!(!satisfied(&BigInt::from(3)));
+}
+
+#[test]
+fn test_interpreter_checks_assertions_against_values() {
+    use num_bigint::BigInt;
+
+    let source = r#"
+    proof Square {
+        input x: field;
+        witness y: field;
+        let z = y + 1 in
+        assert z === 10;
+        assert y === x * x;
+    }"#;
+    let ast = Parser::new(Lexer::new(source)).parse_program().unwrap();
+    let values = |x: i64, y: i64| -> HashMap<String, BigInt> {
+        [
+            ("x".to_string(), BigInt::from(x)),
+            ("y".to_string(), BigInt::from(y)),
+        ]
+        .into()
+    };
+
+    let evaluation = lof::evaluate_proof(&ast[0], &values(3, 9)).expect("3 squared is 9");
+    assert_eq!(evaluation.witness["z"], BigInt::from(10));
+    assert!(evaluation.outputs.is_empty());
+
+    match lof::evaluate_proof(&ast[0], &values(4, 9)) {
+        Err(lof::InterpreterError::AssertionFailed { index, assertion }) => {
+            assert_eq!(index, 2);
+            assert_eq!(assertion, "y === x * x");
+        }
+        other => panic!("expected the square assertion to fail, got {:?}", other),
+    }
+
+    match lof::evaluate_proof(&ast[0], &values(3, 8)) {
+        Err(lof::InterpreterError::AssertionFailed { index, .. }) => assert_eq!(index, 1),
+        other => panic!("expected the first assertion to fail, got {:?}", other),
+    }
+
+    let missing: HashMap<String, BigInt> = [("x".to_string(), BigInt::from(3))].into();
+    assert!(matches!(
+        lof::evaluate_proof(&ast[0], &missing),
+        Err(lof::InterpreterError::MissingValue(name)) if name == "y"
+    ));
+}
+
+#[test]
+fn test_interpreter_returns_computed_outputs() {
+    use num_bigint::BigInt;
+
+    let source = r#"
+    proof HashFunction {
+        input preimage: Field;
+        input salt: Field;
+        output hash: Field;
+        assert hash === preimage * preimage + salt;
+    }"#;
+    let ast = Parser::new(Lexer::new(source)).parse_program().unwrap();
+    let values: HashMap<String, BigInt> = [
+        ("preimage".to_string(), BigInt::from(7)),
+        ("salt".to_string(), BigInt::from(5)),
+    ]
+    .into();
+
+    let evaluation = lof::evaluate_proof(&ast[0], &values).unwrap();
+    assert_eq!(
+        evaluation.outputs,
+        HashMap::from([("hash".to_string(), BigInt::from(54))])
+    );
+    assert_eq!(evaluation.witness["preimage"], BigInt::from(7));
+
+    // a supplied output is checked rather than overwritten
+    let mut wrong = values.clone();
+    wrong.insert("hash".to_string(), BigInt::from(53));
+    assert!(matches!(
+        lof::evaluate_proof(&ast[0], &wrong),
+        Err(lof::InterpreterError::AssertionFailed { .. })
+    ));
+}
+
+#[test]
+fn test_interpreter_takes_the_remainder_of_the_field_value() {
+    use num_bigint::BigInt;
+
+    let source = r#"
+    proof Bucket {
+        input x: Field;
+        output r: Field;
+        assert r === x % 10;
+    }"#;
+    let ast = Parser::new(Lexer::new(source)).parse_program().unwrap();
+    let remainder = |x: BigInt| {
+        let values = HashMap::from([("x".to_string(), x)]);
+        lof::evaluate_proof(&ast[0], &values).unwrap().outputs["r"].clone()
+    };
+
+    assert_eq!(remainder(BigInt::from(1234)), BigInt::from(4));
+    // -1 is p - 1 in the field
+    let modulus: BigInt = lof::ir::FIELD_MODULUS.parse().unwrap();
+    assert_eq!(remainder(BigInt::from(-1)), (modulus - 1) % 10);
+}
+
+#[test]
+fn test_interpreter_evaluates_bitwise_builtins() {
+    use num_bigint::BigInt;
+
+    let source = r#"
+    proof Mix {
+        input x: Field;
+        input y: Field;
+        output rotated: Field;
+        output shifted: Field;
+        output mixed: Field;
+        assert rotated === rotl(x, 8, 32);
+        assert shifted === shr(x, 4, 32);
+        assert mixed === xor3(x, y, 255, 32);
+    }"#;
+    let ast = Parser::new(Lexer::new(source)).parse_program().unwrap();
+    let (x, y): (u32, u32) = (0x8123_4567, 0x0f0f_0f0f);
+    let values: HashMap<String, BigInt> = [
+        ("x".to_string(), BigInt::from(x)),
+        ("y".to_string(), BigInt::from(y)),
+    ]
+    .into();
+
+    let outputs = lof::evaluate_proof(&ast[0], &values).unwrap().outputs;
+    assert_eq!(outputs["rotated"], BigInt::from(x.rotate_left(8)));
+    assert_eq!(outputs["shifted"], BigInt::from(x >> 4));
+    assert_eq!(outputs["mixed"], BigInt::from(x ^ y ^ 255));
+}
@@ -1,4 +1,4 @@
-use lof::lexer::{Keyword, Lexer, Symbol, Token};
+use lof::lexer::{Keyword, Lexer, Span, Symbol, Token};
 
 #[test]
 fn test_basic_tokens() {
@@ -54,6 +54,14 @@ fn test_operators() {
     assert_eq!(lexer.next_token(), Token::Symbol(Symbol::Or));
 }
 
+#[test]
+fn test_percent() {
+    let mut lexer = Lexer::new("x % 8");
+    assert_eq!(lexer.next_token(), Token::Identifier("x".to_string()));
+    assert_eq!(lexer.next_token(), Token::Symbol(Symbol::Percent));
+    assert_eq!(lexer.next_token(), Token::Number(8));
+}
+
 #[test]
 fn test_numbers() {
     let mut lexer = Lexer::new("42 0 999");
@@ -73,6 +81,18 @@ fn test_comments() {
     assert_eq!(lexer.next_token(), Token::Identifier("y".to_string()));
 }
 
+#[test]
+fn test_block_comments() {
+    let mut lexer = Lexer::new("x /* spans\ntwo lines */ y");
+    assert_eq!(lexer.next_token(), Token::Identifier("x".to_string()));
+    assert_eq!(
+        lexer.next_token(),
+        Token::Comment(" spans\ntwo lines ".to_string())
+    );
+    assert_eq!(lexer.next_token(), Token::Identifier("y".to_string()));
+    assert_eq!(lexer.line(), 2);
+}
+
 #[test]
 fn test_whitespace_handling() {
     let mut lexer = Lexer::new("   x    y\n\tz   ");
@@ -169,3 +189,178 @@ fn test_complete_proof_structure() {
         );
     }
 }
+
+#[test]
+fn test_numbers_with_digit_separators() {
+    let mut lexer = Lexer::new("1_000_000 0xFF_FF 0x10 12_3");
+    assert_eq!(lexer.next_token(), Token::Number(1_000_000));
+    assert_eq!(lexer.next_token(), Token::Number(0xFFFF));
+    assert_eq!(lexer.next_token(), Token::Number(16));
+    assert_eq!(lexer.next_token(), Token::Number(123));
+    assert_eq!(lexer.next_token(), Token::EOF);
+
+    let tokens: Vec<Token> = Lexer::new("1_000 + 0xff").collect();
+    assert_eq!(
+        tokens,
+        vec![
+            Token::Number(1000),
+            Token::Symbol(Symbol::Plus),
+            Token::Number(255),
+        ]
+    );
+}
+
+#[test]
+fn test_leading_underscore_is_identifier() {
+    let tokens: Vec<Token> = Lexer::new("_5 _ _tmp").collect();
+    assert_eq!(
+        tokens,
+        vec![
+            Token::Identifier("_5".to_string()),
+            Token::Symbol(Symbol::Underscore),
+            Token::Identifier("_tmp".to_string()),
+        ]
+    );
+}
+
+#[test]
+#[should_panic(expected = "Malformed numeric literal '1__000'")]
+fn test_double_underscore_in_number_rejected() {
+    Lexer::new("1__000").next_token();
+}
+
+#[test]
+#[should_panic(expected = "Malformed numeric literal '1_000_'")]
+fn test_trailing_underscore_in_number_rejected() {
+    Lexer::new("1_000_").next_token();
+}
+
+#[test]
+#[should_panic(expected = "Malformed numeric literal '0x_FF'")]
+fn test_leading_underscore_in_hex_rejected() {
+    let _: Vec<Token> = Lexer::new("0x_FF").collect();
+}
+
+#[test]
+fn test_number_beyond_i64_is_big_number() {
+    let modulus = "21888242871839275222246405745257275088548364400416034343698204186575808495617";
+    let tokens: Vec<Token> = Lexer::new(modulus).collect();
+
+    assert_eq!(
+        tokens[0],
+        Token::BigNumber(modulus.parse::<num_bigint::BigInt>().unwrap())
+    );
+}
+
+#[test]
+fn test_const_keyword() {
+    assert_eq!(
+        Lexer::new("const").next_token(),
+        Token::Keyword(Keyword::Const)
+    );
+}
+
+#[test]
+fn test_declaration_and_control_flow_keywords() {
+    for (source, keyword) in [
+        ("output", Keyword::Output),
+        ("const", Keyword::Const),
+        ("if", Keyword::If),
+        ("else", Keyword::Else),
+        ("for", Keyword::For),
+        ("in", Keyword::In),
+    ] {
+        assert_eq!(
+            Lexer::new(source).next_token(),
+            Token::Keyword(keyword),
+            "{}",
+            source
+        );
+    }
+}
+
+#[test]
+fn test_identifiers_starting_with_keywords() {
+    let mut lexer = Lexer::new("output_value constant iffy elsewhere format index");
+    for name in [
+        "output_value",
+        "constant",
+        "iffy",
+        "elsewhere",
+        "format",
+        "index",
+    ] {
+        assert_eq!(lexer.next_token(), Token::Identifier(name.to_string()));
+    }
+    assert_eq!(lexer.next_token(), Token::EOF);
+}
+
+#[test]
+fn test_bits_is_an_identifier() {
+    // the type is spelled `Bits`, so the lowercase word stays free for signal names
+    let mut lexer = Lexer::new("bits Bits");
+    assert_eq!(lexer.next_token(), Token::Identifier("bits".to_string()));
+    assert_eq!(lexer.next_token(), Token::Keyword(Keyword::Bits));
+    assert_eq!(lexer.next_token(), Token::EOF);
+}
+
+#[test]
+fn test_spanned_tokens_report_byte_offsets() {
+    let source = "proof Té {\n  x === 42 // hi\n}";
+    let tokens: Vec<(Token, Span)> = Lexer::new(source).spanned().collect();
+    let span = |start, end, line, column| Span {
+        start,
+        end,
+        line,
+        column,
+    };
+
+    assert_eq!(
+        tokens,
+        vec![
+            (Token::Keyword(Keyword::Proof), span(0, 5, 1, 1)),
+            (Token::Identifier("Té".to_string()), span(6, 9, 1, 7)),
+            (Token::Symbol(Symbol::LBrace), span(10, 11, 1, 10)),
+            (Token::Identifier("x".to_string()), span(14, 15, 2, 3)),
+            (Token::Symbol(Symbol::TripleEqual), span(16, 19, 2, 5)),
+            (Token::Number(42), span(20, 22, 2, 9)),
+            (Token::Comment(" hi".to_string()), span(23, 28, 2, 12)),
+            (Token::Symbol(Symbol::RBrace), span(29, 30, 3, 1)),
+        ]
+    );
+    for (_, span) in &tokens {
+        assert!(source.is_char_boundary(span.start) && source.is_char_boundary(span.end));
+    }
+
+    // the plain iterator still yields bare tokens
+    let plain: Vec<Token> = Lexer::new(source).collect();
+    assert_eq!(
+        plain,
+        tokens
+            .into_iter()
+            .map(|(token, _)| token)
+            .collect::<Vec<_>>()
+    );
+}
+
+#[test]
+fn test_tokens_track_lines_across_line_endings() {
+    let source = "proof A {\r\n  input x: Field;\r\n  witness y: Field // note\r\n}";
+    let tokens: Vec<(Token, Span)> = Lexer::new(source).spanned().collect();
+    let find = |token: Token| tokens.iter().find(|(t, _)| *t == token).unwrap().1;
+
+    let witness = find(Token::Keyword(Keyword::Witness));
+    assert_eq!((witness.line, witness.column), (3, 3));
+    assert_eq!(&source[witness.start..witness.end], "witness");
+
+    // the comment ends before `\r\n`, which is one line break, not two
+    let comment = find(Token::Comment(" note".to_string()));
+    assert_eq!(comment.line, 3);
+    let close = find(Token::Symbol(Symbol::RBrace));
+    assert_eq!((close.line, close.column), (4, 1));
+
+    let mut lexer = Lexer::new("a\rb");
+    lexer.next_token();
+    lexer.next_token();
+    assert_eq!((lexer.line(), lexer.column()), (2, 2));
+}
@@ -1,4 +1,4 @@
-use lof::ast::{Expression, Type, Visibility};
+use lof::ast::{Expression, Operator, Refinement, Type, Visibility};
 use lof::lexer::Lexer;
 use lof::parser::Parser;
 
@@ -669,6 +669,28 @@ fn test_parse_refined_type_in_signal() {
     );
 }
 
+#[test]
+fn test_parse_bits_type() {
+    let source = r#"
+    proof Byte {
+        witness bits: Bits<8>;
+        witness low: bits<4>;
+        output value: Field;
+        assert value === decompose(bits)
+    }"#;
+
+    let result = parse_source(source).unwrap();
+    let Expression::Proof { signals, .. } = &result[0] else {
+        panic!("Expected proof, got {:?}", result[0]);
+    };
+    assert_eq!(signals[0].name, "bits");
+    assert_eq!(signals[0].typ, Type::Bits(Box::new(Expression::Number(8))));
+    assert_eq!(signals[0].typ.bits_width(), Some(8));
+    assert_eq!(signals[1].typ.bits_width(), Some(4));
+
+    assert!(parse_source("proof P { witness b: Bits<n>; assert b[0] === 1 }").is_err());
+}
+
 #[test]
 fn test_parse_array_indexing_basic() {
     let source = r#"
@@ -683,6 +705,82 @@ fn test_parse_array_indexing_basic() {
     assert!(result.is_ok(), "Should parse basic array indexing");
 }
 
+#[test]
+fn test_unterminated_proof_names_the_proof_and_its_line() {
+    let source =
+        "\nproof Square {\n    input x: Field;\n    witness y: Field;\n    assert y === x * x;\n";
+    let err = lof::parse_dsl(source).unwrap_err().to_string();
+    assert!(
+        err.contains("Unterminated proof 'Square' starting at line 2"),
+        "{}",
+        err
+    );
+
+    // the next declaration starting inside the body gives the same hint
+    let source = "component Twice {\n    input a: Field;\n    a + a\n\nproof P {\n    input x: Field;\n    assert x === 1;\n}\n";
+    let err = lof::parse_dsl(source).unwrap_err().to_string();
+    assert!(
+        err.contains("Unterminated component 'Twice' starting at line 1"),
+        "{}",
+        err
+    );
+
+    // without spans there is no line to report, but the proof is still named
+    let err = parse_source("proof Square { input x: Field; assert x === x").unwrap_err();
+    assert!(err.contains("Unterminated"), "{}", err);
+}
+
+#[test]
+fn test_error_span_points_at_the_offending_token() {
+    let span_of = |source: &str| {
+        let mut parser = Parser::new(Lexer::new(source).spanned());
+        let err = parser.parse_program().unwrap_err();
+        parser.error_span(&err).map(|span| (span.line, span.column))
+    };
+
+    assert_eq!(
+        span_of("proof P {\n    input x: Field;\n    assert x === ;\n}"),
+        Some((3, 18))
+    );
+    assert_eq!(span_of("proof P {\n    input x Field;\n}"), Some((2, 13)));
+    // an unterminated proof points at its opening brace
+    assert_eq!(
+        span_of("\nproof P {\n    input x: Field;\n    assert x === x;\n"),
+        Some((2, 9))
+    );
+    assert_eq!(
+        Parser::new(Lexer::new("proof P { input x Field; }"))
+            .error_span(&lof::parser::ParseError::UnexpectedEOF),
+        None
+    );
+}
+
+#[test]
+fn test_parse_comments_inside_type_syntax() {
+    let source = r#"
+    proof Commented {
+        input arr: Array<Field, /* size */ 8>;
+        input pair: (Field, // first
+            Bool);
+        input small: Field</* min */ 0..255>;
+        witness result: Field;
+        assert result === arr[0]
+    }"#;
+
+    let result = parse_source(source).expect("comments inside types should be skipped");
+    let Expression::Proof { signals, .. } = &result[0] else {
+        panic!("Expected proof");
+    };
+    match &signals[0].typ {
+        Type::Array { size, .. } => assert_eq!(*size, 8),
+        other => panic!("Expected array type, got {:?}", other),
+    }
+    match &signals[1].typ {
+        Type::Tuple(types) => assert_eq!(types.len(), 2),
+        other => panic!("Expected tuple type, got {:?}", other),
+    }
+}
+
 #[test]
 fn test_parse_array_indexing_constant() {
     let source = r#"
@@ -905,3 +1003,244 @@ fn test_parse_array_indexing_with_expression() {
         "Should parse array indexing with complex index expression"
     );
 }
+
+#[test]
+fn test_parse_match_arm_guard() {
+    let source = r#"
+    proof GuardTest {
+        input x: Field;
+        witness y: Field;
+        let bucket = match x with
+            | n if n < 10 => 0
+            | _ => 1
+        in
+        assert y === bucket;
+    }"#;
+
+    let result = parse_source(source).unwrap();
+
+    let body = match &result[0] {
+        Expression::Proof { body, .. } => body,
+        _ => panic!("Expected Proof, got {:?}", result[0]),
+    };
+    let patterns = match body.as_ref() {
+        Expression::Let { value, .. } => match value.as_ref() {
+            Expression::Match { patterns, .. } => patterns,
+            other => panic!("Expected Match, got {:?}", other),
+        },
+        other => panic!("Expected Let, got {:?}", other),
+    };
+
+    assert_eq!(patterns.len(), 2);
+    assert!(matches!(
+        patterns[0].guard.as_deref(),
+        Some(Expression::BinaryOp {
+            op: lof::ast::Operator::Lt,
+            ..
+        })
+    ));
+    assert!(patterns[1].guard.is_none());
+}
+
+#[test]
+fn test_parse_output_signal() {
+    let source = r#"
+    proof Sum {
+        input a: Field;
+        input b: Field;
+        output sum: Field;
+        assert sum === a + b
+    }"#;
+
+    let result = parse_source(source).unwrap();
+    match &result[0] {
+        Expression::Proof { signals, .. } => {
+            assert_eq!(signals.len(), 3);
+            assert_eq!(signals[2].name, "sum");
+            assert_eq!(signals[2].visibility, Visibility::Output);
+        }
+        _ => panic!("Expected Proof, got {:?}", result[0]),
+    }
+}
+
+#[test]
+fn test_parse_chained_comparison_desugars_to_conjunction() {
+    let source = r#"
+    proof Range {
+        input x: Field;
+        assert 0 <= x < 100
+    }"#;
+
+    let cmp = |left: Expression, op: Operator, right: Expression| Expression::BinaryOp {
+        left: Box::new(left),
+        op,
+        right: Box::new(right),
+    };
+    let x = || Expression::Variable("x".to_string());
+    let expected = cmp(
+        cmp(Expression::Number(0), Operator::Le, x()),
+        Operator::And,
+        cmp(x(), Operator::Lt, Expression::Number(100)),
+    );
+
+    let result = parse_source(source).unwrap();
+    match &result[0] {
+        Expression::Proof { body, .. } => {
+            assert_eq!(**body, Expression::Assert(Box::new(expected)));
+        }
+        _ => panic!("Expected Proof, got {:?}", result[0]),
+    }
+}
+
+#[test]
+fn test_parse_parenthesized_comparison_is_not_chained() {
+    let source = r#"
+    proof Range {
+        input x: Field;
+        assert (0 <= x) < 100
+    }"#;
+
+    let result = parse_source(source).unwrap();
+    let found = format!("{:?}", result[0]);
+    assert!(!found.contains("And"), "{}", found);
+}
+
+#[test]
+fn test_parse_const_definition() {
+    let source = r#"
+    const LIMIT = 100;
+
+    proof Bounded {
+        input x: Field;
+        assert x < LIMIT
+    }"#;
+
+    let result = parse_source(source).unwrap();
+    assert_eq!(result.len(), 2);
+    assert_eq!(
+        result[0],
+        Expression::Const {
+            name: "LIMIT".to_string(),
+            value: Box::new(Expression::Number(100)),
+        }
+    );
+}
+
+#[test]
+fn test_parse_function_without_return_type() {
+    let source = "let double(x: Field) = x * 2";
+
+    let result = parse_source(source).unwrap();
+    match &result[0] {
+        Expression::FunctionDef {
+            name, return_type, ..
+        } => {
+            assert_eq!(name, "double");
+            assert_eq!(*return_type, None);
+        }
+        _ => panic!("Expected FunctionDef, got {:?}", result[0]),
+    }
+}
+
+#[test]
+fn test_parse_function_keeps_annotated_return_type() {
+    let result = parse_source("let double(x: Field): Field = x * 2").unwrap();
+    assert!(matches!(
+        &result[0],
+        Expression::FunctionDef {
+            return_type: Some(Type::Field { .. }),
+            ..
+        }
+    ));
+}
+
+#[test]
+fn test_parse_bounded_field_type() {
+    let source = r#"
+    proof Bounded {
+        input x: Field<0..255>;
+        assert x < 200;
+    }"#;
+    let result = parse_source(source).unwrap();
+    match &result[0] {
+        Expression::Proof { signals, .. } => assert_eq!(
+            signals[0].typ,
+            Type::Field {
+                constraint: lof::ast::ConstraintStatus::Unconstrained,
+                refinement: Some(Refinement::Range { min: 0, max: 255 }),
+            }
+        ),
+        _ => panic!("Expected Proof, got {:?}", result[0]),
+    }
+
+    assert!(parse_source("proof P { input x: Field<9..3>; assert x === 1; }").is_err());
+}
+
+#[test]
+fn test_parse_modulo_binds_like_multiplication() {
+    let result = parse_source("let f(x: Field) = x + x % 8").unwrap();
+    match &result[0] {
+        Expression::FunctionDef { body, .. } => match body.as_ref() {
+            Expression::BinaryOp {
+                op: Operator::Add,
+                right,
+                ..
+            } => assert_eq!(
+                **right,
+                Expression::BinaryOp {
+                    left: Box::new(Expression::Variable("x".to_string())),
+                    op: Operator::Mod,
+                    right: Box::new(Expression::Number(8)),
+                }
+            ),
+            other => panic!("Expected addition, got {:?}", other),
+        },
+        _ => panic!("Expected FunctionDef, got {:?}", result[0]),
+    }
+}
+
+#[test]
+fn test_parse_unary_minus() {
+    let result = parse_source("let f(x: Field) = -3 * -x").unwrap();
+    match &result[0] {
+        Expression::FunctionDef { body, .. } => match body.as_ref() {
+            Expression::BinaryOp {
+                left,
+                op: Operator::Mul,
+                right,
+            } => {
+                assert_eq!(**left, Expression::Number(-3));
+                assert!(matches!(
+                    right.as_ref(),
+                    Expression::BinaryOp {
+                        op: Operator::Sub,
+                        ..
+                    }
+                ));
+            }
+            other => panic!("Expected multiplication, got {:?}", other),
+        },
+        _ => panic!("Expected FunctionDef, got {:?}", result[0]),
+    }
+}
+
+#[test]
+fn test_parse_array_literal_ascription() {
+    let result = parse_source("let empty(x: Field) = [] : Array<Field, 0>").unwrap();
+    match &result[0] {
+        Expression::FunctionDef { body, .. } => assert_eq!(
+            **body,
+            Expression::Ascription {
+                value: Box::new(Expression::ArrayLiteral(vec![])),
+                typ: Type::Array {
+                    element_type: Box::new(Type::Field {
+                        constraint: lof::ast::ConstraintStatus::Unconstrained,
+                        refinement: None,
+                    }),
+                    size: 0,
+                },
+            }
+        ),
+        _ => panic!("Expected FunctionDef, got {:?}", result[0]),
+    }
+}
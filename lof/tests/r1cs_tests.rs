@@ -1,7 +1,7 @@
 use lof::ast::Expression;
 use lof::lexer::Lexer;
 use lof::parser::Parser;
-use lof::r1cs::{LinearCombination, R1CSConstraint, R1CSGenerator};
+use lof::r1cs::{LinearCombination, R1CSConstraint, R1CSError, R1CSGenerator};
 use lof::typechecker::TypeChecker;
 use num_bigint::BigInt;
 
@@ -338,6 +338,41 @@ fn test_r1cs_generator_state() {
     assert_eq!(r1cs.temp_var_counter, 0);
 }
 
+#[test]
+fn test_temporaries_are_scoped_to_their_proof() {
+    let source = r#"
+    proof First {
+        input out: Field;
+        witness a: Field;
+        assert out === a * a * a;
+    }
+    proof Second {
+        input out: Field;
+        witness b: Field;
+        assert out === b * b * b;
+    }"#;
+    let ast = Parser::new(Lexer::new(source)).parse_program().unwrap();
+    TypeChecker::new().check_program(&ast).unwrap();
+
+    let mut generator = R1CSGenerator::new();
+    let mut temporaries = Vec::new();
+    for expr in &ast {
+        generator.convert_proof(expr).unwrap();
+        temporaries.push(
+            generator
+                .witnesses
+                .iter()
+                .filter(|w| w.contains("::t_"))
+                .cloned()
+                .collect::<Vec<_>>(),
+        );
+    }
+
+    assert_eq!(temporaries[0], vec!["First::t_0", "First::t_1"]);
+    assert_eq!(temporaries[1], vec!["Second::t_0", "Second::t_1"]);
+    assert!(temporaries[0].iter().all(|t| !temporaries[1].contains(t)));
+}
+
 #[test]
 fn test_constraint_count_accuracy() {
     let source = r#"
@@ -448,7 +483,7 @@ fn test_dynamic_array_index_unsupported_in_r1cs() {
     let source = r#"
     proof DynamicIndex {
         input arr: Array<field, 2>;
-        input idx: field;
+        input idx: Nat;
         witness result: field;
         let value = arr[idx] in
         assert result === value
@@ -472,3 +507,1556 @@ fn test_dynamic_array_index_unsupported_in_r1cs() {
         "R1CS generator should reject dynamic array indices"
     );
 }
+
+#[test]
+fn test_match_guard_multiplies_into_selector() {
+    let source = r#"
+    proof GuardedMatch {
+        input x: Field;
+        input flag: Bool;
+        witness y: Field;
+        let result = match x with
+            | 0 if flag => 5
+            | _ => 7
+        in
+        assert y === result;
+    }"#;
+    let r1cs = compile_to_r1cs(source).unwrap();
+
+    let guard_lc = vec![("flag".to_string(), BigInt::from(1))];
+    let guard_product = r1cs
+        .constraints
+        .iter()
+        .find(|c| c.b.terms == guard_lc && c.a.terms.len() == 1 && c.c.terms.len() == 1)
+        .expect("guard should be multiplied into the arm selector");
+
+    // the guarded selector, not the raw equality bit, must weight the arm body
+    let selector = &guard_product.c.terms[0].0;
+    assert!(r1cs
+        .constraints
+        .iter()
+        .any(|c| c.a.terms.iter().any(|(var, _)| var == selector)
+            || c.b.terms.iter().any(|(var, _)| var == selector)));
+}
+
+#[test]
+fn test_enum_constructor_tags_variant_and_stores_fields() {
+    let source = r#"
+    type List = | Cons(Field, List) | Nil;
+
+    proof EnumConstruct {
+        input x: Field;
+        witness y: Field;
+        let list = Cons(x, Nil) in
+        assert y === x;
+    }"#;
+    let lexer = Lexer::new(source);
+    let mut parser = Parser::new(lexer);
+    let ast = parser.parse_program().expect("parse program");
+    let mut type_checker = TypeChecker::new();
+    type_checker
+        .check_program(&ast)
+        .expect("type check program");
+
+    let mut generator = R1CSGenerator::new();
+    for expr in &ast {
+        if let Expression::EnumDef { variants, .. } = expr {
+            generator.register_enum(variants);
+        }
+    }
+    let proof = ast
+        .iter()
+        .find(|expr| matches!(expr, Expression::Proof { .. }))
+        .expect("expected proof in program");
+    generator.convert_proof(proof).expect("convert proof");
+
+    // Cons is tag 0 with two fields, Nil is tag 1 with none
+    assert_eq!(generator.enum_values.len(), 2);
+    let (cons_tag, cons_fields) = generator
+        .enum_values
+        .iter()
+        .find(|(_, fields)| fields.len() == 2)
+        .expect("Cons value should be recorded");
+    let (nil_tag, _) = generator
+        .enum_values
+        .iter()
+        .find(|(_, fields)| fields.is_empty())
+        .expect("Nil value should be recorded");
+
+    let pins_tag = |tag_var: &str, tag: i64| {
+        generator.constraints.iter().any(|c| {
+            c.a.terms == vec![(tag_var.to_string(), BigInt::from(1))]
+                && c.c.terms == vec![("ONE".to_string(), BigInt::from(tag))]
+        })
+    };
+    assert!(pins_tag(cons_tag, 0));
+    assert!(pins_tag(nil_tag, 1));
+
+    let stores = |field_var: &str, value: &str| {
+        generator.constraints.iter().any(|c| {
+            c.a.terms == vec![(field_var.to_string(), BigInt::from(1))]
+                && c.c.terms == vec![(value.to_string(), BigInt::from(1))]
+        })
+    };
+    assert!(stores(&cons_fields[0], "x"));
+    assert!(stores(&cons_fields[1], nil_tag));
+}
+
+#[test]
+fn test_statistics_for_range_check() {
+    let source = r#"
+    proof RangeCheck {
+        input x: Field;
+        assert x < 256;
+    }"#;
+    let r1cs = compile_to_r1cs(source).unwrap();
+    let stats = r1cs.statistics();
+
+    // 252 bits ranging x, 253 bits of the shifted difference plus the boolean result
+    assert_eq!(stats.boolean_constraints, 252 + 254);
+    assert_eq!(stats.comparisons, 1);
+    assert_eq!(stats.multiplications, 0);
+    assert_eq!(stats.public_inputs, 1);
+    assert_eq!(stats.witnesses, 0);
+    assert_eq!(stats.constraints, r1cs.constraints.len());
+    assert_eq!(stats.temporaries, r1cs.witnesses.len());
+}
+
+#[test]
+fn test_statistics_counts_multiplications() {
+    let source = r#"
+    proof Mul {
+        input x: Field;
+        witness y: Field;
+        witness z: Field;
+        assert z === x * y;
+    }"#;
+    let stats = compile_to_r1cs(source).unwrap().statistics();
+
+    assert_eq!(stats.multiplications, 1);
+    assert_eq!(stats.boolean_constraints, 0);
+    assert_eq!(stats.comparisons, 0);
+    assert_eq!(stats.witnesses, 2);
+}
+
+#[test]
+fn test_output_signal_is_public() {
+    let source = r#"
+    proof Sum {
+        input a: Field;
+        input b: Field;
+        output sum: Field;
+        assert sum === a + b;
+    }"#;
+    let r1cs = compile_to_r1cs(source).unwrap();
+
+    assert_eq!(r1cs.pub_inputs, vec!["a", "b", "sum"]);
+    assert!(!r1cs.witnesses.contains(&"sum".to_string()));
+}
+
+#[test]
+fn test_chained_comparison_emits_two_comparisons() {
+    let source = r#"
+    proof Range {
+        input x: Field;
+        assert 0 <= x < 100;
+    }"#;
+    let stats = compile_to_r1cs(source).unwrap().statistics();
+
+    assert_eq!(stats.comparisons, 2);
+}
+
+#[test]
+fn test_const_inlined_in_each_proof() {
+    let source = r#"
+    const MODULUS = 21888242871839275222246405745257275088548364400416034343698204186575808495617;
+    const OFFSET = MODULUS - 1;
+
+    proof First {
+        input x: Field;
+        witness y: Field;
+        assert y === x + MODULUS;
+    }
+
+    proof Second {
+        input x: Field;
+        assert x === OFFSET;
+    }"#;
+    let ast = Parser::new(Lexer::new(source)).parse_program().unwrap();
+    TypeChecker::new().check_program(&ast).unwrap();
+
+    let modulus: BigInt =
+        "21888242871839275222246405745257275088548364400416034343698204186575808495617"
+            .parse()
+            .unwrap();
+    let mut r1cs = R1CSGenerator::new();
+    for item in &ast {
+        if let Expression::Const { name, value } = item {
+            r1cs.register_constant(name.clone(), value).unwrap();
+        }
+    }
+
+    let mut constants_seen = Vec::new();
+    for proof in ast.iter().filter(|e| matches!(e, Expression::Proof { .. })) {
+        r1cs.convert_proof(proof).unwrap();
+        assert!(!r1cs.witnesses.iter().any(|w| w.contains("MODULUS")));
+        let one_terms: Vec<BigInt> = r1cs
+            .constraints
+            .iter()
+            .flat_map(|c| c.a.terms.iter().chain(&c.b.terms).chain(&c.c.terms))
+            .filter(|(var, _)| var == "ONE")
+            .map(|(_, coeff)| coeff.clone())
+            .collect();
+        constants_seen.push(one_terms);
+    }
+
+    assert!(constants_seen[0].contains(&modulus));
+    assert!(constants_seen[1].contains(&(modulus - 1)));
+}
+
+#[test]
+fn test_dedup_removes_repeated_assertions() {
+    let source = r#"
+    proof RepeatedAssertion {
+        input x: Field;
+        witness y: Field;
+        assert y === x + 1;
+        assert y === x + 1;
+    }"#;
+    let mut r1cs = compile_to_r1cs(source).unwrap();
+    let before = r1cs.constraints.len();
+
+    let removed = r1cs.dedup_constraints();
+
+    assert_eq!(removed, 1);
+    assert_eq!(r1cs.constraints.len(), before - removed);
+    assert_eq!(r1cs.dedup_constraints(), 0);
+}
+
+#[test]
+fn test_repeated_decompose_reuses_bits() {
+    let source = r#"
+    proof RepeatedRangeCheck {
+        input x: Field;
+        let low = decompose(x, 8) in
+        let high = decompose(x, 8) in
+        assert x === x;
+    }"#;
+    let ast = Parser::new(Lexer::new(source)).parse_program().unwrap();
+    let mut r1cs = R1CSGenerator::new();
+    r1cs.convert_proof(&ast[0]).unwrap();
+
+    let bits = r1cs
+        .witnesses
+        .iter()
+        .filter(|w| w.contains("_bit_"))
+        .count();
+    let boolean_checks = r1cs
+        .constraints
+        .iter()
+        .filter(|c| c.c.terms.is_empty() && c.a.terms.len() == 1 && c.b.terms.len() == 2)
+        .count();
+    assert_eq!(bits, 8);
+    assert_eq!(boolean_checks, 8);
+    assert_eq!(r1cs.dedup_constraints(), 0);
+    // a comparison on x can lean on the explicit decomposition
+    assert_eq!(r1cs.range_checked.get("x"), Some(&8));
+}
+
+#[test]
+fn test_dedup_keeps_constraints_over_distinct_temps() {
+    let source = r#"
+    proof RepeatedComparison {
+        input x: Field;
+        assert x < 10;
+        assert x < 10;
+    }"#;
+    let mut r1cs = compile_to_r1cs(source).unwrap();
+    let before = r1cs.constraints.len();
+
+    assert_eq!(r1cs.dedup_constraints(), 0);
+    assert_eq!(r1cs.constraints.len(), before);
+}
+
+#[test]
+fn test_dedup_normalizes_term_order_and_like_terms() {
+    let mut r1cs = R1CSGenerator::new();
+    let lc = |terms: &[(&str, i64)]| LinearCombination {
+        terms: terms
+            .iter()
+            .map(|(var, coeff)| (var.to_string(), BigInt::from(*coeff)))
+            .collect(),
+    };
+    r1cs.constraints.push(R1CSConstraint {
+        a: lc(&[("x", 1), ("y", 2)]),
+        b: lc(&[("ONE", 1)]),
+        c: lc(&[("z", 1)]),
+    });
+    r1cs.constraints.push(R1CSConstraint {
+        a: lc(&[("y", 1), ("x", 1), ("y", 1), ("w", 0)]),
+        b: lc(&[("ONE", 1)]),
+        c: lc(&[("z", 1)]),
+    });
+    r1cs.constraints.push(R1CSConstraint {
+        a: lc(&[("x", 1), ("y", 3)]),
+        b: lc(&[("ONE", 1)]),
+        c: lc(&[("z", 1)]),
+    });
+
+    assert_eq!(r1cs.dedup_constraints(), 1);
+    assert_eq!(r1cs.constraints.len(), 2);
+}
+
+#[test]
+fn test_bounded_comparison_uses_narrow_decomposition() {
+    let unbounded = compile_to_r1cs(
+        r#"
+    proof Unbounded {
+        input x: Field;
+        assert x < 200;
+    }"#,
+    )
+    .unwrap()
+    .statistics();
+    let bounded = compile_to_r1cs(
+        r#"
+    proof Bounded {
+        input x: Field<0..255>;
+        assert x < 200;
+    }"#,
+    )
+    .unwrap()
+    .statistics();
+
+    // 8 bits ranging x, 9 bits of the shifted difference and the boolean result
+    assert_eq!(bounded.boolean_constraints, 18);
+    assert_eq!(unbounded.boolean_constraints, 252 + 254);
+    assert_eq!(bounded.comparisons, 1);
+    assert!(bounded.constraints * 10 < unbounded.constraints);
+}
+
+#[test]
+fn test_comparison_falls_back_to_full_width_when_one_side_is_unbounded() {
+    let stats = compile_to_r1cs(
+        r#"
+    proof Mixed {
+        input x: Field<0..255>;
+        witness y: Field;
+        assert x < y;
+    }"#,
+    )
+    .unwrap()
+    .statistics();
+
+    // x keeps its 8-bit check, y is ranged to the full 252 bits
+    assert_eq!(stats.boolean_constraints, 8 + 252 + 253 + 1);
+}
+
+#[test]
+fn test_declared_range_is_enforced_exactly() {
+    let source = r#"
+    proof Bounded {
+        input x: Field<10..100>;
+        witness y: Field;
+        assert y === x + 1;
+    }"#;
+    let holds = |x: u64| satisfiable(source, &[("x", x)], &[("y", x + 1)]);
+
+    assert!(holds(10));
+    assert!(holds(100));
+    // 7 bits hold up to 127, which the bound still rules out
+    assert!(!holds(101));
+    assert!(!holds(127));
+    assert!(!holds(9));
+}
+
+#[test]
+fn test_comparison_operands_are_range_checked_once() {
+    let source = r#"
+    proof TwoBounds {
+        input x: Field;
+        input y: Field;
+        assert x < 100;
+        assert x + y > 5;
+        assert x >= 1;
+    }"#;
+    let r1cs = compile_to_r1cs(source).unwrap();
+    let stats = r1cs.statistics();
+
+    // 252-bit range checks stop at bit 251, the 253-bit differences go one further
+    let has_bit = |i: usize| {
+        let suffix = format!("_bit_{}", i);
+        r1cs.witnesses
+            .iter()
+            .filter(|w| w.ends_with(&suffix))
+            .count()
+    };
+    // x once, plus a bound temp for `x + y`; constants need no bits
+    assert_eq!(has_bit(251) - has_bit(252), 2);
+    assert_eq!(stats.comparisons, 3);
+}
+
+#[test]
+fn test_comparison_rejects_constant_outside_range() {
+    let source = r#"
+    proof TooWide {
+        input x: Field;
+        assert x < 14474011154664524427946373126085988481658748083205070504932198000989141204992;
+    }"#;
+    match compile_to_r1cs(source) {
+        Err(err) => assert!(err.contains("does not fit"), "{}", err),
+        Ok(_) => panic!("negative comparison constant should be rejected"),
+    }
+}
+
+#[test]
+fn test_proof_without_constraints_is_rejected() {
+    let source = r#"
+    proof Empty {
+        input x: Field;
+        let y = x in
+        y
+    }"#;
+    let ast = Parser::new(Lexer::new(source)).parse_program().unwrap();
+    TypeChecker::new().check_program(&ast).unwrap();
+
+    let err = R1CSGenerator::new().convert_proof(&ast[0]).unwrap_err();
+    assert!(matches!(&err, R1CSError::NoConstraints(name) if name == "Empty"));
+    assert!(err.to_string().contains("no constraints"));
+}
+
+#[test]
+fn test_negative_literal_is_a_single_one_term() {
+    let source = r#"
+    proof Negative {
+        witness out: Field;
+        assert out === -3;
+    }"#;
+    let r1cs = compile_to_r1cs(source).unwrap();
+
+    assert_eq!(r1cs.constraints.len(), 1);
+    assert_eq!(
+        r1cs.constraints[0].c.terms,
+        vec![("ONE".to_string(), BigInt::from(-3))]
+    );
+    assert!(r1cs.witnesses.iter().all(|w| w == "out"));
+}
+
+#[test]
+fn test_logical_operands_must_be_boolean() {
+    use lofit::inputs::parse_partial_witness;
+    use lofit::{generate_full_witness_with_provided, ConstraintSystem};
+
+    // Field operands only reach `||` when the type checker is bypassed, which is what a
+    // malicious prover effectively does by picking non-boolean values
+    let source = r#"
+    proof Or {
+        witness a: Field;
+        witness b: Field;
+        witness out: Field;
+        assert out === (a || b);
+    }"#;
+    let ast = Parser::new(Lexer::new(source)).parse_program().unwrap();
+    let mut r1cs = R1CSGenerator::new();
+    r1cs.convert_proof(&ast[0]).unwrap();
+
+    let dir = tempfile::tempdir().unwrap();
+    r1cs.write_r1cs_file(&dir.path().join("or.lof")).unwrap();
+    let cs = ConstraintSystem::from_file(std::fs::File::open(dir.path().join("or.r1cs")).unwrap())
+        .unwrap();
+
+    let satisfied = |a: &str, b: &str, out: &str| {
+        let witness = [
+            ("a".to_string(), a.to_string()),
+            ("b".to_string(), b.to_string()),
+            ("out".to_string(), out.to_string()),
+        ]
+        .into();
+        let provided = parse_partial_witness(&witness, &cs);
+        let values = generate_full_witness_with_provided(&cs, &[], &provided).unwrap();
+        cs.find_unsatisfied(&ConstraintSystem::full_assignment(&[], &values))
+            .is_none()
+    };
+
+    assert!(satisfied("1", "0", "1"));
+    assert!(satisfied("0", "0", "0"));
+    // 2 + 2 - 2*2 = 0 used to pass as a boolean result
+    assert!(!satisfied("2", "2", "0"));
+}
+
+#[test]
+fn test_compile_and_prove_in_memory() {
+    use lofit::inputs::{parse_inputs_in_order, parse_partial_witness, InputsJson};
+    use lofit::{generate_full_witness_with_provided, LofCircuit, ProverKey};
+
+    let source = "proof Cube {\n    input out: Field;\n    witness y: Field;\n    assert out === y * y * y;\n}";
+    let cs = lof::compile_dsl_to_constraint_system(source).unwrap();
+
+    let public: InputsJson = [("out".to_string(), "27".to_string())].into();
+    let witness: InputsJson = [("y".to_string(), "3".to_string())].into();
+    let pub_values = parse_inputs_in_order(&public, &cs.public_inputs).unwrap();
+    let provided = parse_partial_witness(&witness, &cs);
+    let wit_values = generate_full_witness_with_provided(&cs, &pub_values, &provided).unwrap();
+
+    let (pk, vk) = ProverKey::setup(LofCircuit {
+        public_inputs: vec![Default::default(); pub_values.len()],
+        witness: vec![Default::default(); wit_values.len()],
+        constraints: cs.constraints.clone(),
+    })
+    .unwrap();
+    let proof = pk
+        .prove(LofCircuit {
+            public_inputs: pub_values.clone(),
+            witness: wit_values,
+            constraints: cs.constraints.clone(),
+        })
+        .unwrap();
+    assert!(vk.verify(&proof, &pub_values).unwrap());
+}
+
+#[test]
+fn test_in_memory_constraint_system_matches_r1cs_file() {
+    let source = r#"
+    proof Range {
+        input x: Field;
+        witness y: Field;
+        assert y === x * x;
+        assert x < 10;
+    }"#;
+    let r1cs = lof::compile_dsl_to_r1cs(source).unwrap();
+
+    let dir = tempfile::tempdir().unwrap();
+    r1cs.write_r1cs_file(&dir.path().join("range.lof")).unwrap();
+    let from_file = lofit::ConstraintSystem::from_file(
+        std::fs::File::open(dir.path().join("range.r1cs")).unwrap(),
+    )
+    .unwrap();
+    assert_eq!(from_file.name.as_deref(), Some("Range"));
+
+    assert_eq!(
+        format!("{:?}", r1cs.to_constraint_system()),
+        format!("{:?}", from_file)
+    );
+}
+
+#[test]
+fn test_zero_constant_serializes_like_empty_combination() {
+    let x = || LinearCombination {
+        terms: vec![("x".to_string(), BigInt::from(1))],
+    };
+    let write = |c: LinearCombination, name: &str, dir: &std::path::Path| {
+        let mut r1cs = R1CSGenerator::new();
+        r1cs.witnesses.push("x".to_string());
+        r1cs.constraints.push(R1CSConstraint { a: x(), b: x(), c });
+        r1cs.write_r1cs_file(&dir.join(format!("{}.lof", name)))
+            .unwrap();
+        (
+            std::fs::read(dir.join(format!("{}.r1cs", name))).unwrap(),
+            format!("{:?}", r1cs.to_constraint_system()),
+        )
+    };
+
+    assert!(LinearCombination::zero().terms.is_empty());
+    let dir = tempfile::tempdir().unwrap();
+    let empty = write(LinearCombination::zero(), "empty", dir.path());
+    let one_times_zero = write(
+        LinearCombination {
+            terms: vec![("ONE".to_string(), BigInt::from(0))],
+        },
+        "one_times_zero",
+        dir.path(),
+    );
+    assert_eq!(empty, one_times_zero);
+}
+
+#[test]
+fn test_unflattened_product_is_rejected_as_non_quadratic() {
+    let term = |var: &str| LinearCombination {
+        terms: vec![(var.to_string(), BigInt::from(1))],
+    };
+    let mut r1cs = R1CSGenerator::new();
+    r1cs.witnesses.extend(["x".to_string(), "y".to_string()]);
+    r1cs.constraints.push(R1CSConstraint {
+        a: term("x"),
+        b: term("x"),
+        c: term("y"),
+    });
+    assert!(r1cs.validate_quadratic().is_ok());
+
+    // `x*x` is not a wire at all
+    r1cs.constraints.push(R1CSConstraint {
+        a: term("x*x"),
+        b: term("x"),
+        c: term("y"),
+    });
+    match r1cs.validate_quadratic() {
+        Err(R1CSError::NonQuadratic(index)) => assert_eq!(index, 1),
+        other => panic!("expected NonQuadratic, got {:?}", other),
+    }
+
+    let mut compiled = compile_to_r1cs(
+        r#"
+    proof Cube {
+        input x: Field;
+        witness y: Field;
+        assert y === x * x * x;
+    }"#,
+    )
+    .unwrap();
+    assert!(compiled.validate_quadratic().is_ok());
+
+    // drop the constraint giving `x * x` its wire, as if the product had been fed into the
+    // next multiplication unflattened; the temporary is still an allocated witness
+    let square = compiled
+        .constraints
+        .iter()
+        .position(|c| c.a.terms == term("x").terms && c.b.terms == term("x").terms)
+        .expect("x * x gets its own constraint");
+    let temp = compiled.constraints.remove(square).c.terms[0].0.clone();
+    assert!(compiled.witnesses.contains(&temp));
+    let consumer = compiled
+        .constraints
+        .iter()
+        .position(|c| {
+            c.a.terms
+                .iter()
+                .chain(&c.b.terms)
+                .any(|(var, _)| *var == temp)
+        })
+        .expect("the product feeds the next multiplication");
+    match compiled.validate_quadratic() {
+        Err(R1CSError::NonQuadratic(index)) => assert_eq!(index, consumer),
+        other => panic!("expected NonQuadratic, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_subtraction_is_left_associative() {
+    let rhs_terms = |expr: &str| {
+        let source = format!(
+            r#"
+    proof Sub {{
+        input a: Field;
+        input b: Field;
+        input c: Field;
+        witness out: Field;
+        assert out === {};
+    }}"#,
+            expr
+        );
+        let r1cs = compile_to_r1cs(&source).unwrap();
+        assert_eq!(r1cs.constraints.len(), 1);
+        let mut terms: Vec<(String, i64)> = r1cs.constraints[0]
+            .c
+            .terms
+            .iter()
+            .map(|(var, coeff)| (var.clone(), coeff.try_into().unwrap()))
+            .collect();
+        terms.sort();
+        terms
+    };
+    let terms = |expected: &[(&str, i64)]| {
+        expected
+            .iter()
+            .map(|(var, coeff)| (var.to_string(), *coeff))
+            .collect::<Vec<_>>()
+    };
+
+    assert_eq!(
+        rhs_terms("a - b - c"),
+        terms(&[("a", 1), ("b", -1), ("c", -1)])
+    );
+    assert_eq!(
+        rhs_terms("a - (b - c)"),
+        terms(&[("a", 1), ("b", -1), ("c", 1)])
+    );
+    // like terms merge instead of piling up
+    assert_eq!(rhs_terms("a - b - c + b"), terms(&[("a", 1), ("c", -1)]));
+    assert_eq!(rhs_terms("a - (a - c)"), terms(&[("c", 1)]));
+}
+
+#[test]
+fn test_bare_assert_requires_a_boolean_result() {
+    // parsed without the type checker, which already rejects this
+    let source = r#"
+    proof BareAssert {
+        witness x: Field;
+        assert x
+    }"#;
+    let ast = Parser::new(Lexer::new(source)).parse_program().unwrap();
+    match R1CSGenerator::new().convert_proof(&ast[0]) {
+        Err(R1CSError::TypeError(message)) => assert!(message.contains("boolean condition")),
+        other => panic!("expected a type error, got {:?}", other.map(|_| ())),
+    }
+
+    let r1cs = compile_to_r1cs(
+        r#"
+    proof BareAssert {
+        input x: Field;
+        let ok = x > 3 in
+        assert ok
+    }"#,
+    )
+    .unwrap();
+    let last = r1cs.constraints.last().unwrap();
+    assert_eq!(last.b.terms, vec![("ONE".to_string(), BigInt::from(1))]);
+    assert_eq!(last.c.terms, vec![("ONE".to_string(), BigInt::from(1))]);
+    assert!(matches!(last.a.terms.as_slice(), [(var, _)] if r1cs.boolean_vars.contains(var)));
+}
+
+#[test]
+fn test_assert_of_an_equality_is_the_equality_constraint() {
+    let r1cs = compile_to_r1cs(
+        r#"
+    proof Square {
+        input x: Field;
+        witness y: Field;
+        assert x === y * y
+    }"#,
+    )
+    .unwrap();
+    // the product and the equality, with no `cond * 1 = 1` on top: the equality already holds
+    // or the proof fails
+    let one = vec![("ONE".to_string(), BigInt::from(1))];
+    assert_eq!(r1cs.constraints.len(), 2, "{:?}", r1cs.constraints);
+    assert!(r1cs.constraints.iter().all(|c| c.c.terms != one));
+    assert!(r1cs.boolean_vars.is_empty());
+}
+
+#[test]
+fn test_component_call_inlines_internal_constraints() {
+    use lofit::inputs::{parse_inputs_in_order, parse_partial_witness, InputsJson};
+    use lofit::{generate_full_witness_with_provided, LofCircuit};
+
+    let source = r#"
+    component Square {
+        input x: field;
+        witness y: field;
+
+        {
+            assert y === x * x;
+            y
+        }
+    }
+
+    proof UseSquare {
+        input a: field;
+        witness result: field;
+
+        result === Square(a)
+    }
+    "#;
+    let r1cs = lof::compile_dsl_to_r1cs(source).unwrap();
+    let a = || vec![("a".to_string(), BigInt::from(1))];
+    assert!(r1cs
+        .constraints
+        .iter()
+        .any(|c| c.a.terms == a() && c.b.terms == a()));
+
+    let cs = r1cs.to_constraint_system();
+    let public: InputsJson = [("a".to_string(), "3".to_string())].into();
+    let check = |result: &str| {
+        let witness: InputsJson = [("result".to_string(), result.to_string())].into();
+        let pub_values = parse_inputs_in_order(&public, &cs.public_inputs).unwrap();
+        let provided = parse_partial_witness(&witness, &cs);
+        let Ok(wit_values) = generate_full_witness_with_provided(&cs, &pub_values, &provided)
+        else {
+            return false;
+        };
+        LofCircuit {
+            public_inputs: pub_values,
+            witness: wit_values,
+            constraints: cs.constraints.clone(),
+        }
+        .unsatisfied_constraints()
+        .is_empty()
+    };
+    assert!(check("9"));
+    assert!(!check("10"));
+}
+
+#[test]
+fn test_component_inlines_the_components_it_uses() {
+    // Hasher comes first and still gets to use Mixer
+    let source = r#"
+    component Hasher {
+        input x: field;
+        input y: field;
+        witness h: field;
+
+        {
+            assert h === Mixer(x, y) * x;
+            h
+        }
+    }
+
+    component Mixer {
+        input a: field;
+        input b: field;
+        witness m: field;
+
+        {
+            assert m === a * b;
+            m
+        }
+    }
+
+    proof UseHasher {
+        input p: field;
+        input q: field;
+        witness out: field;
+
+        out === Hasher(p, q)
+    }
+    "#;
+    let r1cs = lof::compile_dsl_to_r1cs(source).unwrap();
+    let var = |name: &str| vec![(name.to_string(), BigInt::from(1))];
+    // Mixer's product of the proof inputs, and Hasher's product of Mixer's result with p
+    assert!(r1cs
+        .constraints
+        .iter()
+        .any(|c| c.a.terms == var("p") && c.b.terms == var("q")));
+    assert!(r1cs.constraints.iter().any(|c| c.b.terms == var("p")
+        && matches!(c.a.terms.as_slice(), [(t, _)] if t.starts_with("UseHasher::t_"))));
+
+    assert!(satisfiable(source, &[("p", 3), ("q", 5)], &[("out", 45)]));
+    assert!(!satisfiable(source, &[("p", 3), ("q", 5)], &[("out", 15)]));
+}
+
+#[test]
+fn test_recursive_function_is_rejected_instead_of_overflowing() {
+    let source = r#"
+    let spin (x: Field): Field = spin(x) + 1
+
+    proof Spin {
+        input x: Field;
+        witness y: Field;
+        assert y === spin(x);
+    }"#;
+    let ast = Parser::new(Lexer::new(source)).parse_program().unwrap();
+
+    let mut generator = R1CSGenerator::new();
+    for expr in &ast {
+        match expr {
+            Expression::FunctionDef {
+                name, params, body, ..
+            } => generator.register_function(name.clone(), params.clone(), *body.clone()),
+            Expression::Proof { .. } => match generator.convert_proof(expr) {
+                Err(R1CSError::RecursionLimitExceeded(function)) => assert_eq!(function, "spin"),
+                other => panic!("expected a recursion error, got {:?}", other),
+            },
+            _ => {}
+        }
+    }
+}
+
+fn convert_decompose_proof(width: &str) -> Result<R1CSGenerator, R1CSError> {
+    let source = format!(
+        "proof Decompose {{\n    input x: Field;\n    let low = decompose(x, {}) in\n    assert x === x;\n}}",
+        width
+    );
+    let ast = Parser::new(Lexer::new(&source)).parse_program().unwrap();
+    let mut r1cs = R1CSGenerator::new();
+    r1cs.convert_proof(&ast[0])?;
+    Ok(r1cs)
+}
+
+#[test]
+fn test_decompose_rejects_widths_outside_the_field() {
+    for width in ["0", "255"] {
+        match convert_decompose_proof(width) {
+            Err(R1CSError::InvalidArgument(msg)) => assert!(msg.contains("254"), "{}", msg),
+            other => panic!("width {} should be rejected, got {:?}", width, other.err()),
+        }
+    }
+
+    let r1cs = convert_decompose_proof("8").unwrap();
+    assert_eq!(
+        r1cs.witnesses
+            .iter()
+            .filter(|w| w.contains("_bit_"))
+            .count(),
+        8
+    );
+}
+
+#[test]
+fn test_full_width_decompose_rejects_aliased_bits() {
+    use lofit::{fr_from_str, LofCircuit};
+
+    let r1cs = convert_decompose_proof("254").unwrap();
+    let cs = r1cs.to_constraint_system();
+    let modulus: BigInt = lof::ir::FIELD_MODULUS.parse().unwrap();
+
+    // `low`, then the bits of `value`, then the prefix-equality chain from the top bit
+    let satisfied = |value: &BigInt| {
+        let flag = |b: bool| fr_from_str(if b { "1" } else { "0" }).unwrap();
+        let x = fr_from_str(&(value % &modulus).to_string()).unwrap();
+        let mut witness = vec![x];
+        witness.extend((0..254u64).map(|i| flag(value.bit(i))));
+        let mut equal = true;
+        for i in (0..254u64).rev() {
+            equal = equal && value.bit(i) == modulus.bit(i);
+            witness.push(flag(equal));
+        }
+        assert_eq!(witness.len(), cs.witnesses.len());
+
+        LofCircuit {
+            public_inputs: vec![x],
+            witness,
+            constraints: cs.constraints.clone(),
+        }
+        .unsatisfied_constraints()
+        .is_empty()
+    };
+
+    assert!(satisfied(&BigInt::from(5)));
+    assert!(satisfied(&(&modulus - 1)));
+    // 5 + p is the same field element and still fits in 254 bits
+    assert!(!satisfied(&(&modulus + 5)));
+}
+
+#[test]
+fn test_optimized_range_check_is_smaller_and_still_proves() {
+    use lof::OptLevel;
+    use lofit::inputs::{parse_inputs_in_order, parse_partial_witness, InputsJson};
+    use lofit::{generate_full_witness_with_provided, LofCircuit, ProverKey};
+
+    let source = r#"
+    proof Range {
+        input x: Field;
+        witness y: Field;
+        assert y === x * x;
+        assert x < 10;
+    }"#;
+    let unoptimized = lof::compile_dsl_to_r1cs(source).unwrap();
+    let mut optimized = lof::compile_dsl_to_r1cs(source).unwrap();
+    let report = optimized.optimize(OptLevel::O1);
+    assert_eq!(report.constraints_before, unoptimized.constraints.len());
+    assert_eq!(report.constraints_after, optimized.constraints.len());
+    assert!(optimized.constraints.len() < unoptimized.constraints.len());
+    assert!(optimized.witnesses.len() < unoptimized.witnesses.len());
+
+    let cs = optimized.to_constraint_system();
+    let (pk, vk) = ProverKey::setup(LofCircuit {
+        public_inputs: vec![Default::default(); cs.public_inputs.len()],
+        witness: vec![Default::default(); cs.witnesses.len()],
+        constraints: cs.constraints.clone(),
+    })
+    .unwrap();
+
+    let prove = |x: &str, y: &str| {
+        let public: InputsJson = [("x".to_string(), x.to_string())].into();
+        let witness: InputsJson = [("y".to_string(), y.to_string())].into();
+        let pub_values = parse_inputs_in_order(&public, &cs.public_inputs).unwrap();
+        let provided = parse_partial_witness(&witness, &cs);
+        let wit_values = generate_full_witness_with_provided(&cs, &pub_values, &provided).ok()?;
+        let circuit = LofCircuit {
+            public_inputs: pub_values.clone(),
+            witness: wit_values,
+            constraints: cs.constraints.clone(),
+        };
+        if !circuit.unsatisfied_constraints().is_empty() {
+            return None;
+        }
+        let proof = pk.prove(circuit).unwrap();
+        Some(vk.verify(&proof, &pub_values).unwrap())
+    };
+
+    assert_eq!(prove("3", "9"), Some(true));
+    // the range check survives optimization
+    assert_eq!(prove("12", "144"), None);
+}
+
+#[test]
+fn test_wire_indices_are_stable_across_compiles() {
+    let layout = |source: &str| {
+        let r1cs = compile_to_r1cs(source).unwrap();
+        let mut wires: Vec<(usize, String)> = r1cs
+            .pub_inputs
+            .iter()
+            .chain(&r1cs.witnesses)
+            .map(|name| (r1cs.wire_index(name).unwrap(), name.clone()))
+            .collect();
+        wires.sort();
+        (wires, r1cs.to_constraint_system())
+    };
+    let source = r#"
+    proof Layout {
+        input b: Field;
+        input a: Array<Field, 11>;
+        witness z: Field;
+        witness y: Field;
+        assert z === a[10] * b;
+        assert y === z * a[2];
+    }"#;
+    // the same circuit with its signals declared in another order
+    let reordered = r#"
+    proof Layout {
+        witness y: Field;
+        input a: Array<Field, 11>;
+        witness z: Field;
+        input b: Field;
+        assert z === a[10] * b;
+        assert y === z * a[2];
+    }"#;
+
+    let (wires, cs) = layout(source);
+    assert_eq!(wires, layout(source).0);
+    assert_eq!(format!("{:?}", cs), format!("{:?}", layout(source).1));
+    assert_eq!(wires, layout(reordered).0);
+
+    let names: Vec<&str> = wires.iter().map(|(_, name)| name.as_str()).collect();
+    assert_eq!(&names[..3], ["a[0]", "a[1]", "a[2]"]);
+    assert_eq!(&names[10..14], ["a[10]", "b", "y", "z"]);
+    assert_eq!(wires[0].0, 1);
+    assert!(names[14..]
+        .iter()
+        .all(|name| name.starts_with("Layout::t_")));
+}
+
+#[test]
+fn test_bits_witness_is_boolean_and_recomposes() {
+    use lofit::{fr_from_str, LofCircuit};
+
+    let source = r#"
+    proof Byte {
+        input value: Field;
+        witness bits: Bits<8>;
+        assert value === decompose(bits);
+    }"#;
+    let r1cs = compile_to_r1cs(source).unwrap();
+    let names: Vec<String> = (0..8).map(|i| format!("bits[{}]", i)).collect();
+    assert_eq!(r1cs.witnesses, names);
+    assert_eq!(r1cs.constraints.len(), 9);
+    assert_eq!(r1cs.statistics().boolean_constraints, 8);
+
+    let cs = r1cs.to_constraint_system();
+    let satisfied = |value: &str, bits: [&str; 8]| {
+        LofCircuit {
+            public_inputs: vec![fr_from_str(value).unwrap()],
+            witness: bits.iter().map(|b| fr_from_str(b).unwrap()).collect(),
+            constraints: cs.constraints.clone(),
+        }
+        .unsatisfied_constraints()
+        .is_empty()
+    };
+    assert!(satisfied("5", ["1", "0", "1", "0", "0", "0", "0", "0"]));
+    assert!(satisfied("255", ["1"; 8]));
+    // 2 + 2*2 + 1*4 = 10, but a bit may not be 2
+    assert!(!satisfied("10", ["2", "2", "1", "0", "0", "0", "0", "0"]));
+    assert!(!satisfied("6", ["1", "0", "1", "0", "0", "0", "0", "0"]));
+}
+
+#[test]
+fn test_full_width_bits_reject_aliased_values() {
+    use lofit::{fr_from_str, LofCircuit};
+
+    let source = r#"
+    proof Wide {
+        input value: Field;
+        witness bits: Bits<254>;
+        assert value === decompose(bits);
+    }"#;
+    let r1cs = compile_to_r1cs(source).unwrap();
+    let cs = r1cs.to_constraint_system();
+    let modulus: BigInt = lof::ir::FIELD_MODULUS.parse().unwrap();
+
+    // the bits, then the prefix-equality chain from the top bit
+    let satisfied = |value: &BigInt| {
+        let flag = |b: bool| fr_from_str(if b { "1" } else { "0" }).unwrap();
+        let mut witness: Vec<_> = (0..254u64).map(|i| flag(value.bit(i))).collect();
+        let mut equal = true;
+        for i in (0..254u64).rev() {
+            equal = equal && value.bit(i) == modulus.bit(i);
+            witness.push(flag(equal));
+        }
+        assert_eq!(witness.len(), cs.witnesses.len());
+
+        LofCircuit {
+            public_inputs: vec![fr_from_str(&(value % &modulus).to_string()).unwrap()],
+            witness,
+            constraints: cs.constraints.clone(),
+        }
+        .unsatisfied_constraints()
+        .is_empty()
+    };
+
+    assert!(satisfied(&BigInt::from(5)));
+    assert!(satisfied(&(&modulus - 1)));
+    assert!(!satisfied(&(&modulus + 5)));
+}
+
+#[test]
+fn test_compose_of_decompose_equals_the_value() {
+    use lofit::inputs::{parse_inputs_in_order, parse_partial_witness, InputsJson};
+    use lofit::{generate_full_witness_with_provided, LofCircuit};
+
+    let source = r#"
+    proof RoundTrip {
+        input x: Field;
+        witness y: Field;
+        assert y === compose(decompose(x, 8));
+    }"#;
+    let cs = compile_to_r1cs(source).unwrap().to_constraint_system();
+
+    let satisfied = |x: &str, y: &str| {
+        let public: InputsJson = [("x".to_string(), x.to_string())].into();
+        let witness: InputsJson = [("y".to_string(), y.to_string())].into();
+        let pub_values = parse_inputs_in_order(&public, &cs.public_inputs).unwrap();
+        let provided = parse_partial_witness(&witness, &cs);
+        let Ok(wit_values) = generate_full_witness_with_provided(&cs, &pub_values, &provided)
+        else {
+            return false;
+        };
+        LofCircuit {
+            public_inputs: pub_values,
+            witness: wit_values,
+            constraints: cs.constraints.clone(),
+        }
+        .unsatisfied_constraints()
+        .is_empty()
+    };
+
+    assert!(satisfied("200", "200"));
+    assert!(!satisfied("200", "201"));
+}
+
+// whether the witness solver finds an assignment satisfying `source` for these signals
+fn satisfiable(source: &str, public: &[(&str, u64)], witness: &[(&str, u64)]) -> bool {
+    use lofit::inputs::{parse_inputs_in_order, parse_partial_witness, InputsJson};
+    use lofit::{generate_full_witness_with_provided, LofCircuit};
+
+    let cs = lof::compile_dsl_to_constraint_system(source).unwrap();
+    let json = |values: &[(&str, u64)]| -> InputsJson {
+        values
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect()
+    };
+    let pub_values = parse_inputs_in_order(&json(public), &cs.public_inputs).unwrap();
+    let provided = parse_partial_witness(&json(witness), &cs);
+    let Ok(wit_values) = generate_full_witness_with_provided(&cs, &pub_values, &provided) else {
+        return false;
+    };
+    LofCircuit {
+        public_inputs: pub_values,
+        witness: wit_values,
+        constraints: cs.constraints.clone(),
+    }
+    .unsatisfied_constraints()
+    .is_empty()
+}
+
+#[test]
+fn test_rotations_and_shifts_of_32_bit_words() {
+    let source = r#"
+    proof Rotate {
+        input x: Field;
+        witness left: Field;
+        witness right: Field;
+        assert left === rotl(x, 7, 32);
+        assert right === shr(x, 28, 32);
+    }"#;
+    let x: u32 = 0x9e37_79b9;
+    let rotated = x.rotate_left(7) as u64;
+    let shifted = (x >> 28) as u64;
+
+    assert!(satisfiable(
+        source,
+        &[("x", x as u64)],
+        &[("left", rotated), ("right", shifted)]
+    ));
+    assert!(!satisfiable(
+        source,
+        &[("x", x as u64)],
+        &[("left", rotated + 1), ("right", shifted)]
+    ));
+    assert!(!satisfiable(
+        source,
+        &[("x", x as u64)],
+        &[("left", rotated), ("right", shifted ^ 1)]
+    ));
+    // the operand has to fit the word
+    assert!(!satisfiable(
+        source,
+        &[("x", 1 << 32)],
+        &[("left", 1 << 7), ("right", 0)]
+    ));
+}
+
+#[test]
+fn test_xor3_of_32_bit_words() {
+    let source = r#"
+    proof Sigma {
+        input a: Field;
+        input b: Field;
+        input c: Field;
+        witness out: Field;
+        witness sigma: Field;
+        assert out === xor3(a, b, c);
+        assert sigma === xor3(rotl(a, 30, 32), rotl(a, 19, 32), rotl(a, 10, 32));
+    }"#;
+    let (a, b, c): (u32, u32, u32) = (0x6a09_e667, 0xbb67_ae85, 0x3c6e_f372);
+    let out = (a ^ b ^ c) as u64;
+    // SHA-256's big sigma 0, written with left rotations
+    let sigma = (a.rotate_right(2) ^ a.rotate_right(13) ^ a.rotate_right(22)) as u64;
+    let public = [("a", a as u64), ("b", b as u64), ("c", c as u64)];
+
+    assert!(satisfiable(
+        source,
+        &public,
+        &[("out", out), ("sigma", sigma)]
+    ));
+    assert!(!satisfiable(
+        source,
+        &public,
+        &[("out", out ^ 0x8000_0000), ("sigma", sigma)]
+    ));
+    assert!(!satisfiable(
+        source,
+        &public,
+        &[("out", out), ("sigma", sigma ^ 1)]
+    ));
+
+    // two products per bit of each xor3
+    let r1cs = compile_to_r1cs(source).unwrap();
+    assert!(r1cs.constraints.len() >= 2 * 2 * 32);
+}
+
+#[test]
+fn test_decompose_of_a_computed_value() {
+    let source = r#"
+    proof SumBits {
+        input a: Field;
+        input b: Field;
+        witness y: Field;
+        assert y === compose(decompose(a + b, 8));
+    }"#;
+    let r1cs = compile_to_r1cs(source).unwrap();
+    assert_eq!(r1cs.statistics().boolean_constraints, 8);
+
+    assert!(satisfiable(source, &[("a", 100), ("b", 55)], &[("y", 155)]));
+    assert!(!satisfiable(
+        source,
+        &[("a", 100), ("b", 55)],
+        &[("y", 154)]
+    ));
+    // the sum has to fit in the 8 bits
+    assert!(!satisfiable(
+        source,
+        &[("a", 200), ("b", 100)],
+        &[("y", 44)]
+    ));
+
+    // constants are placed on a wire as well
+    let constant = r#"
+    proof ConstBits {
+        input a: Field;
+        witness y: Field;
+        assert y === a + compose(decompose(5, 4));
+    }"#;
+    assert!(satisfiable(constant, &[("a", 1)], &[("y", 6)]));
+}
+
+#[test]
+fn test_compose_enforces_literal_bits_boolean() {
+    let source = r#"
+    proof Pack {
+        input a: Field;
+        input b: Field;
+        witness y: Field;
+        assert y === compose([a, b, 1]);
+    }"#;
+    let r1cs = compile_to_r1cs(source).unwrap();
+    // a and b become boolean, the constant 1 needs no check
+    assert_eq!(r1cs.statistics().boolean_constraints, 2);
+}
+
+#[test]
+fn test_constant_constraints_that_differ_are_rejected() {
+    let source = |body: &str| {
+        format!(
+            "proof Constant {{\n    input x: Field;\n    witness y: Field;\n    assert y === x * x;\n    {}\n}}",
+            body
+        )
+    };
+    let convert = |body: &str| {
+        let ast = Parser::new(Lexer::new(&source(body)))
+            .parse_program()
+            .unwrap();
+        R1CSGenerator::new().convert_proof(&ast[0])
+    };
+
+    assert!(lof::compile_dsl_to_r1cs(&source("assert 1 === 2")).is_err());
+    let err = convert("assert 1 === 2").unwrap_err();
+    assert!(matches!(&err, R1CSError::UnsatisfiableConstant(_)));
+    assert!(err.to_string().contains("`1 === 2`"), "{}", err);
+    assert!(matches!(
+        convert("assert 0").unwrap_err(),
+        R1CSError::UnsatisfiableConstant(_)
+    ));
+
+    assert!(lof::compile_dsl_to_r1cs(&source("assert 2 === 2")).is_ok());
+    convert("assert 1").unwrap();
+    // the modulus is 0 in the field
+    convert(&format!("assert {} === 0", lof::ir::FIELD_MODULUS)).unwrap();
+}
+
+#[test]
+fn test_vacuous_constant_constraints_are_dropped() {
+    let constraints = |extra: &str| {
+        let source = format!(
+            "proof Vacuous {{\n    input x: Field;\n    witness y: Field;\n    assert y === x * x;\n    {}\n}}",
+            extra
+        );
+        let ast = Parser::new(Lexer::new(&source)).parse_program().unwrap();
+        let mut r1cs = R1CSGenerator::new();
+        r1cs.convert_proof(&ast[0]).unwrap();
+        r1cs.constraints.len()
+    };
+
+    let baseline = constraints("");
+    assert_eq!(constraints("assert 5 === 5"), baseline);
+    assert_eq!(constraints("assert 2 + 3 === 5"), baseline);
+    assert_eq!(constraints("assert 1"), baseline);
+}
+
+#[test]
+fn test_assertion_builtins_lower_like_triple_equals() {
+    let lowered = |body: &str| {
+        let source = format!(
+            "proof P {{\n    input x: Field;\n    witness y: Field;\n    {};\n}}",
+            body
+        );
+        compile_to_r1cs(&source)
+            .unwrap()
+            .constraints
+            .iter()
+            .map(|c| c.to_string())
+            .collect::<Vec<_>>()
+    };
+
+    let eq = lowered("assert_eq(y, x)");
+    assert_eq!(eq.len(), 1, "{:?}", eq);
+    assert_eq!(eq, lowered("assert y === x"));
+
+    let zero = lowered("assert_zero(y - x)");
+    assert_eq!(zero.len(), 1, "{:?}", zero);
+    assert_eq!(zero, lowered("assert y - x === 0"));
+}
+
+#[test]
+fn test_comparison_rejects_operands_that_are_not_affine() {
+    // converted without the type checker, so only the R1CS guard stands in the way
+    let convert = |assertion: &str| {
+        let source = format!(
+            "proof Compare {{\n    input x: Field<0..100>;\n    witness y: Field<0..100>;\n    assert y === x * x;\n    {}\n}}",
+            assertion
+        );
+        let ast = Parser::new(Lexer::new(&source)).parse_program().unwrap();
+        R1CSGenerator::new().convert_proof(&ast[0])
+    };
+
+    assert!(convert("assert (x * y) < (y * x);").is_ok());
+    for (assertion, operand) in [("assert (x, y) < x;", "(x, y)"), ("assert x < z;", "z")] {
+        let err = convert(assertion).unwrap_err();
+        assert!(matches!(&err, R1CSError::NonAffineOperand(_)), "{:?}", err);
+        assert!(
+            err.to_string().contains(&format!("`{}`", operand)),
+            "{}",
+            err
+        );
+    }
+}
+
+#[test]
+fn test_generator_shared_across_proofs_keeps_them_separate() {
+    let source = r#"
+    let square (a: field): field = a * a
+
+    proof First {
+        input x: Field;
+        witness y: Field;
+        assert y === square(x);
+    }
+
+    proof Second {
+        input u: Field;
+        witness v: Field;
+        assert v === square(u + 1);
+    }"#;
+    let ast = Parser::new(Lexer::new(source)).parse_program().unwrap();
+    let mut generator = R1CSGenerator::new();
+    for item in &ast {
+        if let Expression::FunctionDef {
+            name, params, body, ..
+        } = item
+        {
+            generator.register_function(name.clone(), params.clone(), *body.clone());
+        }
+    }
+    let proofs: Vec<&Expression> = ast
+        .iter()
+        .filter(|item| matches!(item, Expression::Proof { .. }))
+        .collect();
+
+    let wires = |generator: &R1CSGenerator| -> Vec<String> {
+        generator
+            .constraints
+            .iter()
+            .flat_map(|c| [&c.a, &c.b, &c.c])
+            .flat_map(|lc| lc.terms.iter().map(|(var, _)| var.clone()))
+            .collect()
+    };
+
+    generator.convert_proof(proofs[0]).unwrap();
+    let first_constraints = generator.constraints.len();
+    let scoped = generator.new_scoped();
+    assert!(scoped.constraints.is_empty());
+    assert!(scoped.pub_inputs.is_empty() && scoped.witnesses.is_empty());
+
+    generator.convert_proof(proofs[1]).unwrap();
+    let second = wires(&generator);
+    assert!(!second.iter().any(|var| var == "x" || var == "y"));
+    assert!(second
+        .iter()
+        .all(|var| !var.contains("::t_") || var.starts_with("Second::")));
+    assert_eq!(generator.pub_inputs, vec!["u".to_string()]);
+
+    // the scoped generator still inlines the functions registered before it was made
+    let mut scoped = scoped;
+    scoped.convert_proof(proofs[1]).unwrap();
+    assert_eq!(wires(&scoped), second);
+
+    let mut fresh = generator.new_scoped();
+    fresh.convert_proof(proofs[0]).unwrap();
+    assert_eq!(fresh.constraints.len(), first_constraints);
+}
+
+#[test]
+fn test_modulo_is_the_integer_remainder() {
+    let source = |divisor: u64| {
+        format!(
+            r#"
+    proof Bucket {{
+        input x: Field;
+        witness r: Field;
+        assert r === x % {};
+    }}"#,
+            divisor
+        )
+    };
+
+    for (divisor, x) in [(8, 29), (10, 1234), (10, u64::MAX), (7, 6), (1, 42)] {
+        let source = source(divisor);
+        assert!(
+            satisfiable(&source, &[("x", x)], &[("r", x % divisor)]),
+            "{} % {}",
+            x,
+            divisor
+        );
+        assert!(
+            !satisfiable(&source, &[("x", x)], &[("r", x % divisor + divisor)]),
+            "{} % {}",
+            x,
+            divisor
+        );
+    }
+}
+
+#[test]
+fn test_modulo_rejects_a_remainder_shifted_by_the_divisor() {
+    use lofit::inputs::{parse_inputs_in_order, parse_partial_witness, InputsJson};
+    use lofit::{fr_from_str, fr_to_hex, generate_full_witness_with_provided, LofCircuit};
+
+    let source = r#"
+    proof Bucket {
+        input x: Field;
+        witness r: Field;
+        assert r === x % 10;
+    }"#;
+    let cs = lof::compile_dsl_to_constraint_system(source).unwrap();
+    let public: InputsJson = [("x".to_string(), "1234".to_string())].into();
+    let witness: InputsJson = [("r".to_string(), "4".to_string())].into();
+    let pub_values = parse_inputs_in_order(&public, &cs.public_inputs).unwrap();
+    let provided = parse_partial_witness(&witness, &cs);
+    let honest = generate_full_witness_with_provided(&cs, &pub_values, &provided).unwrap();
+
+    // `10 * q + r = s` also holds for q - 1 and r + 10; the prover then redoes every wire
+    // derived from them, so only the bound on r can tell
+    let split = cs
+        .constraints
+        .iter()
+        .find(|c| {
+            c.a.terms
+                .iter()
+                .any(|(_, coeff)| *coeff == BigInt::from(10))
+        })
+        .unwrap();
+    let wire = |coeff: i64| {
+        split
+            .a
+            .terms
+            .iter()
+            .find(|(_, c)| *c == BigInt::from(coeff))
+            .unwrap()
+            .0 as usize
+    };
+    let (quotient, remainder) = (wire(10), wire(1));
+    let declared_r =
+        cs.public_inputs.len() + 1 + cs.witnesses.iter().position(|w| w == "r").unwrap();
+
+    let mut z = vec![fr_from_str("1").unwrap()];
+    z.extend(pub_values.iter().cloned());
+    z.extend(honest.iter().cloned());
+    z[quotient] -= fr_from_str("1").unwrap();
+    z[remainder] += fr_from_str("10").unwrap();
+    z[declared_r] += fr_from_str("10").unwrap();
+
+    let coeff = |c: &BigInt| fr_from_str(&c.to_string()).unwrap();
+    let is_one = |lc: &lofit::r1cs::LinearCombination| matches!(lc.terms.as_slice(), [(0, c)] if *c == BigInt::from(1));
+    for _ in 0..3 {
+        for c in &cs.constraints {
+            if !is_one(&c.b) {
+                continue;
+            }
+            match (c.a.terms.as_slice(), c.c.terms.as_slice()) {
+                // a temp bound to a combination
+                ([(var, one)], _) if *one == BigInt::from(1) && *var != 0 => {
+                    z[*var as usize] =
+                        c.c.terms
+                            .iter()
+                            .map(|(v, k)| z[*v as usize] * coeff(k))
+                            .sum();
+                }
+                // the bits of a wire
+                (bits, [(target, one)]) if *one == BigInt::from(1) && bits.len() > 1 => {
+                    let value = BigInt::parse_bytes(
+                        fr_to_hex(&z[*target as usize])
+                            .trim_start_matches("0x")
+                            .as_bytes(),
+                        16,
+                    )
+                    .unwrap();
+                    for (i, (bit, _)) in bits.iter().enumerate() {
+                        z[*bit as usize] =
+                            fr_from_str(if value.bit(i as u64) { "1" } else { "0" }).unwrap();
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    let unsatisfied = |witness: Vec<_>| {
+        LofCircuit {
+            public_inputs: pub_values.clone(),
+            witness,
+            constraints: cs.constraints.clone(),
+        }
+        .unsatisfied_constraints()
+    };
+    assert!(unsatisfied(honest).is_empty());
+    assert_eq!(unsatisfied(z[1 + pub_values.len()..].to_vec()).len(), 1);
+}
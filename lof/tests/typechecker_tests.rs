@@ -1,6 +1,7 @@
 use lof::lexer::Lexer;
 use lof::parser::Parser;
-use lof::typechecker::{TypeChecker, TypeError};
+use lof::typechecker::{TypeChecker, TypeError, TypeWarning};
+use lof::{CompilerError, DiagnosticKind};
 
 fn parse_and_type_check(source: &str) -> Result<(), TypeError> {
     let lexer = Lexer::new(source);
@@ -1012,6 +1013,34 @@ fn test_refined_type_basic() {
     assert!(type_check_passes(source));
 }
 
+#[test]
+fn test_unasserted_refinement_warns() {
+    let source = r#"
+    proof RefinedTest {
+        input y: Field;
+        witness x: refined { field, x > 0 && x < 100 };
+        assert x === y * y;
+        assert x > 0;
+    }
+    "#;
+    assert_eq!(
+        type_check_warnings(source),
+        vec![TypeWarning::UncheckedRefinement("x".to_string())]
+    );
+
+    // each conjunct may be asserted on its own, with the comparison written either way round
+    let asserted = r#"
+    proof RefinedTest {
+        input y: Field;
+        witness x: refined { field, x > 0 && x < 100 };
+        assert x === y * y;
+        assert 0 < x;
+        assert x < 100;
+    }
+    "#;
+    assert!(type_check_warnings(asserted).is_empty());
+}
+
 #[test]
 fn test_refined_type_with_assertion() {
     let source = r#"
@@ -1058,3 +1087,1183 @@ fn test_refined_type_multiple_witnesses() {
     "#;
     assert!(type_check_passes(source));
 }
+
+#[test]
+fn test_match_guard_boolean_passes() {
+    let source = r#"
+    proof Test {
+        input x: Field;
+        witness y: Field;
+        let bucket = match x with
+            | n if n < 10 => 0
+            | n if n < 100 => 1
+            | _ => 2
+        in
+        assert y === bucket;
+    }"#;
+    assert!(type_check_passes(source));
+}
+
+#[test]
+fn test_match_guard_must_be_boolean() {
+    let source = r#"
+    proof Test {
+        input x: Field;
+        witness y: Field;
+        let bucket = match x with
+            | n if n + 1 => 0
+            | _ => 1
+        in
+        assert y === bucket;
+    }"#;
+    assert!(type_check_fails_with_type_mismatch(source));
+}
+
+#[test]
+fn test_match_arm_type_mismatch_names_the_arm() {
+    let source = r#"
+    proof Test {
+        input x: Field;
+        witness y: Field;
+        let bucket = match x with
+            | 0 => 1
+            | 1 => (x, x)
+            | _ => 2
+        in
+        assert y === bucket;
+    }"#;
+    let err = parse_and_type_check(source).unwrap_err();
+    assert!(
+        matches!(err, TypeError::MatchArmTypeMismatch { arm: 2, .. }),
+        "{:?}",
+        err
+    );
+    assert!(
+        err.to_string().starts_with("Match arm 2 has type"),
+        "{}",
+        err
+    );
+    assert_eq!(err.code(), "match_arm_type_mismatch");
+}
+
+#[test]
+fn test_match_guard_undefined_variable() {
+    let source = r#"
+    proof Test {
+        input x: Field;
+        witness y: Field;
+        let bucket = match x with
+            | 0 if z < 10 => 0
+            | _ => 1
+        in
+        assert y === bucket;
+    }"#;
+    assert!(type_check_fails_with_undefined_error(source));
+}
+
+#[test]
+fn test_array_index_with_nat_passes() {
+    let source = r#"
+    proof Test {
+        input arr: Array<field, 4>;
+        input i: Nat;
+        witness y: Field;
+        assert y === arr[i];
+    }"#;
+    assert!(type_check_passes(source));
+}
+
+#[test]
+fn test_array_index_with_field_fails() {
+    let source = r#"
+    proof Test {
+        input arr: Array<field, 4>;
+        input i: field;
+        witness y: Field;
+        assert y === arr[i];
+    }"#;
+    assert!(matches!(
+        parse_and_type_check(source),
+        Err(TypeError::TypeMismatch {
+            expected: lof::ast::Type::Nat,
+            ..
+        })
+    ));
+}
+
+#[test]
+fn test_array_index_with_literal_passes() {
+    let source = r#"
+    proof Test {
+        input arr: Array<field, 4>;
+        witness y: Field;
+        assert y === arr[2];
+    }"#;
+    assert!(type_check_passes(source));
+}
+
+#[test]
+fn test_nat_arithmetic_stays_nat() {
+    let source = r#"
+    proof Test {
+        input arr: Array<field, 8>;
+        input i: Nat;
+        input j: Nat;
+        witness y: Field;
+        assert y === arr[i * j + 1];
+    }"#;
+    assert!(type_check_passes(source));
+}
+
+#[test]
+fn test_nat_subtraction_is_not_an_index() {
+    let source = r#"
+    proof Test {
+        input arr: Array<field, 8>;
+        input i: Nat;
+        input j: Nat;
+        witness y: Field;
+        assert y === arr[i - j];
+    }"#;
+    assert!(type_check_fails_with_type_mismatch(source));
+}
+
+#[test]
+fn test_type_alias_in_signal() {
+    let source = r#"
+    type Hash = Field;
+
+    proof Test {
+        input x: Hash;
+        witness h: Hash;
+        assert h === x * x;
+    }"#;
+    assert!(type_check_passes(source));
+}
+
+#[test]
+fn test_chained_type_alias_in_array() {
+    let source = r#"
+    type Hash = Field;
+    type Digest = Hash;
+
+    proof Test {
+        input path: Array<Digest, 2>;
+        witness root: Digest;
+        assert root === path[0] * path[1];
+    }"#;
+    assert!(type_check_passes(source));
+}
+
+#[test]
+fn test_cyclic_type_alias_fails() {
+    let source = r#"
+    type A = B;
+    type B = A;
+
+    proof Test {
+        input x: A;
+        witness y: Field;
+        assert y === x;
+    }"#;
+    assert!(matches!(
+        parse_and_type_check(source),
+        Err(TypeError::CyclicTypeAlias(_))
+    ));
+}
+
+#[test]
+fn test_unknown_type_name_still_undefined() {
+    let source = r#"
+    proof Test {
+        input x: Missing;
+        witness y: Field;
+        assert y === x;
+    }"#;
+    assert!(matches!(
+        parse_and_type_check(source),
+        Err(TypeError::UndefinedType(name)) if name == "Missing"
+    ));
+}
+
+#[test]
+fn test_enum_constructor_expression() {
+    let source = r#"
+    type List = | Cons(Field, List) | Nil;
+
+    proof Test {
+        input x: Field;
+        witness y: Field;
+        let list = Cons(1, Nil) in
+        assert y === x;
+    }"#;
+    assert!(type_check_passes(source));
+}
+
+#[test]
+fn test_enum_constructor_wrong_arity() {
+    let source = r#"
+    type List = | Cons(Field, List) | Nil;
+
+    proof Test {
+        input x: Field;
+        witness y: Field;
+        let list = Cons(1) in
+        assert y === x;
+    }"#;
+    assert!(matches!(
+        parse_and_type_check(source),
+        Err(TypeError::ArgumentCountMismatch {
+            expected: 2,
+            found: 1
+        })
+    ));
+}
+
+#[test]
+fn test_enum_constructor_wrong_field_type() {
+    let source = r#"
+    type List = | Cons(Field, List) | Nil;
+
+    proof Test {
+        input x: Field;
+        witness y: Field;
+        let list = Cons(Nil, 1) in
+        assert y === x;
+    }"#;
+    assert!(type_check_fails_with_type_mismatch(source));
+}
+
+#[test]
+fn test_type_error_message_uses_display_form() {
+    let source = r#"
+    proof Test {
+        input x: Field;
+        input b: Bool;
+        witness y: Field;
+        assert y === x + b;
+        assert y < b;
+    }"#;
+    let err = lof::pipeline::CompilerError::TypeCheckerError(
+        Box::new(parse_and_type_check(source).expect_err("comparison with a bool should fail")),
+        None,
+    );
+    let message = err.render_with_source(source);
+
+    assert!(message.starts_with("Type error: Type mismatch: expected"));
+    assert!(!message.contains("TypeMismatch {"));
+    // nothing says where the error was raised, so no line is guessed for it
+    assert!(!message.contains("-->"), "{}", message);
+}
+
+#[test]
+fn test_type_error_renders_source_snippet_with_caret() {
+    let source =
+        "proof Test {\n    input x: Field;\n    witness unused_w: Field;\n    assert x === x;\n}";
+    let Err(err) = lof::compile_dsl_to_r1cs(source) else {
+        panic!("unconstrained witness should fail");
+    };
+    let message = err.render_with_source(source);
+
+    assert!(message.contains("Unconstrained witness 'unused_w'"));
+    assert!(message.contains("--> line 3, column 13"));
+    assert!(message.contains("3 |     witness unused_w: Field;"));
+    assert!(message.contains("  |             ^^^^^^^^"));
+}
+
+#[test]
+fn test_source_snippet_underlines_the_failing_statement() {
+    let source =
+        "proof Test {\n    input x: Field;\n    // y is asserted below\n    assert y === x;\n}";
+    let Err(err) = lof::compile_dsl_to_r1cs(source) else {
+        panic!("undefined variable should fail");
+    };
+    let message = err.render_with_source(source);
+
+    assert!(message.contains("--> line 4, column 5"), "{}", message);
+    assert!(message.contains("4 |     assert y === x;"), "{}", message);
+    assert!(
+        message.contains("  |     ^^^^^^^^^^^^^^\n") || message.ends_with("  |     ^^^^^^^^^^^^^^")
+    );
+}
+
+#[test]
+fn test_compile_with_diagnostics_keeps_typed_error() {
+    let source = "proof Test {\n    input x: Field;\n    assert missing === x;\n}";
+    let (r1cs, diagnostics) = lof::compile_with_diagnostics(source);
+
+    assert!(r1cs.is_none());
+    assert_eq!(diagnostics.len(), 1);
+    match &diagnostics[0].kind {
+        DiagnosticKind::Error(CompilerError::TypeCheckerError(error, _)) => match &**error {
+            TypeError::UndefinedVariable(name) => assert_eq!(name, "missing"),
+            other => panic!("expected an undefined variable error, got {:?}", other),
+        },
+        other => panic!("expected an undefined variable error, got {:?}", other),
+    }
+    // the statement the error was raised in, not the first textual match of the name
+    assert_eq!(diagnostics[0].line, Some(3));
+    assert_eq!(diagnostics[0].col, Some(5));
+}
+
+#[test]
+fn test_diagnostic_position_ignores_earlier_mentions_of_the_name() {
+    let source =
+        "proof Test {\n    input x: Field;\n    // y is asserted below\n    assert y === x;\n}";
+    let (_, diagnostics) = lof::compile_with_diagnostics(source);
+
+    assert_eq!(diagnostics[0].code, "undefined_variable");
+    assert_eq!(diagnostics[0].line, Some(4));
+    assert_eq!(diagnostics[0].col, Some(5));
+}
+
+#[test]
+fn test_diagnostic_position_is_omitted_when_the_statement_is_ambiguous() {
+    let source = "proof A {\n    input x: Field;\n    assert y === x;\n}\n\
+                  proof B {\n    input x: Field;\n    assert y === x;\n}";
+    let (_, diagnostics) = lof::compile_with_diagnostics(source);
+
+    assert_eq!(diagnostics[0].code, "undefined_variable");
+    assert_eq!(diagnostics[0].line, None);
+    assert_eq!(diagnostics[0].col, None);
+}
+
+#[test]
+fn test_unconstrained_witness_is_placed_at_its_declaration() {
+    let source =
+        "proof Test {\n    input x: Field;\n    witness w: Field;\n    assert x === x + 0;\n}";
+    let (_, diagnostics) = lof::compile_with_diagnostics(source);
+
+    assert_eq!(diagnostics[0].code, "unconstrained_witness");
+    assert_eq!(diagnostics[0].line, Some(3));
+    assert_eq!(diagnostics[0].col, Some(13));
+}
+
+#[test]
+fn test_r1cs_error_is_placed_at_its_statement() {
+    let source = "proof Test {\n    input x: Field;\n    witness y: Field;\n    assert y === x * x;\n    assert 1 === 2;\n}";
+    let (_, diagnostics) = lof::compile_with_diagnostics(source);
+
+    assert_eq!(diagnostics[0].code, "r1cs_error");
+    assert_eq!(diagnostics[0].line, Some(5));
+    assert_eq!(diagnostics[0].col, Some(5));
+}
+
+#[test]
+fn test_compile_with_diagnostics_keeps_parse_error() {
+    let (r1cs, diagnostics) = lof::compile_with_diagnostics("proof Test { input x: Field; ");
+
+    assert!(r1cs.is_none());
+    assert!(matches!(
+        diagnostics[0].kind,
+        DiagnosticKind::Error(CompilerError::ParserError(_))
+    ));
+    assert_eq!(diagnostics[0].code, "parse_error");
+}
+
+#[test]
+fn test_compile_with_diagnostics_success_has_no_diagnostics() {
+    let source =
+        "proof Test {\n    input x: Field;\n    witness y: Field;\n    assert y === x * x;\n}";
+    let (r1cs, diagnostics) = lof::compile_with_diagnostics(source);
+
+    assert!(r1cs.is_some());
+    assert!(diagnostics.is_empty());
+}
+
+fn type_check_warnings(source: &str) -> Vec<TypeWarning> {
+    let ast = Parser::new(Lexer::new(source)).parse_program().unwrap();
+    let mut type_checker = TypeChecker::new();
+    type_checker.check_program(&ast).unwrap();
+    type_checker.warnings().to_vec()
+}
+
+#[test]
+fn test_unused_input_warns() {
+    let source = r#"
+    proof Test {
+        input x: Field;
+        input unused: Field;
+        witness y: Field;
+        assert y === x * x;
+    }"#;
+
+    assert_eq!(
+        type_check_warnings(source),
+        vec![TypeWarning::UnusedInput("unused".to_string())]
+    );
+}
+
+#[test]
+fn test_suspicious_but_legal_constraints_warn() {
+    let source = r#"
+    proof Test {
+        input x: Field;
+        witness y: Field;
+        witness z: Field;
+        assert y === x * x;
+        assert y === y;
+        assert z === x * 0 + 1;
+    }"#;
+
+    assert_eq!(
+        type_check_warnings(source),
+        vec![
+            TypeWarning::TrivialAssertion("y".to_string()),
+            TypeWarning::MultipliedByZero("x".to_string()),
+        ]
+    );
+}
+
+#[test]
+fn test_warnings_are_returned_alongside_errors() {
+    let source = r#"
+    proof Test {
+        input x: Field;
+        witness y: Field;
+        assert x === x;
+        assert y === missing * x;
+    }"#;
+    let ast = Parser::new(Lexer::new(source)).parse_program().unwrap();
+    let (result, warnings) = TypeChecker::new().check_program_with_warnings(&ast);
+
+    assert!(result.is_err());
+    assert_eq!(
+        warnings,
+        vec![TypeWarning::TrivialAssertion("x".to_string())]
+    );
+}
+
+#[test]
+fn test_used_inputs_do_not_warn() {
+    let source = r#"
+    proof Test {
+        input x: Field;
+        input z: Field;
+        witness y: Field;
+        assert y === x * z;
+    }"#;
+
+    assert!(type_check_warnings(source).is_empty());
+}
+
+#[test]
+fn test_unused_input_is_a_warning_diagnostic() {
+    let source = "proof Test {\n    input x: Field;\n    input unused: Field;\n    witness y: Field;\n    assert y === x * x;\n}";
+    let (r1cs, diagnostics) = lof::compile_with_diagnostics(source);
+
+    assert!(r1cs.is_some());
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].severity, "warning");
+    assert_eq!(diagnostics[0].code, "unused_input");
+    assert_eq!(diagnostics[0].line, Some(3));
+    assert!(matches!(
+        diagnostics[0].kind,
+        DiagnosticKind::Warning(TypeWarning::UnusedInput(_))
+    ));
+}
+
+#[test]
+fn test_output_signal_is_constrained() {
+    // an output used only in a linear assertion is still public, so it is never unconstrained
+    let source = r#"
+    proof Sum {
+        input a: Field;
+        input b: Field;
+        output sum: Field;
+        assert sum === a + b;
+    }"#;
+    assert!(type_check_passes(source));
+}
+
+#[test]
+fn test_chained_comparison_type_checks() {
+    let source = r#"
+    proof Range {
+        input lo: Field;
+        input x: Field;
+        input hi: Field;
+        assert lo <= x < hi;
+    }"#;
+    assert!(type_check_passes(source));
+}
+
+#[test]
+fn test_const_shared_by_two_proofs() {
+    let source = r#"
+    const MODULUS = 21888242871839275222246405745257275088548364400416034343698204186575808495617;
+    const HALF = 1000 * 2;
+
+    proof First {
+        input x: Field;
+        witness y: Field;
+        assert y === x * MODULUS;
+    }
+
+    proof Second {
+        input x: Field;
+        witness y: Field;
+        assert y === x / HALF;
+    }"#;
+    assert!(type_check_passes(source));
+}
+
+#[test]
+fn test_const_must_be_constant() {
+    let source = r#"
+    const BAD = x + 1;
+
+    proof Test {
+        input x: Field;
+        assert x === BAD;
+    }"#;
+    assert_eq!(
+        parse_and_type_check(source),
+        Err(TypeError::NonConstantInitializer("BAD".to_string()))
+    );
+}
+
+#[test]
+fn test_function_return_type_is_inferred() {
+    let source = r#"
+    let double(x: field) = x * 2
+
+    proof Inferred {
+        input a: field;
+        witness out: field;
+        assert out === double(a);
+    }
+    "#;
+    assert!(type_check_passes(source));
+}
+
+#[test]
+fn test_inferred_return_type_is_checked_at_call_site() {
+    let source = r#"
+    let is_small(x: field) = x < 10
+
+    proof Inferred {
+        input a: field;
+        assert is_small(a) < 5;
+    }
+    "#;
+    assert!(type_check_fails_with_type_mismatch(source));
+}
+
+#[test]
+fn test_wrong_return_annotation_fails() {
+    let source = r#"
+    let is_small(x: field): field = x < 10
+
+    proof Annotated {
+        input a: field;
+        assert is_small(a) === 1;
+    }
+    "#;
+    assert!(type_check_fails_with_type_mismatch(source));
+}
+
+#[test]
+fn test_recursive_function_needs_annotation() {
+    let source = r#"
+    let forever(x: field) = forever(x)
+
+    proof Recursive {
+        input a: field;
+        assert forever(a) === a;
+    }
+    "#;
+    assert_eq!(
+        parse_and_type_check(source),
+        Err(TypeError::CannotInferReturnType("forever".to_string()))
+    );
+}
+
+#[test]
+fn test_allow_unconstrained_downgrades_error_to_warning() {
+    let source = r#"
+    proof Buggy {
+        input x: field;
+        witness forgotten: field;
+        assert x > 0
+    }
+    "#;
+    assert!(type_check_fails_with_unconstrained_witness_error(source));
+
+    let ast = Parser::new(Lexer::new(source)).parse_program().unwrap();
+    let mut type_checker = TypeChecker::new();
+    type_checker.set_allow_unconstrained(true);
+    type_checker.check_program(&ast).unwrap();
+
+    assert_eq!(
+        type_checker.warnings(),
+        &[TypeWarning::UnconstrainedWitness("forgotten".to_string())]
+    );
+}
+
+#[test]
+fn test_pipeline_allow_unconstrained_defaults_off() {
+    let source =
+        "proof Buggy {\n    input x: field;\n    witness forgotten: field;\n    assert x > 0\n}";
+    let path = std::path::Path::new("buggy.lof");
+
+    let mut pipeline = lof::pipeline::CompilerPipeline::new(source.to_string(), false);
+    assert!(matches!(
+        pipeline.type_check_only(path),
+        Err(CompilerError::TypeCheckerError(error, _))
+            if matches!(*error, TypeError::UnconstrainedWitness { .. })
+    ));
+
+    pipeline.allow_unconstrained = true;
+    let warnings = pipeline.type_check_only(path).unwrap();
+    assert_eq!(warnings.len(), 1);
+}
+
+#[test]
+fn test_array_argument_length_mismatch() {
+    let source = r#"
+    let first(xs: Array<Field, 4>): Field = xs[0]
+
+    proof WrongLength {
+        input xs: Array<Field, 8>;
+        witness y: Field;
+        assert y === first(xs)
+    }
+    "#;
+    assert_eq!(
+        parse_and_type_check(source),
+        Err(TypeError::ArrayLengthMismatch {
+            expected: 4,
+            found: 8
+        })
+    );
+}
+
+#[test]
+fn test_array_assertion_length_mismatch() {
+    let source = r#"
+    proof WrongLength {
+        input xs: Array<Field, 2>;
+        witness ys: Array<Field, 3>;
+        assert ys === xs
+    }
+    "#;
+    let err = parse_and_type_check(source).unwrap_err();
+    assert_eq!(err.code(), "array_length_mismatch");
+    assert!(err.to_string().contains("expected 3 elements, found 2"));
+}
+
+#[test]
+fn test_empty_array_with_ascription() {
+    let source = r#"
+    proof EmptyArray {
+        input x: Field;
+        witness y: Field;
+        let nothing = [] : Array<Field, 0> in
+        assert y === x * x
+    }
+    "#;
+    assert!(type_check_passes(source));
+}
+
+#[test]
+fn test_empty_array_without_ascription_is_rejected() {
+    let source = r#"
+    proof EmptyArray {
+        input x: Field;
+        witness y: Field;
+        let nothing = [] in
+        assert y === x * x
+    }
+    "#;
+    assert_eq!(
+        parse_and_type_check(source),
+        Err(TypeError::InvalidExpression)
+    );
+}
+
+#[test]
+fn test_empty_array_ascription_must_have_zero_length() {
+    let source = r#"
+    proof EmptyArray {
+        input x: Field;
+        witness y: Field;
+        let nothing = [] : Array<Field, 2> in
+        assert y === x * x
+    }
+    "#;
+    assert_eq!(
+        parse_and_type_check(source),
+        Err(TypeError::ArrayLengthMismatch {
+            expected: 2,
+            found: 0
+        })
+    );
+}
+
+#[test]
+fn test_bare_assert_on_field_is_rejected() {
+    let source = r#"
+    proof BareAssert {
+        witness x: Field;
+        assert x
+    }"#;
+    assert!(matches!(
+        parse_and_type_check(source),
+        Err(TypeError::NonBooleanInAssert(_))
+    ));
+
+    let source = r#"
+    proof BareAssert {
+        input x: Field;
+        let ok = x > 3 in
+        assert ok
+    }"#;
+    assert!(type_check_passes(source));
+}
+
+#[test]
+fn test_decompose_builtin_is_checked() {
+    let with_call = |call: &str| {
+        format!(
+            "proof Split {{\n    input x: field;\n    witness y: field;\n    assert y === {};\n}}",
+            call
+        )
+    };
+
+    assert!(matches!(
+        parse_and_type_check(&with_call("decompose()")),
+        Err(TypeError::ArgumentCountMismatch {
+            expected: 1,
+            found: 0
+        })
+    ));
+    assert!(matches!(
+        parse_and_type_check(&with_call("decompose(x, 8, 2)")),
+        Err(TypeError::ArgumentCountMismatch {
+            expected: 2,
+            found: 3
+        })
+    ));
+    assert!(matches!(
+        parse_and_type_check(&with_call("decompose(x, y)")),
+        Err(TypeError::InvalidBuiltinArgument { .. })
+    ));
+    assert!(matches!(
+        parse_and_type_check(&with_call("decompose(x, 255)")),
+        Err(TypeError::InvalidBuiltinArgument { .. })
+    ));
+    assert!(type_check_passes(&with_call("decompose(x, 8)")));
+    assert!(type_check_passes(&with_call("decompose(x + 1, 8)")));
+}
+
+#[test]
+fn test_bits_type_is_a_boolean_array() {
+    let with_body = |typ: &str, body: &str| {
+        format!(
+            "proof Byte {{\n    input value: field;\n    witness bits: {};\n    {}\n}}",
+            typ, body
+        )
+    };
+
+    assert!(type_check_passes(&with_body(
+        "Bits<8>",
+        "assert value === decompose(bits);"
+    )));
+    assert!(type_check_passes(&with_body(
+        "Bits<8>",
+        "assert value === decompose(bits, 8);"
+    )));
+    assert!(type_check_passes(&with_body(
+        "Bits<8>",
+        "assert bits[0] === value;"
+    )));
+    assert!(matches!(
+        parse_and_type_check(&with_body(
+            "Bits<8>",
+            "assert value === decompose(bits, 4);"
+        )),
+        Err(TypeError::InvalidBuiltinArgument { .. })
+    ));
+    assert!(matches!(
+        parse_and_type_check(&with_body("Bits<0>", "assert value === decompose(bits);")),
+        Err(TypeError::InvalidBitsWidth(_))
+    ));
+}
+
+#[test]
+fn test_compose_builtin_is_checked() {
+    let with_call = |call: &str| {
+        format!(
+            "proof Pack {{\n    input x: field;\n    input bits: Bits<4>;\n    witness y: field;\n    assert y === {};\n}}",
+            call
+        )
+    };
+
+    assert!(type_check_passes(&with_call("compose(bits)")));
+    assert!(type_check_passes(&with_call("compose(decompose(x, 8))")));
+    assert!(matches!(
+        parse_and_type_check(&with_call("compose(x)")),
+        Err(TypeError::InvalidBuiltinArgument { .. })
+    ));
+    assert!(matches!(
+        parse_and_type_check(&with_call("compose(bits, 4)")),
+        Err(TypeError::ArgumentCountMismatch {
+            expected: 1,
+            found: 2
+        })
+    ));
+}
+
+#[test]
+fn test_bitwise_builtins_are_checked() {
+    let with_call = |call: &str| {
+        format!(
+            "proof Mix {{\n    input x: field;\n    input y: field;\n    input z: field;\n    witness w: field;\n    assert w === {};\n}}",
+            call
+        )
+    };
+
+    assert!(type_check_passes(&with_call("rotl(x, 7, 32)")));
+    assert!(type_check_passes(&with_call("shr(x, 3, 32)")));
+    assert!(type_check_passes(&with_call("xor3(x, y, z)")));
+    assert!(type_check_passes(&with_call(
+        "xor3(rotl(x, 2, 32), rotl(x, 13, 32), shr(x, 22, 32), 32)"
+    )));
+    assert!(matches!(
+        parse_and_type_check(&with_call("rotl(x, 7)")),
+        Err(TypeError::ArgumentCountMismatch {
+            expected: 3,
+            found: 2
+        })
+    ));
+    assert!(matches!(
+        parse_and_type_check(&with_call("xor3(x, y)")),
+        Err(TypeError::ArgumentCountMismatch {
+            expected: 3,
+            found: 2
+        })
+    ));
+    assert!(matches!(
+        parse_and_type_check(&with_call("rotl(x, y, 32)")),
+        Err(TypeError::InvalidBuiltinArgument { .. })
+    ));
+    // a rotation by the full width is no rotation at all
+    assert!(matches!(
+        parse_and_type_check(&with_call("shr(x, 32, 32)")),
+        Err(TypeError::InvalidBuiltinArgument { .. })
+    ));
+    assert!(matches!(
+        parse_and_type_check(&with_call("xor3(x, y, z, 255)")),
+        Err(TypeError::InvalidBuiltinArgument { .. })
+    ));
+}
+
+#[test]
+fn test_reserved_names_cannot_be_bound() {
+    let reserved = |source: &str, name: &str| {
+        matches!(
+            parse_and_type_check(source),
+            Err(TypeError::ReservedName(found)) if found == name
+        )
+    };
+
+    assert!(reserved(
+        "proof P {\n    input ONE: field;\n    witness y: field;\n    assert y === ONE * ONE;\n}",
+        "ONE"
+    ));
+    assert!(reserved(
+        "proof P {\n    input x: field;\n    witness decompose: field;\n    assert decompose === x * x;\n}",
+        "decompose"
+    ));
+    assert!(reserved(
+        "proof P {\n    input x: field;\n    witness y: field;\n    let compose = x * x in\n    assert y === compose;\n}",
+        "compose"
+    ));
+    assert!(reserved(
+        "let decompose (x: field): field = x * x\n\
+         proof P {\n    input x: field;\n    witness y: field;\n    assert y === x * x;\n}",
+        "decompose"
+    ));
+    assert!(reserved(
+        "let square (ONE: field): field = ONE * ONE\n\
+         proof P {\n    input x: field;\n    witness y: field;\n    assert y === square(x);\n}",
+        "ONE"
+    ));
+    assert!(reserved(
+        "const ONE = 1\n\
+         proof P {\n    input x: field;\n    witness y: field;\n    assert y === x * ONE;\n}",
+        "ONE"
+    ));
+    assert!(reserved(
+        "proof P {\n    input x: field;\n    witness y: field;\n    \
+         assert y === match x with\n        | compose => compose * x;\n}",
+        "compose"
+    ));
+
+    // only the exact spelling is reserved
+    assert!(type_check_passes(
+        "proof P {\n    input one: field;\n    witness decomposed: field;\n    assert decomposed === one * one;\n}"
+    ));
+}
+
+#[test]
+fn test_compiler_wire_names_are_reserved() {
+    let source = |name: &str| {
+        format!(
+            "proof P {{\n    input x: field;\n    witness {0}: field;\n    assert {0} * {0} === x;\n}}",
+            name
+        )
+    };
+
+    for name in ["t_9", "t_0_P", "x_bit_0", "sum_bit_"] {
+        assert!(
+            matches!(
+                parse_and_type_check(&source(name)),
+                Err(TypeError::ReservedName(found)) if found == name
+            ),
+            "{}",
+            name
+        );
+    }
+    for name in ["t", "t_x", "total", "bit_0", "orbit_0"] {
+        assert!(type_check_passes(&source(name)), "{}", name);
+    }
+}
+
+#[test]
+fn test_components_can_use_components_declared_later() {
+    let source = r#"
+    component Outer {
+        input x: field;
+        witness y: field;
+
+        {
+            assert y === Inner(x) + 1;
+            y
+        }
+    }
+
+    component Inner {
+        input a: field;
+        witness b: field;
+
+        {
+            assert b === a * a;
+            b
+        }
+    }
+
+    proof Use {
+        input x: field;
+        witness out: field;
+
+        out === Outer(x)
+    }"#;
+    assert!(type_check_passes(source));
+
+    let cyclic = r#"
+    component Ping {
+        input x: field;
+        { Pong(x) }
+    }
+
+    component Pong {
+        input x: field;
+        { Ping(x) }
+    }
+
+    proof Use {
+        input x: field;
+        witness out: field;
+
+        out === Ping(x)
+    }"#;
+    let err = parse_and_type_check(cyclic).unwrap_err();
+    assert!(matches!(err, TypeError::RecursiveComponent(_)), "{:?}", err);
+}
+
+#[test]
+fn test_block_ending_in_an_assertion_is_unit() {
+    use lof::ast::{Expression, Operator, Type};
+
+    let source = r#"
+    proof Chained {
+        input x: field;
+        witness y: field;
+        assert y === x * x;
+        y === x * x
+    }"#;
+    let ast = Parser::new(Lexer::new(source)).parse_program().unwrap();
+    let Expression::Proof { body, .. } = &ast[0] else {
+        panic!("Expected proof");
+    };
+    assert!(
+        matches!(
+            body.as_ref(),
+            Expression::Block {
+                final_expr: None,
+                ..
+            }
+        ),
+        "{:?}",
+        body
+    );
+    assert!(type_check_passes(source));
+
+    let assertion = |op| Expression::BinaryOp {
+        left: Box::new(Expression::Number(1)),
+        op,
+        right: Box::new(Expression::Number(1)),
+    };
+    let mut type_checker = TypeChecker::new();
+    let ends_in_assertion = Expression::Block {
+        statements: vec![],
+        final_expr: Some(Box::new(assertion(Operator::Assert))),
+    };
+    assert_eq!(
+        type_checker.check_expression(&ends_in_assertion).unwrap(),
+        Type::Unit
+    );
+    let ends_in_value = Expression::Block {
+        statements: vec![Expression::Assert(Box::new(assertion(Operator::Assert)))],
+        final_expr: Some(Box::new(assertion(Operator::Add))),
+    };
+    assert_ne!(
+        type_checker.check_expression(&ends_in_value).unwrap(),
+        Type::Unit
+    );
+}
+
+#[test]
+fn test_assertion_builtins_are_checked() {
+    let source = r#"
+    proof P {
+        input x: field;
+        witness y: field;
+        assert_eq(y, x * x);
+        assert_zero(y - x * x)
+    }"#;
+    assert!(type_check_passes(source));
+
+    let err =
+        parse_and_type_check("proof P { input x: field; witness y: field; assert_eq(y, x, x) }")
+            .unwrap_err();
+    assert!(
+        matches!(
+            err,
+            TypeError::ArgumentCountMismatch {
+                expected: 2,
+                found: 3
+            }
+        ),
+        "{:?}",
+        err
+    );
+    assert!(!type_check_passes(
+        "proof P { input x: field; witness y: field; assert_zero() }"
+    ));
+    assert!(!type_check_passes(
+        "proof P { input x: field; witness y: Array<field, 2>; assert_eq(y, x) }"
+    ));
+    assert!(matches!(
+        parse_and_type_check(
+            "let assert_eq (a: field): field = a\n\
+             proof P {\n    input x: field;\n    witness y: field;\n    assert y === x;\n}"
+        ),
+        Err(TypeError::ReservedName(name)) if name == "assert_eq"
+    ));
+}
+
+#[test]
+fn test_literal_patterns_over_bool_and_tuple() {
+    let source = |scrutinee: &str, first: &str| {
+        format!(
+            "proof Test {{\n    input b: Bool;\n    input x: Field;\n    witness y: Field;\n    let v = match {} with\n        | {} => 5\n        | _ => 7\n    in\n    assert y === v;\n}}",
+            scrutinee, first
+        )
+    };
+
+    // a bool matches as the field value 0 or 1
+    assert!(type_check_passes(&source("b", "0")));
+    assert!(type_check_passes(&source("b", "1")));
+
+    for (scrutinee, first) in [("b", "2"), ("(x, x)", "0")] {
+        let err = parse_and_type_check(&source(scrutinee, first)).unwrap_err();
+        assert!(
+            matches!(err, TypeError::PatternMismatch { .. }),
+            "{}: {:?}",
+            scrutinee,
+            err
+        );
+        assert!(
+            err.to_string().starts_with(&format!(
+                "Pattern mismatch: pattern {} does not match",
+                first
+            )),
+            "{}",
+            err
+        );
+    }
+}
+
+#[test]
+fn test_modulo_result_carries_the_divisor_bound() {
+    use lof::ast::{ConstraintStatus, Expression, Operator, Refinement, Type};
+
+    let modulo = Expression::BinaryOp {
+        left: Box::new(Expression::Number(29)),
+        op: Operator::Mod,
+        right: Box::new(Expression::Number(8)),
+    };
+    assert_eq!(
+        TypeChecker::new().check_expression(&modulo).unwrap(),
+        Type::Field {
+            constraint: ConstraintStatus::Constrained,
+            refinement: Some(Refinement::Range { min: 0, max: 7 }),
+        }
+    );
+
+    // the remainder of a field signal is the same bounded field
+    let source = r#"
+    const LEN = 8;
+    proof Bucket {
+        input x: field;
+        input flag: bool;
+        assert flag && x % LEN
+    }"#;
+    assert!(matches!(
+        parse_and_type_check(source),
+        Err(TypeError::TypeMismatch {
+            found: Type::Field {
+                refinement: Some(Refinement::Range { min: 0, max: 7 }),
+                ..
+            },
+            ..
+        })
+    ));
+}
+
+#[test]
+fn test_modulo_requires_a_positive_constant_divisor() {
+    for divisor in ["n", "0", "0 - 3"] {
+        let source = format!(
+            r#"
+    proof Bucket {{
+        input x: field;
+        input n: field;
+        witness r: field;
+        assert r === x % ({})
+    }}"#,
+            divisor
+        );
+        assert!(
+            matches!(
+                parse_and_type_check(&source),
+                Err(TypeError::InvalidModulus(_))
+            ),
+            "{}",
+            divisor
+        );
+    }
+}
@@ -1,4 +1,4 @@
-use crate::r1cs::{Constraint, LinearCombination};
+use crate::r1cs::{unsatisfied, Constraint, LinearCombination};
 use ark_ff::{Field, PrimeField};
 use ark_relations::r1cs::{
     ConstraintSynthesizer, ConstraintSystemRef, LinearCombination as ArkLinearCombination,
@@ -13,6 +13,19 @@ pub struct LofCircuit<F: Field> {
     pub constraints: Vec<Constraint>,
 }
 
+impl<F: PrimeField> LofCircuit<F> {
+    // indices of the constraints the current assignment violates, checked before proving so
+    // failures can be reported per constraint instead of as an opaque proving error
+    pub fn unsatisfied_constraints(&self) -> Vec<usize> {
+        let assignment: Vec<F> = std::iter::once(F::one())
+            .chain(self.public_inputs.iter().copied())
+            .chain(self.witness.iter().copied())
+            .collect();
+
+        unsatisfied(&self.constraints, &assignment).collect()
+    }
+}
+
 impl<F: PrimeField> ConstraintSynthesizer<F> for LofCircuit<F> {
     fn generate_constraints(self, cs: ConstraintSystemRef<F>) -> Result<(), SynthesisError> {
         println!("\nActual values being used:");
@@ -1,5 +1,5 @@
 use ark_bn254::Fr;
-use ark_ff::{BigInt as ArkBigInt, PrimeField};
+use ark_ff::{BigInt as ArkBigInt, BigInteger, PrimeField};
 use num_bigint::{BigInt, ParseBigIntError, Sign};
 use thiserror::Error;
 
@@ -7,14 +7,21 @@ use thiserror::Error;
 pub enum FieldElementParseError {
     #[error("failed to parse integer: {0}")]
     InvalidInteger(#[from] ParseBigIntError),
+    #[error("invalid hexadecimal integer '{0}'")]
+    InvalidHex(String),
     #[error("value exceeds BN254 field capacity")]
     OutOfRange,
     #[error("failed to convert integer into field element")]
     NotInField,
 }
 
+/// Parses a decimal integer, or a `0x`-prefixed hexadecimal one as [`fr_to_hex`] writes it.
 pub fn fr_from_str(input: &str) -> Result<Fr, FieldElementParseError> {
-    let bigint = input.parse::<BigInt>()?;
+    let bigint = match input.strip_prefix("0x") {
+        Some(digits) => BigInt::parse_bytes(digits.as_bytes(), 16)
+            .ok_or_else(|| FieldElementParseError::InvalidHex(input.to_string()))?,
+        None => input.parse::<BigInt>()?,
+    };
     let (sign, magnitude) = bigint.to_bytes_le();
 
     let mut limbs = [0u64; 4];
@@ -40,3 +47,14 @@ pub fn fr_from_str(input: &str) -> Result<Fr, FieldElementParseError> {
 
     Ok(fr)
 }
+
+/// `0x` followed by the 64 hex digits of the canonical value, the way EVM tooling prints a word.
+pub fn fr_to_hex(fr: &Fr) -> String {
+    let digits: String = fr
+        .into_bigint()
+        .to_bytes_be()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect();
+    format!("0x{}", digits)
+}
@@ -0,0 +1,188 @@
+use crate::field::{fr_from_str, FieldElementParseError};
+use crate::r1cs::ConstraintSystem;
+use ark_bn254::Fr;
+use std::collections::{BTreeMap, HashMap};
+use thiserror::Error;
+use tracing::{debug, instrument, warn};
+
+// public inputs and witnesses are flat objects keyed by signal name: {"x": "3", "y": "9"}.
+// files may also give an array or tuple signal as a nested list, see `flatten_inputs`
+pub type InputsJson = HashMap<String, String>;
+
+#[derive(Debug, Error)]
+pub enum InputReadError {
+    #[error("missing variable '{name}' in JSON file")]
+    MissingVariable { name: String },
+    #[error("failed to parse value for '{name}': {source}")]
+    InvalidField {
+        name: String,
+        #[source]
+        source: FieldElementParseError,
+    },
+    #[error("unsupported value for '{name}': expected a string, an integer or an array")]
+    UnsupportedValue { name: String },
+    #[error("invalid inputs JSON: {0}")]
+    Json(#[from] serde_json::Error),
+}
+
+// `{"arr": ["1", "2"]}` becomes `{"arr[0]": "1", "arr[1]": "2"}`, the names the compiler gives
+// array elements; nested lists index further, `m[0][1]`. a tuple `pt` is flattened the same
+// way and `pt[0]` is then looked up as the component `pt_0`, see `input_value`
+pub fn flatten_inputs(
+    raw: HashMap<String, serde_json::Value>,
+) -> Result<InputsJson, InputReadError> {
+    fn flatten(
+        name: String,
+        value: serde_json::Value,
+        out: &mut InputsJson,
+    ) -> Result<(), InputReadError> {
+        match value {
+            serde_json::Value::String(s) => {
+                out.insert(name, s);
+            }
+            serde_json::Value::Number(n) if n.is_i64() || n.is_u64() => {
+                out.insert(name, n.to_string());
+            }
+            serde_json::Value::Array(elements) => {
+                for (i, element) in elements.into_iter().enumerate() {
+                    flatten(format!("{}[{}]", name, i), element, out)?;
+                }
+            }
+            _ => return Err(InputReadError::UnsupportedValue { name }),
+        }
+        Ok(())
+    }
+
+    let mut flat = InputsJson::new();
+    for (name, value) in raw {
+        flatten(name, value, &mut flat)?;
+    }
+    Ok(flat)
+}
+
+pub fn read_inputs_json(reader: impl std::io::Read) -> Result<InputsJson, InputReadError> {
+    flatten_inputs(serde_json::from_reader(reader)?)
+}
+
+// the compiler names tuple components `pt_0`, `pt_1`; a list given for `pt` reaches here as
+// `pt[0]`, `pt[1]`, so the flat name is tried first and the list spelling second
+fn input_value<'a>(json_map: &'a InputsJson, name: &str) -> Option<&'a String> {
+    json_map.get(name).or_else(|| {
+        let (base, index) = name.rsplit_once('_')?;
+        if index.is_empty() || !index.chars().all(|c| c.is_ascii_digit()) {
+            return None;
+        }
+        json_map.get(&format!("{}[{}]", base, index))
+    })
+}
+
+// the reverse of `input_value`: the tuple component a list entry `pt[0]` stands for
+fn tuple_component_name(key: &str) -> Option<String> {
+    let (base, index) = key.strip_suffix(']')?.rsplit_once('[')?;
+    if index.is_empty() || !index.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    Some(format!("{}_{}", base, index))
+}
+
+pub fn input_template(variable_names: &[String]) -> BTreeMap<String, String> {
+    variable_names
+        .iter()
+        .map(|name| (name.clone(), "0".to_string()))
+        .collect()
+}
+
+// a merged inputs file holds public inputs and witnesses side by side, keyed by signal name;
+// the r1cs signal lists decide which side each entry belongs to
+pub fn split_inputs(
+    json_map: &InputsJson,
+    public_names: &[String],
+    witness_names: &[String],
+) -> (InputsJson, InputsJson) {
+    let mut public = InputsJson::new();
+    let mut witness = InputsJson::new();
+    for (key, value) in json_map {
+        let name = match tuple_component_name(key) {
+            Some(component) if !public_names.contains(key) && !witness_names.contains(key) => {
+                component
+            }
+            _ => key.clone(),
+        };
+        if public_names.contains(&name) {
+            public.insert(name, value.clone());
+        } else if witness_names.contains(&name) {
+            witness.insert(name, value.clone());
+        } else {
+            warn!(
+                "Ignoring '{name}': not a signal of this circuit",
+                name = key
+            );
+        }
+    }
+    (public, witness)
+}
+
+#[instrument(level = "debug", skip(json_map))]
+pub fn parse_inputs_in_order(
+    json_map: &InputsJson,
+    variable_names: &[String],
+) -> Result<Vec<Fr>, InputReadError> {
+    let mut values = Vec::new();
+    for name in variable_names {
+        let value_str = input_value(json_map, name)
+            .ok_or_else(|| InputReadError::MissingVariable { name: name.clone() })?;
+
+        let value = fr_from_str(value_str).map_err(|source| InputReadError::InvalidField {
+            name: name.clone(),
+            source,
+        })?;
+
+        values.push(value);
+    }
+    debug!("Parsed {} input values", values.len());
+    Ok(values)
+}
+
+// the public inputs as the prover and verifier take them: in r1cs order, values validated as
+// field elements but kept as strings for the JS side
+pub fn ordered_public_inputs(
+    json_map: &InputsJson,
+    public_names: &[String],
+) -> Result<Vec<String>, InputReadError> {
+    parse_inputs_in_order(json_map, public_names)?;
+    Ok(public_names
+        .iter()
+        .filter_map(|name| input_value(json_map, name).cloned())
+        .collect())
+}
+
+// the declared witnesses the file gives, in wire order up to the first one it leaves out;
+// compiler temporaries are always derived, so a value given for one is ignored
+pub fn parse_partial_witness(json_map: &InputsJson, r1cs: &ConstraintSystem) -> Vec<Fr> {
+    let declared = r1cs.declared_witnesses();
+    for name in &r1cs.witnesses[declared.len()..] {
+        if input_value(json_map, name).is_some() {
+            warn!(
+                "Ignoring provided value for computed witness '{name}'",
+                name = name
+            );
+        }
+    }
+
+    let mut values = Vec::new();
+    for name in declared {
+        if let Some(value_str) = input_value(json_map, name) {
+            match fr_from_str(value_str) {
+                Ok(val) => values.push(val),
+                Err(err) => {
+                    warn!("Failed to parse value for '{name}': {err}", name = name);
+                    break;
+                }
+            }
+        } else {
+            break;
+        }
+    }
+    debug!("Parsed {} partial witness values from JSON", values.len());
+    values
+}
@@ -1,16 +1,17 @@
 use ark_bn254::Fr;
-use clap::{ArgAction, Parser, Subcommand};
+use clap::{ArgAction, Parser, Subcommand, ValueEnum};
 use indexmap::IndexMap;
 use lofit::{
-    field::{fr_from_str, FieldElementParseError},
-    generate_full_witness, generate_full_witness_with_provided, package_for_web, ConstraintSystem,
-    LofCircuit, Proof, ProverKey, VerifierKey,
+    fr_to_hex, generate_named_witness,
+    inputs::{parse_inputs_in_order, parse_partial_witness, read_inputs_json, split_inputs},
+    package_for_web, validate_for_web, ConstraintSystem, LofCircuit, Proof, ProverKey, SymbolTable,
+    VerifierKey,
 };
-use std::collections::HashMap;
+use rayon::prelude::*;
 use std::fs::File;
-use std::io::{BufReader, BufWriter};
+use std::io::{BufReader, BufWriter, Read};
 use std::path::{Path, PathBuf};
-use thiserror::Error;
+use std::time::Instant;
 use tracing::{debug, error, info, instrument, warn};
 
 const VERSION: &str = env!("CARGO_PKG_VERSION");
@@ -20,10 +21,52 @@ const VERSION: &str = env!("CARGO_PKG_VERSION");
 #[command(version = VERSION)]
 #[command(about = "Lof ZK Toolkit - Handles proving and verification for Lof circuits")]
 struct Cli {
+    #[arg(
+        long,
+        global = true,
+        help = "Print constraint counts and how long each phase takes"
+    )]
+    progress: bool,
     #[command(subcommand)]
     command: Commands,
 }
 
+// `--progress` output goes to stdout so it shows regardless of log configuration
+#[derive(Clone, Copy)]
+struct Progress(bool);
+
+impl Progress {
+    fn circuit(self, r1cs: &ConstraintSystem) {
+        if self.0 {
+            println!(
+                "Circuit: {} constraints, {} public inputs, {} witnesses",
+                r1cs.num_constraints(),
+                r1cs.num_public_inputs(),
+                r1cs.num_witnesses()
+            );
+        }
+    }
+
+    fn phase<T>(self, label: &str, run: impl FnOnce() -> T) -> T {
+        if self.0 {
+            println!("{}...", label);
+        }
+        let started = Instant::now();
+        let result = run();
+        if self.0 {
+            println!("{} took {:.2?}", label, started.elapsed());
+        }
+        result
+    }
+}
+
+// how field elements are written to the full witness JSON
+#[derive(Clone, Copy, ValueEnum)]
+enum WitnessFormat {
+    Decimal,
+    Hex,
+}
+
 #[derive(Subcommand)]
 enum Commands {
     Setup {
@@ -33,6 +76,14 @@ enum Commands {
         proving_key: Option<PathBuf>,
         #[arg(short = 'v', long)]
         verification_key: Option<PathBuf>,
+        #[arg(long, help = "Regenerate keys even if they match the circuit")]
+        force: bool,
+    },
+    SetupAll {
+        #[arg(value_name = "DIR")]
+        dir: PathBuf,
+        #[arg(long, help = "Regenerate keys even if they match the circuit")]
+        force: bool,
     },
     Prove {
         #[arg(short, long)]
@@ -43,8 +94,50 @@ enum Commands {
         public_inputs: Option<PathBuf>,
         #[arg(short, long)]
         witness: Option<PathBuf>,
+        #[arg(
+            long,
+            conflicts_with_all = ["public_inputs", "witness"],
+            help = "Single JSON with public inputs and witnesses keyed by signal name"
+        )]
+        inputs: Option<PathBuf>,
+        #[arg(
+            long,
+            conflicts_with_all = ["witness", "inputs"],
+            help = "Complete witness JSON keyed by wire name, used instead of solving for one"
+        )]
+        full_witness: Option<PathBuf>,
         #[arg(short, long)]
         output: Option<PathBuf>,
+        #[arg(
+            long,
+            value_enum,
+            default_value = "decimal",
+            help = "Write full_witness.json values as decimal or 0x-prefixed hex"
+        )]
+        witness_format: WitnessFormat,
+    },
+    Witness {
+        #[arg(short, long)]
+        input: PathBuf,
+        #[arg(short = 'u', long = "public-inputs")]
+        public_inputs: Option<PathBuf>,
+        #[arg(short, long)]
+        witness: Option<PathBuf>,
+        #[arg(
+            long,
+            conflicts_with_all = ["public_inputs", "witness"],
+            help = "Single JSON with public inputs and witnesses keyed by signal name"
+        )]
+        inputs: Option<PathBuf>,
+        #[arg(short, long, help = "Where to write the full assignment (JSON)")]
+        output: Option<PathBuf>,
+        #[arg(
+            long,
+            value_enum,
+            default_value = "decimal",
+            help = "Write the values as decimal or 0x-prefixed hex"
+        )]
+        witness_format: WitnessFormat,
     },
     Verify {
         #[arg(short = 'v', long)]
@@ -55,6 +148,24 @@ enum Commands {
         public_inputs: Option<PathBuf>,
         #[arg(short, long, help = "R1CS input file (used to determine base name)")]
         input: Option<PathBuf>,
+        #[arg(
+            long,
+            conflicts_with = "public_inputs",
+            help = "Merged inputs JSON; only the public entries are used"
+        )]
+        inputs: Option<PathBuf>,
+        #[arg(
+            long,
+            conflicts_with = "proof",
+            help = "Proof as inline JSON, or - to read it from stdin"
+        )]
+        proof_json: Option<String>,
+        #[arg(
+            long,
+            conflicts_with_all = ["public_inputs", "inputs"],
+            help = "Public inputs as inline JSON, or - to read them from stdin"
+        )]
+        public_inputs_json: Option<String>,
     },
     PackageWeb {
         #[arg(short, long, help = "R1CS input file")]
@@ -63,73 +174,28 @@ enum Commands {
         output: Option<PathBuf>,
         #[arg(long, action = ArgAction::SetTrue, help = "Skip building WASM artifacts (generate sources only)")]
         skip_wasm: bool,
+        #[arg(long, action = ArgAction::SetTrue, help = "Only check that the circuit can be packaged; generates no keys and builds nothing")]
+        validate: bool,
     },
     Version,
 }
 
-type InputsJson = HashMap<String, String>;
-
-#[derive(Debug, Error)]
-enum InputReadError {
-    #[error("missing variable '{name}' in JSON file")]
-    MissingVariable { name: String },
-    #[error("failed to parse value for '{name}': {source}")]
-    InvalidField {
-        name: String,
-        #[source]
-        source: FieldElementParseError,
-    },
-}
-
-#[instrument(level = "debug", skip(json_map))]
-fn parse_inputs_in_order(
-    json_map: &InputsJson,
-    variable_names: &[String],
-) -> Result<Vec<Fr>, InputReadError> {
-    let mut values = Vec::new();
-    for name in variable_names {
-        let value_str = json_map
-            .get(name)
-            .ok_or_else(|| InputReadError::MissingVariable { name: name.clone() })?;
-
-        let value = fr_from_str(value_str).map_err(|source| InputReadError::InvalidField {
-            name: name.clone(),
-            source,
-        })?;
-
-        values.push(value);
-    }
-    debug!("Parsed {} input values", values.len());
-    Ok(values)
-}
-
-fn parse_partial_witness(json_map: &InputsJson, variable_names: &[String]) -> Vec<Fr> {
-    let mut values = Vec::new();
-    for name in variable_names {
-        if let Some(value_str) = json_map.get(name) {
-            match fr_from_str(value_str) {
-                Ok(val) => values.push(val),
-                Err(err) => {
-                    warn!("Failed to parse value for '{name}': {err}", name = name);
-                    break;
-                }
-            }
-        } else {
-            break;
-        }
-    }
-    debug!("Parsed {} partial witness values from JSON", values.len());
-    values
-}
-
 fn handle_setup(
     input: PathBuf,
     proving_key: Option<PathBuf>,
     verification_key: Option<PathBuf>,
+    force: bool,
+    progress: Progress,
 ) -> Result<(), Box<dyn std::error::Error>> {
     info!("Reading R1CS from {}", input.display());
     let r1cs_file = File::open(&input)?;
     let r1cs = ConstraintSystem::from_file(r1cs_file)?;
+    info!(
+        "Circuit has {} constraints over {} wires",
+        r1cs.num_constraints(),
+        r1cs.num_variables()
+    );
+    progress.circuit(&r1cs);
 
     let base_name = infer_base_name(&input);
     let keys_dir = Path::new("keys");
@@ -139,14 +205,30 @@ fn handle_setup(
     let verification_key_path =
         verification_key.unwrap_or_else(|| keys_dir.join(format!("{}_vk.bin", base_name)));
 
+    let circuit_hash = r1cs.circuit_hash();
+    if !force && verification_key_path.exists() && proving_key_path.exists() {
+        let existing = ProverKey::read_circuit_hash(File::open(&proving_key_path)?)?;
+        if existing == Some(circuit_hash) {
+            println!(
+                "Keys in {} already match circuit {:016x}; pass --force to regenerate",
+                proving_key_path.display(),
+                circuit_hash
+            );
+            return Ok(());
+        }
+        info!("Existing keys were generated for a different circuit, regenerating");
+    }
+
     let circuit = LofCircuit {
-        public_inputs: vec![Fr::from(0u64); r1cs.public_inputs.len()],
+        public_inputs: vec![Fr::from(0u64); r1cs.num_public_inputs()],
         witness: vec![Fr::from(0u64); 1],
         constraints: r1cs.constraints,
     };
 
     info!("Generating cryptographic keys...");
-    let (pk, vk) = ProverKey::setup(circuit)?;
+    let (pk, vk) = progress.phase("Key generation", || ProverKey::setup(circuit))?;
+    let pk = pk.with_circuit_hash(circuit_hash);
+    let vk = vk.with_circuit_hash(circuit_hash);
 
     if let Some(parent) = proving_key_path.parent() {
         std::fs::create_dir_all(parent)?;
@@ -172,22 +254,68 @@ fn handle_setup(
     Ok(())
 }
 
+// keys land in keys/ under each circuit's file stem, as `setup` would write them; a circuit that
+// fails is reported and the rest of the batch still runs
+fn handle_setup_all(
+    dir: PathBuf,
+    force: bool,
+    progress: Progress,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut circuits: Vec<PathBuf> = std::fs::read_dir(&dir)?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "r1cs"))
+        .collect();
+    circuits.sort();
+    if circuits.is_empty() {
+        return Err(format!("No .r1cs files found in {}", dir.display()).into());
+    }
+    info!("Generating keys for {} circuits", circuits.len());
+
+    let results: Vec<Result<(), String>> = circuits
+        .par_iter()
+        .map(|input| {
+            handle_setup(input.clone(), None, None, force, progress).map_err(|e| e.to_string())
+        })
+        .collect();
+
+    let mut failed = 0;
+    for (input, result) in circuits.iter().zip(&results) {
+        match result {
+            Ok(()) => println!("{}: keys ready", input.display()),
+            Err(err) => {
+                failed += 1;
+                println!("{}: setup failed: {}", input.display(), err);
+            }
+        }
+    }
+
+    if failed > 0 {
+        return Err(format!("Setup failed for {} of {} circuits", failed, circuits.len()).into());
+    }
+    Ok(())
+}
+
 fn handle_prove(
     input: PathBuf,
     proving_key: Option<PathBuf>,
-    public_inputs: Option<PathBuf>,
-    witness: Option<PathBuf>,
+    sources: InputSources,
     output: Option<PathBuf>,
+    witness_format: WitnessFormat,
+    progress: Progress,
 ) -> Result<(), Box<dyn std::error::Error>> {
     info!("Reading R1CS from {}", input.display());
     let r1cs_file = File::open(&input)?;
     let r1cs: ConstraintSystem = ConstraintSystem::from_file(r1cs_file)?;
+    info!(
+        "Circuit has {} constraints over {} wires",
+        r1cs.num_constraints(),
+        r1cs.num_variables()
+    );
+    progress.circuit(&r1cs);
 
     let base_name = infer_base_name(&input);
     let proving_key_path = proving_key
         .unwrap_or_else(|| Path::new("keys").join(format!("{}_pk.bin", base_name.as_str())));
-    let public_inputs_path = public_inputs
-        .unwrap_or_else(|| Path::new("inputs").join(format!("{}_public.json", base_name.as_str())));
     let output_path = output
         .unwrap_or_else(|| Path::new("proofs").join(format!("{}_proof.bin", base_name.as_str())));
 
@@ -195,60 +323,16 @@ fn handle_prove(
     let pk_reader = BufReader::new(File::open(&proving_key_path)?);
     let pk = ProverKey::read(pk_reader)?;
 
-    info!(
-        "Reading public inputs from {}",
-        public_inputs_path.display()
-    );
-    let pub_inputs_json: InputsJson = serde_json::from_reader(File::open(&public_inputs_path)?)?;
-    debug!("Public inputs: {:?}", pub_inputs_json);
-
-    let pub_values = parse_inputs_in_order(&pub_inputs_json, &r1cs.public_inputs)?;
-
-    let witness_path = witness.unwrap_or_else(|| {
-        Path::new("inputs").join(format!("{}_witness.json", base_name.as_str()))
-    });
-
-    let wit_values = if witness_path.exists() {
-        info!("Reading provided witness from {}", witness_path.display());
-        let wit_inputs_json: InputsJson = serde_json::from_reader(File::open(&witness_path)?)?;
-        debug!("Witness inputs: {:?}", wit_inputs_json);
-        let provided_witnesses = parse_partial_witness(&wit_inputs_json, &r1cs.witnesses);
-
-        info!(
-            "Generating full witness with {} provided witness values...",
-            provided_witnesses.len()
-        );
-        generate_full_witness_with_provided(&r1cs, &pub_values, &provided_witnesses)?
-    } else {
-        info!("No witness file found, generating witness from constraints only...");
-        generate_full_witness(&r1cs, &pub_values)?
-    };
-
-    if let Some(parent) = output_path.parent() {
-        std::fs::create_dir_all(parent)?;
-    }
-
-    let full_witness_map: IndexMap<String, String> = if r1cs.witnesses.len() == wit_values.len() {
-        r1cs.witnesses
-            .iter()
-            .zip(wit_values.iter())
-            .map(|(name, fr)| (name.clone(), fr.to_string()))
-            .collect()
-    } else {
-        wit_values
-            .iter()
-            .enumerate()
-            .map(|(i, fr)| (format!("witness_{}", i), fr.to_string()))
-            .collect()
-    };
+    let (pub_values, named_witness) = progress.phase("Witness generation", || {
+        compute_witness(&r1cs, &sources, &base_name)
+    })?;
 
     let witness_output_path = output_path
         .parent()
         .map(|parent| parent.join("full_witness.json"))
         .unwrap_or_else(|| PathBuf::from("full_witness.json"));
-
-    info!("Saving full witness to {}", witness_output_path.display());
-    serde_json::to_writer_pretty(File::create(&witness_output_path)?, &full_witness_map)?;
+    write_full_witness(&named_witness, witness_format, &witness_output_path)?;
+    let wit_values: Vec<Fr> = named_witness.into_values().collect();
 
     if std::env::var("LOFIT_VERBOSE").is_ok() {
         debug!("Converted field elements:");
@@ -268,6 +352,19 @@ fn handle_prove(
         }
     }
 
+    let symbols = load_symbols(&input.with_extension("sym"))
+        .unwrap_or_else(|| SymbolTable::from_constraint_system(&r1cs));
+    let assignment = ConstraintSystem::full_assignment(&pub_values, &wit_values);
+    let failed = report_unsatisfied(&r1cs, &assignment, &symbols);
+    if failed > 0 {
+        return Err(format!(
+            "{} of {} constraints are not satisfied by the witness",
+            failed,
+            r1cs.num_constraints()
+        )
+        .into());
+    }
+
     let circuit = LofCircuit {
         public_inputs: pub_values,
         witness: wit_values,
@@ -275,7 +372,7 @@ fn handle_prove(
     };
 
     info!("Generating proof...");
-    let proof = pk.prove(circuit)?;
+    let proof = progress.phase("Proof generation", || pk.prove(circuit))?;
 
     info!("Writing proof to {}", output_path.display());
     let proof_writer = BufWriter::new(File::create(&output_path)?);
@@ -287,20 +384,209 @@ fn handle_prove(
     Ok(())
 }
 
-fn handle_verify(
-    verification_key: Option<PathBuf>,
+// where prove/witness read their values from: the two per-kind files or one merged file,
+// optionally with every witness wire already computed
+struct InputSources {
+    public_inputs: Option<PathBuf>,
+    witness: Option<PathBuf>,
+    merged: Option<PathBuf>,
+    full_witness: Option<PathBuf>,
+}
+
+fn compute_witness(
+    r1cs: &ConstraintSystem,
+    sources: &InputSources,
+    base_name: &str,
+) -> Result<(Vec<Fr>, IndexMap<String, Fr>), Box<dyn std::error::Error>> {
+    let (pub_inputs_json, wit_inputs_json) = if let Some(merged_path) = &sources.merged {
+        info!("Reading merged inputs from {}", merged_path.display());
+        let merged = read_inputs_json(File::open(merged_path)?)?;
+        let (public, witness) = split_inputs(&merged, &r1cs.public_inputs, &r1cs.witnesses);
+        (public, Some(witness))
+    } else {
+        let public_inputs_path = sources
+            .public_inputs
+            .clone()
+            .unwrap_or_else(|| Path::new("inputs").join(format!("{}_public.json", base_name)));
+        let witness_path = sources
+            .witness
+            .clone()
+            .unwrap_or_else(|| Path::new("inputs").join(format!("{}_witness.json", base_name)));
+
+        info!(
+            "Reading public inputs from {}",
+            public_inputs_path.display()
+        );
+        let public = read_inputs_json(File::open(&public_inputs_path)?)?;
+        let witness = if sources.full_witness.is_some() {
+            None
+        } else if witness_path.exists() {
+            info!("Reading provided witness from {}", witness_path.display());
+            Some(read_inputs_json(File::open(&witness_path)?)?)
+        } else {
+            None
+        };
+        (public, witness)
+    };
+    debug!("Public inputs: {:?}", pub_inputs_json);
+
+    let pub_values = parse_inputs_in_order(&pub_inputs_json, &r1cs.public_inputs)?;
+
+    // taken as given; prove still checks it against every constraint before proving
+    if let Some(full_witness_path) = &sources.full_witness {
+        info!("Reading full witness from {}", full_witness_path.display());
+        let full_witness = read_inputs_json(File::open(full_witness_path)?)?;
+        let values = parse_inputs_in_order(&full_witness, &r1cs.witnesses)?;
+        return Ok((
+            pub_values,
+            r1cs.witnesses.iter().cloned().zip(values).collect(),
+        ));
+    }
+
+    let started = Instant::now();
+    let named_witness = if let Some(wit_inputs_json) = wit_inputs_json {
+        debug!("Witness inputs: {:?}", wit_inputs_json);
+        let provided_witnesses = parse_partial_witness(&wit_inputs_json, r1cs);
+
+        info!(
+            "Generating full witness with {} provided witness values...",
+            provided_witnesses.len()
+        );
+        generate_named_witness(r1cs, &pub_values, &provided_witnesses)?
+    } else {
+        info!("No witness file found, generating witness from constraints only...");
+        generate_named_witness(r1cs, &pub_values, &[])?
+    };
+    info!(
+        phase = "witness",
+        constraints = r1cs.num_constraints(),
+        elapsed_ms = started.elapsed().as_millis() as u64,
+        "Witness generation finished"
+    );
+
+    Ok((pub_values, named_witness))
+}
+
+fn write_full_witness(
+    named_witness: &IndexMap<String, Fr>,
+    format: WitnessFormat,
+    path: &Path,
+) -> Result<(), Box<dyn std::error::Error>> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+
+    let full_witness_map: IndexMap<&str, String> = named_witness
+        .iter()
+        .map(|(name, fr)| {
+            let value = match format {
+                WitnessFormat::Decimal => fr.to_string(),
+                WitnessFormat::Hex => fr_to_hex(fr),
+            };
+            (name.as_str(), value)
+        })
+        .collect();
+
+    info!("Saving full witness to {}", path.display());
+    serde_json::to_writer_pretty(File::create(path)?, &full_witness_map)?;
+    Ok(())
+}
+
+// points at the first violated constraint with its signal values; returns how many failed
+fn report_unsatisfied(r1cs: &ConstraintSystem, assignment: &[Fr], symbols: &SymbolTable) -> usize {
+    let Some(first) = r1cs.find_unsatisfied(assignment) else {
+        return 0;
+    };
+    eprintln!(
+        "First unsatisfied {}",
+        symbols.render_constraint(first, &r1cs.constraints[first], assignment)
+    );
+
+    let failed = r1cs
+        .constraints
+        .iter()
+        .filter(|constraint| !constraint.is_satisfied(assignment))
+        .count();
+    if failed > 1 {
+        eprintln!("{} more constraints are not satisfied", failed - 1);
+    }
+    failed
+}
+
+fn handle_witness(
+    input: PathBuf,
+    sources: InputSources,
+    output: Option<PathBuf>,
+    witness_format: WitnessFormat,
+    progress: Progress,
+) -> Result<(), Box<dyn std::error::Error>> {
+    info!("Reading R1CS from {}", input.display());
+    let r1cs = ConstraintSystem::from_file(File::open(&input)?)?;
+    progress.circuit(&r1cs);
+
+    let base_name = infer_base_name(&input);
+    let output_path = output.unwrap_or_else(|| {
+        Path::new("build").join(format!("{}_full_witness.json", base_name.as_str()))
+    });
+
+    let (pub_values, named_witness) = progress.phase("Witness generation", || {
+        compute_witness(&r1cs, &sources, &base_name)
+    })?;
+    write_full_witness(&named_witness, witness_format, &output_path)?;
+    let wit_values: Vec<Fr> = named_witness.into_values().collect();
+    println!("Full witness written to {}", output_path.display());
+
+    let symbols = load_symbols(&input.with_extension("sym"))
+        .unwrap_or_else(|| SymbolTable::from_constraint_system(&r1cs));
+    let assignment = ConstraintSystem::full_assignment(&pub_values, &wit_values);
+
+    let failed = report_unsatisfied(&r1cs, &assignment, &symbols);
+    if failed > 0 {
+        println!(
+            "{} of {} constraints are not satisfied",
+            failed,
+            r1cs.num_constraints()
+        );
+        return Err("witness does not satisfy the circuit".into());
+    }
+
+    println!("All {} constraints are satisfied", r1cs.num_constraints());
+    Ok(())
+}
+
+// where verify reads the proof and public inputs from: files, or inline JSON arguments
+struct VerifySources {
     proof: Option<PathBuf>,
     public_inputs: Option<PathBuf>,
+    inputs: Option<PathBuf>,
+    proof_json: Option<String>,
+    public_inputs_json: Option<String>,
+}
+
+// an inline JSON argument, with `-` standing for stdin
+fn read_json_arg(arg: &str) -> std::io::Result<String> {
+    if arg != "-" {
+        return Ok(arg.to_string());
+    }
+    let mut json = String::new();
+    std::io::stdin().read_to_string(&mut json)?;
+    Ok(json)
+}
+
+fn handle_verify(
+    verification_key: Option<PathBuf>,
+    sources: VerifySources,
     input: Option<PathBuf>,
 ) -> Result<(), Box<dyn std::error::Error>> {
     let base_name = detect_base_name_for_verify(input.as_ref());
+    if sources.proof_json.as_deref() == Some("-")
+        && sources.public_inputs_json.as_deref() == Some("-")
+    {
+        return Err("Only one of --proof-json and --public-inputs-json can read from stdin".into());
+    }
 
     let verification_key_path = verification_key
         .unwrap_or_else(|| Path::new("keys").join(format!("{}_vk.bin", base_name.as_str())));
-    let proof_path = proof
-        .unwrap_or_else(|| Path::new("proofs").join(format!("{}_proof.bin", base_name.as_str())));
-    let public_inputs_path = public_inputs
-        .unwrap_or_else(|| Path::new("inputs").join(format!("{}_public.json", base_name.as_str())));
 
     info!(
         "Reading verification key from {}",
@@ -313,20 +599,71 @@ fn handle_verify(
     );
     let vk = VerifierKey::read(&vk_contents[..])?;
 
-    info!("Reading proof from {}", proof_path.display());
-    let proof_contents = std::fs::read(&proof_path)?;
-    let proof_obj = Proof::read(&proof_contents[..])?;
+    let (proof_obj, proof_label) = match &sources.proof_json {
+        Some(json) => {
+            info!("Reading proof from inline JSON");
+            (
+                Proof::from_json(&read_json_arg(json)?)?,
+                "--proof-json".to_string(),
+            )
+        }
+        None => {
+            let proof_path = sources.proof.unwrap_or_else(|| {
+                Path::new("proofs").join(format!("{}_proof.bin", base_name.as_str()))
+            });
+            info!("Reading proof from {}", proof_path.display());
+            let proof_contents = std::fs::read(&proof_path)?;
+            (
+                Proof::read(&proof_contents[..])?,
+                proof_path.display().to_string(),
+            )
+        }
+    };
 
-    info!(
-        "Reading public inputs from {}",
-        public_inputs_path.display()
-    );
-    let pub_inputs_json: InputsJson = serde_json::from_reader(File::open(&public_inputs_path)?)?;
+    let pub_inputs_json = match &sources.public_inputs_json {
+        Some(json) => {
+            info!("Reading public inputs from inline JSON");
+            read_inputs_json(read_json_arg(json)?.as_bytes())?
+        }
+        None => {
+            // a merged inputs file works too: only the public entries are looked up below
+            let public_inputs_path =
+                sources.inputs.or(sources.public_inputs).unwrap_or_else(|| {
+                    Path::new("inputs").join(format!("{}_public.json", base_name.as_str()))
+                });
+            info!(
+                "Reading public inputs from {}",
+                public_inputs_path.display()
+            );
+            read_inputs_json(File::open(&public_inputs_path)?)?
+        }
+    };
 
-    let pub_values: Vec<Fr> = pub_inputs_json
-        .values()
-        .map(|s| fr_from_str(s))
-        .collect::<Result<Vec<_>, _>>()?;
+    // order the values exactly as prove did, not by JSON/HashMap iteration order
+    let r1cs_path = match input.as_ref() {
+        Some(path) if path.extension().is_some_and(|ext| ext == "r1cs") => path.clone(),
+        _ => Path::new("build").join(format!("{}.r1cs", base_name.as_str())),
+    };
+    if !r1cs_path.exists() {
+        return Err(format!(
+            "R1CS file {} not found; pass --input <circuit.r1cs> so public inputs are ordered as in prove",
+            r1cs_path.display()
+        )
+        .into());
+    }
+    info!("Reading R1CS from {}", r1cs_path.display());
+    let r1cs = ConstraintSystem::from_file(File::open(&r1cs_path)?)?;
+    let pub_values = parse_inputs_in_order(&pub_inputs_json, &r1cs.public_inputs)?;
+    if let Some(proof_hash) = proof_obj.circuit_hash() {
+        if proof_hash != r1cs.circuit_hash() {
+            return Err(format!(
+                "Proof {} was generated for a different circuit than {}",
+                proof_label,
+                r1cs_path.display()
+            )
+            .into());
+        }
+    }
 
     info!("Verifying proof with {} public inputs...", pub_values.len());
     match vk.verify(&proof_obj, &pub_values) {
@@ -334,19 +671,42 @@ fn handle_verify(
             info!("Proof is valid!");
             info!("Zero-knowledge proof verification successful!");
         }
+        // scripts read the outcome from the exit status, so a rejected proof is an error
         Ok(false) => {
             error!("Proof is invalid!");
             error!("The proof does not satisfy the circuit constraints");
+            warn!(
+                "Public inputs are checked in circuit order: {}",
+                r1cs.public_inputs.join(", ")
+            );
+            return Err("Proof is invalid".into());
         }
         Err(e) => {
             error!("Verification error: {:?}", e);
             warn!("Check your input files and try again");
+            return Err(format!("Verification error: {}", e).into());
         }
     }
 
     Ok(())
 }
 
+fn load_symbols(path: &Path) -> Option<SymbolTable> {
+    if !path.exists() {
+        return None;
+    }
+    match SymbolTable::from_file(path) {
+        Ok(symbols) => {
+            debug!("Loaded {} symbols from {}", symbols.len(), path.display());
+            Some(symbols)
+        }
+        Err(e) => {
+            warn!("Ignoring unreadable symbol file {}: {}", path.display(), e);
+            None
+        }
+    }
+}
+
 fn infer_base_name(path: &Path) -> String {
     path.file_stem()
         .and_then(|s| s.to_str())
@@ -385,6 +745,7 @@ fn infer_base_from_keys_dir() -> Option<String> {
 #[instrument]
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     let cli = Cli::parse();
+    let progress = Progress(cli.progress);
 
     match cli.command {
         Commands::Version => {
@@ -395,7 +756,13 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
             input,
             output,
             skip_wasm,
+            validate,
         } => {
+            if validate {
+                validate_for_web(&input)?;
+                println!("{} is ready to package", input.display());
+                return Ok(());
+            }
             let package_dir = package_for_web(&input, output.as_deref(), skip_wasm)?;
             println!("Web package ready at {}", package_dir.display());
             Ok(())
@@ -404,19 +771,68 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
             input,
             proving_key,
             verification_key,
-        } => handle_setup(input, proving_key, verification_key),
+            force,
+        } => handle_setup(input, proving_key, verification_key, force, progress),
+        Commands::SetupAll { dir, force } => handle_setup_all(dir, force, progress),
         Commands::Prove {
             input,
             proving_key,
             public_inputs,
             witness,
+            inputs,
+            full_witness,
             output,
-        } => handle_prove(input, proving_key, public_inputs, witness, output),
+            witness_format,
+        } => handle_prove(
+            input,
+            proving_key,
+            InputSources {
+                public_inputs,
+                witness,
+                merged: inputs,
+                full_witness,
+            },
+            output,
+            witness_format,
+            progress,
+        ),
+        Commands::Witness {
+            input,
+            public_inputs,
+            witness,
+            inputs,
+            output,
+            witness_format,
+        } => handle_witness(
+            input,
+            InputSources {
+                public_inputs,
+                witness,
+                merged: inputs,
+                full_witness: None,
+            },
+            output,
+            witness_format,
+            progress,
+        ),
         Commands::Verify {
             verification_key,
             proof,
             public_inputs,
             input,
-        } => handle_verify(verification_key, proof, public_inputs, input),
+            inputs,
+            proof_json,
+            public_inputs_json,
+        } => handle_verify(
+            verification_key,
+            VerifySources {
+                proof,
+                public_inputs,
+                inputs,
+                proof_json,
+                public_inputs_json,
+            },
+            input,
+        ),
     }
 }
@@ -5,12 +5,33 @@ use std::process::Command;
 
 use crate::{ConstraintSystem, LofCircuit, ProverKey};
 use ark_bn254::Fr;
+use ark_ff::PrimeField;
 use tracing::{error, info, warn};
 
+// the prover is BN254-only, so every package targets that curve; the name and modulus are
+// still written out so the frontend never has to assume them
+pub const PACKAGE_CURVE: &str = "bn254";
+
+pub fn field_modulus() -> String {
+    Fr::MODULUS.to_string()
+}
+
 pub fn package_for_web(
     r1cs_path: &Path,
     output_dir: Option<&Path>,
     skip_wasm: bool,
+) -> Result<PathBuf, Box<dyn std::error::Error>> {
+    let wasm_pack = find_wasm_pack();
+    package_for_web_with_wasm_pack(r1cs_path, output_dir, skip_wasm, wasm_pack.as_deref())
+}
+
+// `package_for_web` with the wasm-pack binary given instead of looked up on PATH; `None` packages
+// as if it were not installed
+pub fn package_for_web_with_wasm_pack(
+    r1cs_path: &Path,
+    output_dir: Option<&Path>,
+    skip_wasm: bool,
+    wasm_pack: Option<&Path>,
 ) -> Result<PathBuf, Box<dyn std::error::Error>> {
     info!("Packaging circuit for web deployment...\n");
 
@@ -30,13 +51,15 @@ pub fn package_for_web(
     copy_r1cs_to_build(r1cs_path, &package_dir, &circuit_name)?;
     info!("✅ R1CS copied\n");
 
-    handle_wasm_steps(r1cs_path, &package_dir, &circuit_name, skip_wasm)?;
+    let wasm_deferred =
+        handle_wasm_steps(r1cs_path, &package_dir, &circuit_name, skip_wasm, wasm_pack)?;
 
     info!("Step 5/5: Generating integration examples...");
     generate_integration_code(&package_dir, &circuit_name)?;
+    write_package_manifest(&package_dir, &circuit_name)?;
     info!("✅ Integration examples generated\n");
 
-    if skip_wasm {
+    if wasm_deferred {
         info!("Web package ready! (WASM build deferred)");
     } else {
         info!("Web package ready!");
@@ -52,6 +75,67 @@ pub fn package_for_web(
     Ok(package_dir)
 }
 
+// everything `package_for_web` needs before it starts generating keys and building WASM, checked
+// without writing anything; the first problem found is returned
+pub fn validate_for_web(r1cs_path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    info!("Validating circuit for web packaging...");
+
+    let r1cs = File::open(r1cs_path)
+        .and_then(ConstraintSystem::from_file)
+        .map_err(|e| format!("R1CS file {} is not readable: {}", r1cs_path.display(), e))?;
+    info!("  R1CS: {} constraints", r1cs.num_constraints());
+
+    let ir_path = r1cs_path.with_extension("ir");
+    if ir_path.exists() {
+        info!("  IR: {}", ir_path.display());
+    } else if let Some(source_path) = find_colocated_source(r1cs_path) {
+        locate_workspace_binary("lof").map_err(|_| {
+            format!(
+                "IR file not found: {}, and the lof compiler needed to regenerate it from {} is missing",
+                ir_path.display(),
+                source_path.display()
+            )
+        })?;
+        info!("  IR: will be regenerated from {}", source_path.display());
+    } else {
+        return Err(format!(
+            "IR file not found: {} (run 'lof compile', which writes it next to the .r1cs)",
+            ir_path.display()
+        )
+        .into());
+    }
+
+    // setup needs at least one constraint, and every wire a constraint names must exist
+    if r1cs.constraints.is_empty() {
+        return Err("Keys cannot be generated: the circuit has no constraints".into());
+    }
+    let wires = r1cs.num_variables();
+    for (index, constraint) in r1cs.constraints.iter().enumerate() {
+        let terms = [&constraint.a, &constraint.b, &constraint.c]
+            .into_iter()
+            .flat_map(|lc| &lc.terms);
+        for (var, _) in terms {
+            if *var as usize >= wires {
+                return Err(format!(
+                    "Keys cannot be generated: constraint {} references wire {}, but the circuit has {} wires",
+                    index, var, wires
+                )
+                .into());
+            }
+        }
+    }
+    info!("  Keys: {} wires", wires);
+
+    let lof_witness_gen = locate_lof_witness_gen()?;
+    info!("  lof-witness-gen: {}", lof_witness_gen.display());
+
+    if find_wasm_pack().is_none() {
+        warn!("  wasm-pack not found; packaging will generate WASM sources only");
+    }
+
+    Ok(())
+}
+
 fn create_directory_structure(base: &Path) -> Result<(), Box<dyn std::error::Error>> {
     fs::create_dir_all(base)?;
     fs::create_dir_all(base.join("build"))?;
@@ -71,13 +155,16 @@ fn generate_keys(
     let r1cs_file = File::open(r1cs_path)?;
     let r1cs = ConstraintSystem::from_file(r1cs_file)?;
 
+    let circuit_hash = r1cs.circuit_hash();
     let circuit = LofCircuit {
-        public_inputs: vec![Fr::from(0u64); r1cs.public_inputs.len()],
+        public_inputs: vec![Fr::from(0u64); r1cs.num_public_inputs()],
         witness: vec![Fr::from(0u64); 1],
         constraints: r1cs.constraints,
     };
 
     let (pk, vk) = ProverKey::setup(circuit)?;
+    let pk = pk.with_circuit_hash(circuit_hash);
+    let vk = vk.with_circuit_hash(circuit_hash);
 
     let keys_dir = package_dir.join("keys");
     let pk_path = keys_dir.join(format!("{}_pk.bin", circuit_name));
@@ -95,11 +182,12 @@ fn generate_keys(
     Ok(())
 }
 
+// without a wasm-pack to build with, only the sources are generated
 fn generate_witness_wasm(
     r1cs_path: &Path,
     package_dir: &Path,
     circuit_name: &str,
-    skip_wasm: bool,
+    wasm_pack: Option<&Path>,
 ) -> Result<(), Box<dyn std::error::Error>> {
     let ir_path = ensure_ir_exists(r1cs_path)?;
 
@@ -114,7 +202,7 @@ fn generate_witness_wasm(
 
     let wasm_project_dir = witness_output_dir.join(format!("{}_witness_wasm", circuit_name));
 
-    if skip_wasm {
+    let Some(wasm_pack) = wasm_pack else {
         finalize_skip_wasm_sources(
             package_dir,
             circuit_name,
@@ -122,10 +210,10 @@ fn generate_witness_wasm(
             &witness_output_dir,
         )?;
         return Ok(());
-    }
+    };
 
     ensure_wasm_project_exists(&wasm_project_dir)?;
-    build_witness_wasm_project(&wasm_project_dir)?;
+    build_witness_wasm_project(wasm_pack, &wasm_project_dir)?;
     copy_witness_artifacts(&wasm_project_dir, package_dir)?;
     fs::remove_dir_all(&witness_output_dir)?;
 
@@ -134,9 +222,58 @@ fn generate_witness_wasm(
     Ok(())
 }
 
-fn build_lofit_wasm(package_dir: &Path) -> Result<(), Box<dyn std::error::Error>> {
-    match try_build_lofit_wasm(package_dir) {
+const WASM_PACK_INSTALL_URL: &str = "https://rustwasm.github.io/wasm-pack/installer/";
+
+fn find_wasm_pack() -> Option<PathBuf> {
+    which::which("wasm-pack").ok()
+}
+
+#[derive(Debug)]
+struct WasmPackMissing;
+
+impl std::fmt::Display for WasmPackMissing {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "wasm-pack not found; install it from {}",
+            WASM_PACK_INSTALL_URL
+        )
+    }
+}
+
+impl std::error::Error for WasmPackMissing {}
+
+// `wasm-pack build --target web [--out-dir <out_dir>]` in `dir`; a binary that vanished after
+// it was looked up still surfaces as `WasmPackMissing` rather than a build failure
+fn run_wasm_pack(
+    wasm_pack: &Path,
+    dir: Option<&Path>,
+    out_dir: Option<&Path>,
+) -> Result<std::process::Output, Box<dyn std::error::Error>> {
+    let mut command = Command::new(wasm_pack);
+    command.arg("build").arg("--target").arg("web");
+    if let Some(out_dir) = out_dir {
+        command.arg("--out-dir").arg(out_dir);
+    }
+    if let Some(dir) = dir {
+        command.current_dir(dir);
+    }
+    command.output().map_err(|e| -> Box<dyn std::error::Error> {
+        if e.kind() == std::io::ErrorKind::NotFound {
+            WasmPackMissing.into()
+        } else {
+            e.into()
+        }
+    })
+}
+
+fn build_lofit_wasm(
+    wasm_pack: &Path,
+    package_dir: &Path,
+) -> Result<(), Box<dyn std::error::Error>> {
+    match try_build_lofit_wasm(wasm_pack, package_dir) {
         Ok(_) => Ok(()),
+        Err(build_err) if build_err.is::<WasmPackMissing>() => Err(build_err),
         Err(build_err) => {
             warn!("  wasm-pack build for lofit failed: {}", build_err);
             warn!("  Falling back to prebuilt prover bundle shipped with lofit");
@@ -154,7 +291,10 @@ fn build_lofit_wasm(package_dir: &Path) -> Result<(), Box<dyn std::error::Error>
     }
 }
 
-fn try_build_lofit_wasm(package_dir: &Path) -> Result<(), Box<dyn std::error::Error>> {
+fn try_build_lofit_wasm(
+    wasm_pack: &Path,
+    package_dir: &Path,
+) -> Result<(), Box<dyn std::error::Error>> {
     let mut candidates: Vec<PathBuf> = Vec::new();
     if let Ok(path) = std::env::var("LOFIT_SOURCE_DIR") {
         candidates.push(PathBuf::from(path));
@@ -180,7 +320,7 @@ fn try_build_lofit_wasm(package_dir: &Path) -> Result<(), Box<dyn std::error::Er
         dir
     } else {
         info!("  Unable to locate lofit source automatically, attempting current directory...");
-        return build_lofit_wasm_from_cwd(package_dir);
+        return build_lofit_wasm_from_cwd(wasm_pack, package_dir);
     };
 
     info!("  Building lofit WASM from: {}", lofit_dir.display());
@@ -191,14 +331,7 @@ fn try_build_lofit_wasm(package_dir: &Path) -> Result<(), Box<dyn std::error::Er
         .canonicalize()
         .unwrap_or_else(|_| prover_out_dir.clone());
 
-    let output = Command::new("wasm-pack")
-        .arg("build")
-        .arg("--target")
-        .arg("web")
-        .arg("--out-dir")
-        .arg(&prover_out_abs)
-        .current_dir(&lofit_dir)
-        .output()?;
+    let output = run_wasm_pack(wasm_pack, Some(&lofit_dir), Some(&prover_out_abs))?;
 
     if !output.status.success() {
         error!("Failed to build lofit WASM:");
@@ -255,20 +388,17 @@ fn copy_prebuilt_lofit_wasm(package_dir: &Path) -> Result<(), Box<dyn std::error
     Ok(())
 }
 
-fn build_lofit_wasm_from_cwd(package_dir: &Path) -> Result<(), Box<dyn std::error::Error>> {
+fn build_lofit_wasm_from_cwd(
+    wasm_pack: &Path,
+    package_dir: &Path,
+) -> Result<(), Box<dyn std::error::Error>> {
     if !Path::new("Cargo.toml").exists() {
         error!("Cannot find lofit to build WASM");
         error!("Please run this command from the Lof project root");
         return Err("Lofit not found".into());
     }
 
-    let output = Command::new("wasm-pack")
-        .arg("build")
-        .arg("--target")
-        .arg("web")
-        .arg("--out-dir")
-        .arg(package_dir.join("prover"))
-        .output()?;
+    let output = run_wasm_pack(wasm_pack, None, Some(&package_dir.join("prover")))?;
 
     if !output.status.success() {
         error!("Failed to build lofit WASM:");
@@ -279,7 +409,7 @@ fn build_lofit_wasm_from_cwd(package_dir: &Path) -> Result<(), Box<dyn std::erro
     Ok(())
 }
 
-fn generate_integration_code(
+pub fn generate_integration_code(
     package_dir: &Path,
     circuit_name: &str,
 ) -> Result<(), Box<dyn std::error::Error>> {
@@ -296,16 +426,27 @@ fn generate_integration_code(
 // Auto-generated helper that wires the witness calculator and prover WASM modules together.
 
 import initWitness, { compute_witness } from './witness/__CIRCUIT_NAME___witness_wasm.js';
-import initLofit, { WasmProver, init_panic_hook } from './prover/lofit.js';
+import initLofit, {
+    WasmProver,
+    WasmVerifier,
+    init_panic_hook,
+    public_inputs_from_witness,
+} from './prover/lofit.js';
 
 // Global state
 let witnessReady = false;
 let proverReady = false;
 let wasmProver = null;
+let wasmVerifier = null;
+let r1csBytes = null;
 
 export const PUBLIC_INPUT_SIGNALS = __PUBLIC_INPUTS__;
 export const WITNESS_SIGNALS = __WITNESS_INPUTS__;
 
+// Field the circuit was compiled for; inputs must reduce below this modulus
+export const CURVE = '__CURVE__';
+export const FIELD_MODULUS = __FIELD_MODULUS__n;
+
 // Initialize all WASM modules
 async function initializeWasm() {
     console.log('Loading WASM modules...');
@@ -323,7 +464,7 @@ async function initializeWasm() {
 
         // Load R1CS and proving key
         const r1csResp = await fetch('./build/__CIRCUIT_NAME__.r1cs');
-        const r1csBytes = new Uint8Array(await r1csResp.arrayBuffer());
+        r1csBytes = new Uint8Array(await r1csResp.arrayBuffer());
 
         const pkResp = await fetch('./keys/__CIRCUIT_NAME___pk.bin');
         const pkBytes = new Uint8Array(await pkResp.arrayBuffer());
@@ -333,6 +474,11 @@ async function initializeWasm() {
         proverReady = true;
         console.log('✅ Prover initialized');
 
+        // Client-side verifier from the same verification key the server uses
+        const vkResp = await fetch('./keys/__CIRCUIT_NAME___vk.bin');
+        wasmVerifier = new WasmVerifier(new Uint8Array(await vkResp.arrayBuffer()));
+        console.log('✅ Verifier initialized');
+
         return true;
     } catch (error) {
         console.error('Failed to initialize WASM:', error);
@@ -346,6 +492,8 @@ async function generateProof(inputs) {
         throw new Error('WASM not initialized. Call initializeWasm() first.');
     }
 
+    validateInputs(inputs);
+
     console.log('Computing witness...');
     const witness = compute_witness(inputs);
     console.log('✅ Witness computed:', witness);
@@ -380,6 +528,30 @@ async function verifyProof(proofBytes, publicInputs) {
     return result.verified;
 }
 
+// Verify a proof in the browser, without a server round trip
+function verifyProofLocally(proofBytes, publicInputs) {
+    if (!wasmVerifier) {
+        throw new Error('WASM not initialized. Call initializeWasm() first.');
+    }
+    return wasmVerifier.verify(proofBytes, publicInputs);
+}
+
+// Reject values the witness calculator would silently reduce mod FIELD_MODULUS
+function validateInputs(inputs) {
+    for (const [name, value] of Object.entries(inputs)) {
+        let parsed;
+        try {
+            parsed = BigInt(value);
+        } catch {
+            throw new Error(`Input '${name}' is not an integer: ${value}`);
+        }
+        const magnitude = parsed < 0n ? -parsed : parsed;
+        if (magnitude >= FIELD_MODULUS) {
+            throw new Error(`Input '${name}' does not fit the ${CURVE} scalar field`);
+        }
+    }
+}
+
 function buildWitnessArray(witnessOutput) {
     const orderedValues = [];
 
@@ -400,25 +572,104 @@ function buildWitnessArray(witnessOutput) {
     return orderedValues;
 }
 
+// Public inputs in the order the verifier expects, as decided by the R1CS
 function buildPublicInputs(witnessOutput) {
-    const pubInputs = {};
-    for (const name of PUBLIC_INPUT_SIGNALS) {
-        pubInputs[name] = witnessOutput[name].toString();
-    }
-    return pubInputs;
+    return public_inputs_from_witness(r1csBytes, witnessOutput);
 }
 
 // Export for use in other modules
-export { initializeWasm, generateProof, verifyProof, buildWitnessArray, buildPublicInputs };
+export {
+    initializeWasm,
+    generateProof,
+    verifyProof,
+    verifyProofLocally,
+    validateInputs,
+    buildWitnessArray,
+    buildPublicInputs,
+};
 "#;
 
     let integration_code = integration_template
         .replace("__CIRCUIT_NAME__", circuit_name)
         .replace("__PUBLIC_INPUTS__", &public_inputs_json)
-        .replace("__WITNESS_INPUTS__", &witness_inputs_json);
+        .replace("__WITNESS_INPUTS__", &witness_inputs_json)
+        .replace("__CURVE__", PACKAGE_CURVE)
+        .replace("__FIELD_MODULUS__", &field_modulus());
 
     fs::write(package_dir.join("integration.js"), integration_code)?;
 
+    let input_fields = r1cs
+        .public_inputs
+        .iter()
+        .map(|name| (name, "public"))
+        .chain(r1cs.witnesses.iter().map(|name| (name, "witness")))
+        .map(|(name, kind)| {
+            format!(
+                "        <label>{name} <small>({kind})</small> <input name=\"{name}\" placeholder=\"0\"></label>\n"
+            )
+        })
+        .collect::<String>();
+
+    let index_template = r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+    <meta charset="utf-8">
+    <title>__CIRCUIT_NAME__ proof demo</title>
+    <style>
+        body { font-family: sans-serif; max-width: 48rem; margin: 2rem auto; }
+        label { display: block; margin: 0.25rem 0; }
+        pre { white-space: pre-wrap; word-break: break-all; background: #f4f4f4; padding: 1rem; }
+    </style>
+</head>
+<body>
+    <h1>__CIRCUIT_NAME__</h1>
+    <!-- signals with an empty field are left for the witness calculator to derive -->
+    <form id="inputs">
+__INPUT_FIELDS__        <button type="submit" disabled>Generate proof</button>
+    </form>
+    <pre id="output">Loading WASM modules...</pre>
+
+    <script type="module">
+        import { initializeWasm, generateProof, verifyProofLocally } from './integration.js';
+
+        const form = document.getElementById('inputs');
+        const output = document.getElementById('output');
+        const button = form.querySelector('button');
+
+        if (await initializeWasm()) {
+            button.disabled = false;
+            output.textContent = 'Ready';
+        } else {
+            output.textContent = 'Failed to load WASM modules, see the console';
+        }
+
+        form.addEventListener('submit', async (event) => {
+            event.preventDefault();
+            const inputs = {};
+            for (const field of form.querySelectorAll('input')) {
+                if (field.value.trim() !== '') {
+                    inputs[field.name] = field.value.trim();
+                }
+            }
+            try {
+                const { proofBytes, publicInputs } = await generateProof(inputs);
+                const hex = Array.from(proofBytes, (b) => b.toString(16).padStart(2, '0')).join('');
+                const verified = verifyProofLocally(proofBytes, publicInputs);
+                output.textContent = `proof (${proofBytes.length} bytes, verified: ${verified}):\n${hex}`;
+            } catch (error) {
+                output.textContent = `Error: ${error}`;
+            }
+        });
+    </script>
+</body>
+</html>
+"#;
+
+    let index_html = index_template
+        .replace("__CIRCUIT_NAME__", circuit_name)
+        .replace("__INPUT_FIELDS__", &input_fields);
+    fs::write(package_dir.join("index.html"), index_html)?;
+
     let readme_template = r#"# __CIRCUIT_NAME__ Web Package
 
 This package contains everything needed to generate and verify zero-knowledge proofs for the `__CIRCUIT_NAME__` circuit in a web browser.
@@ -439,12 +690,13 @@ __CIRCUIT_NAME___web/
 │   ├── lofit.js             # zkSNARK prover (JS bindings)
 │   └── lofit_bg.wasm        # zkSNARK prover (WASM binary)
 ├── integration.js           # Example integration code (ES module)
+├── index.html               # Demo page: fill in the inputs and generate a proof
 └── README.md                # This file
 ```
 
 ## Quick Start
 
-Serve this directory with your preferred dev server and import the generated helpers:
+Serve this directory (`npm install && npm start` runs http-server on it) and import the generated helpers:
 
 ```js
 import { initializeWasm, generateProof } from './integration.js';
@@ -471,6 +723,7 @@ await initializeWasm();
 ### generateProof(inputs)
 
 Computes the witness, packs it into the expected order, and returns proof bytes plus helper metadata.
+`publicInputs` is the array of public values in R1CS order, as returned by the prover's `public_inputs_from_witness`.
 
 ```js
 const {
@@ -489,6 +742,14 @@ POST helper for sending proofs to a server endpoint.
 await verifyProof(proofBytes, publicInputs);
 ```
 
+### verifyProofLocally(proofBytes, publicInputs)
+
+Verifies the proof in the browser with the bundled verification key; returns `true` or `false`.
+
+```js
+const valid = verifyProofLocally(proofBytes, publicInputs);
+```
+
 ## Server-Side Verification
 
 Use the `lofit verify` command to verify proofs on the server:
@@ -511,10 +772,65 @@ cargo run --bin lofit -- verify \
     fs::write(package_dir.join("README.md"), readme)?;
 
     info!("  integration.js: Example integration code");
+    info!("  index.html: Runnable demo page");
     info!("  README.md: Usage documentation");
 
     Ok(())
 }
+
+// package.json with a `start` script so the directory can be served with `npm start`,
+// plus a .gitignore for the node and wasm build leftovers
+pub fn write_package_manifest(
+    package_dir: &Path,
+    circuit_name: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let manifest = serde_json::json!({
+        "name": npm_package_name(circuit_name),
+        "version": "0.1.0",
+        "private": true,
+        "type": "module",
+        "main": "integration.js",
+        "lof": {
+            "curve": PACKAGE_CURVE,
+            "fieldModulus": field_modulus()
+        },
+        "scripts": {
+            "start": "http-server . -c-1"
+        },
+        "devDependencies": {
+            "http-server": "^14.1.1"
+        }
+    });
+    let mut json = serde_json::to_string_pretty(&manifest)?;
+    json.push('\n');
+    fs::write(package_dir.join("package.json"), json)?;
+
+    let gitignore =
+        "node_modules/\nwitness_temp/\nwitness_sources/*/target/\nwitness_sources/*/pkg/\n";
+    fs::write(package_dir.join(".gitignore"), gitignore)?;
+
+    info!("  package.json: npm start serves the package");
+    info!("  .gitignore: Build artifacts excluded");
+
+    Ok(())
+}
+
+// npm names are lowercase and limited to url-safe characters
+fn npm_package_name(circuit_name: &str) -> String {
+    let name: String = circuit_name
+        .chars()
+        .map(|c| match c.to_ascii_lowercase() {
+            c @ ('a'..='z' | '0'..='9' | '-' | '_' | '.') => c,
+            _ => '-',
+        })
+        .collect();
+    let name = name.trim_start_matches(['.', '_']);
+    if name.is_empty() {
+        "circuit".to_string()
+    } else {
+        name.to_string()
+    }
+}
 fn resolve_circuit_name(r1cs_path: &Path) -> Result<String, Box<dyn std::error::Error>> {
     r1cs_path
         .file_stem()
@@ -539,70 +855,166 @@ fn copy_r1cs_to_build(
     Ok(())
 }
 
+// returns whether the WASM builds were deferred; without wasm-pack neither module can be
+// built, so both take the --skip-wasm route instead of one failing and the other falling back
 fn handle_wasm_steps(
     r1cs_path: &Path,
     package_dir: &Path,
     circuit_name: &str,
     skip_wasm: bool,
-) -> Result<(), Box<dyn std::error::Error>> {
-    if skip_wasm {
-        info!("Step 3/5: Skipping witness calculator WASM build (sources will be generated)");
-        generate_witness_wasm(r1cs_path, package_dir, circuit_name, true)?;
-        info!("Witness calculator sources generated (build later with wasm-pack)\n");
-
-        info!("Step 4/5: Skipping lofit WASM prover build");
-        write_prover_skip_instructions(package_dir)?;
-        info!("Added instructions for building prover WASM later\n");
-    } else {
+    wasm_pack: Option<&Path>,
+) -> Result<bool, Box<dyn std::error::Error>> {
+    let wasm_pack = match wasm_pack {
+        Some(wasm_pack) if !skip_wasm => wasm_pack,
+        None if !skip_wasm => {
+            warn_wasm_pack_missing();
+            return handle_wasm_steps(r1cs_path, package_dir, circuit_name, true, None);
+        }
+        _ => {
+            info!("Step 3/5: Skipping witness calculator WASM build (sources will be generated)");
+            generate_witness_wasm(r1cs_path, package_dir, circuit_name, None)?;
+            info!("Witness calculator sources generated (build later with wasm-pack)\n");
+
+            info!("Step 4/5: Skipping lofit WASM prover build");
+            write_prover_skip_instructions(package_dir)?;
+            info!("Added instructions for building prover WASM later\n");
+            return Ok(true);
+        }
+    };
+
+    let built = (|| -> Result<(), Box<dyn std::error::Error>> {
         info!("Step 3/5: Generating witness calculator WASM...");
-        generate_witness_wasm(r1cs_path, package_dir, circuit_name, false)?;
+        generate_witness_wasm(r1cs_path, package_dir, circuit_name, Some(wasm_pack))?;
         info!("Witness calculator WASM generated\n");
 
         info!("Step 4/5: Building lofit WASM prover...");
-        build_lofit_wasm(package_dir)?;
+        build_lofit_wasm(wasm_pack, package_dir)?;
         info!("Lofit WASM prover ready\n");
+        Ok(())
+    })();
+
+    match built {
+        Ok(()) => Ok(false),
+        Err(err) if err.is::<WasmPackMissing>() => {
+            warn_wasm_pack_missing();
+            handle_wasm_steps(r1cs_path, package_dir, circuit_name, true, None)
+        }
+        Err(err) => Err(err),
     }
+}
 
-    Ok(())
+fn warn_wasm_pack_missing() {
+    warn!("wasm-pack not found; generating WASM sources only, as with --skip-wasm");
+    warn!(
+        "Install wasm-pack ({}) and rerun to build the modules",
+        WASM_PACK_INSTALL_URL
+    );
 }
-fn ensure_ir_exists(r1cs_path: &Path) -> Result<PathBuf, Box<dyn std::error::Error>> {
+
+// the IR cannot be rebuilt from the r1cs, but a `<base>.lof` next to it (or one level up, the
+// layout `lof compile` leaves with its build/ directory) can simply be compiled again
+pub fn ensure_ir_exists(r1cs_path: &Path) -> Result<PathBuf, Box<dyn std::error::Error>> {
     let ir_path = r1cs_path.with_extension("ir");
-    if !ir_path.exists() {
+    if ir_path.exists() {
+        return Ok(ir_path);
+    }
+
+    let Some(source_path) = find_colocated_source(r1cs_path) else {
         error!("IR file not found: {}", ir_path.display());
         error!("Make sure you run 'lof compile' which generates both .r1cs and .ir files");
+        return Err("IR file not found".into());
+    };
+
+    warn!(
+        "IR file not found: {}; regenerating it from {}",
+        ir_path.display(),
+        source_path.display()
+    );
+    let lof = locate_workspace_binary("lof").map_err(|_| {
+        error!("Failed to find the lof compiler needed to regenerate the IR");
+        error!("Run: cargo build --bin lof --release");
+        "lof not found"
+    })?;
+    compile_ir(&lof, &source_path, &ir_path)?;
+
+    if ir_path.exists() {
+        Ok(ir_path)
+    } else {
+        error!(
+            "lof compile finished but {} was not produced",
+            ir_path.display()
+        );
         Err("IR file not found".into())
+    }
+}
+
+fn find_colocated_source(r1cs_path: &Path) -> Option<PathBuf> {
+    let file_name = r1cs_path.with_extension("lof").file_name()?.to_owned();
+    let dir = r1cs_path.parent()?;
+    std::iter::once(dir)
+        .chain(dir.parent())
+        .map(|dir| dir.join(&file_name))
+        .find(|candidate| candidate.is_file())
+}
+
+fn compile_ir(
+    lof: &Path,
+    source_path: &Path,
+    ir_path: &Path,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let out_dir = ir_path
+        .parent()
+        .filter(|dir| !dir.as_os_str().is_empty())
+        .unwrap_or_else(|| Path::new("."));
+    let output = Command::new(lof)
+        .arg("compile")
+        .arg(source_path)
+        .arg("--emit")
+        .arg("ir")
+        .arg("--out-dir")
+        .arg(out_dir)
+        .output()?;
+
+    if output.status.success() {
+        Ok(())
     } else {
-        Ok(ir_path)
+        error!("lof compile failed:");
+        error!("{}", String::from_utf8_lossy(&output.stdout));
+        error!("{}", String::from_utf8_lossy(&output.stderr));
+        Err("Failed to regenerate IR".into())
     }
 }
 
 fn locate_lof_witness_gen() -> Result<PathBuf, Box<dyn std::error::Error>> {
-    which::which("lof-witness-gen")
-        .or_else(|_| -> Result<PathBuf, which::Error> {
-            let current_exe =
-                std::env::current_exe().map_err(|_| which::Error::CannotFindBinaryPath)?;
-            let target_dir = current_exe
-                .ancestors()
-                .find(|p| p.ends_with("target"))
-                .ok_or(which::Error::CannotFindBinaryPath)?;
-
-            let debug_path = target_dir.join("debug").join("lof-witness-gen");
-            let release_path = target_dir.join("release").join("lof-witness-gen");
-
-            if release_path.exists() {
-                Ok(release_path)
-            } else if debug_path.exists() {
-                Ok(debug_path)
-            } else {
-                Err(which::Error::CannotFindBinaryPath)
-            }
-        })
-        .map_err(|_| {
-            error!("Failed to find lof-witness-gen");
-            error!("Make sure lof-witness-gen is installed or built");
-            error!("Run: cargo build --bin lof-witness-gen --release");
-            "lof-witness-gen not found".into()
-        })
+    locate_workspace_binary("lof-witness-gen").map_err(|_| {
+        error!("Failed to find lof-witness-gen");
+        error!("Make sure lof-witness-gen is installed or built");
+        error!("Run: cargo build --bin lof-witness-gen --release");
+        "lof-witness-gen not found".into()
+    })
+}
+
+// PATH first, then the cargo target directory this binary was built into
+fn locate_workspace_binary(name: &str) -> Result<PathBuf, which::Error> {
+    which::which(name).or_else(|_| -> Result<PathBuf, which::Error> {
+        let current_exe =
+            std::env::current_exe().map_err(|_| which::Error::CannotFindBinaryPath)?;
+        let target_dir = current_exe
+            .ancestors()
+            .find(|p| p.ends_with("target"))
+            .ok_or(which::Error::CannotFindBinaryPath)?;
+
+        let debug_path = target_dir.join("debug").join(name);
+        let release_path = target_dir.join("release").join(name);
+
+        if release_path.exists() {
+            Ok(release_path)
+        } else if debug_path.exists() {
+            Ok(debug_path)
+        } else {
+            Err(which::Error::CannotFindBinaryPath)
+        }
+    })
 }
 
 fn run_witness_generator(
@@ -665,23 +1077,14 @@ fn ensure_wasm_project_exists(wasm_project_dir: &Path) -> Result<(), Box<dyn std
     }
 }
 
-fn build_witness_wasm_project(wasm_project_dir: &Path) -> Result<(), Box<dyn std::error::Error>> {
+fn build_witness_wasm_project(
+    wasm_pack: &Path,
+    wasm_project_dir: &Path,
+) -> Result<(), Box<dyn std::error::Error>> {
     info!("  Building WASM with wasm-pack...");
-    let output = Command::new("wasm-pack")
-        .arg("build")
-        .arg("--target")
-        .arg("web")
-        .current_dir(wasm_project_dir)
-        .output();
-
-    let output = match output {
-        Ok(o) => o,
-        Err(e) => {
-            error!("Failed to run wasm-pack: {}", e);
-            error!("Install wasm-pack: https://rustwasm.github.io/wasm-pack/installer/");
-            return Err(e.into());
-        }
-    };
+    let output = run_wasm_pack(wasm_pack, Some(wasm_project_dir), None).inspect_err(|e| {
+        error!("Failed to run wasm-pack: {}", e);
+    })?;
 
     if output.status.success() {
         Ok(())
@@ -1,24 +1,53 @@
 use crate::circuit::LofCircuit;
-use crate::setup::ProverKey;
+use crate::setup::{Curve, ProverKey};
+use crate::timing::PhaseTimer;
 use ark_bn254::{Bn254, Fr};
 use ark_groth16::Groth16;
 use ark_groth16::Proof as ArkProof;
 use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
 use ark_snark::SNARK;
+use serde::Deserialize;
 use std::io::{Read, Write};
+use tracing::info;
 
 pub struct Proof {
     pub(crate) proof: ArkProof<Bn254>,
+    pub(crate) circuit_hash: Option<u64>,
+    pub(crate) public_input_count: Option<usize>,
+    pub(crate) curve: Curve,
 }
 
+// proof files start with this header so `verify` can reject a proof checked against the wrong
+// circuit or input list before any pairing work; proofs without it are read as plain arkworks
+// bytes. a zero hash means the proving key did not record its circuit. version 2 appends the
+// curve tag; older proofs are BN254
+const PROOF_MAGIC: &[u8; 8] = b"lof-prf1";
+const PROOF_VERSION: u32 = 2;
+const PROOF_HEADER_LEN_V1: usize = 8 + 4 + 8 + 4;
+const PROOF_HEADER_LEN: usize = PROOF_HEADER_LEN_V1 + 4;
+
 impl ProverKey {
     pub fn prove(&self, circuit: LofCircuit<Fr>) -> Result<Proof, ProverError> {
         let rng = &mut rand::thread_rng();
+        let constraints = circuit.constraints.len();
+        let public_input_count = circuit.public_inputs.len();
+        let timer = PhaseTimer::start();
 
         let proof = <Groth16<Bn254> as SNARK<Fr>>::prove(&self.params, circuit, rng)
             .map_err(|e| ProverError::ProvingFailed(e.to_string()))?;
 
-        Ok(Proof { proof })
+        info!(
+            phase = "prove",
+            constraints,
+            elapsed_ms = timer.elapsed_ms(),
+            "Proof generation finished"
+        );
+        Ok(Proof {
+            proof,
+            circuit_hash: self.circuit_hash(),
+            public_input_count: Some(public_input_count),
+            curve: Curve::Bn254,
+        })
     }
 }
 
@@ -29,14 +58,146 @@ pub enum ProverError {
 }
 
 impl Proof {
+    pub fn circuit_hash(&self) -> Option<u64> {
+        self.circuit_hash
+    }
+
+    pub fn public_input_count(&self) -> Option<usize> {
+        self.public_input_count
+    }
+
+    pub fn curve(&self) -> Curve {
+        self.curve
+    }
+
     pub fn write<W: Write>(&self, mut writer: W) -> std::io::Result<()> {
+        if let Some(count) = self.public_input_count {
+            writer.write_all(PROOF_MAGIC)?;
+            writer.write_all(&PROOF_VERSION.to_le_bytes())?;
+            writer.write_all(&self.circuit_hash.unwrap_or(0).to_le_bytes())?;
+            writer.write_all(&(count as u32).to_le_bytes())?;
+            writer.write_all(&self.curve.tag().to_le_bytes())?;
+        }
         self.proof
             .serialize_compressed(&mut writer)
             .map_err(std::io::Error::other)
     }
 
     pub fn read<R: Read>(mut reader: R) -> std::io::Result<Self> {
-        let proof = ArkProof::deserialize_compressed(&mut reader).map_err(std::io::Error::other)?;
-        Ok(Self { proof })
+        let mut buffer = Vec::new();
+        reader.read_to_end(&mut buffer)?;
+        let header = split_proof_header(&buffer)?;
+        let mut proof_bytes = header.body;
+        let proof =
+            ArkProof::deserialize_compressed(&mut proof_bytes).map_err(std::io::Error::other)?;
+        Ok(Self {
+            proof,
+            circuit_hash: header.circuit_hash,
+            public_input_count: header.public_input_count,
+            curve: header.curve,
+        })
+    }
+
+    /// The proof as JSON text, for passing it around without a file: the header fields of the
+    /// binary format next to the compressed arkworks proof in hex.
+    pub fn to_json(&self) -> String {
+        let mut bytes = Vec::new();
+        self.proof
+            .serialize_compressed(&mut bytes)
+            .expect("serializing into a Vec cannot fail");
+        serde_json::json!({
+            "curve": self.curve.to_string(),
+            "circuit_hash": self.circuit_hash,
+            "public_input_count": self.public_input_count,
+            "proof": bytes.iter().map(|byte| format!("{:02x}", byte)).collect::<String>(),
+        })
+        .to_string()
+    }
+
+    /// Reads a proof written by [`Proof::to_json`].
+    pub fn from_json(json: &str) -> std::io::Result<Self> {
+        let invalid =
+            |message: String| std::io::Error::new(std::io::ErrorKind::InvalidData, message);
+        let parsed: ProofJson = serde_json::from_str(json)
+            .map_err(|e| invalid(format!("Invalid proof JSON: {}", e)))?;
+
+        let hex = parsed.proof.strip_prefix("0x").unwrap_or(&parsed.proof);
+        if !hex.len().is_multiple_of(2) {
+            return Err(invalid("Proof hex has an odd number of digits".to_string()));
+        }
+        let bytes = (0..hex.len())
+            .step_by(2)
+            .map(|i| {
+                hex.get(i..i + 2)
+                    .and_then(|pair| u8::from_str_radix(pair, 16).ok())
+                    .ok_or_else(|| invalid(format!("Invalid hex in proof at offset {}", i)))
+            })
+            .collect::<std::io::Result<Vec<u8>>>()?;
+        let proof = ArkProof::deserialize_compressed(&bytes[..]).map_err(std::io::Error::other)?;
+
+        Ok(Self {
+            proof,
+            circuit_hash: parsed.circuit_hash.filter(|hash| *hash != 0),
+            public_input_count: parsed.public_input_count,
+            curve: parsed
+                .curve
+                .as_deref()
+                .map_or(Ok(Curve::Bn254), Curve::from_name)?,
+        })
+    }
+}
+
+// only `proof` is required; a proof without the other fields is read like a headerless file
+#[derive(Deserialize)]
+struct ProofJson {
+    curve: Option<String>,
+    circuit_hash: Option<u64>,
+    public_input_count: Option<usize>,
+    proof: String,
+}
+
+struct ProofHeader<'a> {
+    circuit_hash: Option<u64>,
+    public_input_count: Option<usize>,
+    curve: Curve,
+    body: &'a [u8],
+}
+
+fn split_proof_header(bytes: &[u8]) -> std::io::Result<ProofHeader<'_>> {
+    if !bytes.starts_with(PROOF_MAGIC) {
+        return Ok(ProofHeader {
+            circuit_hash: None,
+            public_input_count: None,
+            curve: Curve::Bn254,
+            body: bytes,
+        });
+    }
+    let truncated =
+        || std::io::Error::new(std::io::ErrorKind::UnexpectedEof, "Truncated proof header");
+    if bytes.len() < PROOF_HEADER_LEN_V1 {
+        return Err(truncated());
     }
+    let version = u32::from_le_bytes(bytes[8..12].try_into().unwrap());
+    let (curve, header_len) = match version {
+        1 => (Curve::Bn254, PROOF_HEADER_LEN_V1),
+        PROOF_VERSION if bytes.len() >= PROOF_HEADER_LEN => (
+            Curve::from_tag(u32::from_le_bytes(bytes[24..28].try_into().unwrap()))?,
+            PROOF_HEADER_LEN,
+        ),
+        PROOF_VERSION => return Err(truncated()),
+        _ => {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("Unsupported proof version {}", version),
+            ))
+        }
+    };
+    let hash = u64::from_le_bytes(bytes[12..20].try_into().unwrap());
+    let count = u32::from_le_bytes(bytes[20..24].try_into().unwrap());
+    Ok(ProofHeader {
+        circuit_hash: Some(hash).filter(|hash| *hash != 0),
+        public_input_count: Some(count as usize),
+        curve,
+        body: &bytes[header_len..],
+    })
 }
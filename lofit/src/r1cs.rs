@@ -1,11 +1,24 @@
-use num_bigint::BigInt;
+use ark_bn254::Fr;
+use ark_ff::{BigInteger, PrimeField};
+use num_bigint::{BigInt, BigUint, Sign};
 use std::io::{self, Read};
 
+// version 2 follows the version with the field modulus (length-prefixed, little-endian) and an
+// optional circuit name; version 1 files carry neither and are read as BN254. version 3 adds,
+// after the three counts, how many of the witnesses the user declares
+pub const R1CS_VERSION: u32 = 3;
+
 #[derive(Debug, Clone)]
 pub struct ConstraintSystem {
+    // the proof the file was compiled from, when the header names one
+    pub name: Option<String>,
     pub public_inputs: Vec<String>,
     pub witnesses: Vec<String>,
     pub constraints: Vec<Constraint>,
+    // how many of `witnesses`, from the front, the user declares and may supply; the rest are
+    // computed by the compiler. `None` for files older than version 3, whose temporaries are
+    // recognised by name instead
+    pub declared_witness_count: Option<usize>,
 }
 
 #[derive(Debug, Clone)]
@@ -22,6 +35,20 @@ pub struct LinearCombination {
 
 impl ConstraintSystem {
     pub fn from_file(mut reader: impl Read) -> io::Result<Self> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes)?;
+        Self::from_bytes(&bytes)
+    }
+
+    // for callers that already hold the file in memory, e.g. the wasm prover
+    pub fn from_bytes(bytes: &[u8]) -> io::Result<Self> {
+        Self::from_bytes_for_field::<Fr>(bytes)
+    }
+
+    // rejects a file compiled for another field than `F`, which would otherwise load fine and
+    // produce proofs over reduced, meaningless coefficients
+    pub fn from_bytes_for_field<F: PrimeField>(bytes: &[u8]) -> io::Result<Self> {
+        let mut reader = bytes;
         let mut magic = [0u8; 8];
         reader.read_exact(&mut magic)?;
         if &magic != b"lof-r1cs" {
@@ -33,7 +60,8 @@ impl ConstraintSystem {
 
         let mut version = [0u8; 4];
         reader.read_exact(&mut version)?;
-        if u32::from_le_bytes(version) != 1 {
+        let version = u32::from_le_bytes(version);
+        if version == 0 || version > R1CS_VERSION {
             return Err(io::Error::new(
                 io::ErrorKind::InvalidData,
                 "Unsupported r1cs version",
@@ -42,6 +70,26 @@ impl ConstraintSystem {
 
         let mut buf = [0u8; 4];
 
+        let (modulus, name) = if version >= 2 {
+            let modulus = BigUint::from_bytes_le(&read_bytes(&mut reader)?);
+            let name = read_string(&mut reader)?;
+            (modulus, Some(name).filter(|name| !name.is_empty()))
+        } else {
+            (BigUint::from(Fr::MODULUS), None)
+        };
+        let expected = BigUint::from_bytes_le(&F::MODULUS.to_bytes_le());
+        if modulus != expected {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "R1CS file targets the field {}, but this prover works over {}; \
+                     recompile the circuit for the matching curve",
+                    field_label(&modulus),
+                    field_label(&expected)
+                ),
+            ));
+        }
+
         reader.read_exact(&mut buf)?;
         let pub_inputs_count = u32::from_le_bytes(buf);
 
@@ -51,28 +99,31 @@ impl ConstraintSystem {
         reader.read_exact(&mut buf)?;
         let constraints_count = u32::from_le_bytes(buf);
 
+        let declared_witness_count = if version >= 3 {
+            reader.read_exact(&mut buf)?;
+            let count = u32::from_le_bytes(buf);
+            if count > witnesses_count {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!(
+                        "R1CS file declares {} user witnesses but only has {} witnesses",
+                        count, witnesses_count
+                    ),
+                ));
+            }
+            Some(count as usize)
+        } else {
+            None
+        };
+
         let mut public_inputs = Vec::new();
         for _ in 0..pub_inputs_count {
-            reader.read_exact(&mut buf)?;
-            let len = u32::from_le_bytes(buf) as usize;
-            let mut name = vec![0u8; len];
-            reader.read_exact(&mut name)?;
-            public_inputs.push(
-                String::from_utf8(name)
-                    .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?,
-            );
+            public_inputs.push(read_string(&mut reader)?);
         }
 
         let mut witnesses = Vec::new();
         for _ in 0..witnesses_count {
-            reader.read_exact(&mut buf)?;
-            let len = u32::from_le_bytes(buf) as usize;
-            let mut name = vec![0u8; len];
-            reader.read_exact(&mut name)?;
-            witnesses.push(
-                String::from_utf8(name)
-                    .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?,
-            );
+            witnesses.push(read_string(&mut reader)?);
         }
 
         let mut constraints = Vec::new();
@@ -84,13 +135,177 @@ impl ConstraintSystem {
         }
 
         Ok(Self {
+            name,
             public_inputs,
             witnesses,
             constraints,
+            declared_witness_count,
         })
     }
 }
 
+// the length prefix comes from the file, so it only caps how much is read; sizing a buffer by
+// it would let a corrupt header ask for gigabytes before the read fails
+fn read_bytes(reader: &mut impl Read) -> io::Result<Vec<u8>> {
+    let mut buf = [0u8; 4];
+    reader.read_exact(&mut buf)?;
+    let len = u64::from(u32::from_le_bytes(buf));
+    let mut bytes = Vec::new();
+    reader.take(len).read_to_end(&mut bytes)?;
+    if (bytes.len() as u64) < len {
+        return Err(io::Error::new(
+            io::ErrorKind::UnexpectedEof,
+            "R1CS file ends inside a length-prefixed field",
+        ));
+    }
+    Ok(bytes)
+}
+
+fn read_string(reader: &mut impl Read) -> io::Result<String> {
+    String::from_utf8(read_bytes(reader)?)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+fn field_label(modulus: &BigUint) -> String {
+    if *modulus == BigUint::from(Fr::MODULUS) {
+        format!("BN254 (modulus {})", modulus)
+    } else {
+        format!("with modulus {}", modulus)
+    }
+}
+
+impl ConstraintSystem {
+    pub fn num_constraints(&self) -> usize {
+        self.constraints.len()
+    }
+
+    pub fn num_public_inputs(&self) -> usize {
+        self.public_inputs.len()
+    }
+
+    pub fn num_witnesses(&self) -> usize {
+        self.witnesses.len()
+    }
+
+    // the witnesses a witness file supplies, in wire order; everything after them is derived
+    // from the constraints
+    pub fn declared_witnesses(&self) -> &[String] {
+        let count = self.declared_witness_count.unwrap_or_else(|| {
+            self.witnesses
+                .iter()
+                .take_while(|name| !is_computed_witness(name))
+                .count()
+        });
+        &self.witnesses[..count.min(self.witnesses.len())]
+    }
+
+    // every wire a constraint can refer to: ONE, the public inputs and the witnesses
+    pub fn num_variables(&self) -> usize {
+        1 + self.num_public_inputs() + self.num_witnesses()
+    }
+
+    // full wire assignment z = (1, public inputs, witnesses), the vector constraints index into
+    pub fn full_assignment(pub_inputs: &[Fr], witnesses: &[Fr]) -> Vec<Fr> {
+        std::iter::once(Fr::from(1u64))
+            .chain(pub_inputs.iter().copied())
+            .chain(witnesses.iter().copied())
+            .collect()
+    }
+
+    // FNV-1a over the signal lists and every constraint term; stable across runs and platforms,
+    // so it can be stored in key files and compared later
+    pub fn circuit_hash(&self) -> u64 {
+        const OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+        const PRIME: u64 = 0x0000_0100_0000_01b3;
+
+        let mut hash = OFFSET;
+        let mut feed = |bytes: &[u8]| {
+            for byte in bytes {
+                hash ^= u64::from(*byte);
+                hash = hash.wrapping_mul(PRIME);
+            }
+        };
+
+        for names in [&self.public_inputs, &self.witnesses] {
+            feed(&(names.len() as u32).to_le_bytes());
+            for name in names {
+                feed(&(name.len() as u32).to_le_bytes());
+                feed(name.as_bytes());
+            }
+        }
+        feed(&(self.constraints.len() as u32).to_le_bytes());
+        for constraint in &self.constraints {
+            for lc in [&constraint.a, &constraint.b, &constraint.c] {
+                feed(&(lc.terms.len() as u32).to_le_bytes());
+                for (var, coeff) in &lc.terms {
+                    let bytes = coeff.to_signed_bytes_le();
+                    feed(&var.to_le_bytes());
+                    feed(&(bytes.len() as u32).to_le_bytes());
+                    feed(&bytes);
+                }
+            }
+        }
+
+        hash
+    }
+
+    // index of the first constraint with A·z * B·z != C·z
+    pub fn find_unsatisfied(&self, assignment: &[Fr]) -> Option<usize> {
+        unsatisfied(&self.constraints, assignment).next()
+    }
+}
+
+// indices of the constraints `assignment` (ONE, public inputs, witnesses) violates, in order
+pub(crate) fn unsatisfied<'a, F: PrimeField>(
+    constraints: &'a [Constraint],
+    assignment: &'a [F],
+) -> impl Iterator<Item = usize> + 'a {
+    constraints
+        .iter()
+        .enumerate()
+        .filter(|(_, constraint)| !constraint.is_satisfied(assignment))
+        .map(|(i, _)| i)
+}
+
+impl Constraint {
+    pub fn is_satisfied<F: PrimeField>(&self, assignment: &[F]) -> bool {
+        self.a.evaluate(assignment) * self.b.evaluate(assignment) == self.c.evaluate(assignment)
+    }
+}
+
+impl LinearCombination {
+    // wires past the end of the assignment count as zero
+    pub fn evaluate<F: PrimeField>(&self, assignment: &[F]) -> F {
+        self.terms
+            .iter()
+            .map(|(var, coeff)| {
+                let value = assignment
+                    .get(*var as usize)
+                    .copied()
+                    .unwrap_or_else(F::zero);
+                let magnitude = F::from(coeff.magnitude().clone());
+                if coeff.sign() == Sign::Minus {
+                    -(value * magnitude)
+                } else {
+                    value * magnitude
+                }
+            })
+            .sum()
+    }
+}
+
+// the compiler names the intermediates it introduces (products, quotients, comparison
+// results and everything derived from them) `<proof>::t_<n>`, older files `t_<n>_<proof>`.
+// files from version 3 on say which wires are computed; for older ones this name is the
+// only sign
+pub fn is_computed_witness(name: &str) -> bool {
+    let local = name.rsplit_once("::").map_or(name, |(_, local)| local);
+    local.strip_prefix("t_").is_some_and(|rest| {
+        let index = rest.split('_').next().unwrap_or_default();
+        !index.is_empty() && index.chars().all(|c| c.is_ascii_digit())
+    })
+}
+
 fn read_linear_combination(reader: &mut impl Read) -> io::Result<LinearCombination> {
     let mut buf = [0u8; 4];
     reader.read_exact(&mut buf)?;
@@ -101,12 +316,7 @@ fn read_linear_combination(reader: &mut impl Read) -> io::Result<LinearCombinati
         reader.read_exact(&mut buf)?;
         let var_idx = u32::from_le_bytes(buf);
 
-        reader.read_exact(&mut buf)?;
-        let bytes_len = u32::from_le_bytes(buf) as usize;
-        let mut bytes = vec![0u8; bytes_len];
-        reader.read_exact(&mut bytes)?;
-
-        let coeff = BigInt::from_signed_bytes_le(&bytes);
+        let coeff = BigInt::from_signed_bytes_le(&read_bytes(reader)?);
 
         terms.push((var_idx, coeff));
     }
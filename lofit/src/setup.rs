@@ -1,19 +1,108 @@
 use crate::circuit::LofCircuit;
+use crate::timing::PhaseTimer;
 use ark_bn254::{Bn254, Fr};
 use ark_groth16::{Groth16, ProvingKey as ArkProvingKey, VerifyingKey as ArkVerifyingKey};
 use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
 use ark_snark::SNARK;
 use std::io::{Read, Write};
-use tracing::{debug, error, instrument};
+use tracing::{debug, error, info, instrument};
+
+// the pairing curve a verifying key or proof belongs to, recorded in their file headers so a
+// proof is never checked against a key for another curve. only BN254 is implemented
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Curve {
+    Bn254,
+    Bls12_381,
+}
+
+impl Curve {
+    pub(crate) fn tag(self) -> u32 {
+        match self {
+            Curve::Bn254 => 1,
+            Curve::Bls12_381 => 2,
+        }
+    }
+
+    pub(crate) fn from_tag(tag: u32) -> std::io::Result<Self> {
+        match tag {
+            1 => Ok(Curve::Bn254),
+            2 => Ok(Curve::Bls12_381),
+            _ => Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("Unknown curve tag {}", tag),
+            )),
+        }
+    }
+
+    // the inverse of `Display`
+    pub(crate) fn from_name(name: &str) -> std::io::Result<Self> {
+        match name {
+            "bn254" => Ok(Curve::Bn254),
+            "bls12-381" => Ok(Curve::Bls12_381),
+            _ => Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("Unknown curve '{}'", name),
+            )),
+        }
+    }
+}
+
+impl std::fmt::Display for Curve {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Curve::Bn254 => write!(f, "bn254"),
+            Curve::Bls12_381 => write!(f, "bls12-381"),
+        }
+    }
+}
 
 #[derive(Debug)]
 pub struct ProverKey {
     pub(crate) params: ArkProvingKey<Bn254>,
+    circuit_hash: Option<u64>,
 }
 
+// proving key files start with this header so `setup` can tell whether a key still matches
+// its circuit; keys written before the header existed are read as plain arkworks bytes
+const KEY_MAGIC: &[u8; 8] = b"lof-pkey";
+const KEY_VERSION: u32 = 1;
+const KEY_HEADER_LEN: usize = 8 + 4 + 8;
+
+// splits a proving key file into its embedded circuit hash and the arkworks key bytes
+pub(crate) fn split_key_header(bytes: &[u8]) -> std::io::Result<(Option<u64>, &[u8])> {
+    if !bytes.starts_with(KEY_MAGIC) {
+        return Ok((None, bytes));
+    }
+    if bytes.len() < KEY_HEADER_LEN {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::UnexpectedEof,
+            "Truncated proving key header",
+        ));
+    }
+    let version = u32::from_le_bytes(bytes[8..12].try_into().unwrap());
+    if version != KEY_VERSION {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            format!("Unsupported proving key version {}", version),
+        ));
+    }
+    let hash = u64::from_le_bytes(bytes[12..20].try_into().unwrap());
+    Ok((Some(hash), &bytes[KEY_HEADER_LEN..]))
+}
+
+// verifying key files start with this header naming their curve and, from version 2, the
+// circuit hash (0 when unknown); keys written before it existed are read as plain arkworks
+// bytes for BN254
+const VK_MAGIC: &[u8; 8] = b"lof-vkey";
+const VK_VERSION: u32 = 2;
+const VK_V1_HEADER_LEN: usize = 8 + 4 + 4;
+const VK_HEADER_LEN: usize = VK_V1_HEADER_LEN + 8;
+
 #[derive(Debug)]
 pub struct VerifierKey {
     pub(crate) vk: ArkVerifyingKey<Bn254>,
+    pub(crate) curve: Curve,
+    pub(crate) circuit_hash: Option<u64>,
 }
 
 impl ProverKey {
@@ -22,15 +111,48 @@ impl ProverKey {
         circuit: LofCircuit<Fr>,
     ) -> Result<(Self, VerifierKey), Box<dyn std::error::Error>> {
         let rng = &mut rand::thread_rng();
+        let constraints = circuit.constraints.len();
+        let timer = PhaseTimer::start();
 
         let (params, vk) =
             Groth16::<Bn254>::circuit_specific_setup(circuit, rng).map_err(Box::new)?;
 
-        Ok((Self { params }, VerifierKey { vk }))
+        info!(
+            phase = "setup",
+            constraints,
+            elapsed_ms = timer.elapsed_ms(),
+            "Key generation finished"
+        );
+        Ok((
+            Self {
+                params,
+                circuit_hash: None,
+            },
+            VerifierKey {
+                vk,
+                curve: Curve::Bn254,
+                circuit_hash: None,
+            },
+        ))
+    }
+
+    // records which circuit the key was generated for; see `ConstraintSystem::circuit_hash`
+    pub fn with_circuit_hash(mut self, hash: u64) -> Self {
+        self.circuit_hash = Some(hash);
+        self
+    }
+
+    pub fn circuit_hash(&self) -> Option<u64> {
+        self.circuit_hash
     }
 
     #[instrument(skip(self, writer))]
     pub fn write<W: Write>(&self, mut writer: W) -> std::io::Result<()> {
+        if let Some(hash) = self.circuit_hash {
+            writer.write_all(KEY_MAGIC)?;
+            writer.write_all(&KEY_VERSION.to_le_bytes())?;
+            writer.write_all(&hash.to_le_bytes())?;
+        }
         self.params
             .serialize_compressed(&mut writer)
             .map_err(std::io::Error::other)
@@ -38,16 +160,52 @@ impl ProverKey {
 
     #[instrument(skip(reader))]
     pub fn read<R: Read>(mut reader: R) -> std::io::Result<Self> {
+        let mut buffer = Vec::new();
+        reader.read_to_end(&mut buffer)?;
+        let (circuit_hash, mut key_bytes) = split_key_header(&buffer)?;
         let params =
-            ArkProvingKey::deserialize_compressed(&mut reader).map_err(std::io::Error::other)?;
-        Ok(Self { params })
+            ArkProvingKey::deserialize_compressed(&mut key_bytes).map_err(std::io::Error::other)?;
+        Ok(Self {
+            params,
+            circuit_hash,
+        })
+    }
+
+    // reads only the header, without deserializing the (large) key itself
+    pub fn read_circuit_hash<R: Read>(reader: R) -> std::io::Result<Option<u64>> {
+        let mut header = Vec::with_capacity(KEY_HEADER_LEN);
+        reader
+            .take(KEY_HEADER_LEN as u64)
+            .read_to_end(&mut header)?;
+        if header.len() < KEY_HEADER_LEN {
+            return Ok(None);
+        }
+        Ok(split_key_header(&header)?.0)
     }
 }
 
 impl VerifierKey {
+    pub fn curve(&self) -> Curve {
+        self.curve
+    }
+
+    // records which circuit the key verifies, so `verify` rejects proofs for another one
+    pub fn with_circuit_hash(mut self, hash: u64) -> Self {
+        self.circuit_hash = Some(hash);
+        self
+    }
+
+    pub fn circuit_hash(&self) -> Option<u64> {
+        self.circuit_hash
+    }
+
     #[instrument(skip(self, writer))]
     pub fn write<W: Write>(&self, mut writer: W) -> std::io::Result<()> {
         debug!("Writing verification key");
+        writer.write_all(VK_MAGIC)?;
+        writer.write_all(&VK_VERSION.to_le_bytes())?;
+        writer.write_all(&self.curve.tag().to_le_bytes())?;
+        writer.write_all(&self.circuit_hash.unwrap_or(0).to_le_bytes())?;
         self.vk.serialize_compressed(&mut writer).map_err(|e| {
             error!("Error writing verification key: {:?}", e);
             std::io::Error::other(e)
@@ -58,19 +216,62 @@ impl VerifierKey {
     pub fn read<R: Read>(mut reader: R) -> std::io::Result<Self> {
         let mut buffer = Vec::new();
         reader.read_to_end(&mut buffer)?;
+        let (curve, circuit_hash, key_bytes) = split_vk_header(&buffer)?;
 
-        let attempt_uncompressed = ArkVerifyingKey::deserialize_uncompressed(&mut &buffer[..])
+        let attempt_uncompressed = ArkVerifyingKey::deserialize_uncompressed(&mut &key_bytes[..])
             .map_err(std::io::Error::other);
         match attempt_uncompressed {
-            Ok(vk) => Ok(Self { vk }),
+            Ok(vk) => Ok(Self {
+                vk,
+                curve,
+                circuit_hash,
+            }),
             Err(_) => {
                 let vk =
-                    ArkVerifyingKey::deserialize_compressed(&mut &buffer[..]).map_err(|e| {
+                    ArkVerifyingKey::deserialize_compressed(&mut &key_bytes[..]).map_err(|e| {
                         error!("Error reading verification key: {:?}", e);
                         std::io::Error::other(e)
                     })?;
-                Ok(Self { vk })
+                Ok(Self {
+                    vk,
+                    curve,
+                    circuit_hash,
+                })
             }
         }
     }
 }
+
+fn split_vk_header(bytes: &[u8]) -> std::io::Result<(Curve, Option<u64>, &[u8])> {
+    if !bytes.starts_with(VK_MAGIC) {
+        return Ok((Curve::Bn254, None, bytes));
+    }
+    let truncated = || {
+        std::io::Error::new(
+            std::io::ErrorKind::UnexpectedEof,
+            "Truncated verification key header",
+        )
+    };
+    if bytes.len() < VK_V1_HEADER_LEN {
+        return Err(truncated());
+    }
+    let version = u32::from_le_bytes(bytes[8..12].try_into().unwrap());
+    let header_len = match version {
+        1 => VK_V1_HEADER_LEN,
+        VK_VERSION => VK_HEADER_LEN,
+        _ => {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("Unsupported verification key version {}", version),
+            ))
+        }
+    };
+    if bytes.len() < header_len {
+        return Err(truncated());
+    }
+    let curve = Curve::from_tag(u32::from_le_bytes(bytes[12..16].try_into().unwrap()))?;
+    let circuit_hash = (header_len == VK_HEADER_LEN)
+        .then(|| u64::from_le_bytes(bytes[VK_V1_HEADER_LEN..header_len].try_into().unwrap()))
+        .filter(|hash| *hash != 0);
+    Ok((curve, circuit_hash, &bytes[header_len..]))
+}
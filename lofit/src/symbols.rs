@@ -0,0 +1,122 @@
+use crate::r1cs::{Constraint, ConstraintSystem};
+use ark_bn254::Fr;
+use std::collections::BTreeMap;
+use std::io::{self, BufRead};
+use std::path::Path;
+
+// wire index -> signal name, as written by the compiler to `<base>.sym`
+#[derive(Debug, Clone, Default)]
+pub struct SymbolTable {
+    names: BTreeMap<u32, String>,
+}
+
+impl SymbolTable {
+    pub fn read(reader: impl BufRead) -> io::Result<Self> {
+        let mut names = BTreeMap::new();
+        for (line_no, line) in reader.lines().enumerate() {
+            let line = line?;
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+            let invalid = || {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("Malformed symbol entry on line {}: '{}'", line_no + 1, line),
+                )
+            };
+            let (index, name) = line.split_once(',').ok_or_else(invalid)?;
+            let index = index.trim().parse::<u32>().map_err(|_| invalid())?;
+            names.insert(index, name.trim().to_string());
+        }
+        Ok(Self { names })
+    }
+
+    pub fn from_file(path: &Path) -> io::Result<Self> {
+        Self::read(io::BufReader::new(std::fs::File::open(path)?))
+    }
+
+    // same layout the compiler uses: ONE, then public inputs, then witnesses
+    pub fn from_constraint_system(r1cs: &ConstraintSystem) -> Self {
+        let names = std::iter::once("ONE".to_string())
+            .chain(r1cs.public_inputs.iter().cloned())
+            .chain(r1cs.witnesses.iter().cloned())
+            .enumerate()
+            .map(|(index, name)| (index as u32, name))
+            .collect();
+        Self { names }
+    }
+
+    pub fn name(&self, index: u32) -> Option<&str> {
+        self.names.get(&index).map(|s| s.as_str())
+    }
+
+    pub fn describe(&self, index: u32) -> String {
+        self.name(index)
+            .map(str::to_string)
+            .unwrap_or_else(|| format!("wire_{}", index))
+    }
+
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (u32, &str)> {
+        self.names
+            .iter()
+            .map(|(index, name)| (*index, name.as_str()))
+    }
+
+    // names of the signals a constraint touches, in wire order
+    pub fn constraint_signals(&self, constraint: &Constraint) -> Vec<String> {
+        constraint_wires(constraint)
+            .into_iter()
+            .map(|w| self.describe(w))
+            .collect()
+    }
+
+    // one-line explanation of a violated constraint: both sides and every signal's value
+    pub fn render_constraint(
+        &self,
+        index: usize,
+        constraint: &Constraint,
+        assignment: &[Fr],
+    ) -> String {
+        let lhs = constraint.a.evaluate(assignment) * constraint.b.evaluate(assignment);
+        let rhs = constraint.c.evaluate(assignment);
+        let signals = constraint_wires(constraint)
+            .into_iter()
+            .map(|wire| {
+                let value = assignment
+                    .get(wire as usize)
+                    .map(|v| v.to_string())
+                    .unwrap_or_else(|| "unassigned".to_string());
+                format!("{} = {}", self.describe(wire), value)
+            })
+            .collect::<Vec<_>>()
+            .join(", ");
+        format!(
+            "constraint {}: A*B = {} but C = {} ({})",
+            index, lhs, rhs, signals
+        )
+    }
+}
+
+fn constraint_wires(constraint: &Constraint) -> Vec<u32> {
+    let mut wires: Vec<u32> = constraint
+        .a
+        .terms
+        .iter()
+        .chain(&constraint.b.terms)
+        .chain(&constraint.c.terms)
+        .map(|(var, _)| *var)
+        .filter(|var| *var != 0)
+        .collect();
+    wires.sort_unstable();
+    wires.dedup();
+    wires
+}
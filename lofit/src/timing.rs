@@ -0,0 +1,21 @@
+// std::time::Instant panics on wasm32-unknown-unknown, so phases are only timed natively
+pub(crate) struct PhaseTimer {
+    #[cfg(not(target_arch = "wasm32"))]
+    started: std::time::Instant,
+}
+
+impl PhaseTimer {
+    pub(crate) fn start() -> Self {
+        Self {
+            #[cfg(not(target_arch = "wasm32"))]
+            started: std::time::Instant::now(),
+        }
+    }
+
+    pub(crate) fn elapsed_ms(&self) -> u64 {
+        #[cfg(not(target_arch = "wasm32"))]
+        return self.started.elapsed().as_millis() as u64;
+        #[cfg(target_arch = "wasm32")]
+        return 0;
+    }
+}
@@ -1,3 +1,4 @@
+use crate::setup::Curve;
 use crate::{Proof, VerifierKey};
 use ark_bn254::{Bn254, Fr};
 use ark_groth16::Groth16;
@@ -8,6 +9,12 @@ use tracing::{debug, error, info, instrument};
 pub enum VerificationError {
     #[error("Verification failed: {0}")]
     Failed(String),
+    #[error("Proof expects {expected} public inputs, but {found} were given")]
+    InputCountMismatch { expected: usize, found: usize },
+    #[error("Proof is over {proof}, but the verification key is for {key}")]
+    CurveMismatch { proof: Curve, key: Curve },
+    #[error("Proof was generated for circuit {proof:016x}, but the verification key is for circuit {key:016x}")]
+    CircuitMismatch { proof: u64, key: u64 },
 }
 
 impl VerifierKey {
@@ -16,6 +23,49 @@ impl VerifierKey {
         debug!("Number of public inputs: {}", public_inputs.len());
         debug!("Public inputs: {:?}", public_inputs);
 
+        // pairing a proof with a key from another curve would only ever report Ok(false)
+        if proof.curve != self.curve {
+            error!("Proof is over {}, key is for {}", proof.curve, self.curve);
+            return Err(VerificationError::CurveMismatch {
+                proof: proof.curve,
+                key: self.curve,
+            });
+        }
+
+        // a proof for another circuit with as many inputs would only ever report Ok(false);
+        // proofs and keys that predate the hash are not compared
+        if let (Some(proof_hash), Some(key_hash)) = (proof.circuit_hash, self.circuit_hash) {
+            if proof_hash != key_hash {
+                error!(
+                    "Proof is for circuit {:016x}, key is for {:016x}",
+                    proof_hash, key_hash
+                );
+                return Err(VerificationError::CircuitMismatch {
+                    proof: proof_hash,
+                    key: key_hash,
+                });
+            }
+        }
+
+        // the proof header names the count it was generated with; the verifying key covers
+        // headerless proofs and proofs checked against a key for another circuit
+        let expected_counts = proof.public_input_count.into_iter().chain(std::iter::once(
+            self.vk.gamma_abc_g1.len().saturating_sub(1),
+        ));
+        for expected in expected_counts {
+            if public_inputs.len() != expected {
+                error!(
+                    "Expected {} public inputs, got {}",
+                    expected,
+                    public_inputs.len()
+                );
+                return Err(VerificationError::InputCountMismatch {
+                    expected,
+                    found: public_inputs.len(),
+                });
+            }
+        }
+
         debug!("Calling SNARK::verify with {} inputs", public_inputs.len());
         let result = <Groth16<Bn254> as SNARK<Fr>>::verify(&self.vk, public_inputs, &proof.proof)
             .map_err(|e| {
@@ -1,17 +1,19 @@
 use ark_bn254::{Bn254, Fr};
 use ark_ff::PrimeField;
-use ark_groth16::{Groth16, ProvingKey, VerifyingKey};
-use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use ark_groth16::{Groth16, ProvingKey};
+use ark_serialize::CanonicalDeserialize;
 use ark_snark::SNARK;
 use ark_std::rand::thread_rng;
 use serde::{Deserialize, Serialize};
-use std::io::Cursor;
 use wasm_bindgen::prelude::*;
 
 use crate::circuit::LofCircuit;
 use crate::field::fr_from_str;
+use crate::inputs::{flatten_inputs, ordered_public_inputs};
 use crate::r1cs::ConstraintSystem;
+use crate::setup::split_key_header;
 use crate::witness::generate_full_witness_with_provided;
+use crate::{Curve, Proof, VerifierKey};
 
 #[wasm_bindgen]
 pub fn init_panic_hook() {
@@ -35,10 +37,12 @@ impl WasmProver {
     pub fn new(r1cs_bytes: &[u8], proving_key_bytes: &[u8]) -> Result<WasmProver, JsValue> {
         init_panic_hook();
 
-        let r1cs = ConstraintSystem::from_file(Cursor::new(r1cs_bytes))
+        let r1cs = ConstraintSystem::from_bytes(r1cs_bytes)
             .map_err(|e| JsValue::from_str(&format!("Failed to deserialize R1CS: {}", e)))?;
 
-        let proving_key = ProvingKey::<Bn254>::deserialize_compressed(proving_key_bytes)
+        let (_, key_bytes) = split_key_header(proving_key_bytes)
+            .map_err(|e| JsValue::from_str(&format!("Failed to read proving key header: {}", e)))?;
+        let proving_key = ProvingKey::<Bn254>::deserialize_compressed(key_bytes)
             .map_err(|e| JsValue::from_str(&format!("Failed to deserialize proving key: {}", e)))?;
 
         Ok(WasmProver { r1cs, proving_key })
@@ -54,8 +58,8 @@ impl WasmProver {
 
         let witness_values = parse_field_elements(&witness, "witness")?;
 
-        let num_public = self.r1cs.public_inputs.len();
-        let num_user_witnesses = self.r1cs.witnesses.len();
+        let num_public = self.r1cs.num_public_inputs();
+        let num_user_witnesses = self.r1cs.num_witnesses();
         let expected_total = num_public + num_user_witnesses;
 
         if witness_values.len() < num_public {
@@ -124,13 +128,20 @@ impl WasmProver {
             constraints: r1cs_clone.constraints,
         };
 
+        let public_input_count = circuit.public_inputs.len();
         let mut rng = thread_rng();
         let proof = Groth16::<Bn254>::prove(&self.proving_key, circuit, &mut rng)
             .map_err(|e| JsValue::from_str(&format!("Failed to generate proof: {}", e)))?;
 
+        let proof = Proof {
+            proof,
+            circuit_hash: Some(self.r1cs.circuit_hash()),
+            public_input_count: Some(public_input_count),
+            curve: Curve::Bn254,
+        };
         let mut proof_bytes = Vec::new();
         proof
-            .serialize_compressed(&mut proof_bytes)
+            .write(&mut proof_bytes)
             .map_err(|e| JsValue::from_str(&format!("Failed to serialize proof: {}", e)))?;
 
         #[cfg(target_arch = "wasm32")]
@@ -142,9 +153,10 @@ impl WasmProver {
     }
 }
 
+// same key and proof formats as `lofit verify`, so a proof checks identically in the browser
 #[wasm_bindgen]
 pub struct WasmVerifier {
-    verifying_key: VerifyingKey<Bn254>,
+    verifying_key: VerifierKey,
 }
 
 #[wasm_bindgen]
@@ -153,11 +165,9 @@ impl WasmVerifier {
     pub fn new(verifying_key_bytes: &[u8]) -> Result<WasmVerifier, JsValue> {
         init_panic_hook();
 
-        let verifying_key = VerifyingKey::<Bn254>::deserialize_uncompressed(verifying_key_bytes)
-            .or_else(|_| VerifyingKey::<Bn254>::deserialize_compressed(verifying_key_bytes))
-            .map_err(|e| {
-                JsValue::from_str(&format!("Failed to deserialize verifying key: {}", e))
-            })?;
+        let verifying_key = VerifierKey::read(verifying_key_bytes).map_err(|e| {
+            JsValue::from_str(&format!("Failed to deserialize verifying key: {}", e))
+        })?;
 
         Ok(WasmVerifier { verifying_key })
     }
@@ -169,16 +179,35 @@ impl WasmVerifier {
 
         let public_values = parse_field_elements(&public_inputs, "public input")?;
 
-        let proof = ark_groth16::Proof::<Bn254>::deserialize_compressed(proof_bytes)
+        let proof = Proof::read(proof_bytes)
             .map_err(|e| JsValue::from_str(&format!("Failed to deserialize proof: {}", e)))?;
 
-        let result = Groth16::<Bn254>::verify(&self.verifying_key, &public_values, &proof)
-            .map_err(|e| JsValue::from_str(&format!("Verification failed: {}", e)))?;
-
-        Ok(result)
+        self.verifying_key
+            .verify(&proof, &public_values)
+            .map_err(|e| JsValue::from_str(&e.to_string()))
     }
 }
 
+// orders the public signals of a witness calculator result the way `WasmVerifier::verify`
+// expects them, so the JS glue never has to know the r1cs signal order
+#[wasm_bindgen]
+pub fn public_inputs_from_witness(
+    r1cs_bytes: &[u8],
+    witness_map: JsValue,
+) -> Result<JsValue, JsValue> {
+    let r1cs = ConstraintSystem::from_bytes(r1cs_bytes)
+        .map_err(|e| JsValue::from_str(&format!("Failed to deserialize R1CS: {}", e)))?;
+
+    let raw: std::collections::HashMap<String, serde_json::Value> =
+        serde_wasm_bindgen::from_value(witness_map)
+            .map_err(|e| JsValue::from_str(&format!("Failed to parse witness map: {}", e)))?;
+    let witness = flatten_inputs(raw).map_err(|e| JsValue::from_str(&e.to_string()))?;
+
+    let ordered = ordered_public_inputs(&witness, &r1cs.public_inputs)
+        .map_err(|e| JsValue::from_str(&e.to_string()))?;
+    serde_wasm_bindgen::to_value(&ordered).map_err(|e| JsValue::from_str(&e.to_string()))
+}
+
 fn parse_field_elements(values: &[String], label: &str) -> Result<Vec<Fr>, JsValue> {
     values
         .iter()
@@ -1,7 +1,8 @@
 use crate::r1cs::{Constraint, ConstraintSystem};
 use ark_bn254::Fr;
 use ark_ff::{Field, PrimeField};
-use num_bigint::{BigInt, Sign};
+use indexmap::IndexMap;
+use num_bigint::{BigInt, BigUint, Sign};
 use std::collections::HashMap;
 
 fn coeff_to_fr(coeff: &BigInt) -> Fr {
@@ -56,6 +57,18 @@ pub fn generate_full_witness_with_provided(
     extract_witness_values(r1cs, pub_inputs, &values)
 }
 
+/// Like [`generate_full_witness_with_provided`], with every witness keyed by its signal name,
+/// compiler temporaries and decomposed bits included, in wire order.
+pub fn generate_named_witness(
+    r1cs: &ConstraintSystem,
+    pub_inputs: &[Fr],
+    provided_witnesses: &[Fr],
+) -> Result<IndexMap<String, Fr>, Box<dyn std::error::Error>> {
+    let witness = generate_full_witness_with_provided(r1cs, pub_inputs, provided_witnesses)?;
+    // one value per declared witness, taken by wire index, so the names line up by construction
+    Ok(r1cs.witnesses.iter().cloned().zip(witness).collect())
+}
+
 fn seed_value_table(pub_inputs: &[Fr], provided_witnesses: &[Fr]) -> HashMap<u32, Fr> {
     let mut values = HashMap::new();
     values.insert(0u32, Fr::from(1u64));
@@ -140,6 +153,10 @@ fn process_constraint(
         return true;
     }
 
+    if try_solve_quotient_remainder(constraint, values) {
+        return true;
+    }
+
     let a_known = can_evaluate_terms(&constraint.a.terms, values);
     let b_known = can_evaluate_terms(&constraint.b.terms, values);
     let c_known = can_evaluate_terms(&constraint.c.terms, values);
@@ -276,7 +293,7 @@ fn extract_witness_values(
     values: &HashMap<u32, Fr>,
 ) -> Result<Vec<Fr>, Box<dyn std::error::Error>> {
     let witness_start_idx = (pub_inputs.len() + 1) as u32;
-    let expected_witness_count = r1cs.witnesses.len();
+    let expected_witness_count = r1cs.num_witnesses();
     let mut witness = Vec::new();
 
     for i in 0..expected_witness_count {
@@ -297,13 +314,19 @@ fn extract_witness_values(
                 "Computed variables: {:?}",
                 values.keys().collect::<Vec<_>>()
             );
+            let name = r1cs
+                .witnesses
+                .get(i)
+                .map(|s| s.as_str())
+                .unwrap_or("unknown");
+            let hint = if i >= r1cs.declared_witnesses().len() {
+                "it is derived from its defining constraint, so an input it depends on is missing"
+            } else {
+                "supply it in the witness file"
+            };
             return Err(format!(
-                "Failed to compute witness variable '{}' (index {})",
-                r1cs.witnesses
-                    .get(i)
-                    .map(|s| s.as_str())
-                    .unwrap_or("unknown"),
-                var_idx
+                "Failed to compute witness variable '{}' (index {}): {}",
+                name, var_idx, hint
             )
             .into());
         }
@@ -467,3 +490,35 @@ fn try_solve_is_zero_inverse(constraint: &Constraint, values: &mut HashMap<u32,
 
     true
 }
+
+// `(n * q + r) * 1 = s`, the shape `%` lowers to: q and r are the integer quotient and
+// remainder of s by n, which is the only split the range checks on q and r accept
+fn try_solve_quotient_remainder(constraint: &Constraint, values: &mut HashMap<u32, Fr>) -> bool {
+    let one = BigInt::from(1);
+    if !matches!(constraint.b.terms.as_slice(), [(0, coeff)] if *coeff == one) {
+        return false;
+    }
+
+    let ((quotient, divisor), (remainder, _)) = match constraint.a.terms.as_slice() {
+        [q, r] if r.1 == one => (q, r),
+        [r, q] if r.1 == one => (q, r),
+        _ => return false,
+    };
+    if *divisor <= one
+        || values.contains_key(quotient)
+        || values.contains_key(remainder)
+        || !can_evaluate_terms(&constraint.c.terms, values)
+    {
+        return false;
+    }
+    let Some(divisor) = divisor.to_biguint() else {
+        return false;
+    };
+
+    let sum: BigUint = evaluate_terms(&constraint.c.terms, values)
+        .into_bigint()
+        .into();
+    values.insert(*quotient, Fr::from(&sum / &divisor));
+    values.insert(*remainder, Fr::from(sum % divisor));
+    true
+}
@@ -463,3 +463,26 @@ fn test_square_pattern() {
     assert!(result.is_ok());
     assert!(cs.is_satisfied().unwrap());
 }
+
+#[test]
+fn test_unsatisfied_constraints_reports_indices() {
+    // x * x = y, holds; x * ONE = y, fails
+    let circuit = LofCircuit {
+        public_inputs: vec![fr(3)],
+        witness: vec![fr(9)],
+        constraints: vec![
+            Constraint {
+                a: lc(vec![(1, 1)]),
+                b: lc(vec![(1, 1)]),
+                c: lc(vec![(2, 1)]),
+            },
+            Constraint {
+                a: lc(vec![(1, 1)]),
+                b: lc(vec![(0, 1)]),
+                c: lc(vec![(2, 1)]),
+            },
+        ],
+    };
+
+    assert_eq!(circuit.unsatisfied_constraints(), vec![1]);
+}
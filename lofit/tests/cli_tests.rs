@@ -0,0 +1,493 @@
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+// x * x = y with x public and y a witness
+fn write_square_r1cs(dir: &Path) -> std::path::PathBuf {
+    let mut bytes = Vec::new();
+    bytes.extend_from_slice(b"lof-r1cs");
+    bytes.extend_from_slice(&1u32.to_le_bytes());
+    bytes.extend_from_slice(&1u32.to_le_bytes());
+    bytes.extend_from_slice(&1u32.to_le_bytes());
+    bytes.extend_from_slice(&1u32.to_le_bytes());
+    for name in ["x", "y"] {
+        bytes.extend_from_slice(&(name.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(name.as_bytes());
+    }
+    for var in [1u32, 1, 2] {
+        bytes.extend_from_slice(&1u32.to_le_bytes());
+        bytes.extend_from_slice(&var.to_le_bytes());
+        bytes.extend_from_slice(&1u32.to_le_bytes());
+        bytes.push(1);
+    }
+
+    let path = dir.join("square.r1cs");
+    fs::write(&path, bytes).unwrap();
+    path
+}
+
+fn lofit(dir: &Path, args: &[&str]) -> std::process::Output {
+    Command::new(env!("CARGO_BIN_EXE_lofit"))
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .expect("run lofit binary")
+}
+
+fn lofit_witness(dir: &Path, witness_json: &str) -> (std::process::Output, std::path::PathBuf) {
+    let r1cs = write_square_r1cs(dir);
+    let public = dir.join("public.json");
+    let witness = dir.join("witness.json");
+    let output = dir.join("out").join("full_witness.json");
+    fs::write(&public, r#"{"x": "3"}"#).unwrap();
+    fs::write(&witness, witness_json).unwrap();
+
+    let result = Command::new(env!("CARGO_BIN_EXE_lofit"))
+        .args([
+            "witness",
+            "--input",
+            r1cs.to_str().unwrap(),
+            "--public-inputs",
+            public.to_str().unwrap(),
+            "--witness",
+            witness.to_str().unwrap(),
+            "--output",
+            output.to_str().unwrap(),
+        ])
+        .output()
+        .expect("run lofit binary");
+    (result, output)
+}
+
+#[test]
+fn test_witness_command_writes_assignment_and_reports_satisfied() {
+    let dir = tempfile::tempdir().unwrap();
+    let (result, output) = lofit_witness(dir.path(), r#"{"y": "9"}"#);
+
+    assert!(result.status.success(), "{:?}", result);
+    let stdout = String::from_utf8_lossy(&result.stdout);
+    assert!(
+        stdout.contains("All 1 constraints are satisfied"),
+        "{}",
+        stdout
+    );
+
+    let assignment: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(output).unwrap()).unwrap();
+    assert_eq!(assignment["y"], "9");
+}
+
+#[test]
+fn test_witness_command_hex_format_parses_back() {
+    use lofit::fr_from_str;
+
+    let dir = tempfile::tempdir().unwrap();
+    let r1cs = write_square_r1cs(dir.path());
+    // 2^100 squared, large enough that the decimal and hex forms differ a lot in length
+    let x = "1267650600228229401496703205376";
+    let y = "1606938044258990275541962092341162602522202993782792835301376";
+    fs::write(
+        dir.path().join("public.json"),
+        format!(r#"{{"x": "{}"}}"#, x),
+    )
+    .unwrap();
+    fs::write(
+        dir.path().join("witness.json"),
+        format!(r#"{{"y": "{}"}}"#, y),
+    )
+    .unwrap();
+
+    let witness_as = |format: &str| {
+        let output = dir.path().join(format!("{}.json", format));
+        let result = lofit(
+            dir.path(),
+            &[
+                "witness",
+                "--input",
+                r1cs.to_str().unwrap(),
+                "--public-inputs",
+                "public.json",
+                "--witness",
+                "witness.json",
+                "--output",
+                output.to_str().unwrap(),
+                "--witness-format",
+                format,
+            ],
+        );
+        assert!(result.status.success(), "{:?}", result);
+        let assignment: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(output).unwrap()).unwrap();
+        assignment["y"].as_str().unwrap().to_string()
+    };
+
+    let hex = witness_as("hex");
+    assert_eq!(hex, format!("0x{:0>64}", format!("1{}", "0".repeat(50))));
+    assert_eq!(witness_as("decimal"), y);
+    assert_eq!(fr_from_str(&hex).unwrap(), fr_from_str(y).unwrap());
+}
+
+#[test]
+fn test_witness_command_fails_on_unsatisfied_constraint() {
+    let dir = tempfile::tempdir().unwrap();
+    let (result, output) = lofit_witness(dir.path(), r#"{"y": "10"}"#);
+
+    assert!(!result.status.success());
+    let stdout = String::from_utf8_lossy(&result.stdout);
+    assert!(
+        stdout.contains("1 of 1 constraints are not satisfied"),
+        "{}",
+        stdout
+    );
+    // the assignment is still written so it can be inspected
+    assert!(output.exists());
+}
+
+#[test]
+fn test_merged_inputs_prove_like_separate_files() {
+    use lofit::inputs::{parse_inputs_in_order, InputsJson};
+    use lofit::{ConstraintSystem, Proof, VerifierKey};
+
+    let dir = tempfile::tempdir().unwrap();
+    let root = dir.path();
+    write_square_r1cs(root);
+    fs::write(root.join("public.json"), r#"{"x": "3"}"#).unwrap();
+    fs::write(root.join("witness.json"), r#"{"y": "9"}"#).unwrap();
+    fs::write(root.join("all.json"), r#"{"y": "9", "x": "3"}"#).unwrap();
+
+    let setup = lofit(root, &["setup", "--input", "square.r1cs"]);
+    assert!(setup.status.success(), "{:?}", setup);
+
+    let separate = lofit(
+        root,
+        &[
+            "prove",
+            "--input",
+            "square.r1cs",
+            "--public-inputs",
+            "public.json",
+            "--witness",
+            "witness.json",
+            "--output",
+            "separate/proof.bin",
+        ],
+    );
+    assert!(separate.status.success(), "{:?}", separate);
+
+    let merged = lofit(
+        root,
+        &[
+            "prove",
+            "--input",
+            "square.r1cs",
+            "--inputs",
+            "all.json",
+            "--output",
+            "merged/proof.bin",
+        ],
+    );
+    assert!(merged.status.success(), "{:?}", merged);
+
+    assert_eq!(
+        fs::read_to_string(root.join("separate/full_witness.json")).unwrap(),
+        fs::read_to_string(root.join("merged/full_witness.json")).unwrap()
+    );
+
+    let r1cs =
+        ConstraintSystem::from_file(fs::File::open(root.join("square.r1cs")).unwrap()).unwrap();
+    let merged_inputs: InputsJson =
+        serde_json::from_str(&fs::read_to_string(root.join("all.json")).unwrap()).unwrap();
+    let pub_values = parse_inputs_in_order(&merged_inputs, &r1cs.public_inputs).unwrap();
+    let vk = VerifierKey::read(&fs::read(root.join("keys/square_vk.bin")).unwrap()[..]).unwrap();
+    for proof in ["separate/proof.bin", "merged/proof.bin"] {
+        let proof = Proof::read(&fs::read(root.join(proof)).unwrap()[..]).unwrap();
+        assert!(vk.verify(&proof, &pub_values).unwrap());
+    }
+}
+
+#[test]
+fn test_verify_accepts_inline_proof_and_public_inputs() {
+    use lofit::Proof;
+    use std::io::Write;
+    use std::process::Stdio;
+
+    let dir = tempfile::tempdir().unwrap();
+    let root = dir.path();
+    write_square_r1cs(root);
+    fs::write(root.join("all.json"), r#"{"x": "3", "y": "9"}"#).unwrap();
+    assert!(lofit(root, &["setup", "--input", "square.r1cs"])
+        .status
+        .success());
+    let prove = lofit(
+        root,
+        &[
+            "prove",
+            "--input",
+            "square.r1cs",
+            "--inputs",
+            "all.json",
+            "--output",
+            "proof.bin",
+        ],
+    );
+    assert!(prove.status.success(), "{:?}", prove);
+    let proof_json = Proof::read(&fs::read(root.join("proof.bin")).unwrap()[..])
+        .unwrap()
+        .to_json();
+
+    let verify = |public_inputs: &str| {
+        lofit(
+            root,
+            &[
+                "verify",
+                "--input",
+                "square.r1cs",
+                "--proof-json",
+                &proof_json,
+                "--public-inputs-json",
+                public_inputs,
+            ],
+        )
+    };
+    let accepted = verify(r#"{"x": "3"}"#);
+    assert!(accepted.status.success(), "{:?}", accepted);
+    assert!(!verify(r#"{"x": "4"}"#).status.success());
+
+    let mut piped = Command::new(env!("CARGO_BIN_EXE_lofit"))
+        .args([
+            "verify",
+            "--input",
+            "square.r1cs",
+            "--proof-json",
+            "-",
+            "--public-inputs-json",
+            r#"{"x": "3"}"#,
+        ])
+        .current_dir(root)
+        .stdin(Stdio::piped())
+        .spawn()
+        .expect("run lofit binary");
+    piped
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(proof_json.as_bytes())
+        .unwrap();
+    assert!(piped.wait().unwrap().success());
+}
+
+#[test]
+fn test_prove_with_supplied_full_witness() {
+    use lofit::inputs::{parse_inputs_in_order, InputsJson};
+    use lofit::{ConstraintSystem, Proof, VerifierKey};
+
+    let dir = tempfile::tempdir().unwrap();
+    let root = dir.path();
+    write_square_r1cs(root);
+    fs::write(root.join("public.json"), r#"{"x": "3"}"#).unwrap();
+    fs::write(root.join("valid.json"), r#"{"y": "9"}"#).unwrap();
+    fs::write(root.join("invalid.json"), r#"{"y": "10"}"#).unwrap();
+    assert!(lofit(root, &["setup", "--input", "square.r1cs"])
+        .status
+        .success());
+
+    let prove = |full_witness: &str| {
+        lofit(
+            root,
+            &[
+                "prove",
+                "--input",
+                "square.r1cs",
+                "--public-inputs",
+                "public.json",
+                "--full-witness",
+                full_witness,
+                "--output",
+                "proof.bin",
+            ],
+        )
+    };
+
+    let valid = prove("valid.json");
+    assert!(valid.status.success(), "{:?}", valid);
+    let r1cs =
+        ConstraintSystem::from_file(fs::File::open(root.join("square.r1cs")).unwrap()).unwrap();
+    let public: InputsJson =
+        serde_json::from_str(&fs::read_to_string(root.join("public.json")).unwrap()).unwrap();
+    let pub_values = parse_inputs_in_order(&public, &r1cs.public_inputs).unwrap();
+    let vk = VerifierKey::read(&fs::read(root.join("keys/square_vk.bin")).unwrap()[..]).unwrap();
+    let proof = Proof::read(&fs::read(root.join("proof.bin")).unwrap()[..]).unwrap();
+    assert!(vk.verify(&proof, &pub_values).unwrap());
+
+    fs::remove_file(root.join("proof.bin")).unwrap();
+    let invalid = prove("invalid.json");
+    assert!(!invalid.status.success());
+    let stderr = String::from_utf8_lossy(&invalid.stderr);
+    assert!(stderr.contains("not satisfied"), "{}", stderr);
+    assert!(!root.join("proof.bin").exists());
+}
+
+#[test]
+fn test_merged_inputs_conflict_with_separate_files() {
+    let dir = tempfile::tempdir().unwrap();
+    write_square_r1cs(dir.path());
+
+    let output = lofit(
+        dir.path(),
+        &[
+            "witness",
+            "--input",
+            "square.r1cs",
+            "--inputs",
+            "all.json",
+            "--public-inputs",
+            "public.json",
+        ],
+    );
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("cannot be used with"));
+}
+
+#[test]
+fn test_progress_flag_prints_phase_durations() {
+    let dir = tempfile::tempdir().unwrap();
+    write_square_r1cs(dir.path());
+
+    let output = lofit(
+        dir.path(),
+        &["setup", "--input", "square.r1cs", "--progress"],
+    );
+    assert!(output.status.success(), "{:?}", output);
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains("Circuit: 1 constraints, 1 public inputs, 1 witnesses"),
+        "{}",
+        stdout
+    );
+    assert!(stdout.contains("Key generation took"), "{}", stdout);
+}
+
+#[test]
+fn test_setup_reuses_keys_until_circuit_changes() {
+    let dir = tempfile::tempdir().unwrap();
+    let r1cs = write_square_r1cs(dir.path());
+    let pk_path = dir.path().join("keys/square_pk.bin");
+
+    let first = lofit(dir.path(), &["setup", "--input", "square.r1cs"]);
+    assert!(first.status.success(), "{:?}", first);
+    let first_key = fs::read(&pk_path).unwrap();
+
+    let second = lofit(dir.path(), &["setup", "--input", "square.r1cs"]);
+    assert!(second.status.success(), "{:?}", second);
+    assert!(String::from_utf8_lossy(&second.stdout).contains("already match"));
+    assert_eq!(fs::read(&pk_path).unwrap(), first_key);
+
+    let forced = lofit(dir.path(), &["setup", "--input", "square.r1cs", "--force"]);
+    assert!(forced.status.success(), "{:?}", forced);
+    assert!(!String::from_utf8_lossy(&forced.stdout).contains("already match"));
+    assert_ne!(fs::read(&pk_path).unwrap(), first_key);
+
+    // x * x = 2y: only the coefficient of the last term changes
+    let mut bytes = fs::read(&r1cs).unwrap();
+    *bytes.last_mut().unwrap() = 2;
+    fs::write(&r1cs, bytes).unwrap();
+
+    let changed = lofit(dir.path(), &["setup", "--input", "square.r1cs"]);
+    assert!(changed.status.success(), "{:?}", changed);
+    assert!(!String::from_utf8_lossy(&changed.stdout).contains("already match"));
+
+    let r1cs = lofit::ConstraintSystem::from_file(fs::File::open(&r1cs).unwrap()).unwrap();
+    let stored = lofit::ProverKey::read_circuit_hash(fs::File::open(&pk_path).unwrap()).unwrap();
+    assert_eq!(stored, Some(r1cs.circuit_hash()));
+}
+
+#[test]
+fn test_web_package_manifest_names_the_circuit() {
+    let dir = tempfile::tempdir().unwrap();
+    lofit::package_web::write_package_manifest(dir.path(), "square").unwrap();
+
+    let manifest: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(dir.path().join("package.json")).unwrap())
+            .unwrap();
+    assert_eq!(manifest["name"], "square");
+    assert!(manifest["scripts"]["start"].is_string());
+
+    let gitignore = fs::read_to_string(dir.path().join(".gitignore")).unwrap();
+    assert!(gitignore.lines().any(|line| line == "node_modules/"));
+}
+
+#[test]
+fn test_web_package_demo_page_lists_public_inputs() {
+    let dir = tempfile::tempdir().unwrap();
+    let build = dir.path().join("build");
+    fs::create_dir(&build).unwrap();
+    write_square_r1cs(&build);
+
+    lofit::package_web::generate_integration_code(dir.path(), "square").unwrap();
+
+    let html = fs::read_to_string(dir.path().join("index.html")).unwrap();
+    assert!(html.contains("./integration.js"));
+    assert!(html.contains("name=\"x\""));
+}
+
+#[test]
+fn test_web_package_records_curve_and_modulus() {
+    const BN254_MODULUS: &str =
+        "21888242871839275222246405745257275088548364400416034343698204186575808495617";
+    let dir = tempfile::tempdir().unwrap();
+    let build = dir.path().join("build");
+    fs::create_dir(&build).unwrap();
+    write_square_r1cs(&build);
+
+    lofit::package_web::generate_integration_code(dir.path(), "square").unwrap();
+    lofit::package_web::write_package_manifest(dir.path(), "square").unwrap();
+
+    let js = fs::read_to_string(dir.path().join("integration.js")).unwrap();
+    assert!(js.contains("export const CURVE = 'bn254';"));
+    assert!(js.contains(&format!("export const FIELD_MODULUS = {}n;", BN254_MODULUS)));
+
+    let manifest: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(dir.path().join("package.json")).unwrap())
+            .unwrap();
+    assert_eq!(manifest["lof"]["curve"], "bn254");
+    assert_eq!(manifest["lof"]["fieldModulus"], BN254_MODULUS);
+}
+
+#[test]
+fn test_setup_all_generates_keys_for_every_circuit() {
+    let dir = tempfile::tempdir().unwrap();
+    let root = dir.path();
+    let circuits = root.join("circuits");
+    fs::create_dir_all(&circuits).unwrap();
+    let square = write_square_r1cs(root);
+    fs::copy(&square, circuits.join("square.r1cs")).unwrap();
+    fs::copy(&square, circuits.join("other.r1cs")).unwrap();
+    fs::write(circuits.join("broken.r1cs"), b"not an r1cs file").unwrap();
+
+    let output = lofit(root, &["setup-all", "circuits"]);
+    // the broken circuit fails the command but not the others
+    assert!(!output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("broken.r1cs: setup failed"), "{}", stdout);
+    assert!(stdout.contains("square.r1cs: keys ready"), "{}", stdout);
+
+    let mut keys: Vec<_> = fs::read_dir(root.join("keys"))
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+        .collect();
+    keys.sort();
+    assert_eq!(
+        keys,
+        vec![
+            "other_pk.bin",
+            "other_vk.bin",
+            "square_pk.bin",
+            "square_vk.bin"
+        ]
+    );
+
+    fs::remove_file(circuits.join("broken.r1cs")).unwrap();
+    let output = lofit(root, &["setup-all", "circuits"]);
+    assert!(output.status.success(), "{:?}", output);
+}
@@ -1,5 +1,6 @@
 use ark_bn254::Fr;
 use lofit::r1cs::{Constraint, LinearCombination};
+use lofit::verification::VerificationError;
 use lofit::{generate_full_witness, ConstraintSystem, LofCircuit, ProverKey, VerifierKey};
 use num_bigint::BigInt;
 use std::io::Cursor;
@@ -23,9 +24,11 @@ fn create_test_r1cs(
     constraints: Vec<Constraint>,
 ) -> ConstraintSystem {
     ConstraintSystem {
+        name: None,
         public_inputs,
         witnesses,
         constraints,
+        declared_witness_count: None,
     }
 }
 
@@ -522,3 +525,336 @@ fn test_multiple_independent_proofs_same_keys() {
 
     assert!(!vk.verify(&proof1, &pub_inputs2).unwrap());
 }
+
+#[test]
+fn test_public_inputs_follow_circuit_order_not_json_order() {
+    use indexmap::IndexMap;
+    use lofit::inputs::{parse_inputs_in_order, InputsJson};
+
+    // a * ONE = b + c
+    let r1cs = create_test_r1cs(
+        vec!["a".to_string(), "b".to_string()],
+        vec!["c".to_string()],
+        vec![Constraint {
+            a: lc(vec![(1, 1)]),
+            b: lc(vec![(0, 1)]),
+            c: lc(vec![(2, 1), (3, 1)]),
+        }],
+    );
+
+    let json = r#"{"b": "3", "a": "10"}"#;
+    let inputs: InputsJson = serde_json::from_str(json).unwrap();
+    let pub_inputs = parse_inputs_in_order(&inputs, &r1cs.public_inputs).unwrap();
+    assert_eq!(pub_inputs, vec![fr(10), fr(3)]);
+
+    let (pk, vk) = ProverKey::setup(LofCircuit {
+        public_inputs: vec![fr(0); pub_inputs.len()],
+        witness: vec![fr(0); 1],
+        constraints: r1cs.constraints.clone(),
+    })
+    .unwrap();
+
+    let witness = generate_full_witness(&r1cs, &pub_inputs).unwrap();
+    let proof = pk
+        .prove(LofCircuit {
+            public_inputs: pub_inputs.clone(),
+            witness,
+            constraints: r1cs.constraints.clone(),
+        })
+        .unwrap();
+
+    assert!(vk.verify(&proof, &pub_inputs).unwrap());
+
+    // taking the values in file order pairs them with the wrong wires
+    let file_order: IndexMap<String, String> = serde_json::from_str(json).unwrap();
+    let file_order_values: Vec<Fr> = file_order
+        .values()
+        .map(|s| lofit::fr_from_str(s).unwrap())
+        .collect();
+    assert!(!vk.verify(&proof, &file_order_values).unwrap());
+}
+
+#[derive(Clone, Default)]
+struct CapturedLogs(std::sync::Arc<std::sync::Mutex<Vec<u8>>>);
+
+impl std::io::Write for CapturedLogs {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+impl<'a> tracing_subscriber::fmt::MakeWriter<'a> for CapturedLogs {
+    type Writer = Self;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        self.clone()
+    }
+}
+
+#[test]
+fn test_setup_and_prove_log_phase_timings() {
+    let r1cs = create_test_r1cs(
+        vec!["a".to_string(), "b".to_string()],
+        vec!["c".to_string()],
+        vec![Constraint {
+            a: lc(vec![(1, 1)]),
+            b: lc(vec![(2, 1)]),
+            c: lc(vec![(3, 1)]),
+        }],
+    );
+    let pub_inputs = vec![fr(5), fr(7)];
+    let witness = generate_full_witness(&r1cs, &pub_inputs).unwrap();
+
+    let logs = CapturedLogs::default();
+    let subscriber = tracing_subscriber::fmt()
+        .with_writer(logs.clone())
+        .with_ansi(false)
+        .finish();
+    tracing::subscriber::with_default(subscriber, || {
+        let (pk, _vk) = ProverKey::setup(LofCircuit {
+            public_inputs: vec![fr(0); pub_inputs.len()],
+            witness: vec![fr(0); 1],
+            constraints: r1cs.constraints.clone(),
+        })
+        .unwrap();
+        pk.prove(LofCircuit {
+            public_inputs: pub_inputs.clone(),
+            witness,
+            constraints: r1cs.constraints.clone(),
+        })
+        .unwrap();
+    });
+
+    let output = String::from_utf8(logs.0.lock().unwrap().clone()).unwrap();
+    for phase in ["setup", "prove"] {
+        let line = output
+            .lines()
+            .find(|line| line.contains(&format!("phase=\"{}\"", phase)))
+            .unwrap_or_else(|| panic!("no timing logged for {}:\n{}", phase, output));
+        assert!(line.contains("constraints=1"), "{}", line);
+        assert!(line.contains("elapsed_ms="), "{}", line);
+    }
+}
+
+#[test]
+fn test_proving_key_header_round_trips_circuit_hash() {
+    let r1cs = create_test_r1cs(
+        vec!["a".to_string()],
+        vec!["b".to_string()],
+        vec![Constraint {
+            a: lc(vec![(1, 1)]),
+            b: lc(vec![(1, 1)]),
+            c: lc(vec![(2, 1)]),
+        }],
+    );
+    let hash = r1cs.circuit_hash();
+    assert_eq!(hash, r1cs.clone().circuit_hash());
+
+    let (pk, _vk) = ProverKey::setup(LofCircuit {
+        public_inputs: vec![fr(0)],
+        witness: vec![fr(0)],
+        constraints: r1cs.constraints.clone(),
+    })
+    .unwrap();
+    assert_eq!(pk.circuit_hash(), None);
+
+    // keys without a recorded hash keep the plain arkworks encoding
+    let mut legacy = Vec::new();
+    pk.write(&mut legacy).unwrap();
+    assert_eq!(
+        ProverKey::read_circuit_hash(Cursor::new(&legacy)).unwrap(),
+        None
+    );
+    assert_eq!(
+        ProverKey::read(Cursor::new(&legacy))
+            .unwrap()
+            .circuit_hash(),
+        None
+    );
+
+    let pk = pk.with_circuit_hash(hash);
+    let mut bytes = Vec::new();
+    pk.write(&mut bytes).unwrap();
+    assert_eq!(bytes.len(), legacy.len() + 20);
+    assert_eq!(
+        ProverKey::read_circuit_hash(Cursor::new(&bytes)).unwrap(),
+        Some(hash)
+    );
+    assert_eq!(
+        ProverKey::read(Cursor::new(&bytes)).unwrap().circuit_hash(),
+        Some(hash)
+    );
+
+    let mut changed = r1cs.clone();
+    changed.constraints[0].c = lc(vec![(2, 2)]);
+    assert_ne!(changed.circuit_hash(), hash);
+}
+
+#[test]
+fn test_proof_header_rejects_wrong_public_input_count() {
+    let r1cs = create_test_r1cs(
+        vec!["a".to_string()],
+        vec!["b".to_string()],
+        vec![Constraint {
+            a: lc(vec![(1, 1)]),
+            b: lc(vec![(1, 1)]),
+            c: lc(vec![(2, 1)]),
+        }],
+    );
+    let hash = r1cs.circuit_hash();
+    let (pk, vk) = ProverKey::setup(LofCircuit {
+        public_inputs: vec![fr(0)],
+        witness: vec![fr(0)],
+        constraints: r1cs.constraints.clone(),
+    })
+    .unwrap();
+    let pk = pk.with_circuit_hash(hash);
+
+    let proof = pk
+        .prove(LofCircuit {
+            public_inputs: vec![fr(3)],
+            witness: vec![fr(9)],
+            constraints: r1cs.constraints.clone(),
+        })
+        .unwrap();
+    let mut bytes = Vec::new();
+    proof.write(&mut bytes).unwrap();
+    let proof = lofit::Proof::read(Cursor::new(&bytes)).unwrap();
+    assert_eq!(proof.circuit_hash(), Some(hash));
+    assert_eq!(proof.public_input_count(), Some(1));
+
+    assert!(vk.verify(&proof, &[fr(3)]).unwrap());
+    for inputs in [vec![], vec![fr(3), fr(3)]] {
+        match vk.verify(&proof, &inputs) {
+            Err(VerificationError::InputCountMismatch { expected, found }) => {
+                assert_eq!(expected, 1);
+                assert_eq!(found, inputs.len());
+            }
+            other => panic!("expected an input count mismatch, got {:?}", other),
+        }
+    }
+    let message = vk.verify(&proof, &[]).unwrap_err().to_string();
+    assert!(message.contains("expects 1 public inputs, but 0 were given"));
+}
+
+#[test]
+fn test_verify_rejects_proof_and_key_for_different_curves() {
+    let r1cs = create_test_r1cs(
+        vec!["a".to_string()],
+        vec!["b".to_string()],
+        vec![Constraint {
+            a: lc(vec![(1, 1)]),
+            b: lc(vec![(1, 1)]),
+            c: lc(vec![(2, 1)]),
+        }],
+    );
+    let (pk, vk) = ProverKey::setup(LofCircuit {
+        public_inputs: vec![fr(0)],
+        witness: vec![fr(0)],
+        constraints: r1cs.constraints.clone(),
+    })
+    .unwrap();
+    let proof = pk
+        .prove(LofCircuit {
+            public_inputs: vec![fr(3)],
+            witness: vec![fr(9)],
+            constraints: r1cs.constraints.clone(),
+        })
+        .unwrap();
+    assert_eq!(proof.curve(), lofit::Curve::Bn254);
+
+    // retag the key as BLS12-381: magic, version, then the curve tag
+    let mut vk_bytes = Vec::new();
+    vk.write(&mut vk_bytes).unwrap();
+    assert_eq!(&vk_bytes[..8], b"lof-vkey");
+    vk_bytes[12..16].copy_from_slice(&2u32.to_le_bytes());
+    let bls_vk = VerifierKey::read(Cursor::new(&vk_bytes)).unwrap();
+    assert_eq!(bls_vk.curve(), lofit::Curve::Bls12_381);
+
+    match bls_vk.verify(&proof, &[fr(3)]) {
+        Err(VerificationError::CurveMismatch { proof, key }) => {
+            assert_eq!(proof, lofit::Curve::Bn254);
+            assert_eq!(key, lofit::Curve::Bls12_381);
+        }
+        other => panic!("expected a curve mismatch, got {:?}", other),
+    }
+    let message = bls_vk.verify(&proof, &[fr(3)]).unwrap_err().to_string();
+    assert!(message.contains("Proof is over bn254, but the verification key is for bls12-381"));
+
+    // the same key bytes with the BN254 tag still verify
+    vk_bytes[12..16].copy_from_slice(&1u32.to_le_bytes());
+    let vk = VerifierKey::read(Cursor::new(&vk_bytes)).unwrap();
+    assert!(vk.verify(&proof, &[fr(3)]).unwrap());
+
+    vk_bytes[12..16].copy_from_slice(&9u32.to_le_bytes());
+    assert!(VerifierKey::read(Cursor::new(&vk_bytes)).is_err());
+}
+
+#[test]
+fn test_proof_fails_against_another_circuits_key() {
+    let setup = |r1cs: &ConstraintSystem| {
+        let (pk, vk) = ProverKey::setup(LofCircuit {
+            public_inputs: vec![fr(0)],
+            witness: vec![fr(0)],
+            constraints: r1cs.constraints.clone(),
+        })
+        .unwrap();
+        let hash = r1cs.circuit_hash();
+        (pk.with_circuit_hash(hash), vk.with_circuit_hash(hash))
+    };
+    // x * x = y and x * (x + 1) = y take the same inputs, so only the hash tells them apart
+    let square = create_test_r1cs(
+        vec!["x".to_string()],
+        vec!["y".to_string()],
+        vec![Constraint {
+            a: lc(vec![(1, 1)]),
+            b: lc(vec![(1, 1)]),
+            c: lc(vec![(2, 1)]),
+        }],
+    );
+    let pronic = create_test_r1cs(
+        vec!["x".to_string()],
+        vec!["y".to_string()],
+        vec![Constraint {
+            a: lc(vec![(1, 1)]),
+            b: lc(vec![(1, 1), (0, 1)]),
+            c: lc(vec![(2, 1)]),
+        }],
+    );
+    let (square_pk, square_vk) = setup(&square);
+    let (_, pronic_vk) = setup(&pronic);
+
+    let proof = square_pk
+        .prove(LofCircuit {
+            public_inputs: vec![fr(3)],
+            witness: vec![fr(9)],
+            constraints: square.constraints.clone(),
+        })
+        .unwrap();
+    assert!(square_vk.verify(&proof, &[fr(3)]).unwrap());
+
+    // the hash survives writing the key out
+    let mut vk_bytes = Vec::new();
+    pronic_vk.write(&mut vk_bytes).unwrap();
+    let pronic_vk = VerifierKey::read(Cursor::new(&vk_bytes)).unwrap();
+    assert_eq!(pronic_vk.circuit_hash(), Some(pronic.circuit_hash()));
+
+    match pronic_vk.verify(&proof, &[fr(3)]) {
+        Err(VerificationError::CircuitMismatch { proof, key }) => {
+            assert_eq!(proof, square.circuit_hash());
+            assert_eq!(key, pronic.circuit_hash());
+        }
+        other => panic!("expected a circuit mismatch, got {:?}", other),
+    }
+    let message = pronic_vk.verify(&proof, &[fr(3)]).unwrap_err().to_string();
+    assert!(
+        message.contains("but the verification key is for circuit"),
+        "{}",
+        message
+    );
+}
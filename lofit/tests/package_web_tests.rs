@@ -0,0 +1,72 @@
+use lof::ir_generator::IRGenerator;
+use lof::lexer::Lexer;
+use lof::parser::Parser;
+use lofit::package_web::package_for_web_with_wasm_pack;
+use lofit::validate_for_web;
+use std::path::{Path, PathBuf};
+
+// the .r1cs and .ir `lof compile` leaves for `source`, without the .lof itself; packaging runs
+// lof-witness-gen, found on PATH or in the target directory the workspace build leaves it in
+fn write_circuit(dir: &Path, name: &str, source: &str) -> PathBuf {
+    lof::compile_dsl_to_r1cs(source)
+        .unwrap()
+        .write_r1cs_file(&dir.join(format!("{}.lof", name)))
+        .unwrap();
+    let ast = Parser::new(Lexer::new(source)).parse_program().unwrap();
+    IRGenerator::new()
+        .convert_proof(&ast[0])
+        .unwrap()
+        .write_to_file(&dir.join(format!("{}.ir", name)))
+        .unwrap();
+    dir.join(format!("{}.r1cs", name))
+}
+
+#[test]
+fn test_package_web_falls_back_to_sources_without_wasm_pack() {
+    let source =
+        "proof Square {\n    input x: Field;\n    witness y: Field;\n    assert y === x * x;\n}\n";
+    let dir = tempfile::tempdir().unwrap();
+    let r1cs_path = write_circuit(dir.path(), "square", source);
+
+    // not installed, and found but gone by the time it runs
+    let vanished = dir.path().join("wasm-pack");
+    for (wasm_pack, package) in [
+        (None, "none_web"),
+        (Some(vanished.as_path()), "vanished_web"),
+    ] {
+        let package_dir = dir.path().join(package);
+        package_for_web_with_wasm_pack(&r1cs_path, Some(&package_dir), false, wasm_pack).unwrap();
+
+        assert!(package_dir
+            .join("witness_sources")
+            .join("square_witness_wasm")
+            .exists());
+        assert!(package_dir.join("witness").join("README.txt").exists());
+        assert!(package_dir.join("prover").join("README.txt").exists());
+        assert!(!package_dir.join("prover").join("lofit_bg.wasm").exists());
+        assert!(package_dir.join("keys").join("square_pk.bin").exists());
+    }
+}
+
+#[test]
+fn test_validate_reports_ready_or_the_missing_ir() {
+    let source = "proof Cube {\n    input x: Field;\n    witness y: Field;\n    assert y === x * x * x;\n}\n";
+    let dir = tempfile::tempdir().unwrap();
+    // no .lof is written, so nothing can stand in for the IR once it is removed
+    let r1cs_path = write_circuit(dir.path(), "cube", source);
+    let ir_path = dir.path().join("cube.ir");
+
+    validate_for_web(&r1cs_path).unwrap();
+    let entries = std::fs::read_dir(dir.path()).unwrap().count();
+    assert_eq!(entries, 2, "validation must not write anything");
+
+    std::fs::remove_file(&ir_path).unwrap();
+    let err = validate_for_web(&r1cs_path).unwrap_err().to_string();
+    assert!(err.contains("IR file not found"), "{}", err);
+    assert!(err.contains("cube.ir"), "{}", err);
+
+    let err = validate_for_web(&dir.path().join("missing.r1cs"))
+        .unwrap_err()
+        .to_string();
+    assert!(err.contains("not readable"), "{}", err);
+}
@@ -135,6 +135,25 @@ fn test_r1cs_with_multiple_constraints() {
     assert_eq!(r1cs.constraints[1].c.terms[0].1, BigInt::from(1));
 }
 
+#[test]
+fn test_size_accessors_match_loaded_circuit() {
+    let bytes = create_r1cs_bytes(
+        &["x", "y"],
+        &["temp", "result", "unused"],
+        &[
+            (vec![(1, 1), (2, 1)], vec![(0, 1)], vec![(3, 1)]),
+            (vec![(3, 1)], vec![(1, 1)], vec![(4, 1)]),
+        ],
+    );
+    let r1cs = ConstraintSystem::from_file(Cursor::new(bytes)).unwrap();
+
+    assert_eq!(r1cs.num_constraints(), r1cs.constraints.len());
+    assert_eq!(r1cs.num_public_inputs(), r1cs.public_inputs.len());
+    assert_eq!(r1cs.num_witnesses(), r1cs.witnesses.len());
+    // ONE, x, y, temp, result, unused
+    assert_eq!(r1cs.num_variables(), 6);
+}
+
 #[test]
 fn test_r1cs_with_negative_coefficients() {
     // a - b = c: (a - b) * 1 = c
@@ -172,6 +191,28 @@ fn test_r1cs_with_larger_coefficients() {
     assert_eq!(r1cs.constraints[0].b.terms[0].1, BigInt::from(3));
 }
 
+#[test]
+fn test_from_bytes_matches_from_file() {
+    let bytes = create_r1cs_bytes(
+        &["a"],
+        &["b", "c"],
+        &[
+            (vec![(1, 1)], vec![(2, 1)], vec![(3, 1)]),
+            (vec![(3, 2), (0, -1)], vec![(0, 1)], vec![(2, 1)]),
+        ],
+    );
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("circuit.r1cs");
+    std::fs::write(&path, &bytes).unwrap();
+
+    let from_bytes = ConstraintSystem::from_bytes(&bytes).unwrap();
+    let from_file = ConstraintSystem::from_file(std::fs::File::open(&path).unwrap()).unwrap();
+    assert_eq!(format!("{:?}", from_bytes), format!("{:?}", from_file));
+    assert_eq!(from_bytes.circuit_hash(), from_file.circuit_hash());
+
+    assert!(ConstraintSystem::from_bytes(&bytes[..bytes.len() - 1]).is_err());
+}
+
 // ============================================================================
 // VARIABLE NAME TESTS
 // ============================================================================
@@ -243,6 +284,121 @@ fn test_unsupported_version() {
     assert!(result.is_err());
 }
 
+// scalar field of BLS12-381, standing in for a prover over another curve; the derive checks
+// an `asm` feature this crate does not declare
+#[allow(unexpected_cfgs)]
+mod bls {
+    #[derive(ark_ff::MontConfig)]
+    #[modulus = "52435875175126190479447740508185965837690552500527637822603658699938581184513"]
+    #[generator = "7"]
+    pub struct FrConfig;
+}
+type BlsFr = ark_ff::Fp256<ark_ff::MontBackend<bls::FrConfig, 4>>;
+
+fn with_v2_header(modulus: &BigInt, name: &str, v1_bytes: &[u8]) -> Vec<u8> {
+    let mut bytes = b"lof-r1cs".to_vec();
+    bytes.extend_from_slice(&2u32.to_le_bytes());
+    let modulus = modulus.to_bytes_le().1;
+    bytes.extend_from_slice(&(modulus.len() as u32).to_le_bytes());
+    bytes.extend_from_slice(&modulus);
+    bytes.extend_from_slice(&(name.len() as u32).to_le_bytes());
+    bytes.extend_from_slice(name.as_bytes());
+    bytes.extend_from_slice(&v1_bytes[12..]);
+    bytes
+}
+
+#[test]
+fn test_header_records_field_and_name() {
+    let body = create_r1cs_bytes(
+        &["x"],
+        &["y"],
+        &[(vec![(1, 1)], vec![(1, 1)], vec![(2, 1)])],
+    );
+    let bn254: BigInt =
+        "21888242871839275222246405745257275088548364400416034343698204186575808495617"
+            .parse()
+            .unwrap();
+    let bytes = with_v2_header(&bn254, "Square", &body);
+
+    let r1cs = ConstraintSystem::from_bytes(&bytes).unwrap();
+    assert_eq!(r1cs.name.as_deref(), Some("Square"));
+    assert_eq!(r1cs.public_inputs, vec!["x"]);
+    assert_eq!(r1cs.constraints.len(), 1);
+
+    // version 1 files carry no modulus and are taken to be BN254
+    assert!(ConstraintSystem::from_bytes(&body).unwrap().name.is_none());
+
+    let err = ConstraintSystem::from_bytes_for_field::<BlsFr>(&bytes).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+    let message = err.to_string();
+    assert!(message.contains("targets the field BN254"), "{}", message);
+    assert!(
+        message.contains(
+            "5243587517512619047944774050818596583769055250052763782260365869993858118451"
+        ),
+        "{}",
+        message
+    );
+    assert!(ConstraintSystem::from_bytes_for_field::<BlsFr>(&body).is_err());
+
+    let bls: BigInt =
+        "52435875175126190479447740508185965837690552500527637822603658699938581184513"
+            .parse()
+            .unwrap();
+    let err = ConstraintSystem::from_bytes(&with_v2_header(&bls, "", &body)).unwrap_err();
+    assert!(
+        err.to_string().contains("this prover works over BN254"),
+        "{}",
+        err
+    );
+}
+
+fn with_v3_header(declared_witnesses: u32, v1_bytes: &[u8]) -> Vec<u8> {
+    let bn254: BigInt =
+        "21888242871839275222246405745257275088548364400416034343698204186575808495617"
+            .parse()
+            .unwrap();
+    let mut bytes = with_v2_header(&bn254, "", &v1_bytes[..12]);
+    bytes[8..12].copy_from_slice(&3u32.to_le_bytes());
+    bytes.extend_from_slice(&v1_bytes[12..24]);
+    bytes.extend_from_slice(&declared_witnesses.to_le_bytes());
+    bytes.extend_from_slice(&v1_bytes[24..]);
+    bytes
+}
+
+#[test]
+fn test_declared_witness_named_like_a_temporary_is_read() {
+    use ark_bn254::Fr;
+    use lofit::inputs::{parse_partial_witness, InputsJson};
+
+    // t_9 * t_9 = x, where `t_9` is the user's own witness and not a compiler temporary
+    let body = create_r1cs_bytes(
+        &["x"],
+        &["t_9"],
+        &[(vec![(2, 1)], vec![(2, 1)], vec![(1, 1)])],
+    );
+    let r1cs = ConstraintSystem::from_bytes(&with_v3_header(1, &body)).unwrap();
+    assert_eq!(r1cs.declared_witness_count, Some(1));
+    assert_eq!(r1cs.declared_witnesses(), ["t_9"]);
+
+    let mut json = InputsJson::new();
+    json.insert("t_9".to_string(), "3".to_string());
+    let provided = parse_partial_witness(&json, &r1cs);
+    assert_eq!(provided, vec![Fr::from(3u64)]);
+
+    let witness =
+        lofit::generate_full_witness_with_provided(&r1cs, &[Fr::from(9u64)], &provided).unwrap();
+    assert_eq!(witness, vec![Fr::from(3u64)]);
+
+    // without the count, older files fall back to the name and take `t_9` for a temporary
+    let legacy = ConstraintSystem::from_bytes(&body).unwrap();
+    assert_eq!(legacy.declared_witness_count, None);
+    assert!(legacy.declared_witnesses().is_empty());
+
+    let err = ConstraintSystem::from_bytes(&with_v3_header(2, &body)).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+}
+
 #[test]
 fn test_truncated_file() {
     let mut bytes = Vec::new();
@@ -256,6 +412,29 @@ fn test_truncated_file() {
     assert!(result.is_err());
 }
 
+#[test]
+fn test_oversized_length_prefix_is_rejected() {
+    // a name claiming 4 GiB in a file a few bytes long
+    let mut bytes = Vec::new();
+    bytes.extend_from_slice(b"lof-r1cs");
+    bytes.extend_from_slice(&1u32.to_le_bytes());
+    bytes.extend_from_slice(&1u32.to_le_bytes()); // pub inputs
+    bytes.extend_from_slice(&0u32.to_le_bytes()); // witnesses
+    bytes.extend_from_slice(&0u32.to_le_bytes()); // constraints
+    bytes.extend_from_slice(&u32::MAX.to_le_bytes());
+    bytes.extend_from_slice(b"x");
+
+    let err = ConstraintSystem::from_bytes(&bytes).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::UnexpectedEof);
+
+    // the same for a coefficient
+    let mut bytes = create_r1cs_bytes(&["x"], &[], &[(vec![(1, 1)], vec![], vec![])]);
+    let coeff_len = bytes.len() - 2 * 4 - 1 - 4;
+    bytes[coeff_len..coeff_len + 4].copy_from_slice(&u32::MAX.to_le_bytes());
+    let err = ConstraintSystem::from_bytes(&bytes).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::UnexpectedEof);
+}
+
 #[test]
 fn test_empty_file() {
     let bytes = Vec::new();
@@ -395,3 +574,79 @@ fn test_constraint_debug() {
     let debug_str = format!("{:?}", constraint);
     assert!(debug_str.contains("Constraint"));
 }
+
+#[test]
+fn test_symbol_table_read_and_describe() {
+    use lofit::SymbolTable;
+
+    let symbols = SymbolTable::read(Cursor::new("0,ONE\n1,x\n2,y\n3,t_0\n")).unwrap();
+    assert_eq!(symbols.len(), 4);
+    assert_eq!(symbols.name(1), Some("x"));
+    assert_eq!(symbols.describe(3), "t_0");
+    assert_eq!(symbols.describe(9), "wire_9");
+
+    assert!(SymbolTable::read(Cursor::new("zero,ONE\n")).is_err());
+}
+
+#[test]
+fn test_symbol_table_from_constraint_system_matches_wire_order() {
+    use lofit::SymbolTable;
+
+    let bytes = create_r1cs_bytes(&["x"], &["y", "t_0"], &[]);
+    let r1cs = ConstraintSystem::from_file(Cursor::new(bytes)).unwrap();
+    let symbols = SymbolTable::from_constraint_system(&r1cs);
+
+    let entries: Vec<_> = symbols.iter().collect();
+    assert_eq!(entries, vec![(0, "ONE"), (1, "x"), (2, "y"), (3, "t_0")]);
+}
+
+#[test]
+fn test_find_unsatisfied_returns_first_failing_constraint() {
+    use ark_bn254::Fr;
+    use lofit::SymbolTable;
+
+    // x * x = y, y * ONE = z, x * y = z
+    let bytes = create_r1cs_bytes(
+        &["x"],
+        &["y", "z"],
+        &[
+            (vec![(1, 1)], vec![(1, 1)], vec![(2, 1)]),
+            (vec![(2, 1)], vec![(0, 1)], vec![(3, 1)]),
+            (vec![(1, 1)], vec![(2, 1)], vec![(3, 1)]),
+        ],
+    );
+    let r1cs = ConstraintSystem::from_file(Cursor::new(bytes)).unwrap();
+
+    let good =
+        ConstraintSystem::full_assignment(&[Fr::from(1u64)], &[Fr::from(1u64), Fr::from(1u64)]);
+    assert_eq!(r1cs.find_unsatisfied(&good), None);
+
+    // x = 3, y = 9, z = 9 satisfies the first two but not x * y = z
+    let bad =
+        ConstraintSystem::full_assignment(&[Fr::from(3u64)], &[Fr::from(9u64), Fr::from(9u64)]);
+    assert_eq!(r1cs.find_unsatisfied(&bad), Some(2));
+
+    let symbols = SymbolTable::from_constraint_system(&r1cs);
+    assert_eq!(
+        symbols.render_constraint(2, &r1cs.constraints[2], &bad),
+        "constraint 2: A*B = 27 but C = 9 (x = 3, y = 9, z = 9)"
+    );
+}
+
+#[test]
+fn test_negative_coefficients_evaluate_in_the_field() {
+    use ark_bn254::Fr;
+
+    // (x - y) * ONE = 0 holds when x == y
+    let bytes = create_r1cs_bytes(
+        &["x"],
+        &["y"],
+        &[(vec![(1, 1), (2, -1)], vec![(0, 1)], vec![])],
+    );
+    let r1cs = ConstraintSystem::from_file(Cursor::new(bytes)).unwrap();
+
+    let equal = ConstraintSystem::full_assignment(&[Fr::from(4u64)], &[Fr::from(4u64)]);
+    assert_eq!(r1cs.find_unsatisfied(&equal), None);
+    let different = ConstraintSystem::full_assignment(&[Fr::from(4u64)], &[Fr::from(5u64)]);
+    assert_eq!(r1cs.find_unsatisfied(&different), Some(0));
+}
@@ -0,0 +1,84 @@
+#![cfg(target_arch = "wasm32")]
+
+use lofit::wasm::public_inputs_from_witness;
+use wasm_bindgen::JsValue;
+use wasm_bindgen_test::wasm_bindgen_test;
+
+// public inputs "b" then "a", one witness "c"; no constraints are needed for ordering
+fn r1cs_bytes() -> Vec<u8> {
+    let mut bytes = Vec::new();
+    bytes.extend_from_slice(b"lof-r1cs");
+    bytes.extend_from_slice(&1u32.to_le_bytes());
+    bytes.extend_from_slice(&2u32.to_le_bytes());
+    bytes.extend_from_slice(&1u32.to_le_bytes());
+    bytes.extend_from_slice(&0u32.to_le_bytes());
+    for name in ["b", "a", "c"] {
+        bytes.extend_from_slice(&(name.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(name.as_bytes());
+    }
+    bytes
+}
+
+#[wasm_bindgen_test]
+fn public_inputs_follow_r1cs_order() {
+    let witness = js_sys::JSON::parse(r#"{"a": "1", "c": "3", "b": 2}"#).unwrap();
+    let ordered = public_inputs_from_witness(&r1cs_bytes(), witness).unwrap();
+    let ordered: Vec<String> = serde_wasm_bindgen::from_value(ordered).unwrap();
+    assert_eq!(ordered, vec!["2".to_string(), "1".to_string()]);
+}
+
+#[wasm_bindgen_test]
+fn missing_public_input_is_an_error() {
+    let witness = js_sys::JSON::parse(r#"{"a": "1"}"#).unwrap();
+    let err: JsValue = public_inputs_from_witness(&r1cs_bytes(), witness).unwrap_err();
+    assert!(err.as_string().unwrap().contains("'b'"));
+}
+
+#[wasm_bindgen_test]
+fn verifier_accepts_valid_proof_and_rejects_tampered_one() {
+    use ark_bn254::Fr;
+    use lofit::r1cs::{Constraint, LinearCombination};
+    use lofit::wasm::WasmVerifier;
+    use lofit::{LofCircuit, ProverKey};
+    use num_bigint::BigInt;
+
+    // x * x = y
+    let lc = |var: u32| LinearCombination {
+        terms: vec![(var, BigInt::from(1))],
+    };
+    let constraints = vec![Constraint {
+        a: lc(1),
+        b: lc(1),
+        c: lc(2),
+    }];
+
+    let (pk, vk) = ProverKey::setup(LofCircuit {
+        public_inputs: vec![Fr::from(0u64)],
+        witness: vec![Fr::from(0u64)],
+        constraints: constraints.clone(),
+    })
+    .unwrap();
+    let proof = pk
+        .prove(LofCircuit {
+            public_inputs: vec![Fr::from(3u64)],
+            witness: vec![Fr::from(9u64)],
+            constraints,
+        })
+        .unwrap();
+
+    let mut vk_bytes = Vec::new();
+    vk.write(&mut vk_bytes).unwrap();
+    let mut proof_bytes = Vec::new();
+    proof.write(&mut proof_bytes).unwrap();
+
+    let verifier = WasmVerifier::new(&vk_bytes).unwrap();
+    let inputs = |value: &str| serde_wasm_bindgen::to_value(&vec![value.to_string()]).unwrap();
+
+    assert!(verifier.verify(&proof_bytes, inputs("3")).unwrap());
+    assert!(!verifier.verify(&proof_bytes, inputs("4")).unwrap());
+
+    // flipping a bit in the proof either breaks decoding or the pairing check
+    let mut tampered = proof_bytes.clone();
+    tampered[0] ^= 1;
+    assert!(!verifier.verify(&tampered, inputs("3")).unwrap_or(false));
+}
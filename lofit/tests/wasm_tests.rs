@@ -12,9 +12,11 @@ fn create_test_r1cs(
     constraints: Vec<Constraint>,
 ) -> ConstraintSystem {
     ConstraintSystem {
+        name: None,
         public_inputs,
         witnesses,
         constraints,
+        declared_witness_count: None,
     }
 }
 
@@ -9,9 +9,11 @@ fn create_test_r1cs(
     constraints: Vec<Constraint>,
 ) -> ConstraintSystem {
     ConstraintSystem {
+        name: None,
         public_inputs,
         witnesses,
         constraints,
+        declared_witness_count: None,
     }
 }
 
@@ -462,3 +464,87 @@ fn test_comparison_result_pattern() {
     assert_eq!(witness[0], fr(7)); // comparison_temp = 10 - 3
     assert_eq!(witness[1], fr(7)); // result = comparison_temp
 }
+
+#[test]
+fn test_computed_temporaries_ignore_user_values() {
+    use lofit::inputs::{parse_partial_witness, InputsJson};
+
+    // x * y = t_0, t_0 * y = out
+    // 0=ONE, 1=out, 2=x, 3=y, 4=t_0
+    let r1cs = create_test_r1cs(
+        vec!["out".to_string()],
+        vec!["x".to_string(), "y".to_string(), "t_0".to_string()],
+        vec![
+            Constraint {
+                a: lc(vec![(2, 1)]),
+                b: lc(vec![(3, 1)]),
+                c: lc(vec![(4, 1)]),
+            },
+            Constraint {
+                a: lc(vec![(4, 1)]),
+                b: lc(vec![(3, 1)]),
+                c: lc(vec![(1, 1)]),
+            },
+        ],
+    );
+
+    let mut json = InputsJson::new();
+    json.insert("x".to_string(), "2".to_string());
+    json.insert("y".to_string(), "3".to_string());
+    json.insert("t_0".to_string(), "999".to_string());
+
+    let provided = parse_partial_witness(&json, &r1cs);
+    assert_eq!(provided, vec![fr(2), fr(3)]);
+
+    let witness = lofit::generate_full_witness_with_provided(&r1cs, &[fr(18)], &provided).unwrap();
+    assert_eq!(witness, vec![fr(2), fr(3), fr(6)]);
+}
+
+#[test]
+fn test_is_computed_witness_matches_compiler_temporaries() {
+    use lofit::r1cs::is_computed_witness;
+
+    assert!(is_computed_witness("t_0"));
+    assert!(is_computed_witness("t_12"));
+    assert!(is_computed_witness("t_3_bit_7"));
+    assert!(is_computed_witness("Multiply::t_0"));
+    assert!(is_computed_witness("Multiply::t_3_bit_7"));
+    assert!(!is_computed_witness("Multiply::y"));
+    assert!(!is_computed_witness("t"));
+    assert!(!is_computed_witness("t_"));
+    assert!(!is_computed_witness("t_x"));
+    assert!(!is_computed_witness("total"));
+    assert!(!is_computed_witness("y"));
+}
+
+#[test]
+fn test_split_inputs_routes_by_signal_kind() {
+    use lofit::inputs::{split_inputs, InputsJson};
+
+    let mut merged = InputsJson::new();
+    merged.insert("x".to_string(), "3".to_string());
+    merged.insert("y".to_string(), "9".to_string());
+    merged.insert("stale".to_string(), "1".to_string());
+
+    let (public, witness) = split_inputs(&merged, &["x".to_string()], &["y".to_string()]);
+    assert_eq!(public.len(), 1);
+    assert_eq!(public["x"], "3");
+    assert_eq!(witness.len(), 1);
+    assert_eq!(witness["y"], "9");
+}
+
+#[test]
+fn test_ordered_public_inputs_uses_circuit_order() {
+    use lofit::inputs::{ordered_public_inputs, InputsJson};
+
+    let mut witness = InputsJson::new();
+    witness.insert("a".to_string(), "1".to_string());
+    witness.insert("b".to_string(), "2".to_string());
+    witness.insert("c".to_string(), "3".to_string());
+
+    let ordered = ordered_public_inputs(&witness, &["b".to_string(), "a".to_string()]).unwrap();
+    assert_eq!(ordered, vec!["2".to_string(), "1".to_string()]);
+
+    witness.insert("a".to_string(), "not a number".to_string());
+    assert!(ordered_public_inputs(&witness, &["a".to_string()]).is_err());
+}